@@ -666,6 +666,172 @@ impl State {
 		}
 	}
 
+	fn impl_fn_verify_packet_data_batch(&self) -> proc_macro2::TokenStream {
+		let crate_ = &self.crate_ident;
+		let trait_ = &self.current_impl_trait;
+		let error = &self.current_impl_error;
+		let client_state_trait = &self.client_state_trait;
+		let cases = self.clients.iter().map(|client| {
+			let variant_ident = &client.variant_ident;
+			let attrs = &client.attrs;
+			quote! {
+				#(#attrs)*
+				Self::#variant_ident(client) => {
+					let client_type = #client_state_trait::client_type(client_state).to_owned();
+					let client_state = #crate_::downcast!(
+						client_state => Self::ClientState::#variant_ident
+					)
+					.ok_or_else(|| #error::client_args_type_mismatch(client_type))?;
+
+					#trait_::verify_packet_data_batch(
+						client,
+						ctx,
+						client_id,
+						client_state,
+						height,
+						connection_end,
+						proof,
+						root,
+						port_id,
+						channel_id,
+						commitments,
+					)
+				}
+			}
+		});
+
+		quote! {
+			fn verify_packet_data_batch<Ctx: #crate_::core::ics26_routing::context::ReaderContext>(
+				&self,
+				ctx: &Ctx,
+				client_id: &#crate_::core::ics24_host::identifier::ClientId,
+				client_state: &Self::ClientState,
+				height: #crate_::core::ics02_client::height::Height,
+				connection_end: &#crate_::core::ics03_connection::connection::ConnectionEnd,
+				proof: &#crate_::core::ics23_commitment::commitment::CommitmentProofBytes,
+				root: &#crate_::core::ics23_commitment::commitment::CommitmentRoot,
+				port_id: &#crate_::core::ics24_host::identifier::PortId,
+				channel_id: &#crate_::core::ics24_host::identifier::ChannelId,
+				commitments: ::alloc::vec::Vec<(
+					#crate_::core::ics04_channel::packet::Sequence,
+					#crate_::core::ics04_channel::commitment::PacketCommitment,
+				)>,
+			) -> ::core::result::Result<(), #error> {
+				match self {
+					#(#cases)*
+				}
+			}
+		}
+	}
+
+	fn impl_fn_verify_packet_receipt_absence_batch(&self) -> proc_macro2::TokenStream {
+		let crate_ = &self.crate_ident;
+		let trait_ = &self.current_impl_trait;
+		let error = &self.current_impl_error;
+		let client_state_trait = &self.client_state_trait;
+		let cases = self.clients.iter().map(|client| {
+			let variant_ident = &client.variant_ident;
+			let attrs = &client.attrs;
+			quote! {
+				#(#attrs)*
+				Self::#variant_ident(client) => {
+					let client_type = #client_state_trait::client_type(client_state).to_owned();
+					let client_state = #crate_::downcast!(
+						client_state => Self::ClientState::#variant_ident
+					)
+					.ok_or_else(|| #error::client_args_type_mismatch(client_type))?;
+
+					#trait_::verify_packet_receipt_absence_batch(
+						client,
+						ctx,
+						client_id,
+						client_state,
+						height,
+						connection_end,
+						proof,
+						root,
+						port_id,
+						channel_id,
+						sequences,
+					)
+				}
+			}
+		});
+
+		quote! {
+			fn verify_packet_receipt_absence_batch<Ctx: #crate_::core::ics26_routing::context::ReaderContext>(
+				&self,
+				ctx: &Ctx,
+				client_id: &#crate_::core::ics24_host::identifier::ClientId,
+				client_state: &Self::ClientState,
+				height: #crate_::core::ics02_client::height::Height,
+				connection_end: &#crate_::core::ics03_connection::connection::ConnectionEnd,
+				proof: &#crate_::core::ics23_commitment::commitment::CommitmentProofBytes,
+				root: &#crate_::core::ics23_commitment::commitment::CommitmentRoot,
+				port_id: &#crate_::core::ics24_host::identifier::PortId,
+				channel_id: &#crate_::core::ics24_host::identifier::ChannelId,
+				sequences: ::alloc::vec::Vec<#crate_::core::ics04_channel::packet::Sequence>,
+			) -> ::core::result::Result<(), #error> {
+				match self {
+					#(#cases)*
+				}
+			}
+		}
+	}
+
+	fn impl_fn_verify_membership(&self) -> proc_macro2::TokenStream {
+		let crate_ = &self.crate_ident;
+		let trait_ = &self.current_impl_trait;
+		let error = &self.current_impl_error;
+		let client_state_trait = &self.client_state_trait;
+		let cases = self.clients.iter().map(|client| {
+			let variant_ident = &client.variant_ident;
+			let attrs = &client.attrs;
+			quote! {
+				#(#attrs)*
+				Self::#variant_ident(client) => {
+					let client_type = #client_state_trait::client_type(client_state).to_owned();
+					let client_state = #crate_::downcast!(
+						client_state => Self::ClientState::#variant_ident
+					)
+					.ok_or_else(|| #error::client_args_type_mismatch(client_type))?;
+
+					#trait_::verify_membership(
+						client,
+						ctx,
+						client_id,
+						client_state,
+						height,
+						prefix,
+						proof,
+						root,
+						path,
+						value,
+					)
+				}
+			}
+		});
+
+		quote! {
+			fn verify_membership<Ctx: #crate_::core::ics26_routing::context::ReaderContext>(
+				&self,
+				ctx: &Ctx,
+				client_id: &#crate_::core::ics24_host::identifier::ClientId,
+				client_state: &Self::ClientState,
+				height: #crate_::core::ics02_client::height::Height,
+				prefix: &#crate_::core::ics23_commitment::commitment::CommitmentPrefix,
+				proof: &#crate_::core::ics23_commitment::commitment::CommitmentProofBytes,
+				root: &#crate_::core::ics23_commitment::commitment::CommitmentRoot,
+				path: ::alloc::string::String,
+				value: ::alloc::vec::Vec<u8>,
+			) -> ::core::result::Result<(), #error> {
+				match self {
+					#(#cases)*
+				}
+			}
+		}
+	}
+
 	pub fn impl_client_def(&mut self) -> proc_macro2::TokenStream {
 		let crate_ = &self.crate_ident;
 		self.current_impl_trait =
@@ -693,6 +859,10 @@ impl State {
 		let fn_verify_packet_acknowledgement = self.impl_fn_verify_packet_acknowledgement();
 		let fn_verify_next_sequence_recv = self.impl_fn_verify_next_sequence_recv();
 		let fn_verify_packet_receipt_absence = self.impl_fn_verify_packet_receipt_absence();
+		let fn_verify_packet_data_batch = self.impl_fn_verify_packet_data_batch();
+		let fn_verify_packet_receipt_absence_batch =
+			self.impl_fn_verify_packet_receipt_absence_batch();
+		let fn_verify_membership = self.impl_fn_verify_membership();
 
 		quote! {
 			impl #impl_generics #client_def_trait for #this #ty_generics #where_clause {
@@ -713,6 +883,9 @@ impl State {
 				#fn_verify_packet_acknowledgement
 				#fn_verify_next_sequence_recv
 				#fn_verify_packet_receipt_absence
+				#fn_verify_packet_data_batch
+				#fn_verify_packet_receipt_absence_batch
+				#fn_verify_membership
 			}
 		}
 	}
@@ -163,6 +163,48 @@ impl State {
 		}
 	}
 
+	fn impl_fn_proof_format(&self) -> proc_macro2::TokenStream {
+		let cases = self.clients.iter().map(|client| {
+			let variant_ident = &client.variant_ident;
+			let attrs = &client.attrs;
+			let trait_ = &self.current_impl_trait;
+			quote! {
+				#(#attrs)*
+				Self::#variant_ident(state) => #trait_::proof_format(state),
+			}
+		});
+
+		let crate_ = &self.crate_ident;
+		quote! {
+			fn proof_format(&self) -> #crate_::core::ics23_commitment::verify::ProofFormat {
+				match self {
+					#(#cases)*
+				}
+			}
+		}
+	}
+
+	fn impl_fn_proof_specs(&self) -> proc_macro2::TokenStream {
+		let cases = self.clients.iter().map(|client| {
+			let variant_ident = &client.variant_ident;
+			let attrs = &client.attrs;
+			let trait_ = &self.current_impl_trait;
+			quote! {
+				#(#attrs)*
+				Self::#variant_ident(state) => #trait_::proof_specs(state),
+			}
+		});
+
+		let crate_ = &self.crate_ident;
+		quote! {
+			fn proof_specs(&self) -> #crate_::core::ics23_commitment::specs::ProofSpecs {
+				match self {
+					#(#cases)*
+				}
+			}
+		}
+	}
+
 	pub fn impl_client_state(&mut self) -> proc_macro2::TokenStream {
 		let crate_ = &self.crate_ident;
 		self.current_impl_trait =
@@ -180,6 +222,8 @@ impl State {
 		let fn_frozen_height = self.impl_fn_frozen_height();
 		let fn_upgrade = self.impl_fn_upgrade();
 		let fn_expired = self.impl_fn_expired();
+		let fn_proof_format = self.impl_fn_proof_format();
+		let fn_proof_specs = self.impl_fn_proof_specs();
 		let fn_downcast = self.impl_fn_downcast();
 		let fn_wrap = self.impl_fn_wrap();
 		let fn_encode_to_vec = self.impl_fn_encode_to_vec();
@@ -197,6 +241,8 @@ impl State {
 				#fn_frozen_height
 				#fn_upgrade
 				#fn_expired
+				#fn_proof_format
+				#fn_proof_specs
 				#fn_downcast
 				#fn_wrap
 				#fn_encode_to_vec
@@ -0,0 +1,499 @@
+use subtle_encoding::hex;
+
+use crate::{
+	applications::{
+		atomic_swap::{
+			self,
+			error::Error,
+			events::{AckEvent, RecvEvent, TimeoutEvent},
+			order::{Order, OrderId},
+			packet::SwapPacketData,
+			relay::{process_ack_packet, process_recv_packet},
+			MODULE_ID_STR,
+		},
+		transfer::{
+			acknowledgement::{Acknowledgement, ACK_ERR_STR},
+			context::BankKeeper,
+			escrow::EscrowAddressScheme,
+		},
+	},
+	core::{
+		ics04_channel::{
+			acknowledgement::error_message,
+			channel::{Counterparty, Order as ChannelOrder},
+			context::{ChannelKeeper, ChannelReader},
+			error::Error as ChannelError,
+			msgs::acknowledgement::Acknowledgement as GenericAcknowledgement,
+			packet::Packet,
+			Version,
+		},
+		ics05_port::capabilities::{lookup_capability, Capability, CapabilityName},
+		ics23_commitment::ethereum::KeccakHostFunctions,
+		ics24_host::identifier::{ChannelId, ConnectionId, PortId},
+		ics26_routing::context::{ModuleId, ModuleOutputBuilder, OnRecvPacketAck, ReaderContext},
+	},
+	prelude::*,
+	signer::Signer,
+};
+
+pub trait SwapReader: ChannelReader
+where
+	Self: Sized,
+{
+	type AccountId: TryFrom<Signer>;
+	/// Host functions escrow accounts are derived with.
+	type HostFunctions: KeccakHostFunctions;
+
+	fn get_port(&self) -> Result<PortId, Error>;
+
+	/// Returns the order with the given identifier, if any.
+	fn get_order(&self, order_id: &OrderId) -> Result<Option<Order>, Error>;
+
+	/// Returns the account escrowing the swapped tokens of a port and channel combination,
+	/// derived like ICS20 escrow accounts.
+	fn get_swap_escrow_address(
+		&self,
+		port_id: &PortId,
+		channel_id: ChannelId,
+	) -> Result<<Self as SwapReader>::AccountId, Error> {
+		let address = self
+			.escrow_address_scheme()
+			.escrow_address::<Self::HostFunctions>(self, port_id, channel_id);
+		self.escrow_account(address)
+	}
+
+	/// Returns the scheme escrow accounts are derived with, Cosmos ADR-028 by default.
+	fn escrow_address_scheme(&self) -> EscrowAddressScheme {
+		EscrowAddressScheme::default()
+	}
+
+	/// Converts the raw bytes of an escrow account into an account. By default, the upper-case
+	/// hex encoding of the bytes is parsed as a [`Signer`].
+	fn escrow_account(&self, address: Vec<u8>) -> Result<<Self as SwapReader>::AccountId, Error> {
+		String::from_utf8(hex::encode_upper(address))
+			.expect("hex encoded bytes are not valid UTF8")
+			.parse::<Signer>()
+			.map_err(|_| Error::parse_account_failure())?
+			.try_into()
+			.map_err(|_| Error::parse_account_failure())
+	}
+}
+
+pub trait SwapKeeper:
+	ChannelKeeper + BankKeeper<AccountId = <Self as SwapKeeper>::AccountId>
+{
+	type AccountId;
+
+	/// Stores an order, or deletes it if `order` is `None`.
+	fn store_order(&mut self, order_id: OrderId, order: Option<Order>) -> Result<(), Error>;
+}
+
+/// Captures all the dependencies which the ICS100 module requires to be able to dispatch and
+/// process IBC messages.
+pub trait SwapContext:
+	SwapKeeper<AccountId = <Self as SwapContext>::AccountId>
+	+ SwapReader<AccountId = <Self as SwapContext>::AccountId>
+	+ ReaderContext
+{
+	type AccountId: TryFrom<Signer>;
+}
+
+/// Returns the capability of a channel opened by the swap module, required to send packets on the
+/// channel. The capabilities of the channels of the module are issued to it under
+/// [`MODULE_ID_STR`].
+pub(crate) fn channel_capability(
+	ctx: &impl SwapContext,
+	port_id: &PortId,
+	channel_id: ChannelId,
+) -> Result<Capability, Error> {
+	let module_id: ModuleId = MODULE_ID_STR.parse().expect("invalid ModuleId");
+	lookup_capability(ctx, &CapabilityName::channel(port_id, channel_id), &module_id)
+		.map_err(|e| Error::ics04_channel(ChannelError::ics05_port(e)))
+}
+
+/// Builds the acknowledgement of a packet that failed to be processed.
+pub(crate) fn error_ack(err: Error) -> Acknowledgement {
+	Acknowledgement::Error(format!("{}: {}", ACK_ERR_STR, error_message(err)))
+}
+
+fn validate_swap_channel_params(
+	ctx: &impl SwapContext,
+	order: ChannelOrder,
+	port_id: &PortId,
+	version: &Version,
+) -> Result<(), Error> {
+	if order != ChannelOrder::Unordered {
+		return Err(Error::channel_not_unordered(order))
+	}
+
+	let bound_port = ctx.get_port()?;
+	if port_id != &bound_port {
+		return Err(Error::invalid_port(port_id.clone(), bound_port))
+	}
+
+	validate_version(version)
+}
+
+fn validate_version(version: &Version) -> Result<(), Error> {
+	if version == &atomic_swap::version() {
+		Ok(())
+	} else {
+		Err(Error::invalid_version(version.clone()))
+	}
+}
+
+pub fn on_chan_open_init_validate(
+	ctx: &impl SwapContext,
+	order: ChannelOrder,
+	_connection_hops: &[ConnectionId],
+	port_id: &PortId,
+	_channel_id: &ChannelId,
+	_counterparty: &Counterparty,
+	version: &Version,
+) -> Result<(), Error> {
+	validate_swap_channel_params(ctx, order, port_id, version)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn on_chan_open_init(
+	ctx: &mut impl SwapContext,
+	_output: &mut ModuleOutputBuilder,
+	order: ChannelOrder,
+	_connection_hops: &[ConnectionId],
+	port_id: &PortId,
+	_channel_id: &ChannelId,
+	_counterparty: &Counterparty,
+	version: &Version,
+) -> Result<(), Error> {
+	validate_swap_channel_params(ctx, order, port_id, version)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn on_chan_open_try_validate(
+	ctx: &impl SwapContext,
+	order: ChannelOrder,
+	_connection_hops: &[ConnectionId],
+	port_id: &PortId,
+	_channel_id: &ChannelId,
+	_counterparty: &Counterparty,
+	version: &Version,
+	counterparty_version: &Version,
+) -> Result<(), Error> {
+	validate_swap_channel_params(ctx, order, port_id, version)?;
+	validate_version(counterparty_version)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn on_chan_open_try(
+	ctx: &mut impl SwapContext,
+	_output: &mut ModuleOutputBuilder,
+	order: ChannelOrder,
+	_connection_hops: &[ConnectionId],
+	port_id: &PortId,
+	_channel_id: &ChannelId,
+	_counterparty: &Counterparty,
+	version: &Version,
+	counterparty_version: &Version,
+) -> Result<Version, Error> {
+	validate_swap_channel_params(ctx, order, port_id, version)?;
+	validate_version(counterparty_version)?;
+	Ok(atomic_swap::version())
+}
+
+pub fn on_chan_open_ack_validate(
+	_ctx: &impl SwapContext,
+	_port_id: &PortId,
+	_channel_id: &ChannelId,
+	counterparty_version: &Version,
+) -> Result<(), Error> {
+	validate_version(counterparty_version)
+}
+
+pub fn on_chan_open_ack(
+	_ctx: &mut impl SwapContext,
+	_output: &mut ModuleOutputBuilder,
+	_port_id: &PortId,
+	_channel_id: &ChannelId,
+	counterparty_version: &Version,
+) -> Result<(), Error> {
+	validate_version(counterparty_version)
+}
+
+pub fn on_chan_open_confirm(
+	_ctx: &mut impl SwapContext,
+	_output: &mut ModuleOutputBuilder,
+	_port_id: &PortId,
+	_channel_id: &ChannelId,
+) -> Result<(), Error> {
+	Ok(())
+}
+
+pub fn on_chan_close_init_validate(
+	_ctx: &impl SwapContext,
+	_port_id: &PortId,
+	_channel_id: &ChannelId,
+) -> Result<(), Error> {
+	Err(Error::cant_close_channel())
+}
+
+pub fn on_chan_close_init(
+	_ctx: &mut impl SwapContext,
+	_output: &mut ModuleOutputBuilder,
+	_port_id: &PortId,
+	_channel_id: &ChannelId,
+) -> Result<(), Error> {
+	Err(Error::cant_close_channel())
+}
+
+pub fn on_chan_close_confirm(
+	_ctx: &mut impl SwapContext,
+	_output: &mut ModuleOutputBuilder,
+	_port_id: &PortId,
+	_channel_id: &ChannelId,
+) -> Result<(), Error> {
+	Ok(())
+}
+
+pub fn on_recv_packet<Ctx: 'static + SwapContext>(
+	ctx: &Ctx,
+	output: &mut ModuleOutputBuilder,
+	packet: &Packet,
+	_relayer: &Signer,
+) -> OnRecvPacketAck {
+	let data = match serde_json::from_slice::<SwapPacketData>(&packet.data) {
+		Ok(data) => data,
+		Err(_) =>
+			return OnRecvPacketAck::Failed(Box::new(
+				error_ack(Error::packet_data_deserialization()),
+			)),
+	};
+
+	let ack = match process_recv_packet(ctx, packet, &data) {
+		Ok(write_fn) => OnRecvPacketAck::Successful(Box::new(Acknowledgement::success()), write_fn),
+		Err(e) => OnRecvPacketAck::Failed(Box::new(error_ack(e))),
+	};
+
+	let recv_event = RecvEvent { message_type: data.message_type(), success: ack.is_successful() };
+	output.emit(recv_event.into());
+
+	ack
+}
+
+pub fn on_acknowledgement_packet_validate(
+	_ctx: &impl SwapContext,
+	packet: &Packet,
+	acknowledgement: &GenericAcknowledgement,
+	_relayer: &Signer,
+) -> Result<(), Error> {
+	serde_json::from_slice::<SwapPacketData>(&packet.data)
+		.map_err(|_| Error::packet_data_deserialization())?;
+	serde_json::from_slice::<Acknowledgement>(acknowledgement.as_ref())
+		.map_err(|_| Error::ack_deserialization())?;
+	Ok(())
+}
+
+pub fn on_acknowledgement_packet(
+	ctx: &mut impl SwapContext,
+	output: &mut ModuleOutputBuilder,
+	packet: &Packet,
+	acknowledgement: &GenericAcknowledgement,
+	_relayer: &Signer,
+) -> Result<(), Error> {
+	let data = serde_json::from_slice::<SwapPacketData>(&packet.data)
+		.map_err(|_| Error::packet_data_deserialization())?;
+
+	let acknowledgement = serde_json::from_slice::<Acknowledgement>(acknowledgement.as_ref())
+		.map_err(|_| Error::ack_deserialization())?;
+	let success = matches!(acknowledgement, Acknowledgement::Success(_));
+
+	process_ack_packet(ctx, packet, &data, success)?;
+
+	output.emit(AckEvent { message_type: data.message_type(), success }.into());
+
+	Ok(())
+}
+
+pub fn on_timeout_packet_validate(
+	_ctx: &impl SwapContext,
+	packet: &Packet,
+	_relayer: &Signer,
+) -> Result<(), Error> {
+	serde_json::from_slice::<SwapPacketData>(&packet.data)
+		.map_err(|_| Error::packet_data_deserialization())?;
+	Ok(())
+}
+
+pub fn on_timeout_packet(
+	ctx: &mut impl SwapContext,
+	output: &mut ModuleOutputBuilder,
+	packet: &Packet,
+	_relayer: &Signer,
+) -> Result<(), Error> {
+	let data = serde_json::from_slice::<SwapPacketData>(&packet.data)
+		.map_err(|_| Error::packet_data_deserialization())?;
+
+	// a packet that timed out was not processed by the counterparty, which is handled like a
+	// failure acknowledgement
+	process_ack_packet(ctx, packet, &data, false)?;
+
+	output.emit(TimeoutEvent { message_type: data.message_type() }.into());
+
+	Ok(())
+}
+
+#[cfg(test)]
+pub mod test_util {
+	use super::*;
+	use crate::{
+		applications::atomic_swap::PORT_ID_STR,
+		core::{
+			ics03_connection::{
+				connection::{
+					ConnectionEnd, Counterparty as ConnectionCounterparty, State as ConnectionState,
+				},
+				version::get_compatible_versions,
+			},
+			ics04_channel::{
+				channel::{ChannelEnd, State},
+				events::SendPacket,
+			},
+			ics24_host::identifier::ClientId,
+		},
+		events::IbcEvent,
+		handler::HandlerOutputBuilder,
+		mock::context::{HostBlockType, MockClientTypes, MockContext},
+		test_utils::{get_dummy_account_id, Crypto, DummyTransferModule},
+		timestamp::{Timestamp, ZERO_DURATION},
+		Height,
+	};
+	use alloc::collections::BTreeMap;
+
+	/// The orders of the swap module.
+	#[derive(Clone, Debug, Default)]
+	pub struct Orders(BTreeMap<OrderId, Order>);
+
+	pub type TestContext = DummyTransferModule<MockClientTypes, Orders>;
+
+	impl<C: HostBlockType> SwapReader for DummyTransferModule<C, Orders> {
+		type AccountId = Signer;
+		type HostFunctions = Crypto;
+
+		fn get_port(&self) -> Result<PortId, Error> {
+			Ok(swap_port())
+		}
+
+		fn get_order(&self, order_id: &OrderId) -> Result<Option<Order>, Error> {
+			Ok(self.state().0.get(order_id).cloned())
+		}
+	}
+
+	impl<C: HostBlockType> SwapKeeper for DummyTransferModule<C, Orders> {
+		type AccountId = Signer;
+
+		fn store_order(&mut self, order_id: OrderId, order: Option<Order>) -> Result<(), Error> {
+			let orders = &mut self.state_mut().0;
+			match order {
+				Some(order) => orders.insert(order_id, order),
+				None => orders.remove(&order_id),
+			};
+			Ok(())
+		}
+	}
+
+	impl<C: HostBlockType> SwapContext for DummyTransferModule<C, Orders> {
+		type AccountId = Signer;
+	}
+
+	pub fn swap_port() -> PortId {
+		PORT_ID_STR.parse().unwrap()
+	}
+
+	pub fn now() -> Timestamp {
+		Timestamp::from_nanoseconds(1_000_000_000).unwrap()
+	}
+
+	/// A context whose swap module owns the open channel `swap/channel-0`, the counterparty of
+	/// `swap/channel-1` on the other side of the swap.
+	pub fn swap_context() -> TestContext {
+		let connection_end = ConnectionEnd::new(
+			ConnectionState::Open,
+			ClientId::default(),
+			ConnectionCounterparty::new(
+				ClientId::default(),
+				Some(ConnectionId::default()),
+				Default::default(),
+			),
+			get_compatible_versions(),
+			ZERO_DURATION,
+		);
+		let channel_end = ChannelEnd::new(
+			State::Open,
+			ChannelOrder::Unordered,
+			Counterparty::new(swap_port(), Some(ChannelId::new(1))),
+			vec![ConnectionId::default()],
+			atomic_swap::version(),
+		);
+		let mut ctx = MockContext::<MockClientTypes>::default()
+			.with_client(&ClientId::default(), Height::new(0, 10))
+			.with_connection(ConnectionId::default(), connection_end);
+		ctx.scope_port_to_module(swap_port(), MODULE_ID_STR.parse().unwrap());
+		let ctx = ctx
+			.with_channel(swap_port(), ChannelId::default(), channel_end)
+			.with_send_sequence(swap_port(), ChannelId::default(), 1.into());
+
+		let mut swap_ctx =
+			DummyTransferModule::with_state(ctx.ibc_store_share(), Orders::default());
+		swap_ctx.set_host_timestamp(now());
+		swap_ctx
+	}
+
+	/// Returns the packet sent by a swap message.
+	pub fn sent_packet(output: &HandlerOutputBuilder<()>) -> Packet {
+		output
+			.events()
+			.iter()
+			.find_map(|event| match event {
+				IbcEvent::SendPacket(SendPacket { packet, .. }) => Some(packet.clone()),
+				_ => None,
+			})
+			.expect("no packet sent")
+	}
+
+	/// Returns a packet received on `swap/channel-0` carrying `data`.
+	pub fn recv_packet(data: &SwapPacketData, sequence: u64) -> Packet {
+		Packet {
+			sequence: sequence.into(),
+			source_port: swap_port(),
+			source_channel: ChannelId::new(1),
+			destination_port: swap_port(),
+			destination_channel: ChannelId::default(),
+			data: serde_json::to_vec(data).unwrap(),
+			..Default::default()
+		}
+	}
+
+	/// Receives `packet`, applying its write function if it is acknowledged successfully.
+	pub fn recv(ctx: &mut TestContext, packet: &Packet) -> bool {
+		let relayer = get_dummy_account_id();
+		match on_recv_packet(ctx, &mut ModuleOutputBuilder::new(), packet, &relayer) {
+			OnRecvPacketAck::Successful(_, write_fn) => {
+				write_fn(ctx).unwrap();
+				true
+			},
+			OnRecvPacketAck::Failed(_) => false,
+			OnRecvPacketAck::Nil(_) => panic!("swap packets are acknowledged synchronously"),
+		}
+	}
+
+	/// Acknowledges `packet`, successfully if `success` is true.
+	pub fn ack(ctx: &mut TestContext, packet: &Packet, success: bool) -> Result<(), Error> {
+		let ack = if success {
+			Acknowledgement::success()
+		} else {
+			Acknowledgement::Error(ACK_ERR_STR.to_string())
+		};
+		let ack = serde_json::to_vec(&ack.to_string()).unwrap().into();
+		let relayer = get_dummy_account_id();
+		on_acknowledgement_packet(ctx, &mut ModuleOutputBuilder::new(), packet, &ack, &relayer)
+	}
+}
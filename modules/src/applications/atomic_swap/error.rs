@@ -0,0 +1,128 @@
+use flex_error::{define_error, TraceError};
+use tendermint_proto::Error as TendermintProtoError;
+
+use crate::{
+	applications::{
+		atomic_swap::{
+			self,
+			order::{OrderId, Status},
+		},
+		transfer::error::Error as Ics20Error,
+	},
+	core::{
+		ics04_channel::{channel::Order, error as channel_error, Version},
+		ics24_host::{error::ValidationError, identifier::PortId},
+	},
+	prelude::*,
+	signer::SignerError,
+};
+
+define_error! {
+	#[derive(Debug, PartialEq, Eq)]
+	Error {
+		Ics04Channel
+			[ channel_error::Error ]
+			| _ | { "ics04 channel error" },
+
+		Bank
+			[ Ics20Error ]
+			| _ | { "failed to move the swapped tokens" },
+
+		InvalidSwap
+			{ reason: String }
+			| e | { format_args!("invalid swap: {0}", e.reason) },
+
+		OrderNotFound
+			{ order_id: OrderId }
+			| e | { format_args!("swap order {0} not found", e.order_id) },
+
+		OrderAlreadyExists
+			{ order_id: OrderId }
+			| e | { format_args!("swap order {0} already exists", e.order_id) },
+
+		InvalidOrderStatus
+			{ order_id: OrderId, status: Status }
+			| e | {
+				format_args!("swap order {0} cannot be processed in status {1}",
+					e.order_id, e.status)
+			},
+
+		WrongSide
+			{ order_id: OrderId }
+			| e | {
+				format_args!("swap order {0} cannot be processed on this side of the swap",
+					e.order_id)
+			},
+
+		OrderExpired
+			{ order_id: OrderId }
+			| e | { format_args!("swap order {0} has expired", e.order_id) },
+
+		UnauthorizedTaker
+			{ order_id: OrderId, taker: String }
+			| e | {
+				format_args!("{0} is not allowed to take swap order {1}", e.taker, e.order_id)
+			},
+
+		UnauthorizedMaker
+			{ order_id: OrderId, sender: String }
+			| e | { format_args!("{0} is not the maker of swap order {1}", e.sender, e.order_id) },
+
+		TokenMismatch
+			{ order_id: OrderId, expected: String, got: String }
+			| e | {
+				format_args!("swap order {0} buys {1}, got {2}",
+					e.order_id, e.expected, e.got)
+			},
+
+		ChannelNotUnordered
+			{ order: Order }
+			| e | { format_args!("expected '{0}' channel, got '{1}'", Order::Unordered, e.order) },
+
+		InvalidPort
+			{ port_id: PortId, exp_port_id: PortId }
+			| e | { format_args!("invalid port: '{0}', expected '{1}'", e.port_id, e.exp_port_id) },
+
+		InvalidVersion
+			{ version: Version }
+			| e | {
+				format_args!("expected version '{0}', got '{1}'", atomic_swap::version(), e.version)
+			},
+
+		CantCloseChannel
+			| _ | { "channel cannot be closed" },
+
+		PacketDataDeserialization
+			| _ | { "failed to deserialize packet data" },
+
+		AckDeserialization
+			| _ | { "failed to deserialize acknowledgement" },
+
+		ParseAccountFailure
+			| _ | { "failed to parse as AccountId" },
+
+		InvalidIdentifier
+			[ ValidationError ]
+			| _ | { "invalid port or channel identifier" },
+
+		InvalidToken
+			[ Ics20Error ]
+			| _ | { "invalid swapped token" },
+
+		Signer
+			[ SignerError ]
+			| _ | { "failed to parse signer" },
+
+		InvalidTimestamp
+			{ timestamp: u64 }
+			| e | { format_args!("invalid timestamp {0}", e.timestamp) },
+
+		DecodeRawMsg
+			[ TraceError<TendermintProtoError> ]
+			| _ | { "error decoding raw msg" },
+
+		UnknownMsgType
+			{ msg_type: String }
+			| e | { format_args!("unknown msg type: {0}", e.msg_type) },
+	}
+}
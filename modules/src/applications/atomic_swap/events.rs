@@ -0,0 +1,123 @@
+use crate::{
+	applications::{
+		atomic_swap::{order::OrderId, MODULE_ID_STR},
+		transfer::PrefixedCoin,
+	},
+	events::ModuleEvent,
+	prelude::*,
+	signer::Signer,
+};
+
+const EVENT_TYPE_MAKE_SWAP: &str = "make_swap";
+const EVENT_TYPE_TAKE_SWAP: &str = "take_swap";
+const EVENT_TYPE_CANCEL_SWAP: &str = "cancel_swap";
+const EVENT_TYPE_PACKET: &str = "atomic_swap_packet";
+const EVENT_TYPE_TIMEOUT: &str = "timeout";
+
+fn module_event(kind: &str, attributes: Vec<(&str, String)>) -> ModuleEvent {
+	ModuleEvent {
+		kind: kind.to_string(),
+		module_name: MODULE_ID_STR.parse().expect("invalid ModuleId"),
+		attributes: attributes.into_iter().map(Into::into).collect(),
+	}
+}
+
+pub struct MakeSwapEvent {
+	pub order_id: OrderId,
+	pub maker: Signer,
+	pub sell_token: PrefixedCoin,
+	pub buy_token: PrefixedCoin,
+}
+
+impl From<MakeSwapEvent> for ModuleEvent {
+	fn from(ev: MakeSwapEvent) -> Self {
+		let MakeSwapEvent { order_id, maker, sell_token, buy_token } = ev;
+		module_event(
+			EVENT_TYPE_MAKE_SWAP,
+			vec![
+				("order_id", order_id.to_string()),
+				("maker", maker.to_string()),
+				("sell_token", sell_token.to_string()),
+				("buy_token", buy_token.to_string()),
+			],
+		)
+	}
+}
+
+pub struct TakeSwapEvent {
+	pub order_id: OrderId,
+	pub taker: Signer,
+	pub sell_token: PrefixedCoin,
+}
+
+impl From<TakeSwapEvent> for ModuleEvent {
+	fn from(ev: TakeSwapEvent) -> Self {
+		let TakeSwapEvent { order_id, taker, sell_token } = ev;
+		module_event(
+			EVENT_TYPE_TAKE_SWAP,
+			vec![
+				("order_id", order_id.to_string()),
+				("taker", taker.to_string()),
+				("sell_token", sell_token.to_string()),
+			],
+		)
+	}
+}
+
+pub struct CancelSwapEvent {
+	pub order_id: OrderId,
+	pub maker: Signer,
+}
+
+impl From<CancelSwapEvent> for ModuleEvent {
+	fn from(ev: CancelSwapEvent) -> Self {
+		let CancelSwapEvent { order_id, maker } = ev;
+		module_event(
+			EVENT_TYPE_CANCEL_SWAP,
+			vec![("order_id", order_id.to_string()), ("maker", maker.to_string())],
+		)
+	}
+}
+
+pub struct RecvEvent {
+	pub message_type: &'static str,
+	pub success: bool,
+}
+
+impl From<RecvEvent> for ModuleEvent {
+	fn from(ev: RecvEvent) -> Self {
+		let RecvEvent { message_type, success } = ev;
+		module_event(
+			EVENT_TYPE_PACKET,
+			vec![("message_type", message_type.to_string()), ("success", success.to_string())],
+		)
+	}
+}
+
+pub struct AckEvent {
+	pub message_type: &'static str,
+	pub success: bool,
+}
+
+impl From<AckEvent> for ModuleEvent {
+	fn from(ev: AckEvent) -> Self {
+		let AckEvent { message_type, success } = ev;
+		module_event(
+			EVENT_TYPE_PACKET,
+			vec![
+				("message_type", message_type.to_string()),
+				("acknowledgement_success", success.to_string()),
+			],
+		)
+	}
+}
+
+pub struct TimeoutEvent {
+	pub message_type: &'static str,
+}
+
+impl From<TimeoutEvent> for ModuleEvent {
+	fn from(ev: TimeoutEvent) -> Self {
+		module_event(EVENT_TYPE_TIMEOUT, vec![("message_type", ev.message_type.to_string())])
+	}
+}
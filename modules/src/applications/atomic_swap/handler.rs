@@ -0,0 +1,537 @@
+use crate::{
+	applications::{
+		atomic_swap::{
+			context::{channel_capability, SwapContext},
+			error::Error,
+			events::{CancelSwapEvent, MakeSwapEvent, TakeSwapEvent},
+			msgs::{MsgCancelSwap, MsgMakeSwap, MsgTakeSwap},
+			order::{Order, OrderId, Side, Status},
+			packet::{CancelSwapData, MakeSwapData, SwapPacketData, TakeSwapData},
+		},
+		transfer::PrefixedCoin,
+	},
+	core::{
+		ics04_channel::{
+			handler::send_packet::send_packet,
+			packet::{Packet, PacketResult},
+		},
+		ics24_host::identifier::{ChannelId, PortId},
+	},
+	events::ModuleEvent,
+	handler::{HandlerOutput, HandlerOutputBuilder},
+	prelude::*,
+	timestamp::Timestamp,
+	Height,
+};
+
+/// Makes a swap order: escrows the tokens sold by the maker, and announces the order to the
+/// counterparty chain.
+/// Returns the identifier of the order.
+/// If this method returns an error, the runtime is expected to rollback all state modifications to
+/// the `Ctx` caused by all messages from the transaction that this `msg` is a part of.
+pub fn make_swap<Ctx: SwapContext>(
+	ctx: &mut Ctx,
+	output: &mut HandlerOutputBuilder<()>,
+	msg: MsgMakeSwap,
+) -> Result<OrderId, Error> {
+	let (swap, order_id) = prepare_make_swap(ctx, msg)?;
+	swap.execute(ctx, output)?;
+	Ok(order_id)
+}
+
+/// Runs all the checks of [`make_swap`] without modifying the `Ctx`, and returns the identifier
+/// the order would be made with, along with the events making it would emit.
+/// Whether the maker holds the tokens sold is left to the bank, and only checked on execution.
+pub fn validate_make_swap<Ctx: SwapContext>(
+	ctx: &Ctx,
+	msg: MsgMakeSwap,
+) -> Result<HandlerOutput<OrderId>, Error> {
+	let (swap, order_id) = prepare_make_swap(ctx, msg)?;
+	Ok(swap.validate(order_id))
+}
+
+fn prepare_make_swap<Ctx: SwapContext>(
+	ctx: &Ctx,
+	msg: MsgMakeSwap,
+) -> Result<(PreparedSwap<<Ctx as SwapContext>::AccountId>, OrderId), Error> {
+	if msg.sell_token.amount == 0u64.into() || msg.buy_token.amount == 0u64.into() {
+		return Err(Error::invalid_swap("swapped amounts must be positive".to_string()))
+	}
+
+	let maker = msg
+		.maker_address
+		.clone()
+		.try_into()
+		.map_err(|_| Error::parse_account_failure())?;
+
+	let sequence = ctx
+		.get_next_sequence_send(&(msg.source_port.clone(), msg.source_channel))
+		.map_err(Error::ics04_channel)?;
+	let order_id = OrderId::new(ctx, &msg.source_port, msg.source_channel, sequence);
+	if ctx.get_order(&order_id)?.is_some() {
+		return Err(Error::order_already_exists(order_id))
+	}
+
+	let escrow_address = ctx.get_swap_escrow_address(&msg.source_port, msg.source_channel)?;
+
+	let data = MakeSwapData {
+		sell_token: msg.sell_token,
+		buy_token: msg.buy_token,
+		maker_address: msg.maker_address,
+		maker_receiving_address: msg.maker_receiving_address,
+		desired_taker: msg.desired_taker,
+		expiration_timestamp: msg.expiration_timestamp.nanoseconds(),
+	};
+	let send_packet = prepare_swap_packet(
+		ctx,
+		msg.source_port.clone(),
+		msg.source_channel,
+		SwapPacketData::MakeSwap(data.clone()),
+		msg.timeout_height,
+		msg.timeout_timestamp,
+	)?;
+
+	let make_swap_event = MakeSwapEvent {
+		order_id: order_id.clone(),
+		maker: data.maker_address.clone(),
+		sell_token: data.sell_token.clone(),
+		buy_token: data.buy_token.clone(),
+	};
+	let escrow = (maker, escrow_address, data.sell_token.clone());
+	let order = Order {
+		id: order_id.clone(),
+		port_id: msg.source_port,
+		channel_id: msg.source_channel,
+		side: Side::Maker,
+		maker: data,
+		taker: None,
+		status: Status::Initial,
+	};
+
+	let swap = PreparedSwap {
+		escrow: Some(escrow),
+		send_packet,
+		order: Some(order),
+		log: Some(format!("atomic swap: made order {}", order_id)),
+		event: make_swap_event.into(),
+	};
+	Ok((swap, order_id))
+}
+
+/// Takes a swap order received from the counterparty chain: escrows the tokens sold by the taker,
+/// which are released to the maker once the maker chain acknowledges the take.
+pub fn take_swap<Ctx: SwapContext>(
+	ctx: &mut Ctx,
+	output: &mut HandlerOutputBuilder<()>,
+	msg: MsgTakeSwap,
+) -> Result<(), Error> {
+	prepare_take_swap(ctx, msg)?.execute(ctx, output)
+}
+
+/// Runs all the checks of [`take_swap`] without modifying the `Ctx`, and returns the events taking
+/// the order would emit.
+pub fn validate_take_swap<Ctx: SwapContext>(
+	ctx: &Ctx,
+	msg: MsgTakeSwap,
+) -> Result<HandlerOutput<()>, Error> {
+	Ok(prepare_take_swap(ctx, msg)?.validate(()))
+}
+
+fn prepare_take_swap<Ctx: SwapContext>(
+	ctx: &Ctx,
+	msg: MsgTakeSwap,
+) -> Result<PreparedSwap<<Ctx as SwapContext>::AccountId>, Error> {
+	let mut order = ctx
+		.get_order(&msg.order_id)?
+		.ok_or_else(|| Error::order_not_found(msg.order_id.clone()))?;
+	if order.side != Side::Taker {
+		return Err(Error::wrong_side(order.id))
+	}
+	if order.status != Status::Sync || order.taker.is_some() {
+		return Err(Error::invalid_order_status(order.id, order.status))
+	}
+	if order.is_expired(&ctx.host_timestamp()) {
+		return Err(Error::order_expired(order.id))
+	}
+	if let Some(desired_taker) = &order.maker.desired_taker {
+		if desired_taker != &msg.taker_address {
+			return Err(Error::unauthorized_taker(order.id, msg.taker_address.to_string()))
+		}
+	}
+	if msg.sell_token != order.maker.buy_token {
+		return Err(Error::token_mismatch(
+			order.id,
+			order.maker.buy_token.to_string(),
+			msg.sell_token.to_string(),
+		))
+	}
+
+	let taker = msg
+		.taker_address
+		.clone()
+		.try_into()
+		.map_err(|_| Error::parse_account_failure())?;
+	let escrow_address = ctx.get_swap_escrow_address(&order.port_id, order.channel_id)?;
+
+	let data = TakeSwapData {
+		order_id: order.id.clone(),
+		sell_token: msg.sell_token,
+		taker_address: msg.taker_address,
+		taker_receiving_address: msg.taker_receiving_address,
+	};
+	let send_packet = prepare_swap_packet(
+		ctx,
+		order.port_id.clone(),
+		order.channel_id,
+		SwapPacketData::TakeSwap(data.clone()),
+		msg.timeout_height,
+		msg.timeout_timestamp,
+	)?;
+
+	let take_swap_event = TakeSwapEvent {
+		order_id: order.id.clone(),
+		taker: data.taker_address.clone(),
+		sell_token: data.sell_token.clone(),
+	};
+	let escrow = (taker, escrow_address, data.sell_token.clone());
+	order.taker = Some(data);
+
+	Ok(PreparedSwap {
+		escrow: Some(escrow),
+		send_packet,
+		order: Some(order),
+		log: None,
+		event: take_swap_event.into(),
+	})
+}
+
+/// Requests the cancellation of a swap order that has not been taken yet. The tokens sold by the
+/// maker are refunded once the taker chain acknowledges the cancellation.
+pub fn cancel_swap<Ctx: SwapContext>(
+	ctx: &mut Ctx,
+	output: &mut HandlerOutputBuilder<()>,
+	msg: MsgCancelSwap,
+) -> Result<(), Error> {
+	prepare_cancel_swap(ctx, msg)?.execute(ctx, output)
+}
+
+/// Runs all the checks of [`cancel_swap`] without modifying the `Ctx`, and returns the events
+/// requesting the cancellation would emit.
+pub fn validate_cancel_swap<Ctx: SwapContext>(
+	ctx: &Ctx,
+	msg: MsgCancelSwap,
+) -> Result<HandlerOutput<()>, Error> {
+	Ok(prepare_cancel_swap(ctx, msg)?.validate(()))
+}
+
+fn prepare_cancel_swap<Ctx: SwapContext>(
+	ctx: &Ctx,
+	msg: MsgCancelSwap,
+) -> Result<PreparedSwap<<Ctx as SwapContext>::AccountId>, Error> {
+	let order = ctx
+		.get_order(&msg.order_id)?
+		.ok_or_else(|| Error::order_not_found(msg.order_id.clone()))?;
+	if order.side != Side::Maker {
+		return Err(Error::wrong_side(order.id))
+	}
+	if order.status != Status::Sync {
+		return Err(Error::invalid_order_status(order.id, order.status))
+	}
+	if order.maker.maker_address != msg.maker_address {
+		return Err(Error::unauthorized_maker(order.id, msg.maker_address.to_string()))
+	}
+
+	let data = CancelSwapData { order_id: order.id.clone(), maker_address: msg.maker_address };
+	let send_packet = prepare_swap_packet(
+		ctx,
+		order.port_id,
+		order.channel_id,
+		SwapPacketData::CancelSwap(data.clone()),
+		msg.timeout_height,
+		msg.timeout_timestamp,
+	)?;
+
+	let cancel_swap_event = CancelSwapEvent { order_id: data.order_id, maker: data.maker_address };
+	Ok(PreparedSwap {
+		escrow: None,
+		send_packet,
+		order: None,
+		log: None,
+		event: cancel_swap_event.into(),
+	})
+}
+
+/// A swap message that passed all checks, and the state changes its execution consists of.
+struct PreparedSwap<AccountId> {
+	/// The account the sold tokens are escrowed from, its escrow account and the tokens, if any.
+	escrow: Option<(AccountId, AccountId, PrefixedCoin)>,
+	send_packet: HandlerOutput<PacketResult>,
+	/// The order as updated by the message, if it is.
+	order: Option<Order>,
+	log: Option<String>,
+	event: ModuleEvent,
+}
+
+impl<AccountId> PreparedSwap<AccountId> {
+	fn execute<Ctx>(self, ctx: &mut Ctx, output: &mut HandlerOutputBuilder<()>) -> Result<(), Error>
+	where
+		Ctx: SwapContext<AccountId = AccountId>,
+	{
+		if let Some((from, escrow_address, token)) = &self.escrow {
+			ctx.send_coins(from, escrow_address, token).map_err(Error::bank)?;
+		}
+		ctx.store_packet_result(self.send_packet.result.clone())
+			.map_err(Error::ics04_channel)?;
+		if let Some(order) = &self.order {
+			ctx.store_order(order.id.clone(), Some(order.clone()))?;
+		}
+
+		self.emit(output);
+		Ok(())
+	}
+
+	fn validate<T>(self, result: T) -> HandlerOutput<T> {
+		let mut output = HandlerOutputBuilder::new();
+		self.emit(&mut output);
+		let HandlerOutput { log, events, .. } = output.with_result(());
+		HandlerOutput { result, log, events }
+	}
+
+	fn emit(self, output: &mut HandlerOutputBuilder<()>) {
+		let HandlerOutput { log, events, .. } = self.send_packet;
+		output.merge_output(
+			HandlerOutput::builder().with_log(log).with_events(events).with_result(()),
+		);
+
+		if let Some(log) = self.log {
+			output.log(log);
+		}
+		output.emit(self.event.into());
+	}
+}
+
+/// Builds the packet carrying `data`, and checks that it can be sent.
+fn prepare_swap_packet<Ctx: SwapContext>(
+	ctx: &Ctx,
+	source_port: PortId,
+	source_channel: ChannelId,
+	data: SwapPacketData,
+	timeout_height: Height,
+	timeout_timestamp: Timestamp,
+) -> Result<HandlerOutput<PacketResult>, Error> {
+	let source_channel_end = ctx
+		.channel_end(&(source_port.clone(), source_channel))
+		.map_err(Error::ics04_channel)?;
+	let counterparty = source_channel_end.counterparty();
+	let destination_port = counterparty.port_id().clone();
+	let destination_channel = *counterparty.channel_id().ok_or_else(|| {
+		Error::invalid_swap(format!("channel {} has no counterparty channel", source_channel))
+	})?;
+
+	let sequence = ctx
+		.get_next_sequence_send(&(source_port.clone(), source_channel))
+		.map_err(Error::ics04_channel)?;
+
+	let packet = Packet {
+		sequence,
+		source_port,
+		source_channel,
+		destination_port,
+		destination_channel,
+		data: serde_json::to_vec(&data).expect("SwapPacketData's infallible Serialize impl failed"),
+		timeout_height,
+		timeout_timestamp,
+	};
+
+	let capability = channel_capability(ctx, &packet.source_port, packet.source_channel)?;
+	send_packet(ctx, &capability, packet).map_err(Error::ics04_channel)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		applications::atomic_swap::{
+			context::{test_util::*, SwapReader},
+			error::ErrorDetail,
+			msgs::test_util::{coin, get_dummy_msg_make_swap},
+		},
+		core::ics04_channel::context::ChannelReader,
+		signer::Signer,
+		test_utils::get_dummy_account_id,
+	};
+
+	fn escrow(ctx: &TestContext) -> Signer {
+		ctx.get_swap_escrow_address(&swap_port(), ChannelId::default()).unwrap()
+	}
+
+	/// Receives an order made on the counterparty chain, which only `taker` may take, and returns
+	/// its identifier.
+	fn receive_order(ctx: &mut TestContext, sequence: u64, expiration_timestamp: u64) -> OrderId {
+		let make = MakeSwapData {
+			sell_token: coin("uatom", 100),
+			buy_token: coin("ppica", 50),
+			maker_address: "maker".parse().unwrap(),
+			maker_receiving_address: "maker-on-taker".parse().unwrap(),
+			desired_taker: Some("taker".parse().unwrap()),
+			expiration_timestamp,
+		};
+		let packet = recv_packet(&SwapPacketData::MakeSwap(make), sequence);
+		assert!(recv(ctx, &packet));
+		OrderId::new(ctx, &swap_port(), ChannelId::new(1), sequence.into())
+	}
+
+	fn msg_take_swap(order_id: &OrderId, sell_token: PrefixedCoin, taker: &str) -> MsgTakeSwap {
+		MsgTakeSwap {
+			order_id: order_id.clone(),
+			sell_token,
+			taker_address: taker.parse().unwrap(),
+			taker_receiving_address: "taker-on-maker".parse().unwrap(),
+			timeout_height: Height::new(0, 100),
+			timeout_timestamp: Timestamp::none(),
+		}
+	}
+
+	fn msg_cancel_swap(order_id: &OrderId, maker: Signer) -> MsgCancelSwap {
+		MsgCancelSwap {
+			order_id: order_id.clone(),
+			maker_address: maker,
+			timeout_height: Height::new(0, 100),
+			timeout_timestamp: Timestamp::none(),
+		}
+	}
+
+	#[test]
+	fn test_make_swap() {
+		let mut ctx = swap_context();
+		let msg = get_dummy_msg_make_swap();
+
+		let zero = MsgMakeSwap { sell_token: coin("uatom", 0), ..msg.clone() };
+		let err = make_swap(&mut ctx, &mut HandlerOutputBuilder::new(), zero).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::InvalidSwap(_)));
+
+		let mut output = HandlerOutputBuilder::new();
+		let order_id = make_swap(&mut ctx, &mut output, msg.clone()).unwrap();
+		assert_eq!(order_id, OrderId::new(&ctx, &swap_port(), ChannelId::default(), 1.into()));
+
+		// the tokens sold are escrowed, and the order announced to the taker chain
+		let escrow = escrow(&ctx);
+		assert_eq!(
+			ctx.bank_transfers(),
+			&[(msg.maker_address.clone(), escrow, msg.sell_token.clone())]
+		);
+		let packet = sent_packet(&output);
+		assert_eq!(packet.sequence, 1.into());
+		match serde_json::from_slice::<SwapPacketData>(&packet.data).unwrap() {
+			SwapPacketData::MakeSwap(data) => assert_eq!(data.sell_token, msg.sell_token),
+			data => panic!("unexpected packet data {:?}", data),
+		}
+		assert_eq!(
+			ctx.get_next_sequence_send(&(swap_port(), ChannelId::default())).unwrap(),
+			2.into()
+		);
+
+		let order = ctx.get_order(&order_id).unwrap().unwrap();
+		assert_eq!(order.side, Side::Maker);
+		assert_eq!(order.status, Status::Initial);
+		assert_eq!(order.maker.maker_address, msg.maker_address);
+		assert_eq!(order.taker, None);
+	}
+
+	#[test]
+	fn test_take_swap() {
+		let mut ctx = swap_context();
+		let order_id = receive_order(&mut ctx, 1, 0);
+
+		let unknown = OrderId::from("unknown".to_string());
+		let msg = msg_take_swap(&unknown, coin("ppica", 50), "taker");
+		let err = take_swap(&mut ctx, &mut HandlerOutputBuilder::new(), msg).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::OrderNotFound(_)));
+
+		let msg = msg_take_swap(&order_id, coin("ppica", 49), "taker");
+		let err = take_swap(&mut ctx, &mut HandlerOutputBuilder::new(), msg).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::TokenMismatch(_)));
+
+		let msg = msg_take_swap(&order_id, coin("ppica", 50), "someone");
+		let err = take_swap(&mut ctx, &mut HandlerOutputBuilder::new(), msg).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::UnauthorizedTaker(_)));
+		assert!(ctx.bank_transfers().is_empty());
+
+		// the tokens sold by the taker are escrowed until the maker chain acknowledges the take
+		let msg = msg_take_swap(&order_id, coin("ppica", 50), "taker");
+		let mut output = HandlerOutputBuilder::new();
+		take_swap(&mut ctx, &mut output, msg.clone()).unwrap();
+		let escrow = escrow(&ctx);
+		assert_eq!(
+			ctx.bank_transfers(),
+			&[(msg.taker_address.clone(), escrow, msg.sell_token.clone())]
+		);
+		let packet = sent_packet(&output);
+		assert!(matches!(
+			serde_json::from_slice::<SwapPacketData>(&packet.data).unwrap(),
+			SwapPacketData::TakeSwap(TakeSwapData { order_id: id, .. }) if id == order_id
+		));
+
+		let order = ctx.get_order(&order_id).unwrap().unwrap();
+		assert_eq!(order.status, Status::Sync);
+		assert_eq!(order.taker.unwrap().taker_address, msg.taker_address);
+
+		// an order is taken once
+		let err = take_swap(&mut ctx, &mut HandlerOutputBuilder::new(), msg).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::InvalidOrderStatus(_)));
+	}
+
+	#[test]
+	fn test_take_expired_swap() {
+		let mut ctx = swap_context();
+		let order_id = receive_order(&mut ctx, 1, now().nanoseconds() - 1);
+
+		let msg = msg_take_swap(&order_id, coin("ppica", 50), "taker");
+		let err = take_swap(&mut ctx, &mut HandlerOutputBuilder::new(), msg).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::OrderExpired(_)));
+		assert!(ctx.bank_transfers().is_empty());
+	}
+
+	#[test]
+	fn test_take_own_swap() {
+		let mut ctx = swap_context();
+		let order_id =
+			make_swap(&mut ctx, &mut HandlerOutputBuilder::new(), get_dummy_msg_make_swap())
+				.unwrap();
+
+		let msg = msg_take_swap(&order_id, coin("ppica", 50), "taker");
+		let err = take_swap(&mut ctx, &mut HandlerOutputBuilder::new(), msg).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::WrongSide(_)));
+	}
+
+	#[test]
+	fn test_cancel_swap() {
+		let mut ctx = swap_context();
+		let mut output = HandlerOutputBuilder::new();
+		let order_id = make_swap(&mut ctx, &mut output, get_dummy_msg_make_swap()).unwrap();
+		let make_packet = sent_packet(&output);
+
+		// the order cannot be cancelled before the taker chain knows about it
+		let msg = msg_cancel_swap(&order_id, get_dummy_account_id());
+		let err = cancel_swap(&mut ctx, &mut HandlerOutputBuilder::new(), msg).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::InvalidOrderStatus(_)));
+
+		ack(&mut ctx, &make_packet, true).unwrap();
+
+		let msg = msg_cancel_swap(&order_id, "someone".parse().unwrap());
+		let err = cancel_swap(&mut ctx, &mut HandlerOutputBuilder::new(), msg).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::UnauthorizedMaker(_)));
+
+		// the escrowed tokens are only refunded once the taker chain acknowledges the cancellation
+		let mut output = HandlerOutputBuilder::new();
+		let msg = msg_cancel_swap(&order_id, get_dummy_account_id());
+		cancel_swap(&mut ctx, &mut output, msg).unwrap();
+		assert_eq!(ctx.bank_transfers().len(), 1);
+		let packet = sent_packet(&output);
+		assert!(matches!(
+			serde_json::from_slice::<SwapPacketData>(&packet.data).unwrap(),
+			SwapPacketData::CancelSwap(CancelSwapData { order_id: id, .. }) if id == order_id
+		));
+		assert_eq!(ctx.get_order(&order_id).unwrap().unwrap().status, Status::Sync);
+	}
+}
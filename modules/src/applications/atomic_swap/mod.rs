@@ -0,0 +1,29 @@
+//! ICS 100: Atomic Swap lets two parties on different chains exchange tokens without a trusted
+//! intermediary. The maker escrows the tokens it sells and announces the order to the counterparty
+//! chain, where a taker escrows the tokens the maker buys. The swap settles once the maker chain
+//! acknowledges the take, and escrowed tokens are refunded on cancellation or timeout.
+pub mod context;
+pub mod error;
+pub mod events;
+pub mod handler;
+pub mod module;
+pub mod msgs;
+pub mod order;
+pub mod packet;
+pub mod relay;
+
+use crate::{core::ics04_channel::Version, prelude::*};
+
+/// Module identifier for the ICS100 application.
+pub const MODULE_ID_STR: &str = "atomicswap";
+
+/// The port identifier that the ICS100 applications typically bind with.
+pub const PORT_ID_STR: &str = "swap";
+
+/// ICS100 application current version.
+pub const VERSION: &str = "ics100-1";
+
+/// Returns the version of ICS100 channels.
+pub fn version() -> Version {
+	Version::new(VERSION.to_string())
+}
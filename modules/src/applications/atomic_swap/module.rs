@@ -0,0 +1,318 @@
+use core::any::Any;
+
+use ibc_proto::google::protobuf::Any as ProtoAny;
+
+use crate::{
+	applications::atomic_swap::{
+		context, context::SwapContext, error::Error, handler, msgs::SwapMsg,
+	},
+	core::{
+		ics04_channel::{
+			channel::{Counterparty, Order},
+			error::Error as Ics04Error,
+			msgs::acknowledgement::Acknowledgement as GenericAcknowledgement,
+			packet::Packet,
+			Version,
+		},
+		ics24_host::identifier::{ChannelId, ConnectionId, PortId},
+		ics26_routing::context::{Module, ModuleOutputBuilder, OnRecvPacketAck, WriteFn},
+	},
+	handler::{HandlerOutput, HandlerOutputBuilder},
+	prelude::*,
+	signer::Signer,
+};
+
+/// The ICS100 application, as a [`Module`] owning the host context it swaps tokens with.
+///
+/// Orders are made, taken and cancelled with the messages of [`SwapMsg`], which the host hands to
+/// [`Self::deliver`].
+#[derive(Debug)]
+pub struct AtomicSwapModule<Ctx> {
+	ctx: Ctx,
+}
+
+impl<Ctx> AtomicSwapModule<Ctx> {
+	pub fn new(ctx: Ctx) -> Self {
+		Self { ctx }
+	}
+
+	pub fn ctx(&self) -> &Ctx {
+		&self.ctx
+	}
+
+	pub fn ctx_mut(&mut self) -> &mut Ctx {
+		&mut self.ctx
+	}
+}
+
+impl<Ctx: SwapContext> AtomicSwapModule<Ctx> {
+	/// Delivers a message of the ICS100 application, i.e. one of the messages of [`SwapMsg`].
+	/// Messages making an order return the identifier of the order in their log.
+	pub fn deliver(&mut self, message: ProtoAny) -> Result<HandlerOutput<()>, Error> {
+		let mut output = HandlerOutputBuilder::new();
+		match SwapMsg::try_from(message)? {
+			SwapMsg::MakeSwap(msg) => {
+				handler::make_swap(&mut self.ctx, &mut output, msg)?;
+			},
+			SwapMsg::TakeSwap(msg) => handler::take_swap(&mut self.ctx, &mut output, msg)?,
+			SwapMsg::CancelSwap(msg) => handler::cancel_swap(&mut self.ctx, &mut output, msg)?,
+		}
+		Ok(output.with_result(()))
+	}
+}
+
+fn app_error(e: impl ToString) -> Ics04Error {
+	Ics04Error::app_module(e.to_string())
+}
+
+impl<Ctx: 'static + SwapContext + Send + Sync> Module for AtomicSwapModule<Ctx> {
+	fn on_chan_open_init_validate(
+		&self,
+		order: Order,
+		connection_hops: &[ConnectionId],
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		counterparty: &Counterparty,
+		version: &Version,
+	) -> Result<(), Ics04Error> {
+		context::on_chan_open_init_validate(
+			&self.ctx,
+			order,
+			connection_hops,
+			port_id,
+			channel_id,
+			counterparty,
+			version,
+		)
+		.map_err(app_error)
+	}
+
+	fn on_chan_open_init(
+		&mut self,
+		output: &mut ModuleOutputBuilder,
+		order: Order,
+		connection_hops: &[ConnectionId],
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		counterparty: &Counterparty,
+		version: &Version,
+	) -> Result<(), Ics04Error> {
+		context::on_chan_open_init(
+			&mut self.ctx,
+			output,
+			order,
+			connection_hops,
+			port_id,
+			channel_id,
+			counterparty,
+			version,
+		)
+		.map_err(app_error)
+	}
+
+	fn on_chan_open_try_validate(
+		&self,
+		order: Order,
+		connection_hops: &[ConnectionId],
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		counterparty: &Counterparty,
+		version: &Version,
+		counterparty_version: &Version,
+	) -> Result<(), Ics04Error> {
+		context::on_chan_open_try_validate(
+			&self.ctx,
+			order,
+			connection_hops,
+			port_id,
+			channel_id,
+			counterparty,
+			version,
+			counterparty_version,
+		)
+		.map_err(app_error)
+	}
+
+	fn on_chan_open_try(
+		&mut self,
+		output: &mut ModuleOutputBuilder,
+		order: Order,
+		connection_hops: &[ConnectionId],
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		counterparty: &Counterparty,
+		version: &Version,
+		counterparty_version: &Version,
+	) -> Result<Version, Ics04Error> {
+		context::on_chan_open_try(
+			&mut self.ctx,
+			output,
+			order,
+			connection_hops,
+			port_id,
+			channel_id,
+			counterparty,
+			version,
+			counterparty_version,
+		)
+		.map_err(app_error)
+	}
+
+	fn on_chan_open_ack_validate(
+		&self,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		counterparty_version: &Version,
+	) -> Result<(), Ics04Error> {
+		context::on_chan_open_ack_validate(&self.ctx, port_id, channel_id, counterparty_version)
+			.map_err(app_error)
+	}
+
+	fn on_chan_open_ack(
+		&mut self,
+		output: &mut ModuleOutputBuilder,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		counterparty_version: &Version,
+	) -> Result<(), Ics04Error> {
+		context::on_chan_open_ack(&mut self.ctx, output, port_id, channel_id, counterparty_version)
+			.map_err(app_error)
+	}
+
+	fn on_chan_open_confirm(
+		&mut self,
+		output: &mut ModuleOutputBuilder,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+	) -> Result<(), Ics04Error> {
+		context::on_chan_open_confirm(&mut self.ctx, output, port_id, channel_id).map_err(app_error)
+	}
+
+	fn on_chan_close_init_validate(
+		&self,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+	) -> Result<(), Ics04Error> {
+		context::on_chan_close_init_validate(&self.ctx, port_id, channel_id).map_err(app_error)
+	}
+
+	fn on_chan_close_init(
+		&mut self,
+		output: &mut ModuleOutputBuilder,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+	) -> Result<(), Ics04Error> {
+		context::on_chan_close_init(&mut self.ctx, output, port_id, channel_id).map_err(app_error)
+	}
+
+	fn on_chan_close_confirm(
+		&mut self,
+		output: &mut ModuleOutputBuilder,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+	) -> Result<(), Ics04Error> {
+		context::on_chan_close_confirm(&mut self.ctx, output, port_id, channel_id)
+			.map_err(app_error)
+	}
+
+	fn on_recv_packet(
+		&self,
+		output: &mut ModuleOutputBuilder,
+		packet: &Packet,
+		relayer: &Signer,
+	) -> OnRecvPacketAck {
+		// the write functions of the context expect to be handed the context, not this module
+		let wrap = |write_fn: Box<WriteFn>| {
+			Box::new(move |module: &mut dyn Any| {
+				let module = module.downcast_mut::<Self>().unwrap();
+				write_fn(&mut module.ctx)
+			}) as Box<WriteFn>
+		};
+
+		match context::on_recv_packet(&self.ctx, output, packet, relayer) {
+			OnRecvPacketAck::Nil(write_fn) => OnRecvPacketAck::Nil(wrap(write_fn)),
+			OnRecvPacketAck::Successful(ack, write_fn) =>
+				OnRecvPacketAck::Successful(ack, wrap(write_fn)),
+			ack @ OnRecvPacketAck::Failed(_) => ack,
+		}
+	}
+
+	fn on_acknowledgement_packet_validate(
+		&self,
+		packet: &Packet,
+		acknowledgement: &GenericAcknowledgement,
+		relayer: &Signer,
+	) -> Result<(), Ics04Error> {
+		context::on_acknowledgement_packet_validate(&self.ctx, packet, acknowledgement, relayer)
+			.map_err(app_error)
+	}
+
+	fn on_acknowledgement_packet(
+		&mut self,
+		output: &mut ModuleOutputBuilder,
+		packet: &Packet,
+		acknowledgement: &GenericAcknowledgement,
+		relayer: &Signer,
+	) -> Result<(), Ics04Error> {
+		context::on_acknowledgement_packet(&mut self.ctx, output, packet, acknowledgement, relayer)
+			.map_err(app_error)
+	}
+
+	fn on_timeout_packet_validate(
+		&self,
+		packet: &Packet,
+		relayer: &Signer,
+	) -> Result<(), Ics04Error> {
+		context::on_timeout_packet_validate(&self.ctx, packet, relayer).map_err(app_error)
+	}
+
+	fn on_timeout_packet(
+		&mut self,
+		output: &mut ModuleOutputBuilder,
+		packet: &Packet,
+		relayer: &Signer,
+	) -> Result<(), Ics04Error> {
+		context::on_timeout_packet(&mut self.ctx, output, packet, relayer).map_err(app_error)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		applications::atomic_swap::{
+			context::{test_util::swap_context, SwapReader},
+			error::ErrorDetail,
+			msgs::{test_util::get_dummy_msg_make_swap, MsgCancelSwap},
+			order::{OrderId, Status},
+		},
+		timestamp::Timestamp,
+		Height,
+	};
+
+	#[test]
+	fn test_deliver_swap_msgs() {
+		let mut module = AtomicSwapModule::new(swap_context());
+
+		let msg = get_dummy_msg_make_swap();
+		let output = module.deliver(msg.clone().into()).unwrap();
+		assert!(!output.events.is_empty());
+		let order_id = OrderId::new(module.ctx(), &msg.source_port, msg.source_channel, 1.into());
+		let order = module.ctx().get_order(&order_id).unwrap().unwrap();
+		assert_eq!(order.status, Status::Initial);
+		assert_eq!(module.ctx().bank_transfers().len(), 1);
+
+		let cancel = MsgCancelSwap {
+			order_id,
+			maker_address: msg.maker_address,
+			timeout_height: Height::new(0, 100),
+			timeout_timestamp: Timestamp::none(),
+		};
+		let err = module.deliver(cancel.into()).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::InvalidOrderStatus(_)));
+
+		let unknown = ProtoAny { type_url: "/unknown".to_string(), value: vec![] };
+		let err = module.deliver(unknown).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::UnknownMsgType(_)));
+	}
+}
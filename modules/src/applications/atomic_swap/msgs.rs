@@ -0,0 +1,341 @@
+use ibc_proto::{
+	cosmos::base::v1beta1::Coin as RawCoin,
+	google::protobuf::Any,
+	ibc::applications::atomic_swap::v1::{
+		MsgCancelSwap as RawMsgCancelSwap, MsgMakeSwap as RawMsgMakeSwap,
+		MsgTakeSwap as RawMsgTakeSwap,
+	},
+};
+use tendermint_proto::Protobuf;
+
+use crate::{
+	applications::{
+		atomic_swap::{error::Error, order::OrderId},
+		transfer::PrefixedCoin,
+	},
+	core::ics24_host::identifier::{ChannelId, PortId},
+	prelude::*,
+	signer::Signer,
+	timestamp::Timestamp,
+	tx_msg::Msg,
+	Height,
+};
+
+pub const MAKE_SWAP_TYPE_URL: &str = "/ibc.applications.atomic_swap.v1.MsgMakeSwap";
+pub const TAKE_SWAP_TYPE_URL: &str = "/ibc.applications.atomic_swap.v1.MsgTakeSwap";
+pub const CANCEL_SWAP_TYPE_URL: &str = "/ibc.applications.atomic_swap.v1.MsgCancelSwap";
+
+/// Makes an order selling `sell_token` for `buy_token` on the chain at the other end of
+/// `source_channel`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MsgMakeSwap {
+	pub source_port: PortId,
+	pub source_channel: ChannelId,
+	pub sell_token: PrefixedCoin,
+	/// The tokens bought, as denominated on the counterparty chain.
+	pub buy_token: PrefixedCoin,
+	pub maker_address: Signer,
+	/// The account receiving `buy_token` on the counterparty chain.
+	pub maker_receiving_address: Signer,
+	/// The only account allowed to take the order, if any.
+	pub desired_taker: Option<Signer>,
+	/// Timestamp after which the order can no longer be taken. `Timestamp::none()` disables the
+	/// expiration.
+	pub expiration_timestamp: Timestamp,
+	/// Timeout height of the packet announcing the order.
+	pub timeout_height: Height,
+	/// Timeout timestamp of the packet announcing the order.
+	pub timeout_timestamp: Timestamp,
+}
+
+/// Takes an order received from the counterparty chain, selling `sell_token`, which must match
+/// the tokens bought by the maker.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MsgTakeSwap {
+	pub order_id: OrderId,
+	pub sell_token: PrefixedCoin,
+	pub taker_address: Signer,
+	/// The account receiving the tokens sold by the maker on the counterparty chain.
+	pub taker_receiving_address: Signer,
+	pub timeout_height: Height,
+	pub timeout_timestamp: Timestamp,
+}
+
+/// Cancels an order that has not been taken yet.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MsgCancelSwap {
+	pub order_id: OrderId,
+	pub maker_address: Signer,
+	pub timeout_height: Height,
+	pub timeout_timestamp: Timestamp,
+}
+
+/// The messages of the ICS100 application.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SwapMsg {
+	MakeSwap(MsgMakeSwap),
+	TakeSwap(MsgTakeSwap),
+	CancelSwap(MsgCancelSwap),
+}
+
+impl TryFrom<Any> for SwapMsg {
+	type Error = Error;
+
+	fn try_from(raw: Any) -> Result<Self, Self::Error> {
+		match raw.type_url.as_str() {
+			MAKE_SWAP_TYPE_URL => MsgMakeSwap::decode_vec(&raw.value)
+				.map(SwapMsg::MakeSwap)
+				.map_err(Error::decode_raw_msg),
+			TAKE_SWAP_TYPE_URL => MsgTakeSwap::decode_vec(&raw.value)
+				.map(SwapMsg::TakeSwap)
+				.map_err(Error::decode_raw_msg),
+			CANCEL_SWAP_TYPE_URL => MsgCancelSwap::decode_vec(&raw.value)
+				.map(SwapMsg::CancelSwap)
+				.map_err(Error::decode_raw_msg),
+			_ => Err(Error::unknown_msg_type(raw.type_url)),
+		}
+	}
+}
+
+fn timestamp(nanoseconds: u64) -> Result<Timestamp, Error> {
+	Timestamp::from_nanoseconds(nanoseconds).map_err(|_| Error::invalid_timestamp(nanoseconds))
+}
+
+fn token(raw: Option<RawCoin>) -> Result<PrefixedCoin, Error> {
+	raw.ok_or_else(|| Error::invalid_swap("missing token".to_string()))?
+		.try_into()
+		.map_err(Error::invalid_token)
+}
+
+impl Msg for MsgMakeSwap {
+	type ValidationError = Error;
+	type Raw = RawMsgMakeSwap;
+
+	fn route(&self) -> String {
+		crate::keys::ROUTER_KEY.to_string()
+	}
+
+	fn type_url(&self) -> String {
+		MAKE_SWAP_TYPE_URL.to_string()
+	}
+}
+
+impl TryFrom<RawMsgMakeSwap> for MsgMakeSwap {
+	type Error = Error;
+
+	fn try_from(raw_msg: RawMsgMakeSwap) -> Result<Self, Self::Error> {
+		let desired_taker = match raw_msg.desired_taker.as_str() {
+			"" => None,
+			taker => Some(taker.parse().map_err(Error::signer)?),
+		};
+		Ok(MsgMakeSwap {
+			source_port: raw_msg.source_port.parse().map_err(Error::invalid_identifier)?,
+			source_channel: raw_msg.source_channel.parse().map_err(Error::invalid_identifier)?,
+			sell_token: token(raw_msg.sell_token)?,
+			buy_token: token(raw_msg.buy_token)?,
+			maker_address: raw_msg.maker_address.parse().map_err(Error::signer)?,
+			maker_receiving_address: raw_msg
+				.maker_receiving_address
+				.parse()
+				.map_err(Error::signer)?,
+			desired_taker,
+			expiration_timestamp: timestamp(raw_msg.expiration_timestamp)?,
+			timeout_height: raw_msg.timeout_height.map(Into::into).unwrap_or_else(Height::zero),
+			timeout_timestamp: timestamp(raw_msg.timeout_timestamp)?,
+		})
+	}
+}
+
+impl From<MsgMakeSwap> for RawMsgMakeSwap {
+	fn from(domain_msg: MsgMakeSwap) -> Self {
+		RawMsgMakeSwap {
+			source_port: domain_msg.source_port.to_string(),
+			source_channel: domain_msg.source_channel.to_string(),
+			sell_token: Some(domain_msg.sell_token.into()),
+			buy_token: Some(domain_msg.buy_token.into()),
+			maker_address: domain_msg.maker_address.to_string(),
+			maker_receiving_address: domain_msg.maker_receiving_address.to_string(),
+			desired_taker: domain_msg
+				.desired_taker
+				.map(|taker| taker.to_string())
+				.unwrap_or_default(),
+			expiration_timestamp: domain_msg.expiration_timestamp.nanoseconds(),
+			timeout_height: Some(domain_msg.timeout_height.into()),
+			timeout_timestamp: domain_msg.timeout_timestamp.nanoseconds(),
+		}
+	}
+}
+
+impl Protobuf<RawMsgMakeSwap> for MsgMakeSwap {}
+
+impl From<MsgMakeSwap> for Any {
+	fn from(msg: MsgMakeSwap) -> Self {
+		Self { type_url: MAKE_SWAP_TYPE_URL.to_string(), value: msg.encode_vec() }
+	}
+}
+
+impl Msg for MsgTakeSwap {
+	type ValidationError = Error;
+	type Raw = RawMsgTakeSwap;
+
+	fn route(&self) -> String {
+		crate::keys::ROUTER_KEY.to_string()
+	}
+
+	fn type_url(&self) -> String {
+		TAKE_SWAP_TYPE_URL.to_string()
+	}
+}
+
+impl TryFrom<RawMsgTakeSwap> for MsgTakeSwap {
+	type Error = Error;
+
+	fn try_from(raw_msg: RawMsgTakeSwap) -> Result<Self, Self::Error> {
+		Ok(MsgTakeSwap {
+			order_id: raw_msg.order_id.into(),
+			sell_token: token(raw_msg.sell_token)?,
+			taker_address: raw_msg.taker_address.parse().map_err(Error::signer)?,
+			taker_receiving_address: raw_msg
+				.taker_receiving_address
+				.parse()
+				.map_err(Error::signer)?,
+			timeout_height: raw_msg.timeout_height.map(Into::into).unwrap_or_else(Height::zero),
+			timeout_timestamp: timestamp(raw_msg.timeout_timestamp)?,
+		})
+	}
+}
+
+impl From<MsgTakeSwap> for RawMsgTakeSwap {
+	fn from(domain_msg: MsgTakeSwap) -> Self {
+		RawMsgTakeSwap {
+			order_id: domain_msg.order_id.to_string(),
+			sell_token: Some(domain_msg.sell_token.into()),
+			taker_address: domain_msg.taker_address.to_string(),
+			taker_receiving_address: domain_msg.taker_receiving_address.to_string(),
+			timeout_height: Some(domain_msg.timeout_height.into()),
+			timeout_timestamp: domain_msg.timeout_timestamp.nanoseconds(),
+		}
+	}
+}
+
+impl Protobuf<RawMsgTakeSwap> for MsgTakeSwap {}
+
+impl From<MsgTakeSwap> for Any {
+	fn from(msg: MsgTakeSwap) -> Self {
+		Self { type_url: TAKE_SWAP_TYPE_URL.to_string(), value: msg.encode_vec() }
+	}
+}
+
+impl Msg for MsgCancelSwap {
+	type ValidationError = Error;
+	type Raw = RawMsgCancelSwap;
+
+	fn route(&self) -> String {
+		crate::keys::ROUTER_KEY.to_string()
+	}
+
+	fn type_url(&self) -> String {
+		CANCEL_SWAP_TYPE_URL.to_string()
+	}
+}
+
+impl TryFrom<RawMsgCancelSwap> for MsgCancelSwap {
+	type Error = Error;
+
+	fn try_from(raw_msg: RawMsgCancelSwap) -> Result<Self, Self::Error> {
+		Ok(MsgCancelSwap {
+			order_id: raw_msg.order_id.into(),
+			maker_address: raw_msg.maker_address.parse().map_err(Error::signer)?,
+			timeout_height: raw_msg.timeout_height.map(Into::into).unwrap_or_else(Height::zero),
+			timeout_timestamp: timestamp(raw_msg.timeout_timestamp)?,
+		})
+	}
+}
+
+impl From<MsgCancelSwap> for RawMsgCancelSwap {
+	fn from(domain_msg: MsgCancelSwap) -> Self {
+		RawMsgCancelSwap {
+			order_id: domain_msg.order_id.to_string(),
+			maker_address: domain_msg.maker_address.to_string(),
+			timeout_height: Some(domain_msg.timeout_height.into()),
+			timeout_timestamp: domain_msg.timeout_timestamp.nanoseconds(),
+		}
+	}
+}
+
+impl Protobuf<RawMsgCancelSwap> for MsgCancelSwap {}
+
+impl From<MsgCancelSwap> for Any {
+	fn from(msg: MsgCancelSwap) -> Self {
+		Self { type_url: CANCEL_SWAP_TYPE_URL.to_string(), value: msg.encode_vec() }
+	}
+}
+
+#[cfg(test)]
+pub mod test_util {
+	use super::*;
+	use crate::{applications::atomic_swap::PORT_ID_STR, test_utils::get_dummy_account_id};
+
+	pub fn coin(denom: &str, amount: u64) -> PrefixedCoin {
+		PrefixedCoin { denom: denom.parse().unwrap(), amount: amount.into() }
+	}
+
+	/// Returns a message making an order on `swap/channel-0`, selling 100 uatom for 50 ppica.
+	pub fn get_dummy_msg_make_swap() -> MsgMakeSwap {
+		MsgMakeSwap {
+			source_port: PORT_ID_STR.parse().unwrap(),
+			source_channel: ChannelId::default(),
+			sell_token: coin("uatom", 100),
+			buy_token: coin("ppica", 50),
+			maker_address: get_dummy_account_id(),
+			maker_receiving_address: "maker-on-taker".parse().unwrap(),
+			desired_taker: None,
+			expiration_timestamp: Timestamp::none(),
+			timeout_height: Height::new(0, 100),
+			timeout_timestamp: Timestamp::none(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{test_util::get_dummy_msg_make_swap, *};
+
+	#[test]
+	fn test_swap_msgs_roundtrip() {
+		let make = MsgMakeSwap {
+			desired_taker: Some("taker".parse().unwrap()),
+			expiration_timestamp: Timestamp::from_nanoseconds(1_000_000_000).unwrap(),
+			..get_dummy_msg_make_swap()
+		};
+		let take = MsgTakeSwap {
+			order_id: "AB12".to_string().into(),
+			sell_token: make.buy_token.clone(),
+			taker_address: "taker".parse().unwrap(),
+			taker_receiving_address: "taker-on-maker".parse().unwrap(),
+			timeout_height: Height::new(0, 100),
+			timeout_timestamp: Timestamp::none(),
+		};
+		let cancel = MsgCancelSwap {
+			order_id: "AB12".to_string().into(),
+			maker_address: make.maker_address.clone(),
+			timeout_height: Height::zero(),
+			timeout_timestamp: Timestamp::from_nanoseconds(1_000_000_000).unwrap(),
+		};
+
+		let msgs = [
+			(Any::from(make.clone()), SwapMsg::MakeSwap(make)),
+			(Any::from(take.clone()), SwapMsg::TakeSwap(take)),
+			(Any::from(cancel.clone()), SwapMsg::CancelSwap(cancel)),
+		];
+		for (any, msg) in msgs {
+			assert_eq!(SwapMsg::try_from(any).unwrap(), msg);
+		}
+
+		let mut raw = RawMsgMakeSwap::from(get_dummy_msg_make_swap());
+		raw.sell_token = None;
+		assert!(MsgMakeSwap::try_from(raw).is_err());
+		let any = Any { type_url: "/unknown".to_string(), value: vec![] };
+		assert!(SwapMsg::try_from(any).is_err());
+	}
+}
@@ -0,0 +1,109 @@
+use core::fmt;
+
+use serde::{Deserialize, Serialize};
+use subtle_encoding::hex;
+
+use crate::{
+	applications::atomic_swap::packet::{MakeSwapData, TakeSwapData},
+	core::{
+		ics04_channel::{context::ChannelReader, packet::Sequence},
+		ics24_host::identifier::{ChannelId, PortId},
+	},
+	prelude::*,
+	timestamp::{Expiry, Timestamp},
+};
+
+/// Identifier of a swap order, derived from the packet announcing it so that both chains agree on
+/// it.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct OrderId(String);
+
+impl OrderId {
+	/// Returns the identifier of the order made by the packet sent on `port_id` and `channel_id`
+	/// with `sequence`: the upper-case hex encoding of the hash of `{port}/{channel}/{sequence}`.
+	pub fn new(
+		ctx: &dyn ChannelReader,
+		port_id: &PortId,
+		channel_id: ChannelId,
+		sequence: Sequence,
+	) -> Self {
+		let hash = ctx.hash(format!("{}/{}/{}", port_id, channel_id, sequence).into_bytes());
+		Self(String::from_utf8(hex::encode_upper(hash)).expect("hex is valid UTF8"))
+	}
+
+	pub fn as_str(&self) -> &str {
+		&self.0
+	}
+}
+
+impl From<String> for OrderId {
+	fn from(id: String) -> Self {
+		Self(id)
+	}
+}
+
+impl fmt::Display for OrderId {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.0)
+	}
+}
+
+/// The lifecycle of a swap order.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Status {
+	/// Made on the maker chain, not yet acknowledged by the taker chain.
+	Initial,
+	/// Known to both chains, and open to takers.
+	Sync,
+	/// Cancelled by the maker, or refunded after the make failed.
+	Cancelled,
+	/// Settled on this chain.
+	Complete,
+}
+
+impl fmt::Display for Status {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Status::Initial => write!(f, "initial"),
+			Status::Sync => write!(f, "sync"),
+			Status::Cancelled => write!(f, "cancelled"),
+			Status::Complete => write!(f, "complete"),
+		}
+	}
+}
+
+/// The side of a swap a chain is on.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Side {
+	/// The order was made on this chain, which escrows the tokens sold by the maker.
+	Maker,
+	/// The order was received from the counterparty, this chain escrows the tokens sold by the
+	/// taker.
+	Taker,
+}
+
+/// A swap order, as stored by both chains.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Order {
+	pub id: OrderId,
+	/// The local end of the channel the order was made on.
+	pub port_id: PortId,
+	pub channel_id: ChannelId,
+	pub side: Side,
+	pub maker: MakeSwapData,
+	/// The take of the order, once sent or received.
+	pub taker: Option<TakeSwapData>,
+	pub status: Status,
+}
+
+impl Order {
+	/// Checks whether the order can no longer be taken on a chain at `host_timestamp`.
+	pub fn is_expired(&self, host_timestamp: &Timestamp) -> bool {
+		match Timestamp::from_nanoseconds(self.maker.expiration_timestamp) {
+			Ok(expiration) if expiration != Timestamp::none() =>
+				host_timestamp.check_expiry(&expiration) == Expiry::Expired,
+			_ => false,
+		}
+	}
+}
@@ -0,0 +1,96 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	applications::{atomic_swap::order::OrderId, transfer::PrefixedCoin},
+	prelude::*,
+	signer::Signer,
+};
+
+/// Announces a new order to the taker chain.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MakeSwapData {
+	/// The tokens sold by the maker, escrowed on the maker chain.
+	pub sell_token: PrefixedCoin,
+	/// The tokens bought by the maker, as denominated on the taker chain.
+	pub buy_token: PrefixedCoin,
+	/// The account of the maker on the maker chain.
+	pub maker_address: Signer,
+	/// The account receiving `buy_token` on the taker chain.
+	pub maker_receiving_address: Signer,
+	/// The only account allowed to take the order on the taker chain, if any.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub desired_taker: Option<Signer>,
+	/// Timestamp, in nanoseconds, after which the order can no longer be taken. Zero disables the
+	/// expiration.
+	#[serde(default)]
+	pub expiration_timestamp: u64,
+}
+
+/// Takes an order on behalf of a taker that escrowed the tokens bought by the maker.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TakeSwapData {
+	pub order_id: OrderId,
+	/// The tokens sold by the taker, escrowed on the taker chain.
+	pub sell_token: PrefixedCoin,
+	/// The account of the taker on the taker chain.
+	pub taker_address: Signer,
+	/// The account receiving the tokens sold by the maker on the maker chain.
+	pub taker_receiving_address: Signer,
+}
+
+/// Cancels an order that has not been taken yet.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CancelSwapData {
+	pub order_id: OrderId,
+	pub maker_address: Signer,
+}
+
+/// The data of ICS100 packets, JSON encoded as `{"type": "MAKE_SWAP", "data": {...}}`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SwapPacketData {
+	MakeSwap(MakeSwapData),
+	TakeSwap(TakeSwapData),
+	CancelSwap(CancelSwapData),
+}
+
+impl SwapPacketData {
+	pub fn message_type(&self) -> &'static str {
+		match self {
+			SwapPacketData::MakeSwap(_) => "make_swap",
+			SwapPacketData::TakeSwap(_) => "take_swap",
+			SwapPacketData::CancelSwap(_) => "cancel_swap",
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_swap_packet_data_serde() {
+		let data = SwapPacketData::MakeSwap(MakeSwapData {
+			sell_token: PrefixedCoin { denom: "uatom".parse().unwrap(), amount: 100u64.into() },
+			buy_token: PrefixedCoin { denom: "ppica".parse().unwrap(), amount: 50u64.into() },
+			maker_address: "alice".parse().unwrap(),
+			maker_receiving_address: "alice-on-taker".parse().unwrap(),
+			desired_taker: None,
+			expiration_timestamp: 0,
+		});
+
+		let json = serde_json::to_string(&data).unwrap();
+		assert!(json.starts_with(r#"{"type":"MAKE_SWAP","data":{"sell_token""#));
+		assert!(!json.contains("desired_taker"));
+		assert_eq!(serde_json::from_str::<SwapPacketData>(&json).unwrap(), data);
+
+		let cancel = r#"{"type":"CANCEL_SWAP","data":{"order_id":"AB12","maker_address":"alice"}}"#;
+		assert_eq!(
+			serde_json::from_str::<SwapPacketData>(cancel).unwrap(),
+			SwapPacketData::CancelSwap(CancelSwapData {
+				order_id: "AB12".to_string().into(),
+				maker_address: "alice".parse().unwrap(),
+			})
+		);
+	}
+}
@@ -0,0 +1,408 @@
+use crate::{
+	applications::{
+		atomic_swap::{
+			context::SwapContext,
+			error::Error,
+			order::{Order, OrderId, Side, Status},
+			packet::SwapPacketData,
+		},
+		transfer::PrefixedCoin,
+	},
+	core::{ics04_channel::packet::Packet, ics26_routing::context::WriteFn},
+	prelude::*,
+	signer::Signer,
+};
+
+/// Checks a swap packet received from the counterparty chain, and returns the function applying
+/// it to the order book.
+pub fn process_recv_packet<Ctx: 'static + SwapContext>(
+	ctx: &Ctx,
+	packet: &Packet,
+	data: &SwapPacketData,
+) -> Result<Box<WriteFn>, Error> {
+	match data {
+		SwapPacketData::MakeSwap(make) => {
+			let order_id =
+				OrderId::new(ctx, &packet.source_port, packet.source_channel, packet.sequence);
+			if ctx.get_order(&order_id)?.is_some() {
+				return Err(Error::order_already_exists(order_id))
+			}
+
+			let order = Order {
+				id: order_id,
+				port_id: packet.destination_port.clone(),
+				channel_id: packet.destination_channel,
+				side: Side::Taker,
+				maker: make.clone(),
+				taker: None,
+				status: Status::Sync,
+			};
+			Ok(Box::new(move |ctx| {
+				let ctx = ctx.downcast_mut::<Ctx>().unwrap();
+				ctx.store_order(order.id.clone(), Some(order)).map_err(|e| e.to_string())
+			}))
+		},
+		SwapPacketData::TakeSwap(take) => {
+			let mut order = get_order(ctx, &take.order_id, Side::Maker)?;
+			// the take may be relayed before the acknowledgement of the make, which it proves to
+			// have succeeded
+			let open = matches!(order.status, Status::Initial | Status::Sync);
+			if !open || order.taker.is_some() {
+				return Err(Error::invalid_order_status(order.id, order.status))
+			}
+			if order.is_expired(&ctx.host_timestamp()) {
+				return Err(Error::order_expired(order.id))
+			}
+			if let Some(desired_taker) = &order.maker.desired_taker {
+				if desired_taker != &take.taker_address {
+					return Err(Error::unauthorized_taker(order.id, take.taker_address.to_string()))
+				}
+			}
+			if take.sell_token != order.maker.buy_token {
+				return Err(Error::token_mismatch(
+					order.id,
+					order.maker.buy_token.to_string(),
+					take.sell_token.to_string(),
+				))
+			}
+
+			// the maker's tokens are released to the taker right away, the taker chain releases
+			// the taker's tokens to the maker on acknowledgement
+			let receiver = take
+				.taker_receiving_address
+				.clone()
+				.try_into()
+				.map_err(|_| Error::parse_account_failure())?;
+			let escrow_address = ctx.get_swap_escrow_address(&order.port_id, order.channel_id)?;
+
+			order.taker = Some(take.clone());
+			order.status = Status::Complete;
+			Ok(Box::new(move |ctx| {
+				let ctx = ctx.downcast_mut::<Ctx>().unwrap();
+				ctx.send_coins(&escrow_address, &receiver, &order.maker.sell_token)
+					.map_err(Error::bank)
+					.and_then(|_| ctx.store_order(order.id.clone(), Some(order)))
+					.map_err(|e| e.to_string())
+			}))
+		},
+		SwapPacketData::CancelSwap(cancel) => {
+			let mut order = get_order(ctx, &cancel.order_id, Side::Taker)?;
+			// an order whose take is in flight can no longer be cancelled
+			if order.status != Status::Sync || order.taker.is_some() {
+				return Err(Error::invalid_order_status(order.id, order.status))
+			}
+			if order.maker.maker_address != cancel.maker_address {
+				let sender = cancel.maker_address.to_string();
+				return Err(Error::unauthorized_maker(order.id, sender))
+			}
+
+			order.status = Status::Cancelled;
+			Ok(Box::new(move |ctx| {
+				let ctx = ctx.downcast_mut::<Ctx>().unwrap();
+				ctx.store_order(order.id.clone(), Some(order)).map_err(|e| e.to_string())
+			}))
+		},
+	}
+}
+
+/// Settles, or refunds if `success` is false, a swap packet acknowledged by the counterparty
+/// chain. Timed out packets are handled like failed ones.
+pub fn process_ack_packet(
+	ctx: &mut impl SwapContext,
+	packet: &Packet,
+	data: &SwapPacketData,
+	success: bool,
+) -> Result<(), Error> {
+	match data {
+		SwapPacketData::MakeSwap(make) => {
+			let order_id =
+				OrderId::new(&*ctx, &packet.source_port, packet.source_channel, packet.sequence);
+			let mut order = get_order(ctx, &order_id, Side::Maker)?;
+			if success {
+				// the order may already have been taken
+				if order.status == Status::Initial {
+					order.status = Status::Sync;
+				}
+			} else {
+				release(ctx, &order, &make.maker_address, &make.sell_token)?;
+				order.status = Status::Cancelled;
+			}
+			ctx.store_order(order_id, Some(order))
+		},
+		SwapPacketData::TakeSwap(take) => {
+			let mut order = get_order(ctx, &take.order_id, Side::Taker)?;
+			if success {
+				release(ctx, &order, &order.maker.maker_receiving_address, &take.sell_token)?;
+				order.status = Status::Complete;
+			} else {
+				release(ctx, &order, &take.taker_address, &take.sell_token)?;
+				order.taker = None;
+			}
+			ctx.store_order(order.id.clone(), Some(order))
+		},
+		SwapPacketData::CancelSwap(cancel) => {
+			if !success {
+				return Ok(())
+			}
+			let mut order = get_order(ctx, &cancel.order_id, Side::Maker)?;
+			release(ctx, &order, &order.maker.maker_address, &order.maker.sell_token)?;
+			order.status = Status::Cancelled;
+			ctx.store_order(order.id.clone(), Some(order))
+		},
+	}
+}
+
+fn get_order(ctx: &impl SwapContext, order_id: &OrderId, side: Side) -> Result<Order, Error> {
+	let order = ctx
+		.get_order(order_id)?
+		.ok_or_else(|| Error::order_not_found(order_id.clone()))?;
+	if order.side != side {
+		return Err(Error::wrong_side(order.id))
+	}
+	Ok(order)
+}
+
+/// Releases tokens escrowed for `order` to `receiver`.
+fn release(
+	ctx: &mut impl SwapContext,
+	order: &Order,
+	receiver: &Signer,
+	token: &PrefixedCoin,
+) -> Result<(), Error> {
+	let receiver = receiver.clone().try_into().map_err(|_| Error::parse_account_failure())?;
+	let escrow_address = ctx.get_swap_escrow_address(&order.port_id, order.channel_id)?;
+	ctx.send_coins(&escrow_address, &receiver, token).map_err(Error::bank)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		applications::atomic_swap::{
+			context::{on_timeout_packet, test_util::*, SwapReader},
+			handler::{make_swap, take_swap},
+			msgs::{
+				test_util::{coin, get_dummy_msg_make_swap},
+				MsgTakeSwap,
+			},
+			packet::{CancelSwapData, MakeSwapData, TakeSwapData},
+		},
+		core::{ics24_host::identifier::ChannelId, ics26_routing::context::ModuleOutputBuilder},
+		handler::HandlerOutputBuilder,
+		test_utils::get_dummy_account_id,
+		timestamp::Timestamp,
+		Height,
+	};
+
+	fn escrow(ctx: &TestContext) -> Signer {
+		ctx.get_swap_escrow_address(&swap_port(), ChannelId::default()).unwrap()
+	}
+
+	fn make_data() -> MakeSwapData {
+		MakeSwapData {
+			sell_token: coin("uatom", 100),
+			buy_token: coin("ppica", 50),
+			maker_address: "maker".parse().unwrap(),
+			maker_receiving_address: "maker-on-taker".parse().unwrap(),
+			desired_taker: None,
+			expiration_timestamp: 0,
+		}
+	}
+
+	fn take_data(order_id: &OrderId, sell_token: PrefixedCoin) -> TakeSwapData {
+		TakeSwapData {
+			order_id: order_id.clone(),
+			sell_token,
+			taker_address: "taker".parse().unwrap(),
+			taker_receiving_address: "taker-on-maker".parse().unwrap(),
+		}
+	}
+
+	/// Makes an order on this chain, and returns its identifier and the packet announcing it.
+	fn make_order(ctx: &mut TestContext) -> (OrderId, Packet) {
+		let mut output = HandlerOutputBuilder::new();
+		let order_id = make_swap(ctx, &mut output, get_dummy_msg_make_swap()).unwrap();
+		(order_id, sent_packet(&output))
+	}
+
+	/// Takes an order received on this chain, and returns the packet announcing the take.
+	fn take_order(ctx: &mut TestContext, order_id: &OrderId) -> Packet {
+		let data = take_data(order_id, coin("ppica", 50));
+		let msg = MsgTakeSwap {
+			order_id: data.order_id,
+			sell_token: data.sell_token,
+			taker_address: data.taker_address,
+			taker_receiving_address: data.taker_receiving_address,
+			timeout_height: Height::new(0, 100),
+			timeout_timestamp: Timestamp::none(),
+		};
+		let mut output = HandlerOutputBuilder::new();
+		take_swap(ctx, &mut output, msg).unwrap();
+		sent_packet(&output)
+	}
+
+	fn order(ctx: &TestContext, order_id: &OrderId) -> Order {
+		ctx.get_order(order_id).unwrap().unwrap()
+	}
+
+	#[test]
+	fn test_recv_make_swap() {
+		let mut ctx = swap_context();
+		let packet = recv_packet(&SwapPacketData::MakeSwap(make_data()), 1);
+		assert!(recv(&mut ctx, &packet));
+
+		let order_id = OrderId::new(&ctx, &swap_port(), ChannelId::new(1), 1.into());
+		let order = order(&ctx, &order_id);
+		assert_eq!(order.side, Side::Taker);
+		assert_eq!(order.status, Status::Sync);
+		assert_eq!((order.port_id, order.channel_id), (swap_port(), ChannelId::default()));
+		assert_eq!(order.maker, make_data());
+		assert!(ctx.bank_transfers().is_empty());
+
+		// the same order cannot be made twice
+		assert!(!recv(&mut ctx, &packet));
+	}
+
+	#[test]
+	fn test_recv_take_swap() {
+		let mut ctx = swap_context();
+		let (order_id, _) = make_order(&mut ctx);
+
+		let unknown = OrderId::from("unknown".to_string());
+		let data = SwapPacketData::TakeSwap(take_data(&unknown, coin("ppica", 50)));
+		assert!(!recv(&mut ctx, &recv_packet(&data, 1)));
+		let data = SwapPacketData::TakeSwap(take_data(&order_id, coin("ppica", 49)));
+		assert!(!recv(&mut ctx, &recv_packet(&data, 1)));
+
+		// the take proves the make succeeded, even if its acknowledgement is still in flight, and
+		// the maker's tokens are released to the taker
+		let take = take_data(&order_id, coin("ppica", 50));
+		assert!(recv(&mut ctx, &recv_packet(&SwapPacketData::TakeSwap(take.clone()), 1)));
+		let escrow = escrow(&ctx);
+		assert_eq!(
+			ctx.bank_transfers().last().unwrap(),
+			&(escrow, take.taker_receiving_address.clone(), coin("uatom", 100))
+		);
+		let order = order(&ctx, &order_id);
+		assert_eq!(order.status, Status::Complete);
+		assert_eq!(order.taker, Some(take.clone()));
+
+		assert!(!recv(&mut ctx, &recv_packet(&SwapPacketData::TakeSwap(take), 2)));
+	}
+
+	#[test]
+	fn test_recv_cancel_swap() {
+		let mut ctx = swap_context();
+		assert!(recv(&mut ctx, &recv_packet(&SwapPacketData::MakeSwap(make_data()), 1)));
+		let order_id = OrderId::new(&ctx, &swap_port(), ChannelId::new(1), 1.into());
+
+		let cancel = |maker: &str| {
+			let data = CancelSwapData {
+				order_id: order_id.clone(),
+				maker_address: maker.parse().unwrap(),
+			};
+			recv_packet(&SwapPacketData::CancelSwap(data), 2)
+		};
+		assert!(!recv(&mut ctx, &cancel("someone")));
+		assert!(recv(&mut ctx, &cancel("maker")));
+		assert_eq!(order(&ctx, &order_id).status, Status::Cancelled);
+		assert!(ctx.bank_transfers().is_empty());
+	}
+
+	#[test]
+	fn test_recv_cancel_taken_swap() {
+		let mut ctx = swap_context();
+		assert!(recv(&mut ctx, &recv_packet(&SwapPacketData::MakeSwap(make_data()), 1)));
+		let order_id = OrderId::new(&ctx, &swap_port(), ChannelId::new(1), 1.into());
+		take_order(&mut ctx, &order_id);
+
+		// the take in flight wins over the cancellation
+		let data =
+			CancelSwapData { order_id: order_id.clone(), maker_address: "maker".parse().unwrap() };
+		assert!(!recv(&mut ctx, &recv_packet(&SwapPacketData::CancelSwap(data), 2)));
+		assert_eq!(order(&ctx, &order_id).status, Status::Sync);
+	}
+
+	#[test]
+	fn test_ack_make_swap() {
+		let mut ctx = swap_context();
+		let maker = get_dummy_account_id();
+		let escrow = escrow(&ctx);
+
+		let (order_id, packet) = make_order(&mut ctx);
+		ack(&mut ctx, &packet, true).unwrap();
+		assert_eq!(order(&ctx, &order_id).status, Status::Sync);
+		assert_eq!(ctx.bank_transfers().len(), 1);
+
+		// a failed make refunds the maker
+		let (order_id, packet) = make_order(&mut ctx);
+		ack(&mut ctx, &packet, false).unwrap();
+		assert_eq!(order(&ctx, &order_id).status, Status::Cancelled);
+		assert_eq!(
+			ctx.bank_transfers().last().unwrap(),
+			&(escrow.clone(), maker.clone(), coin("uatom", 100))
+		);
+
+		// and so does a make that timed out
+		let (order_id, packet) = make_order(&mut ctx);
+		let relayer = get_dummy_account_id();
+		on_timeout_packet(&mut ctx, &mut ModuleOutputBuilder::new(), &packet, &relayer).unwrap();
+		assert_eq!(order(&ctx, &order_id).status, Status::Cancelled);
+		assert_eq!(ctx.bank_transfers().last().unwrap(), &(escrow, maker, coin("uatom", 100)));
+		assert_eq!(ctx.bank_transfers().len(), 5);
+	}
+
+	#[test]
+	fn test_ack_take_swap() {
+		let mut ctx = swap_context();
+		assert!(recv(&mut ctx, &recv_packet(&SwapPacketData::MakeSwap(make_data()), 1)));
+		let order_id = OrderId::new(&ctx, &swap_port(), ChannelId::new(1), 1.into());
+		let escrow = escrow(&ctx);
+
+		// a failed take refunds the taker, and reopens the order
+		let packet = take_order(&mut ctx, &order_id);
+		ack(&mut ctx, &packet, false).unwrap();
+		let taker = "taker".parse::<Signer>().unwrap();
+		assert_eq!(
+			ctx.bank_transfers().last().unwrap(),
+			&(escrow.clone(), taker, coin("ppica", 50))
+		);
+		let order = order(&ctx, &order_id);
+		assert_eq!((order.status, order.taker), (Status::Sync, None));
+
+		// a successful take releases the taker's tokens to the maker
+		let packet = take_order(&mut ctx, &order_id);
+		ack(&mut ctx, &packet, true).unwrap();
+		let receiver = make_data().maker_receiving_address;
+		assert_eq!(ctx.bank_transfers().last().unwrap(), &(escrow, receiver, coin("ppica", 50)));
+		assert_eq!(ctx.bank_transfers().len(), 4);
+		assert_eq!(order(&ctx, &order_id).status, Status::Complete);
+	}
+
+	#[test]
+	fn test_ack_cancel_swap() {
+		let mut ctx = swap_context();
+		let (order_id, packet) = make_order(&mut ctx);
+		ack(&mut ctx, &packet, true).unwrap();
+
+		let data =
+			CancelSwapData { order_id: order_id.clone(), maker_address: get_dummy_account_id() };
+		let packet = Packet {
+			data: serde_json::to_vec(&SwapPacketData::CancelSwap(data)).unwrap(),
+			..packet
+		};
+
+		// a failed cancellation leaves the order open
+		ack(&mut ctx, &packet, false).unwrap();
+		assert_eq!(order(&ctx, &order_id).status, Status::Sync);
+		assert_eq!(ctx.bank_transfers().len(), 1);
+
+		ack(&mut ctx, &packet, true).unwrap();
+		assert_eq!(order(&ctx, &order_id).status, Status::Cancelled);
+		let escrow = escrow(&ctx);
+		assert_eq!(
+			ctx.bank_transfers().last().unwrap(),
+			&(escrow, get_dummy_account_id(), coin("uatom", 100))
+		);
+	}
+}
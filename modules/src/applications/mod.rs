@@ -1,3 +1,5 @@
 //! Various packet encoding semantics which underpin the various types of transactions.
 
+pub mod atomic_swap;
+pub mod query;
 pub mod transfer;
@@ -0,0 +1,39 @@
+use crate::{
+	applications::query::{
+		error::Error,
+		query::{CrossChainQuery, QueryId, QueryResult},
+	},
+	core::ics26_routing::context::ReaderContext,
+	prelude::*,
+};
+
+pub trait QueryReader {
+	/// Returns the pending query with the given identifier, if any.
+	fn get_query(&self, query_id: QueryId) -> Result<Option<CrossChainQuery>, Error>;
+
+	/// Returns the sequence of the identifier of the next query.
+	fn get_next_query_sequence(&self) -> Result<u64, Error>;
+}
+
+pub trait QueryKeeper {
+	/// Stores a pending query, or deletes it once answered or timed out if `query` is `None`.
+	fn store_query(
+		&mut self,
+		query_id: QueryId,
+		query: Option<CrossChainQuery>,
+	) -> Result<(), Error>;
+
+	fn store_next_query_sequence(&mut self, sequence: u64) -> Result<(), Error>;
+
+	/// Delivers the result of `query` to the module that requested it, i.e. `query.requester`.
+	/// Returning an error fails the submission of the result.
+	fn on_query_result(
+		&mut self,
+		query: &CrossChainQuery,
+		result: QueryResult,
+	) -> Result<(), Error>;
+}
+
+/// Captures all the dependencies which the ICS31 module requires to be able to register queries
+/// and verify their results.
+pub trait QueryContext: QueryKeeper + QueryReader + ReaderContext {}
@@ -0,0 +1,73 @@
+use flex_error::{define_error, TraceError};
+use tendermint_proto::Error as TendermintProtoError;
+
+use crate::{
+	applications::query::query::QueryId,
+	core::{ics02_client::error as client_error, ics24_host::identifier::ClientId},
+	prelude::*,
+	proofs::ProofError,
+	signer::SignerError,
+	Height,
+};
+
+define_error! {
+	#[derive(Debug, PartialEq, Eq)]
+	Error {
+		Ics02Client
+			[ client_error::Error ]
+			| _ | { "ics02 client error" },
+
+		InvalidQuery
+			{ reason: String }
+			| e | { format_args!("invalid cross-chain query: {0}", e.reason) },
+
+		QueryNotFound
+			{ query_id: QueryId }
+			| e | { format_args!("no pending cross-chain query with id {0}", e.query_id) },
+
+		QueryNotTimedOut
+			{ query_id: QueryId }
+			| e | { format_args!("cross-chain query {0} has not timed out", e.query_id) },
+
+		FrozenClient
+			{ client_id: ClientId }
+			| e | { format_args!("client {0} is frozen", e.client_id) },
+
+		MissingConsensusState
+			{ client_id: ClientId, height: Height }
+			| e | {
+				format_args!("client {0} has no consensus state at query height {1}",
+					e.client_id, e.height)
+			},
+
+		VerificationFailed
+			{ query_id: QueryId }
+			[ client_error::Error ]
+			| e | {
+				format_args!("failed to verify the result of cross-chain query {0}", e.query_id)
+			},
+
+		CallbackFailed
+			{ query_id: QueryId, reason: String }
+			| e | {
+				format_args!("failed to deliver the result of cross-chain query {0}: {1}",
+					e.query_id, e.reason)
+			},
+
+		InvalidProof
+			[ ProofError ]
+			| _ | { "invalid proof" },
+
+		Signer
+			[ SignerError ]
+			| _ | { "failed to parse signer" },
+
+		DecodeRawMsg
+			[ TraceError<TendermintProtoError> ]
+			| _ | { "error decoding raw msg" },
+
+		UnknownMsgType
+			{ msg_type: String }
+			| e | { format_args!("unknown msg type: {0}", e.msg_type) },
+	}
+}
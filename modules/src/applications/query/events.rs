@@ -0,0 +1,65 @@
+use subtle_encoding::{Encoding, Hex};
+
+use crate::{
+	applications::query::{
+		query::{CrossChainQuery, QueryResult},
+		MODULE_ID_STR,
+	},
+	events::ModuleEvent,
+	prelude::*,
+};
+
+const EVENT_TYPE_QUERY: &str = "cross_chain_query";
+const EVENT_TYPE_QUERY_RESULT: &str = "cross_chain_query_result";
+
+/// Announces a new query to relayers. The prefix of the queried store is hex-encoded, as it need
+/// not be valid UTF-8.
+pub struct QueryEvent {
+	pub query: CrossChainQuery,
+}
+
+impl From<QueryEvent> for ModuleEvent {
+	fn from(ev: QueryEvent) -> Self {
+		let CrossChainQuery { id, requester, request } = ev.query;
+		Self {
+			kind: EVENT_TYPE_QUERY.to_string(),
+			module_name: MODULE_ID_STR.parse().expect("invalid ModuleId"),
+			attributes: vec![
+				("query_id", id).into(),
+				("requester", requester).into(),
+				("client_id", request.client_id).into(),
+				("prefix", Hex::upper_case().encode_to_string(request.prefix.as_bytes()).unwrap())
+					.into(),
+				("path", request.path).into(),
+				("query_height", request.query_height).into(),
+				("timeout_height", request.timeout_height).into(),
+				("timeout_timestamp", request.timeout_timestamp).into(),
+			],
+		}
+	}
+}
+
+/// Emitted when the result of a query is delivered to its requester.
+pub struct QueryResultEvent {
+	pub query: CrossChainQuery,
+	pub result: QueryResult,
+}
+
+impl From<QueryResultEvent> for ModuleEvent {
+	fn from(ev: QueryResultEvent) -> Self {
+		let QueryResultEvent { query, result } = ev;
+		let result = match result {
+			QueryResult::Success(_) => "success",
+			QueryResult::Timeout => "timeout",
+		};
+		Self {
+			kind: EVENT_TYPE_QUERY_RESULT.to_string(),
+			module_name: MODULE_ID_STR.parse().expect("invalid ModuleId"),
+			attributes: vec![
+				("query_id", query.id).into(),
+				("requester", query.requester).into(),
+				("result", result).into(),
+			],
+		}
+	}
+}
@@ -0,0 +1,364 @@
+use crate::{
+	applications::query::{
+		context::QueryContext,
+		error::Error,
+		events::{QueryEvent, QueryResultEvent},
+		msgs::MsgSubmitQueryResult,
+		query::{CrossChainQuery, QueryId, QueryRequest, QueryResult},
+	},
+	core::{
+		ics02_client::{
+			client_consensus::ConsensusState, client_def::ClientDef, client_state::ClientState,
+		},
+		ics26_routing::context::ModuleId,
+	},
+	events::ModuleEvent,
+	handler::HandlerOutputBuilder,
+	prelude::*,
+};
+
+/// Registers a query of the counterparty chain of `request.client_id` on behalf of the local
+/// module `requester`, and announces it to relayers.
+/// Returns the identifier of the query.
+pub fn send_query<Ctx: QueryContext>(
+	ctx: &mut Ctx,
+	output: &mut HandlerOutputBuilder<()>,
+	requester: ModuleId,
+	request: QueryRequest,
+) -> Result<QueryId, Error> {
+	if request.path.is_empty() {
+		return Err(Error::invalid_query("empty query path".to_string()))
+	}
+
+	let client_state = ctx.client_state(&request.client_id).map_err(Error::ics02_client)?;
+	if client_state.is_frozen() {
+		return Err(Error::frozen_client(request.client_id))
+	}
+
+	let sequence = ctx.get_next_query_sequence()?;
+	let query = CrossChainQuery { id: QueryId::new(sequence), requester, request };
+	if query.timed_out(&ctx.host_timestamp(), ctx.host_height()) {
+		return Err(Error::invalid_query(format!("query {} has already timed out", query.id)))
+	}
+
+	ctx.store_next_query_sequence(sequence + 1)?;
+	ctx.store_query(query.id, Some(query.clone()))?;
+
+	output.log(format!(
+		"cross-chain query {}: {} at {} on client {}",
+		query.id, query.request.path, query.request.query_height, query.request.client_id
+	));
+	let query_id = query.id;
+	output.emit(ModuleEvent::from(QueryEvent { query }).into());
+
+	Ok(query_id)
+}
+
+/// Verifies the submitted result of a pending query against the consensus state of the queried
+/// chain at the query height, and delivers it to the requesting module.
+/// Results submitted after the query timed out are discarded, and the requester is notified of
+/// the timeout instead.
+pub fn submit_query_result<Ctx: QueryContext>(
+	ctx: &mut Ctx,
+	output: &mut HandlerOutputBuilder<()>,
+	msg: MsgSubmitQueryResult,
+) -> Result<(), Error> {
+	let query = ctx
+		.get_query(msg.query_id)?
+		.ok_or_else(|| Error::query_not_found(msg.query_id))?;
+
+	if query.timed_out(&ctx.host_timestamp(), ctx.host_height()) {
+		return deliver_result(ctx, output, query, QueryResult::Timeout)
+	}
+
+	let request = &query.request;
+	let client_state = ctx.client_state(&request.client_id).map_err(Error::ics02_client)?;
+	if client_state.is_frozen() {
+		return Err(Error::frozen_client(request.client_id.clone()))
+	}
+
+	let consensus_state =
+		ctx.consensus_state(&request.client_id, request.query_height).map_err(|_| {
+			Error::missing_consensus_state(request.client_id.clone(), request.query_height)
+		})?;
+
+	client_state
+		.client_def()
+		.verify_membership(
+			&*ctx,
+			&request.client_id,
+			&client_state,
+			request.query_height,
+			&request.prefix,
+			&msg.proof,
+			consensus_state.root(),
+			request.path.clone(),
+			msg.result.clone(),
+		)
+		.map_err(|e| Error::verification_failed(query.id, e))?;
+
+	output.log(format!("cross-chain query {} answered by {}", query.id, msg.signer));
+	deliver_result(ctx, output, query, QueryResult::Success(msg.result))
+}
+
+/// Notifies the requester of a query that timed out before any result was submitted, and drops
+/// the query.
+pub fn timeout_query<Ctx: QueryContext>(
+	ctx: &mut Ctx,
+	output: &mut HandlerOutputBuilder<()>,
+	query_id: QueryId,
+) -> Result<(), Error> {
+	let query = ctx.get_query(query_id)?.ok_or_else(|| Error::query_not_found(query_id))?;
+	if !query.timed_out(&ctx.host_timestamp(), ctx.host_height()) {
+		return Err(Error::query_not_timed_out(query_id))
+	}
+	deliver_result(ctx, output, query, QueryResult::Timeout)
+}
+
+fn deliver_result<Ctx: QueryContext>(
+	ctx: &mut Ctx,
+	output: &mut HandlerOutputBuilder<()>,
+	query: CrossChainQuery,
+	result: QueryResult,
+) -> Result<(), Error> {
+	ctx.store_query(query.id, None)?;
+	ctx.on_query_result(&query, result.clone())?;
+	output.emit(ModuleEvent::from(QueryResultEvent { query, result }).into());
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use core::time::Duration;
+
+	use ibc_proto::google::protobuf::Any;
+
+	use super::*;
+	use crate::{
+		applications::query::{
+			context::{QueryKeeper, QueryReader},
+			error::ErrorDetail,
+			module::QueryModule,
+		},
+		core::{
+			ics04_channel::{
+				channel::{Counterparty, Order},
+				Version,
+			},
+			ics24_host::identifier::{ChannelId, ClientId, ConnectionId, PortId},
+			ics26_routing::context::{Module, ModuleOutputBuilder},
+		},
+		events::IbcEvent,
+		mock::context::{HostBlockType, MockClientTypes, MockContext},
+		test_utils::{get_dummy_account_id, DummyTransferModule},
+		timestamp::Timestamp,
+		Height,
+	};
+	use alloc::collections::BTreeMap;
+
+	/// The queries of the context of the tested module, and the results delivered to it.
+	#[derive(Clone, Debug, Default)]
+	struct Queries {
+		queries: BTreeMap<QueryId, CrossChainQuery>,
+		next_query_sequence: u64,
+		/// The results delivered so far, in order.
+		results: Vec<(QueryId, QueryResult)>,
+		/// Whether the delivery of every result fails.
+		failing: bool,
+	}
+
+	type TestModule = QueryModule<DummyTransferModule<MockClientTypes, Queries>>;
+
+	impl<C: HostBlockType> QueryReader for DummyTransferModule<C, Queries> {
+		fn get_query(&self, query_id: QueryId) -> Result<Option<CrossChainQuery>, Error> {
+			Ok(self.state().queries.get(&query_id).cloned())
+		}
+
+		fn get_next_query_sequence(&self) -> Result<u64, Error> {
+			Ok(self.state().next_query_sequence)
+		}
+	}
+
+	impl<C: HostBlockType> QueryKeeper for DummyTransferModule<C, Queries> {
+		fn store_query(
+			&mut self,
+			query_id: QueryId,
+			query: Option<CrossChainQuery>,
+		) -> Result<(), Error> {
+			let queries = &mut self.state_mut().queries;
+			match query {
+				Some(query) => queries.insert(query_id, query),
+				None => queries.remove(&query_id),
+			};
+			Ok(())
+		}
+
+		fn store_next_query_sequence(&mut self, sequence: u64) -> Result<(), Error> {
+			self.state_mut().next_query_sequence = sequence;
+			Ok(())
+		}
+
+		fn on_query_result(
+			&mut self,
+			query: &CrossChainQuery,
+			result: QueryResult,
+		) -> Result<(), Error> {
+			if self.state().failing {
+				return Err(Error::callback_failed(query.id, "failing".to_string()))
+			}
+			self.state_mut().results.push((query.id, result));
+			Ok(())
+		}
+	}
+
+	impl<C: HostBlockType> QueryContext for DummyTransferModule<C, Queries> {}
+
+	fn now() -> Timestamp {
+		Timestamp::from_nanoseconds(1_000_000_000).unwrap()
+	}
+
+	fn query_module() -> TestModule {
+		let ctx = MockContext::<MockClientTypes>::default()
+			.with_client(&ClientId::default(), Height::new(0, 10));
+		let mut inner = DummyTransferModule::with_state(ctx.ibc_store_share(), Queries::default());
+		inner.set_host_timestamp(now());
+		QueryModule::new(inner)
+	}
+
+	fn request(query_height: Height) -> QueryRequest {
+		QueryRequest {
+			client_id: ClientId::default(),
+			prefix: b"bank".to_vec().try_into().unwrap(),
+			path: "balances".to_string(),
+			query_height,
+			timeout_height: Height::zero(),
+			timeout_timestamp: (now() + Duration::from_secs(10)).unwrap(),
+		}
+	}
+
+	fn requester() -> ModuleId {
+		"contract".parse().unwrap()
+	}
+
+	fn submit_result(query_id: QueryId) -> Any {
+		MsgSubmitQueryResult {
+			query_id,
+			result: b"100uatom".to_vec(),
+			proof: vec![1].try_into().unwrap(),
+			signer: get_dummy_account_id(),
+		}
+		.into()
+	}
+
+	#[test]
+	fn test_send_query() {
+		let mut module = query_module();
+
+		let output = module.send_query(requester(), request(Height::new(0, 10))).unwrap();
+		assert_eq!(output.result, QueryId::new(0));
+		match &output.events[..] {
+			[IbcEvent::AppModule(event)] => {
+				let prefix = event.attributes.iter().find(|attr| attr.key == "prefix").unwrap();
+				assert_eq!(prefix.value, "62616E6B");
+			},
+			events => panic!("unexpected events {:?}", events),
+		}
+		let output = module.send_query(requester(), request(Height::new(0, 10))).unwrap();
+		assert_eq!(output.result, QueryId::new(1));
+
+		let empty_path = QueryRequest { path: String::new(), ..request(Height::new(0, 10)) };
+		let err = module.send_query(requester(), empty_path).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::InvalidQuery(_)));
+
+		let timed_out = QueryRequest { timeout_timestamp: now(), ..request(Height::new(0, 10)) };
+		let err = module.send_query(requester(), timed_out).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::InvalidQuery(_)));
+
+		let unknown_client = QueryRequest {
+			client_id: ClientId::new("00-uninitialized", 1).unwrap(),
+			..request(Height::new(0, 10))
+		};
+		let err = module.send_query(requester(), unknown_client).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::Ics02Client(_)));
+	}
+
+	#[test]
+	fn test_submit_query_result() {
+		let mut module = query_module();
+		let query_id = module.send_query(requester(), request(Height::new(0, 10))).unwrap().result;
+
+		let output = module.deliver(submit_result(query_id)).unwrap();
+		assert_eq!(output.events.len(), 1);
+		assert_eq!(
+			module.ctx().state().results,
+			&[(query_id, QueryResult::Success(b"100uatom".to_vec()))]
+		);
+		assert_eq!(module.ctx().get_query(query_id).unwrap(), None);
+
+		// the query is answered only once
+		let err = module.deliver(submit_result(query_id)).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::QueryNotFound(_)));
+
+		let mut any = submit_result(query_id);
+		any.type_url = "/ibc.applications.transfer.v1.MsgTransfer".to_string();
+		let err = module.deliver(any).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::UnknownMsgType(_)));
+	}
+
+	#[test]
+	fn test_submit_query_result_without_consensus_state() {
+		let mut module = query_module();
+		let query_id = module.send_query(requester(), request(Height::new(0, 5))).unwrap().result;
+
+		let err = module.deliver(submit_result(query_id)).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::MissingConsensusState(_)));
+		assert!(module.ctx().get_query(query_id).unwrap().is_some());
+		assert!(module.ctx().state().results.is_empty());
+	}
+
+	#[test]
+	fn test_query_timeout() {
+		let mut module = query_module();
+		let answered = module.send_query(requester(), request(Height::new(0, 10))).unwrap().result;
+		let dropped = module.send_query(requester(), request(Height::new(0, 10))).unwrap().result;
+
+		let err = module.timeout_query(dropped).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::QueryNotTimedOut(_)));
+
+		module.ctx_mut().set_host_timestamp((now() + Duration::from_secs(10)).unwrap());
+		// results submitted too late are discarded in favour of the timeout
+		module.deliver(submit_result(answered)).unwrap();
+		module.timeout_query(dropped).unwrap();
+		assert_eq!(
+			module.ctx().state().results,
+			&[(answered, QueryResult::Timeout), (dropped, QueryResult::Timeout)]
+		);
+		assert_eq!(module.ctx().get_query(dropped).unwrap(), None);
+	}
+
+	#[test]
+	fn test_failing_query_result_delivery() {
+		let mut module = query_module();
+		let query_id = module.send_query(requester(), request(Height::new(0, 10))).unwrap().result;
+
+		module.ctx_mut().state_mut().failing = true;
+		let err = module.deliver(submit_result(query_id)).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::CallbackFailed(_)));
+	}
+
+	#[test]
+	fn test_query_module_rejects_channels() {
+		let mut module = query_module();
+		let result = module.on_chan_open_try(
+			&mut ModuleOutputBuilder::new(),
+			Order::Unordered,
+			&[ConnectionId::default()],
+			&PortId::default(),
+			&ChannelId::default(),
+			&Counterparty::default(),
+			&Version::default(),
+			&Version::default(),
+		);
+		assert!(result.is_err());
+	}
+}
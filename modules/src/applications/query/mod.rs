@@ -0,0 +1,14 @@
+//! ICS 31: Cross-chain queries let local modules read the state of a counterparty chain at a given
+//! height. Queries are registered on the querying chain and announced through events, relayers
+//! submit the results along with a proof, which is verified against the consensus state stored by
+//! the light client of the counterparty before the result is delivered to the requesting module.
+pub mod context;
+pub mod error;
+pub mod events;
+pub mod handler;
+pub mod module;
+pub mod msgs;
+pub mod query;
+
+/// Module identifier for the ICS31 application.
+pub const MODULE_ID_STR: &str = "query";
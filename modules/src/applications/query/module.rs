@@ -0,0 +1,132 @@
+use ibc_proto::google::protobuf::Any;
+
+use crate::{
+	applications::query::{
+		context::QueryContext,
+		error::Error,
+		handler,
+		msgs::MsgSubmitQueryResult,
+		query::{QueryId, QueryRequest},
+	},
+	core::{
+		ics04_channel::{
+			channel::{Counterparty, Order},
+			error::Error as Ics04Error,
+			Version,
+		},
+		ics24_host::identifier::{ChannelId, ConnectionId, PortId},
+		ics26_routing::context::{Module, ModuleId, ModuleOutputBuilder},
+	},
+	handler::{HandlerOutput, HandlerOutputBuilder},
+	prelude::*,
+};
+
+/// The ICS31 application, as a [`Module`] owning the host context it registers queries in.
+///
+/// Queries are not relayed over channels, so the module rejects the opening of channels on its
+/// ports. Local modules send queries with [`Self::send_query`], and relayers answer them with a
+/// [`MsgSubmitQueryResult`], which the host hands to [`Self::deliver`].
+#[derive(Debug)]
+pub struct QueryModule<Ctx> {
+	ctx: Ctx,
+}
+
+impl<Ctx> QueryModule<Ctx> {
+	pub fn new(ctx: Ctx) -> Self {
+		Self { ctx }
+	}
+
+	pub fn ctx(&self) -> &Ctx {
+		&self.ctx
+	}
+
+	pub fn ctx_mut(&mut self) -> &mut Ctx {
+		&mut self.ctx
+	}
+}
+
+impl<Ctx: QueryContext> QueryModule<Ctx> {
+	/// Registers a query on behalf of the local module `requester`, see [`handler::send_query`].
+	pub fn send_query(
+		&mut self,
+		requester: ModuleId,
+		request: QueryRequest,
+	) -> Result<HandlerOutput<QueryId>, Error> {
+		let mut output = HandlerOutputBuilder::new();
+		let query_id = handler::send_query(&mut self.ctx, &mut output, requester, request)?;
+		Ok(output.with_result(query_id))
+	}
+
+	/// Delivers a message of the ICS31 application, i.e. a [`MsgSubmitQueryResult`].
+	pub fn deliver(&mut self, message: Any) -> Result<HandlerOutput<()>, Error> {
+		let msg = MsgSubmitQueryResult::try_from(message)?;
+		let mut output = HandlerOutputBuilder::new();
+		handler::submit_query_result(&mut self.ctx, &mut output, msg)?;
+		Ok(output.with_result(()))
+	}
+
+	/// Notifies the requester of a query that timed out, see [`handler::timeout_query`].
+	pub fn timeout_query(&mut self, query_id: QueryId) -> Result<HandlerOutput<()>, Error> {
+		let mut output = HandlerOutputBuilder::new();
+		handler::timeout_query(&mut self.ctx, &mut output, query_id)?;
+		Ok(output.with_result(()))
+	}
+}
+
+fn no_channels() -> Ics04Error {
+	Ics04Error::app_module("cross-chain queries are not relayed over channels".to_string())
+}
+
+impl<Ctx: 'static + QueryContext + Send + Sync> Module for QueryModule<Ctx> {
+	fn on_chan_open_init_validate(
+		&self,
+		_order: Order,
+		_connection_hops: &[ConnectionId],
+		_port_id: &PortId,
+		_channel_id: &ChannelId,
+		_counterparty: &Counterparty,
+		_version: &Version,
+	) -> Result<(), Ics04Error> {
+		Err(no_channels())
+	}
+
+	fn on_chan_open_init(
+		&mut self,
+		_output: &mut ModuleOutputBuilder,
+		_order: Order,
+		_connection_hops: &[ConnectionId],
+		_port_id: &PortId,
+		_channel_id: &ChannelId,
+		_counterparty: &Counterparty,
+		_version: &Version,
+	) -> Result<(), Ics04Error> {
+		Err(no_channels())
+	}
+
+	fn on_chan_open_try_validate(
+		&self,
+		_order: Order,
+		_connection_hops: &[ConnectionId],
+		_port_id: &PortId,
+		_channel_id: &ChannelId,
+		_counterparty: &Counterparty,
+		_version: &Version,
+		_counterparty_version: &Version,
+	) -> Result<(), Ics04Error> {
+		Err(no_channels())
+	}
+
+	fn on_chan_open_try(
+		&mut self,
+		_output: &mut ModuleOutputBuilder,
+		_order: Order,
+		_connection_hops: &[ConnectionId],
+		_port_id: &PortId,
+		_channel_id: &ChannelId,
+		_counterparty: &Counterparty,
+		_version: &Version,
+		_counterparty_version: &Version,
+	) -> Result<Version, Ics04Error> {
+		Err(no_channels())
+	}
+}
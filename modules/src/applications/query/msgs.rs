@@ -0,0 +1,105 @@
+use ibc_proto::{
+	google::protobuf::Any,
+	ibc::applications::query::v1::MsgSubmitQueryResult as RawMsgSubmitQueryResult,
+};
+use tendermint_proto::Protobuf;
+
+use crate::{
+	applications::query::{error::Error, query::QueryId},
+	core::ics23_commitment::commitment::CommitmentProofBytes,
+	prelude::*,
+	signer::Signer,
+	tx_msg::Msg,
+};
+
+pub const TYPE_URL: &str = "/ibc.applications.query.v1.MsgSubmitQueryResult";
+
+/// Submission of the result of a pending cross-chain query, typically by a relayer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MsgSubmitQueryResult {
+	pub query_id: QueryId,
+	/// The value stored under the queried path at the query height.
+	pub result: Vec<u8>,
+	/// Proof that `result` is stored under the queried path, at the query height.
+	pub proof: CommitmentProofBytes,
+	pub signer: Signer,
+}
+
+impl Msg for MsgSubmitQueryResult {
+	type ValidationError = Error;
+	type Raw = RawMsgSubmitQueryResult;
+
+	fn route(&self) -> String {
+		crate::keys::ROUTER_KEY.to_string()
+	}
+
+	fn type_url(&self) -> String {
+		TYPE_URL.to_string()
+	}
+}
+
+impl TryFrom<RawMsgSubmitQueryResult> for MsgSubmitQueryResult {
+	type Error = Error;
+
+	fn try_from(raw_msg: RawMsgSubmitQueryResult) -> Result<Self, Self::Error> {
+		Ok(MsgSubmitQueryResult {
+			query_id: raw_msg.query_id.parse()?,
+			result: raw_msg.result,
+			proof: raw_msg.proof.try_into().map_err(Error::invalid_proof)?,
+			signer: raw_msg.signer.parse().map_err(Error::signer)?,
+		})
+	}
+}
+
+impl From<MsgSubmitQueryResult> for RawMsgSubmitQueryResult {
+	fn from(domain_msg: MsgSubmitQueryResult) -> Self {
+		RawMsgSubmitQueryResult {
+			query_id: domain_msg.query_id.to_string(),
+			result: domain_msg.result,
+			proof: domain_msg.proof.into(),
+			signer: domain_msg.signer.to_string(),
+		}
+	}
+}
+
+impl Protobuf<RawMsgSubmitQueryResult> for MsgSubmitQueryResult {}
+
+impl TryFrom<Any> for MsgSubmitQueryResult {
+	type Error = Error;
+
+	fn try_from(raw: Any) -> Result<Self, Self::Error> {
+		match raw.type_url.as_str() {
+			TYPE_URL => MsgSubmitQueryResult::decode_vec(&raw.value).map_err(Error::decode_raw_msg),
+			_ => Err(Error::unknown_msg_type(raw.type_url)),
+		}
+	}
+}
+
+impl From<MsgSubmitQueryResult> for Any {
+	fn from(msg: MsgSubmitQueryResult) -> Self {
+		Self { type_url: TYPE_URL.to_string(), value: msg.encode_vec() }
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::get_dummy_account_id;
+
+	#[test]
+	fn test_msg_submit_query_result_roundtrip() {
+		let msg = MsgSubmitQueryResult {
+			query_id: QueryId::new(7),
+			result: vec![0xff, 0x00],
+			proof: vec![1, 2, 3].try_into().unwrap(),
+			signer: get_dummy_account_id(),
+		};
+		let any = Any::from(msg.clone());
+		assert_eq!(any.type_url, TYPE_URL);
+		assert_eq!(MsgSubmitQueryResult::try_from(any).unwrap(), msg);
+
+		let mut raw = RawMsgSubmitQueryResult::from(msg);
+		raw.proof.clear();
+		assert!(MsgSubmitQueryResult::try_from(raw).is_err());
+	}
+}
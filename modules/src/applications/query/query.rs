@@ -0,0 +1,144 @@
+use core::{fmt, str::FromStr};
+
+use crate::{
+	applications::query::error::Error,
+	core::{
+		ics23_commitment::commitment::CommitmentPrefix, ics24_host::identifier::ClientId,
+		ics26_routing::context::ModuleId,
+	},
+	prelude::*,
+	timestamp::{Expiry, Timestamp},
+	Height,
+};
+
+const QUERY_ID_PREFIX: &str = "query";
+
+/// Identifier of a cross-chain query, of the form `query-{sequence}`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct QueryId(u64);
+
+impl QueryId {
+	pub fn new(sequence: u64) -> Self {
+		Self(sequence)
+	}
+
+	pub fn sequence(&self) -> u64 {
+		self.0
+	}
+}
+
+impl fmt::Display for QueryId {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}-{}", QUERY_ID_PREFIX, self.0)
+	}
+}
+
+impl FromStr for QueryId {
+	type Err = Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		s.strip_prefix(QUERY_ID_PREFIX)
+			.and_then(|s| s.strip_prefix('-'))
+			.and_then(|sequence| sequence.parse().ok())
+			.map(Self)
+			.ok_or_else(|| Error::invalid_query(format!("invalid query identifier {}", s)))
+	}
+}
+
+/// A query of the counterparty chain of a client, as requested by a local module.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QueryRequest {
+	/// The client tracking the queried chain, whose consensus states the result is verified
+	/// against.
+	pub client_id: ClientId,
+	/// The prefix of the queried store, e.g. the store key of a cosmos-sdk module.
+	pub prefix: CommitmentPrefix,
+	/// The path of the queried value in the store.
+	pub path: String,
+	/// The height of the queried chain at which the value is read.
+	pub query_height: Height,
+	/// Height of the local chain after which the query can no longer be answered. Zero disables
+	/// the height timeout.
+	pub timeout_height: Height,
+	/// Timestamp of the local chain after which the query can no longer be answered.
+	/// `Timestamp::none()` disables the timestamp timeout.
+	pub timeout_timestamp: Timestamp,
+}
+
+/// A pending cross-chain query.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CrossChainQuery {
+	pub id: QueryId,
+	/// The module the result is delivered to.
+	pub requester: ModuleId,
+	pub request: QueryRequest,
+}
+
+impl CrossChainQuery {
+	/// Checks whether the query can no longer be answered on a chain at `host_height` and
+	/// `host_timestamp`.
+	pub fn timed_out(&self, host_timestamp: &Timestamp, host_height: Height) -> bool {
+		let request = &self.request;
+		(request.timeout_height != Height::zero() && request.timeout_height <= host_height) ||
+			(request.timeout_timestamp != Timestamp::none() &&
+				host_timestamp.check_expiry(&request.timeout_timestamp) == Expiry::Expired)
+	}
+}
+
+/// The outcome of a cross-chain query, delivered to the requesting module.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum QueryResult {
+	/// The value stored under the queried path, verified against the consensus state of the
+	/// queried chain at the query height.
+	Success(Vec<u8>),
+	/// No result was submitted before the query timed out.
+	Timeout,
+}
+
+impl QueryResult {
+	pub fn is_success(&self) -> bool {
+		matches!(self, QueryResult::Success(_))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use core::time::Duration;
+
+	use super::*;
+
+	#[test]
+	fn test_query_id() {
+		let id: QueryId = "query-42".parse().unwrap();
+		assert_eq!(id, QueryId::new(42));
+		assert_eq!(id.to_string(), "query-42");
+		assert!("query42".parse::<QueryId>().is_err());
+		assert!("channel-42".parse::<QueryId>().is_err());
+	}
+
+	#[test]
+	fn test_query_timeout() {
+		let now = Timestamp::from_nanoseconds(1_000_000_000).unwrap();
+		let query = |timeout_height, timeout_timestamp| CrossChainQuery {
+			id: QueryId::new(0),
+			requester: "module".parse().unwrap(),
+			request: QueryRequest {
+				client_id: ClientId::default(),
+				prefix: b"bank".to_vec().try_into().unwrap(),
+				path: "balances".to_string(),
+				query_height: Height::new(0, 10),
+				timeout_height,
+				timeout_timestamp,
+			},
+		};
+
+		let host_height = Height::new(0, 100);
+		assert!(!query(Height::zero(), Timestamp::none()).timed_out(&now, host_height));
+		assert!(query(Height::new(0, 100), Timestamp::none()).timed_out(&now, host_height));
+		assert!(!query(Height::new(0, 101), Timestamp::none()).timed_out(&now, host_height));
+
+		let later = (now + Duration::from_secs(1)).unwrap();
+		assert!(!query(Height::zero(), later).timed_out(&now, host_height));
+		assert!(query(Height::zero(), now).timed_out(&later, host_height));
+	}
+}
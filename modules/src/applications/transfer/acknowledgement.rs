@@ -1,5 +1,11 @@
 use super::error::Error;
-use crate::{core::ics26_routing::context::Acknowledgement as AckTrait, prelude::*};
+use crate::{
+	core::{
+		ics04_channel::acknowledgement::error_message,
+		ics26_routing::context::Acknowledgement as AckTrait,
+	},
+	prelude::*,
+};
 use core::fmt::{Display, Formatter};
 
 use serde::{Deserialize, Deserializer};
@@ -22,8 +28,10 @@ impl Acknowledgement {
 		Self::Success(ACK_SUCCESS_B64.to_vec())
 	}
 
+	/// Builds an error acknowledgement from `err`, keeping only the first line of its message, see
+	/// [`error_message`].
 	pub fn from_error(err: Error) -> Self {
-		Self::Error(format!("{}: {}", ACK_ERR_STR, err))
+		Self::Error(format!("{}: {}", ACK_ERR_STR, error_message(err)))
 	}
 }
 
@@ -57,4 +65,8 @@ impl Display for Acknowledgement {
 	}
 }
 
-impl AckTrait for Acknowledgement {}
+impl AckTrait for Acknowledgement {
+	fn is_successful(&self) -> bool {
+		matches!(self, Acknowledgement::Success(_))
+	}
+}
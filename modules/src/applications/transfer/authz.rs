@@ -0,0 +1,496 @@
+//! Authz-style grants allowing an account, the grantee, to send ICS20 transfers on behalf of
+//! another one, the granter, within per-channel spend limits.
+
+use ibc_proto::{
+	cosmos::base::v1beta1::Coin as RawCoin,
+	google::protobuf::Any,
+	ibc::applications::transfer::v1::{
+		Allocation as RawAllocation, TransferAuthorization as RawTransferAuthorization,
+	},
+};
+use tendermint_proto::Protobuf;
+
+use crate::{
+	applications::transfer::{
+		context::{Ics20Context, Ics20Reader},
+		error::Error as Ics20Error,
+		msgs::transfer::MsgTransfer,
+		relay::send_transfer::{
+			resolve_coin, send_transfer as inner_send_transfer,
+			validate_transfer as inner_validate_transfer,
+		},
+		Amount, Coin, PrefixedCoin, PrefixedDenom,
+	},
+	core::{
+		ics04_channel::packet::Packet,
+		ics24_host::identifier::{ChannelId, PortId},
+	},
+	handler::{HandlerOutput, HandlerOutputBuilder},
+	prelude::*,
+	signer::Signer,
+};
+
+pub const TYPE_URL: &str = "/ibc.applications.transfer.v1.TransferAuthorization";
+
+/// The tokens a grantee may send through a given channel.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Allocation {
+	pub source_port: PortId,
+	pub source_channel: ChannelId,
+	/// The remaining amount of each denomination that may be sent. As in ibc-go, denominations
+	/// are given either with their full trace or as `ibc/{hash}`.
+	pub spend_limit: Vec<Coin<String>>,
+	/// The receivers allowed on the counterparty chain, any receiver is allowed if empty.
+	pub allow_list: Vec<String>,
+}
+
+impl Allocation {
+	fn validate_basic(&self) -> Result<(), Ics20Error> {
+		if self.spend_limit.is_empty() {
+			return Err(Ics20Error::invalid_transfer_authorization(format!(
+				"empty spend limit for {}/{}",
+				self.source_port, self.source_channel
+			)))
+		}
+		for (i, coin) in self.spend_limit.iter().enumerate() {
+			if coin.amount == Amount::from(0u64) {
+				return Err(Ics20Error::invalid_transfer_authorization(format!(
+					"zero spend limit for {}",
+					coin.denom
+				)))
+			}
+			if self.spend_limit[..i].iter().any(|other| other.denom == coin.denom) {
+				return Err(Ics20Error::invalid_transfer_authorization(format!(
+					"duplicate spend limit for {}",
+					coin.denom
+				)))
+			}
+		}
+		for (i, receiver) in self.allow_list.iter().enumerate() {
+			if self.allow_list[..i].contains(receiver) {
+				return Err(Ics20Error::invalid_transfer_authorization(format!(
+					"duplicate receiver {} in allow list",
+					receiver
+				)))
+			}
+		}
+		Ok(())
+	}
+}
+
+impl TryFrom<RawAllocation> for Allocation {
+	type Error = Ics20Error;
+
+	fn try_from(raw: RawAllocation) -> Result<Self, Self::Error> {
+		Ok(Self {
+			source_port: raw
+				.source_port
+				.parse()
+				.map_err(|e| Ics20Error::invalid_port_id(raw.source_port.clone(), e))?,
+			source_channel: raw
+				.source_channel
+				.parse()
+				.map_err(|e| Ics20Error::invalid_channel_id(raw.source_channel.clone(), e))?,
+			spend_limit: raw
+				.spend_limit
+				.into_iter()
+				.map(|coin| Ok(Coin { amount: coin.amount.parse()?, denom: coin.denom }))
+				.collect::<Result<_, Ics20Error>>()?,
+			allow_list: raw.allow_list,
+		})
+	}
+}
+
+impl From<Allocation> for RawAllocation {
+	fn from(allocation: Allocation) -> Self {
+		Self {
+			source_port: allocation.source_port.to_string(),
+			source_channel: allocation.source_channel.to_string(),
+			spend_limit: allocation.spend_limit.into_iter().map(RawCoin::from).collect(),
+			allow_list: allocation.allow_list,
+		}
+	}
+}
+
+/// Allows a grantee to send transfers on behalf of a granter through the channels of its
+/// allocations.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TransferAuthorization {
+	pub allocations: Vec<Allocation>,
+}
+
+impl TransferAuthorization {
+	pub fn validate_basic(&self) -> Result<(), Ics20Error> {
+		if self.allocations.is_empty() {
+			return Err(Ics20Error::invalid_transfer_authorization("no allocation".to_string()))
+		}
+		for (i, allocation) in self.allocations.iter().enumerate() {
+			allocation.validate_basic()?;
+			let is_duplicate = self.allocations[..i].iter().any(|other| {
+				other.source_port == allocation.source_port &&
+					other.source_channel == allocation.source_channel
+			});
+			if is_duplicate {
+				return Err(Ics20Error::invalid_transfer_authorization(format!(
+					"duplicate allocation for {}/{}",
+					allocation.source_port, allocation.source_channel
+				)))
+			}
+		}
+		Ok(())
+	}
+
+	/// Checks `msg`, whose token has been resolved to its full denomination, against this grant.
+	/// Denominations are compared in their `ibc/{hash}` form, whichever form the spend limits use.
+	/// Returns the authorization left once the transfer is made, or `None` if all its spend
+	/// limits are exhausted.
+	pub fn accept(
+		&self,
+		ctx: &impl Ics20Reader,
+		msg: &MsgTransfer<PrefixedCoin>,
+	) -> Result<Option<Self>, Ics20Error> {
+		let mut allocations = self.allocations.clone();
+		let index = allocations
+			.iter()
+			.position(|allocation| {
+				allocation.source_port == msg.source_port &&
+					allocation.source_channel == msg.source_channel
+			})
+			.ok_or_else(|| {
+				Ics20Error::unauthorized_transfer(format!(
+					"no allocation for {}/{}",
+					msg.source_port, msg.source_channel
+				))
+			})?;
+		let allocation = &mut allocations[index];
+
+		let receiver = msg.receiver.as_ref();
+		if !allocation.allow_list.is_empty() &&
+			!allocation.allow_list.iter().any(|allowed| allowed == receiver)
+		{
+			return Err(Ics20Error::unauthorized_transfer(format!(
+				"receiver {} is not in the allow list",
+				receiver
+			)))
+		}
+
+		let denom = &msg.token.denom;
+		let ibc_denom = ctx.ibc_denom(denom);
+		// denominations that do not parse as a trace, i.e. `ibc/{hash}` ones, are already hashed
+		let spend_limit_denom = |denom: &str| match denom.parse::<PrefixedDenom>() {
+			Ok(denom) => ctx.ibc_denom(&denom),
+			Err(_) => denom.to_string(),
+		};
+		let coin_index = allocation
+			.spend_limit
+			.iter()
+			.position(|coin| spend_limit_denom(&coin.denom) == ibc_denom)
+			.ok_or_else(|| Ics20Error::spend_limit_exceeded(denom.to_string()))?;
+		let coin = &mut allocation.spend_limit[coin_index];
+		coin.amount = coin
+			.amount
+			.checked_sub(msg.token.amount)
+			.ok_or_else(|| Ics20Error::spend_limit_exceeded(denom.to_string()))?;
+
+		// exhausted limits are removed, along with the allocations and grants left empty
+		if coin.amount == Amount::from(0u64) {
+			allocation.spend_limit.remove(coin_index);
+		}
+		if allocation.spend_limit.is_empty() {
+			allocations.remove(index);
+		}
+		if allocations.is_empty() {
+			return Ok(None)
+		}
+		Ok(Some(Self { allocations }))
+	}
+}
+
+impl TryFrom<RawTransferAuthorization> for TransferAuthorization {
+	type Error = Ics20Error;
+
+	fn try_from(raw: RawTransferAuthorization) -> Result<Self, Self::Error> {
+		Ok(Self {
+			allocations: raw
+				.allocations
+				.into_iter()
+				.map(Allocation::try_from)
+				.collect::<Result<_, _>>()?,
+		})
+	}
+}
+
+impl From<TransferAuthorization> for RawTransferAuthorization {
+	fn from(authorization: TransferAuthorization) -> Self {
+		Self { allocations: authorization.allocations.into_iter().map(Into::into).collect() }
+	}
+}
+
+impl Protobuf<RawTransferAuthorization> for TransferAuthorization {}
+
+impl TryFrom<Any> for TransferAuthorization {
+	type Error = Ics20Error;
+
+	fn try_from(raw: Any) -> Result<Self, Self::Error> {
+		match raw.type_url.as_str() {
+			TYPE_URL =>
+				TransferAuthorization::decode_vec(&raw.value).map_err(Ics20Error::decode_raw_msg),
+			_ => Err(Ics20Error::unknown_msg_type(raw.type_url)),
+		}
+	}
+}
+
+impl From<TransferAuthorization> for Any {
+	fn from(authorization: TransferAuthorization) -> Self {
+		Self { type_url: TYPE_URL.to_string(), value: authorization.encode_vec() }
+	}
+}
+
+/// A context supplying the read-only dependencies of transfer authorizations.
+pub trait TransferAuthorizationReader {
+	/// Returns the authorization `granter` gave to `grantee`, if any.
+	fn get_transfer_authorization(
+		&self,
+		granter: &Signer,
+		grantee: &Signer,
+	) -> Result<Option<TransferAuthorization>, Ics20Error>;
+}
+
+/// A context supplying the write dependencies of transfer authorizations.
+pub trait TransferAuthorizationKeeper {
+	/// Stores the authorization `granter` gives to `grantee`. Storing `None` removes it.
+	fn store_transfer_authorization(
+		&mut self,
+		granter: Signer,
+		grantee: Signer,
+		authorization: Option<TransferAuthorization>,
+	) -> Result<(), Ics20Error>;
+}
+
+/// Captures all the dependencies of an ICS20 module supporting transfer authorizations.
+pub trait TransferAuthorizationContext:
+	Ics20Context + TransferAuthorizationReader + TransferAuthorizationKeeper
+{
+}
+
+/// Grants `authorization` to `grantee`, replacing any previous grant from `granter`.
+pub fn grant_transfer_authorization<Ctx: TransferAuthorizationContext>(
+	ctx: &mut Ctx,
+	granter: Signer,
+	grantee: Signer,
+	authorization: TransferAuthorization,
+) -> Result<(), Ics20Error> {
+	if granter == grantee {
+		return Err(Ics20Error::invalid_transfer_authorization(
+			"granter and grantee must differ".to_string(),
+		))
+	}
+	authorization.validate_basic()?;
+	ctx.store_transfer_authorization(granter, grantee, Some(authorization))
+}
+
+/// Revokes the authorization `granter` gave to `grantee`.
+pub fn revoke_transfer_authorization<Ctx: TransferAuthorizationContext>(
+	ctx: &mut Ctx,
+	granter: Signer,
+	grantee: Signer,
+) -> Result<(), Ics20Error> {
+	if ctx.get_transfer_authorization(&granter, &grantee)?.is_none() {
+		return Err(Ics20Error::transfer_authorization_not_found(
+			granter.to_string(),
+			grantee.to_string(),
+		))
+	}
+	ctx.store_transfer_authorization(granter, grantee, None)
+}
+
+/// Counterpart of
+/// [`send_transfer`](crate::applications::transfer::relay::send_transfer::send_transfer)
+/// for transfers sent by `grantee` on behalf of `msg.sender`. The transfer is rejected unless the
+/// authorization given by the sender covers it, in which case the spent amount is deducted from
+/// the authorization along with the transfer.
+pub fn send_transfer<Ctx, C>(
+	ctx: &mut Ctx,
+	output: &mut HandlerOutputBuilder<()>,
+	grantee: &Signer,
+	msg: MsgTransfer<C>,
+) -> Result<Packet, Ics20Error>
+where
+	Ctx: TransferAuthorizationContext,
+	C: Into<RawCoin>,
+{
+	let (msg, remaining) = check_authorization(ctx, grantee, msg)?;
+	let granter = msg.sender.clone();
+
+	let packet = inner_send_transfer(ctx, output, msg)?;
+
+	ctx.store_transfer_authorization(granter, grantee.clone(), remaining)?;
+	Ok(packet)
+}
+
+/// Counterpart of
+/// [`validate_transfer`](crate::applications::transfer::relay::send_transfer::validate_transfer)
+/// for transfers sent by `grantee` on behalf of `msg.sender`.
+pub fn validate_transfer<Ctx, C>(
+	ctx: &Ctx,
+	grantee: &Signer,
+	msg: MsgTransfer<C>,
+) -> Result<HandlerOutput<Packet>, Ics20Error>
+where
+	Ctx: TransferAuthorizationContext,
+	C: Into<RawCoin>,
+{
+	let (msg, _) = check_authorization(ctx, grantee, msg)?;
+	inner_validate_transfer(ctx, msg)
+}
+
+/// Checks that the authorization given by the sender of `msg` to `grantee` covers it, returning
+/// the transfer with its denomination resolved along with what remains of the authorization.
+fn check_authorization<Ctx, C>(
+	ctx: &Ctx,
+	grantee: &Signer,
+	msg: MsgTransfer<C>,
+) -> Result<(MsgTransfer<PrefixedCoin>, Option<TransferAuthorization>), Ics20Error>
+where
+	Ctx: TransferAuthorizationContext,
+	C: Into<RawCoin>,
+{
+	let msg = MsgTransfer {
+		source_port: msg.source_port,
+		source_channel: msg.source_channel,
+		token: resolve_coin(ctx, msg.token.into())?,
+		sender: msg.sender,
+		receiver: msg.receiver,
+		timeout_height: msg.timeout_height,
+		timeout_timestamp: msg.timeout_timestamp,
+		memo: msg.memo,
+	};
+
+	let granter = &msg.sender;
+	let authorization = ctx.get_transfer_authorization(granter, grantee)?.ok_or_else(|| {
+		Ics20Error::transfer_authorization_not_found(granter.to_string(), grantee.to_string())
+	})?;
+	let remaining = authorization.accept(ctx, &msg)?;
+	Ok((msg, remaining))
+}
+
+#[cfg(test)]
+mod tests {
+	use core::{fmt::Debug, str::FromStr};
+
+	use super::*;
+	use crate::{
+		applications::transfer::msgs::transfer::test_util::get_dummy_msg_transfer,
+		mock::context::{MockClientTypes, MockContext},
+		test_utils::{get_dummy_bech32_account, DummyTransferModule},
+	};
+
+	fn coin<D: FromStr>(denom: &str, amount: u64) -> Coin<D>
+	where
+		D::Err: Debug,
+	{
+		Coin { denom: denom.parse().unwrap(), amount: Amount::from(amount) }
+	}
+
+	fn allocation(channel_id: u64, spend_limit: Vec<Coin<String>>) -> Allocation {
+		Allocation {
+			source_port: PortId::transfer(),
+			source_channel: ChannelId::new(channel_id),
+			spend_limit,
+			allow_list: vec![],
+		}
+	}
+
+	fn msg(channel_id: u64, token: PrefixedCoin) -> MsgTransfer<PrefixedCoin> {
+		let msg = get_dummy_msg_transfer(10);
+		MsgTransfer {
+			source_port: PortId::transfer(),
+			source_channel: ChannelId::new(channel_id),
+			token,
+			sender: msg.sender,
+			receiver: msg.receiver,
+			timeout_height: msg.timeout_height,
+			timeout_timestamp: msg.timeout_timestamp,
+			memo: msg.memo,
+		}
+	}
+
+	#[test]
+	fn test_transfer_authorization_validation() {
+		let valid = allocation(0, vec![coin("uatom", 100)]);
+		assert!(TransferAuthorization { allocations: vec![valid.clone()] }
+			.validate_basic()
+			.is_ok());
+
+		let invalid = [
+			vec![],
+			vec![allocation(0, vec![])],
+			vec![allocation(0, vec![coin("uatom", 0)])],
+			vec![allocation(0, vec![coin("uatom", 1), coin("uatom", 2)])],
+			vec![valid.clone(), allocation(0, vec![coin("uosmo", 100)])],
+			vec![Allocation { allow_list: vec!["a".to_string(), "a".to_string()], ..valid }],
+		];
+		for allocations in invalid {
+			assert!(TransferAuthorization { allocations }.validate_basic().is_err());
+		}
+	}
+
+	#[test]
+	fn test_transfer_authorization_accept() -> Result<(), Ics20Error> {
+		let ctx =
+			DummyTransferModule::new(MockContext::<MockClientTypes>::default().ibc_store_share());
+		let authorization = TransferAuthorization {
+			allocations: vec![
+				allocation(0, vec![coin("uatom", 100), coin("uosmo", 50)]),
+				allocation(1, vec![coin("uatom", 10)]),
+			],
+		};
+
+		// limits are decremented, and removed once exhausted
+		let remaining = authorization.accept(&ctx, &msg(0, coin("uatom", 40)))?.unwrap();
+		assert_eq!(remaining.allocations[0].spend_limit[0], coin("uatom", 60));
+		let remaining = remaining.accept(&ctx, &msg(0, coin("uosmo", 50)))?.unwrap();
+		assert_eq!(remaining.allocations[0].spend_limit, vec![coin("uatom", 60)]);
+		let remaining = remaining.accept(&ctx, &msg(1, coin("uatom", 10)))?.unwrap();
+		assert_eq!(remaining.allocations.len(), 1);
+		assert_eq!(remaining.accept(&ctx, &msg(0, coin("uatom", 60)))?, None);
+
+		// unknown channels and denoms, and exceeded limits are rejected
+		assert!(authorization.accept(&ctx, &msg(2, coin("uatom", 1))).is_err());
+		assert!(authorization.accept(&ctx, &msg(0, coin("ujuno", 1))).is_err());
+		assert!(authorization.accept(&ctx, &msg(1, coin("uatom", 11))).is_err());
+
+		// receivers must be in the allow list, if any
+		let mut authorization = authorization;
+		authorization.allocations[0].allow_list = vec!["cosmos1other".to_string()];
+		assert!(authorization.accept(&ctx, &msg(0, coin("uatom", 1))).is_err());
+		authorization.allocations[0].allow_list.push(get_dummy_bech32_account());
+		assert!(authorization.accept(&ctx, &msg(0, coin("uatom", 1))).is_ok());
+
+		// spend limits match transfers whatever the form of their denomination
+		let denom = "transfer/channel-0/uatom";
+		let ibc_denom = ctx.ibc_denom(&denom.parse().unwrap());
+		let authorization =
+			TransferAuthorization { allocations: vec![allocation(0, vec![coin(&ibc_denom, 10)])] };
+		let remaining = authorization.accept(&ctx, &msg(0, coin(denom, 4)))?.unwrap();
+		assert_eq!(remaining.allocations[0].spend_limit, vec![coin(&ibc_denom, 6)]);
+		let authorization =
+			TransferAuthorization { allocations: vec![allocation(0, vec![coin(denom, 10)])] };
+		assert!(authorization.accept(&ctx, &msg(0, coin(denom, 4))).is_ok());
+		assert!(authorization.accept(&ctx, &msg(0, coin("uatom", 4))).is_err());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_transfer_authorization_proto_roundtrip() {
+		let authorization = TransferAuthorization {
+			allocations: vec![Allocation {
+				allow_list: vec!["cosmos1receiver".to_string()],
+				..allocation(3, vec![coin("transfer/channel-0/uatom", 100)])
+			}],
+		};
+		let any = Any::from(authorization.clone());
+		assert_eq!(TransferAuthorization::try_from(any).unwrap(), authorization);
+	}
+}
@@ -0,0 +1,891 @@
+//! Packet lifecycle callbacks for ICS20 transfers, in the spirit of ibc-go's ADR-008.
+//!
+//! A transfer opts into callbacks through its memo, which must then be a JSON object of the form
+//!
+//! ```json
+//! {
+//!   "src_callback": { "address": "<contract>", "gas_limit": "<gas>" },
+//!   "dest_callback": { "address": "<contract>", "gas_limit": "<gas>" }
+//! }
+//! ```
+//!
+//! where both entries, as well as `gas_limit`, are optional and other keys are ignored. The source
+//! callback is invoked when the transfer is sent, acknowledged or timed out, the destination
+//! callback when it is received. Callbacks are executed by the host through [`ContractCallbacks`],
+//! within the gas limit requested in the memo, capped by [`ContractCallbacks::max_callback_gas`].
+//!
+//! The acknowledgement of a received packet with a destination callback is written by
+//! [`CallbacksModule`] once the callback has run, the host must therefore not write one when the
+//! module returns [`OnRecvPacketAck::Nil`].
+
+use ibc_proto::cosmos::base::v1beta1::Coin as RawCoin;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+	applications::transfer::{
+		acknowledgement::Acknowledgement,
+		context::Ics20Context,
+		error::Error as Ics20Error,
+		events::CallbackEvent,
+		msgs::transfer::MsgTransfer,
+		packet::PacketData,
+		relay::{
+			revert_received_token,
+			send_transfer::{
+				send_transfer as inner_send_transfer, validate_transfer as inner_validate_transfer,
+			},
+			write_packet_acknowledgement,
+		},
+	},
+	core::{
+		ics04_channel::{
+			channel::{Counterparty, Order},
+			error::Error as Ics04Error,
+			msgs::acknowledgement::Acknowledgement as GenericAcknowledgement,
+			packet::Packet,
+			Version,
+		},
+		ics24_host::identifier::{ChannelId, ConnectionId, PortId},
+		ics26_routing::context::{Module, ModuleOutputBuilder, OnRecvPacketAck, WriteFn},
+	},
+	events::{IbcEvent, ModuleEvent},
+	handler::{HandlerOutput, HandlerOutputBuilder},
+	prelude::*,
+	signer::Signer,
+};
+
+/// The packet lifecycle events a callback can be attached to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CallbackType {
+	SendPacket,
+	ReceivePacket,
+	AcknowledgementPacket,
+	TimeoutPacket,
+}
+
+impl core::fmt::Display for CallbackType {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		match self {
+			CallbackType::SendPacket => write!(f, "send_packet"),
+			CallbackType::ReceivePacket => write!(f, "receive_packet"),
+			CallbackType::AcknowledgementPacket => write!(f, "acknowledgement_packet"),
+			CallbackType::TimeoutPacket => write!(f, "timeout_packet"),
+		}
+	}
+}
+
+/// What happens to the packet operation a callback is attached to when the callback fails.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CallbackFailurePolicy {
+	/// The operation fails along with the callback: sends are rejected, acknowledgement and
+	/// timeout messages fail, and received packets are answered with an error acknowledgement,
+	/// the tokens credited to their receiver being taken back. Note that a source callback that
+	/// keeps failing then prevents its packet from ever being acknowledged or timed out.
+	Revert,
+	/// The failure is reported in an event and the operation proceeds.
+	Ignore,
+}
+
+/// A contract to call back, along with the gas it may consume.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CallbackTarget {
+	pub address: String,
+	pub gas_limit: u64,
+}
+
+/// The callbacks requested in the memo of a transfer.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Callbacks {
+	pub src: Option<CallbackTarget>,
+	pub dest: Option<CallbackTarget>,
+}
+
+#[derive(Deserialize)]
+struct RawCallbackTarget {
+	address: String,
+	#[serde(default)]
+	gas_limit: Option<String>,
+}
+
+impl Callbacks {
+	/// Parses the callbacks requested in `memo`, capping their gas limit to `max_gas`. A missing
+	/// or zero gas limit stands for `max_gas`. Memos that are not JSON objects request no
+	/// callbacks.
+	pub fn from_memo(memo: &str, max_gas: u64) -> Result<Self, Ics20Error> {
+		let value = match serde_json::from_str::<Value>(memo) {
+			Ok(value @ Value::Object(_)) => value,
+			_ => return Ok(Self::default()),
+		};
+		let target =
+			|key: &str| value.get(key).map(|raw| parse_target(raw.clone(), max_gas)).transpose();
+		Ok(Self { src: target("src_callback")?, dest: target("dest_callback")? })
+	}
+
+	/// Parses the callbacks requested in the memo of an ICS20 packet. Packets that cannot be
+	/// decoded request no callbacks.
+	pub fn from_packet(packet: &Packet, max_gas: u64) -> Result<Self, Ics20Error> {
+		match serde_json::from_slice::<PacketData>(&packet.data) {
+			Ok(data) => Self::from_memo(&data.memo, max_gas),
+			Err(_) => Ok(Self::default()),
+		}
+	}
+}
+
+fn parse_target(raw: Value, max_gas: u64) -> Result<CallbackTarget, Ics20Error> {
+	let raw = serde_json::from_value::<RawCallbackTarget>(raw)
+		.map_err(|e| Ics20Error::invalid_callback(e.to_string()))?;
+	if raw.address.trim().is_empty() {
+		return Err(Ics20Error::invalid_callback("empty callback address".to_string()))
+	}
+
+	let gas_limit = match raw.gas_limit {
+		Some(gas_limit) => gas_limit
+			.parse::<u64>()
+			.map_err(|e| Ics20Error::invalid_callback(format!("invalid gas limit: {}", e)))?,
+		None => 0,
+	};
+	let gas_limit = if gas_limit == 0 { max_gas } else { gas_limit.min(max_gas) };
+
+	Ok(CallbackTarget { address: raw.address, gas_limit })
+}
+
+/// Executes packet callbacks on behalf of [`CallbacksModule`]. Implementations must abort a
+/// callback that exceeds `target.gas_limit`, e.g. with [`Ics20Error::callback_out_of_gas`], and
+/// are responsible for checking that the target accepts calls for the given packet, e.g. that it
+/// is the packet sender for source callbacks.
+pub trait ContractCallbacks {
+	/// Maximum gas any single callback may be given, whatever its memo requests.
+	fn max_callback_gas(&self) -> u64;
+
+	/// Called on the source chain once `packet` has been sent.
+	fn send_packet_callback(
+		&mut self,
+		_target: &CallbackTarget,
+		_packet: &Packet,
+	) -> Result<(), Ics20Error> {
+		Ok(())
+	}
+
+	/// Called on the destination chain once `packet` has been received successfully.
+	fn recv_packet_callback(
+		&mut self,
+		_target: &CallbackTarget,
+		_packet: &Packet,
+		_acknowledgement: &GenericAcknowledgement,
+	) -> Result<(), Ics20Error> {
+		Ok(())
+	}
+
+	/// Reports the events of the destination callbacks and of the acknowledgements written by
+	/// [`CallbacksModule`], which cannot go through the output of module callbacks.
+	fn emit_ibc_events(&mut self, events: Vec<IbcEvent>);
+
+	/// Called on the source chain once `packet` has been acknowledged, and refunded if the
+	/// acknowledgement is an error.
+	fn acknowledgement_packet_callback(
+		&mut self,
+		_target: &CallbackTarget,
+		_packet: &Packet,
+		_acknowledgement: &GenericAcknowledgement,
+	) -> Result<(), Ics20Error> {
+		Ok(())
+	}
+
+	/// Called on the source chain once `packet` has timed out and been refunded.
+	fn timeout_packet_callback(
+		&mut self,
+		_target: &CallbackTarget,
+		_packet: &Packet,
+	) -> Result<(), Ics20Error> {
+		Ok(())
+	}
+}
+
+/// Reports the outcome of a callback and decides, according to `policy`, whether its failure
+/// fails the surrounding operation.
+fn handle_callback_result<E: From<ModuleEvent>>(
+	output: &mut HandlerOutputBuilder<(), E>,
+	policy: CallbackFailurePolicy,
+	callback_type: CallbackType,
+	target: CallbackTarget,
+	packet: &Packet,
+	result: Result<(), Ics20Error>,
+) -> Result<(), Ics20Error> {
+	let event = CallbackEvent {
+		callback_type,
+		target,
+		sequence: packet.sequence,
+		error: result.as_ref().err().map(|e| e.to_string()),
+	};
+	output.emit(ModuleEvent::from(event).into());
+
+	match result {
+		Err(e) if policy == CallbackFailurePolicy::Revert => Err(e),
+		_ => Ok(()),
+	}
+}
+
+/// Counterpart of
+/// [`send_transfer`](crate::applications::transfer::relay::send_transfer::send_transfer)
+/// invoking the source callback requested in the memo, if any. Transfers with a malformed
+/// callback in their memo are rejected.
+pub fn send_transfer<Ctx, C>(
+	ctx: &mut Ctx,
+	output: &mut HandlerOutputBuilder<()>,
+	msg: MsgTransfer<C>,
+	policy: CallbackFailurePolicy,
+) -> Result<Packet, Ics20Error>
+where
+	Ctx: Ics20Context + ContractCallbacks,
+	C: Into<RawCoin>,
+{
+	let callbacks = Callbacks::from_memo(&msg.memo, ctx.max_callback_gas())?;
+	let packet = inner_send_transfer(ctx, output, msg)?;
+
+	if let Some(target) = callbacks.src {
+		let result = ctx.send_packet_callback(&target, &packet);
+		handle_callback_result(output, policy, CallbackType::SendPacket, target, &packet, result)?;
+	}
+
+	Ok(packet)
+}
+
+/// Counterpart of
+/// [`validate_transfer`](crate::applications::transfer::relay::send_transfer::validate_transfer)
+/// rejecting transfers with a malformed callback in their memo. The source callback is not invoked,
+/// as contract calls are only made on execution.
+pub fn validate_transfer<Ctx, C>(
+	ctx: &Ctx,
+	msg: MsgTransfer<C>,
+) -> Result<HandlerOutput<Packet>, Ics20Error>
+where
+	Ctx: Ics20Context + ContractCallbacks,
+	C: Into<RawCoin>,
+{
+	Callbacks::from_memo(&msg.memo, ctx.max_callback_gas())?;
+	inner_validate_transfer(ctx, msg)
+}
+
+/// Wraps an ICS20 transfer `Module`, invoking the callbacks requested in the memo of the packets
+/// it handles once the inner module is done with them. Outgoing transfers must go through
+/// this module's [`send_transfer`] for their send callback to be invoked.
+///
+/// Received packets with a destination callback are acknowledged by this module, which returns
+/// [`OnRecvPacketAck::Nil`] for them.
+#[derive(Clone, Debug)]
+pub struct CallbacksModule<M> {
+	inner: M,
+	policy: CallbackFailurePolicy,
+}
+
+impl<M> CallbacksModule<M> {
+	pub fn new(inner: M, policy: CallbackFailurePolicy) -> Self {
+		Self { inner, policy }
+	}
+
+	pub fn inner(&self) -> &M {
+		&self.inner
+	}
+
+	pub fn inner_mut(&mut self) -> &mut M {
+		&mut self.inner
+	}
+
+	pub fn policy(&self) -> CallbackFailurePolicy {
+		self.policy
+	}
+}
+
+impl<M: Module + Ics20Context + ContractCallbacks + 'static> CallbacksModule<M> {
+	/// Returns the source callback of an outgoing packet. Its memo was validated when the packet
+	/// was sent, a malformed callback is thus ignored rather than blocking the packet lifecycle.
+	fn src_callback(&self, packet: &Packet) -> Option<CallbackTarget> {
+		Callbacks::from_packet(packet, self.inner.max_callback_gas()).ok()?.src
+	}
+
+	/// Runs the destination callback once the inner module has credited the receiver, then writes
+	/// the acknowledgement of `packet`. A failure under [`CallbackFailurePolicy::Revert`] takes
+	/// back the tokens credited and turns the acknowledgement into an error one.
+	fn wrap_write_fn(
+		write_fn: Box<WriteFn>,
+		target: CallbackTarget,
+		packet: Packet,
+		acknowledgement: GenericAcknowledgement,
+	) -> Box<WriteFn> {
+		Box::new(move |module| {
+			let module = module.downcast_mut::<Self>().unwrap();
+			write_fn(&mut module.inner)?;
+
+			let data = serde_json::from_slice::<PacketData>(&packet.data)
+				.map_err(|_| Ics20Error::packet_data_deserialization().to_string())?;
+			let result = module.inner.recv_packet_callback(&target, &packet, &acknowledgement);
+			let mut output = HandlerOutputBuilder::<(), IbcEvent>::new();
+			let ack = match handle_callback_result(
+				&mut output,
+				module.policy,
+				CallbackType::ReceivePacket,
+				target,
+				&packet,
+				result,
+			) {
+				Ok(()) => acknowledgement.as_ref().to_vec(),
+				Err(e) => {
+					revert_received_token(&mut module.inner, &packet, data.receiver)
+						.map_err(|e| e.to_string())?;
+					Acknowledgement::from_error(e).as_ref().to_vec()
+				},
+			};
+
+			let mut events = output.with_result(()).events;
+			events.extend(
+				write_packet_acknowledgement(&mut module.inner, packet, ack)
+					.map_err(|e| e.to_string())?,
+			);
+			module.inner.emit_ibc_events(events);
+			Ok(())
+		})
+	}
+}
+
+impl<M: Module + Ics20Context + ContractCallbacks + 'static> Module for CallbacksModule<M> {
+	fn inner_module(&self) -> Option<&dyn Module> {
+		Some(&self.inner)
+	}
+
+	fn on_chan_open_init(
+		&mut self,
+		output: &mut ModuleOutputBuilder,
+		order: Order,
+		connection_hops: &[ConnectionId],
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		counterparty: &Counterparty,
+		version: &Version,
+	) -> Result<(), Ics04Error> {
+		self.inner.on_chan_open_init(
+			output,
+			order,
+			connection_hops,
+			port_id,
+			channel_id,
+			counterparty,
+			version,
+		)
+	}
+
+	fn on_chan_open_try(
+		&mut self,
+		output: &mut ModuleOutputBuilder,
+		order: Order,
+		connection_hops: &[ConnectionId],
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		counterparty: &Counterparty,
+		version: &Version,
+		counterparty_version: &Version,
+	) -> Result<Version, Ics04Error> {
+		self.inner.on_chan_open_try(
+			output,
+			order,
+			connection_hops,
+			port_id,
+			channel_id,
+			counterparty,
+			version,
+			counterparty_version,
+		)
+	}
+
+	fn on_chan_open_ack(
+		&mut self,
+		output: &mut ModuleOutputBuilder,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		counterparty_version: &Version,
+	) -> Result<(), Ics04Error> {
+		self.inner.on_chan_open_ack(output, port_id, channel_id, counterparty_version)
+	}
+
+	fn on_chan_open_confirm(
+		&mut self,
+		output: &mut ModuleOutputBuilder,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+	) -> Result<(), Ics04Error> {
+		self.inner.on_chan_open_confirm(output, port_id, channel_id)
+	}
+
+	fn on_chan_close_init(
+		&mut self,
+		output: &mut ModuleOutputBuilder,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+	) -> Result<(), Ics04Error> {
+		self.inner.on_chan_close_init(output, port_id, channel_id)
+	}
+
+	fn on_chan_close_confirm(
+		&mut self,
+		output: &mut ModuleOutputBuilder,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+	) -> Result<(), Ics04Error> {
+		self.inner.on_chan_close_confirm(output, port_id, channel_id)
+	}
+
+	fn on_recv_packet(
+		&self,
+		output: &mut ModuleOutputBuilder,
+		packet: &Packet,
+		relayer: &Signer,
+	) -> OnRecvPacketAck {
+		let target = match Callbacks::from_packet(packet, self.inner.max_callback_gas()) {
+			Ok(callbacks) => callbacks.dest,
+			Err(e) => return OnRecvPacketAck::Failed(Box::new(Acknowledgement::from_error(e))),
+		};
+
+		match (self.inner.on_recv_packet(output, packet, relayer), target) {
+			(OnRecvPacketAck::Successful(ack, write_fn), Some(target)) => {
+				let acknowledgement = GenericAcknowledgement::from((*ack).as_ref().to_vec());
+				let write_fn =
+					Self::wrap_write_fn(write_fn, target, packet.clone(), acknowledgement);
+				OnRecvPacketAck::Nil(write_fn)
+			},
+			(ack, _) => ack,
+		}
+	}
+
+	fn on_acknowledgement_packet(
+		&mut self,
+		output: &mut ModuleOutputBuilder,
+		packet: &Packet,
+		acknowledgement: &GenericAcknowledgement,
+		relayer: &Signer,
+	) -> Result<(), Ics04Error> {
+		self.inner.on_acknowledgement_packet(output, packet, acknowledgement, relayer)?;
+
+		if let Some(target) = self.src_callback(packet) {
+			let result =
+				self.inner.acknowledgement_packet_callback(&target, packet, acknowledgement);
+			handle_callback_result(
+				output,
+				self.policy,
+				CallbackType::AcknowledgementPacket,
+				target,
+				packet,
+				result,
+			)
+			.map_err(|e| Ics04Error::app_module(e.to_string()))?;
+		}
+
+		Ok(())
+	}
+
+	fn on_timeout_packet(
+		&mut self,
+		output: &mut ModuleOutputBuilder,
+		packet: &Packet,
+		relayer: &Signer,
+	) -> Result<(), Ics04Error> {
+		self.inner.on_timeout_packet(output, packet, relayer)?;
+
+		if let Some(target) = self.src_callback(packet) {
+			let result = self.inner.timeout_packet_callback(&target, packet);
+			handle_callback_result(
+				output,
+				self.policy,
+				CallbackType::TimeoutPacket,
+				target,
+				packet,
+				result,
+			)
+			.map_err(|e| Ics04Error::app_module(e.to_string()))?;
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		applications::transfer::{
+			context::{Ics20Keeper, Ics20Reader},
+			msgs::transfer::test_util::get_dummy_msg_transfer,
+			PrefixedCoin,
+		},
+		core::{
+			ics03_connection::{
+				connection::{
+					ConnectionEnd, Counterparty as ConnectionCounterparty, State as ConnectionState,
+				},
+				version::get_compatible_versions,
+			},
+			ics04_channel::channel::{ChannelEnd, State},
+			ics24_host::identifier::ClientId,
+		},
+		mock::context::{HostBlockType, MockClientTypes, MockContext},
+		test_utils::{get_dummy_account_id, DummyTransferModule},
+		timestamp::ZERO_DURATION,
+		Height,
+	};
+
+	/// The contracts called back by the transfer module wrapped by the tested one.
+	#[derive(Clone, Debug, Default)]
+	struct Contracts {
+		/// The callbacks invoked so far, in order.
+		callbacks: Vec<(CallbackType, CallbackTarget)>,
+		/// Whether every callback fails.
+		failing: bool,
+		/// The events emitted outside of module callbacks so far.
+		ibc_events: Vec<IbcEvent>,
+	}
+
+	type TestModule = CallbacksModule<DummyTransferModule<MockClientTypes, Contracts>>;
+
+	impl<C: HostBlockType> DummyTransferModule<C, Contracts> {
+		fn invoke_callback(
+			&mut self,
+			callback_type: CallbackType,
+			target: &CallbackTarget,
+		) -> Result<(), Ics20Error> {
+			let contracts = self.state_mut();
+			contracts.callbacks.push((callback_type, target.clone()));
+			if contracts.failing {
+				return Err(Ics20Error::callback_failed(
+					target.address.clone(),
+					"failing".to_string(),
+				))
+			}
+			Ok(())
+		}
+	}
+
+	impl<C: HostBlockType> ContractCallbacks for DummyTransferModule<C, Contracts> {
+		fn max_callback_gas(&self) -> u64 {
+			MAX_GAS
+		}
+
+		fn send_packet_callback(
+			&mut self,
+			target: &CallbackTarget,
+			_packet: &Packet,
+		) -> Result<(), Ics20Error> {
+			self.invoke_callback(CallbackType::SendPacket, target)
+		}
+
+		fn recv_packet_callback(
+			&mut self,
+			target: &CallbackTarget,
+			_packet: &Packet,
+			_acknowledgement: &GenericAcknowledgement,
+		) -> Result<(), Ics20Error> {
+			self.invoke_callback(CallbackType::ReceivePacket, target)
+		}
+
+		fn emit_ibc_events(&mut self, events: Vec<IbcEvent>) {
+			self.state_mut().ibc_events.extend(events);
+		}
+
+		fn acknowledgement_packet_callback(
+			&mut self,
+			target: &CallbackTarget,
+			_packet: &Packet,
+			_acknowledgement: &GenericAcknowledgement,
+		) -> Result<(), Ics20Error> {
+			self.invoke_callback(CallbackType::AcknowledgementPacket, target)
+		}
+
+		fn timeout_packet_callback(
+			&mut self,
+			target: &CallbackTarget,
+			_packet: &Packet,
+		) -> Result<(), Ics20Error> {
+			self.invoke_callback(CallbackType::TimeoutPacket, target)
+		}
+	}
+
+	const MAX_GAS: u64 = 1_000_000;
+
+	fn target(address: &str, gas_limit: u64) -> Option<CallbackTarget> {
+		Some(CallbackTarget { address: address.to_string(), gas_limit })
+	}
+
+	#[test]
+	fn test_callbacks_from_memo() {
+		let parse = |memo: &str| Callbacks::from_memo(memo, MAX_GAS);
+
+		assert_eq!(parse("").unwrap(), Callbacks::default());
+		assert_eq!(parse("thanks for the tokens").unwrap(), Callbacks::default());
+		assert_eq!(parse(r#"["src_callback"]"#).unwrap(), Callbacks::default());
+		assert_eq!(parse(r#"{"wasm":{"contract":"foo"}}"#).unwrap(), Callbacks::default());
+
+		let callbacks =
+			parse(r#"{"src_callback":{"address":"alice","gas_limit":"5000"},"other":1}"#).unwrap();
+		assert_eq!(callbacks, Callbacks { src: target("alice", 5000), dest: None });
+
+		let callbacks = parse(r#"{"dest_callback":{"address":"bob"}}"#).unwrap();
+		assert_eq!(callbacks, Callbacks { src: None, dest: target("bob", MAX_GAS) });
+
+		// gas limits are capped by the host
+		let callbacks = parse(r#"{"src_callback":{"address":"alice","gas_limit":"5000000"}}"#);
+		assert_eq!(callbacks.unwrap().src, target("alice", MAX_GAS));
+		let callbacks = parse(r#"{"src_callback":{"address":"alice","gas_limit":"0"}}"#);
+		assert_eq!(callbacks.unwrap().src, target("alice", MAX_GAS));
+
+		assert!(parse(r#"{"src_callback":{"gas_limit":"5000"}}"#).is_err());
+		assert!(parse(r#"{"src_callback":{"address":" "}}"#).is_err());
+		assert!(parse(r#"{"dest_callback":{"address":"bob","gas_limit":"lots"}}"#).is_err());
+	}
+
+	#[test]
+	fn test_callback_failure_policy() {
+		let packet = Packet::default();
+		let failure = || Err(Ics20Error::callback_failed("alice".to_string(), "boom".to_string()));
+		let mut output = ModuleOutputBuilder::new();
+		let mut handle = |policy, result| {
+			let target = target("alice", MAX_GAS).unwrap();
+			let ty = CallbackType::TimeoutPacket;
+			handle_callback_result(&mut output, policy, ty, target, &packet, result)
+		};
+
+		assert!(handle(CallbackFailurePolicy::Ignore, failure()).is_ok());
+		assert!(handle(CallbackFailurePolicy::Revert, Ok(())).is_ok());
+		assert!(handle(CallbackFailurePolicy::Revert, failure()).is_err());
+
+		let events = output.with_result(()).events;
+		assert_eq!(events.len(), 3);
+		let results: Vec<_> = events
+			.iter()
+			.map(|event| {
+				let attr = event.attributes.iter().find(|attr| attr.key == "callback_result");
+				attr.unwrap().value.clone()
+			})
+			.collect();
+		assert_eq!(results, vec!["failure", "success", "failure"]);
+	}
+
+	/// A transfer module with an open channel, wrapped in a `CallbacksModule` applying `policy`.
+	fn callbacks_module(policy: CallbackFailurePolicy) -> TestModule {
+		let connection_end = ConnectionEnd::new(
+			ConnectionState::Open,
+			ClientId::default(),
+			ConnectionCounterparty::new(
+				ClientId::default(),
+				Some(ConnectionId::default()),
+				Default::default(),
+			),
+			get_compatible_versions(),
+			ZERO_DURATION,
+		);
+		let channel_end = ChannelEnd::new(
+			State::Open,
+			Order::Unordered,
+			Counterparty::new(PortId::transfer(), Some(ChannelId::new(1))),
+			vec![ConnectionId::default()],
+			Version::ics20(),
+		);
+		let ctx = MockContext::<MockClientTypes>::default()
+			.with_client(&ClientId::default(), Height::new(0, 10))
+			.with_connection(ConnectionId::default(), connection_end)
+			.with_channel(PortId::transfer(), ChannelId::default(), channel_end)
+			.with_send_sequence(PortId::transfer(), ChannelId::default(), 1.into());
+
+		let inner = DummyTransferModule::with_state(ctx.ibc_store_share(), Contracts::default());
+		CallbacksModule::new(inner, policy)
+	}
+
+	fn msg_transfer(memo: &str) -> MsgTransfer<PrefixedCoin> {
+		MsgTransfer {
+			source_port: PortId::transfer(),
+			source_channel: ChannelId::default(),
+			token: PrefixedCoin { denom: "uatom".parse().unwrap(), amount: 100u64.into() },
+			memo: memo.to_string(),
+			..get_dummy_msg_transfer(20)
+		}
+	}
+
+	/// A packet received on channel-0 carrying `denom`, with `memo`.
+	fn recv_packet(denom: &str, memo: &str) -> Packet {
+		let data = PacketData {
+			token: PrefixedCoin { denom: denom.parse().unwrap(), amount: 40u64.into() },
+			sender: get_dummy_account_id(),
+			receiver: get_dummy_account_id(),
+			memo: memo.to_string(),
+		};
+		Packet {
+			source_port: PortId::transfer(),
+			source_channel: ChannelId::new(1),
+			destination_port: PortId::transfer(),
+			destination_channel: ChannelId::default(),
+			data: serde_json::to_vec(&data).unwrap(),
+			..Default::default()
+		}
+	}
+
+	fn success_ack() -> GenericAcknowledgement {
+		serde_json::to_vec(&Acknowledgement::success().to_string()).unwrap().into()
+	}
+
+	fn invoked(module: &TestModule) -> Vec<(CallbackType, String)> {
+		let callbacks = module.inner().state().callbacks.iter();
+		callbacks.map(|(ty, target)| (*ty, target.address.clone())).collect()
+	}
+
+	/// Returns the acknowledgement written by the module, and the results of the callbacks
+	/// reported alongside it.
+	fn written_ack(module: &TestModule) -> (Vec<u8>, Vec<String>) {
+		let mut ack = None;
+		let mut results = vec![];
+		for event in &module.inner().state().ibc_events {
+			match event {
+				IbcEvent::WriteAcknowledgement(event) => ack = Some(event.ack.clone()),
+				IbcEvent::AppModule(event) => results.extend(
+					event
+						.attributes
+						.iter()
+						.filter(|attr| attr.key == "callback_result")
+						.map(|attr| attr.value.clone()),
+				),
+				_ => {},
+			}
+		}
+		(ack.expect("no acknowledgement written"), results)
+	}
+
+	#[test]
+	fn test_src_callbacks() {
+		let mut module = callbacks_module(CallbackFailurePolicy::Ignore);
+		let relayer = get_dummy_account_id();
+		let memo = r#"{"src_callback":{"address":"alice"}}"#;
+
+		// no callback is invoked on validation
+		let mut output = HandlerOutputBuilder::new();
+		assert!(validate_transfer(module.inner(), msg_transfer(memo)).is_ok());
+		assert!(validate_transfer(module.inner(), msg_transfer(r#"{"src_callback":{}}"#)).is_err());
+		assert!(invoked(&module).is_empty());
+
+		let policy = module.policy();
+		let packet = send_transfer(module.inner_mut(), &mut output, msg_transfer(memo), policy);
+		let packet = packet.unwrap();
+		// the callback is reported after the send packet and transfer events
+		assert_eq!(output.with_result(()).events.len(), 3);
+
+		module
+			.on_acknowledgement_packet(
+				&mut ModuleOutputBuilder::new(),
+				&packet,
+				&success_ack(),
+				&relayer,
+			)
+			.unwrap();
+		module
+			.on_timeout_packet(&mut ModuleOutputBuilder::new(), &packet, &relayer)
+			.unwrap();
+		assert_eq!(
+			invoked(&module),
+			vec![
+				(CallbackType::SendPacket, "alice".to_string()),
+				(CallbackType::AcknowledgementPacket, "alice".to_string()),
+				(CallbackType::TimeoutPacket, "alice".to_string()),
+			]
+		);
+
+		// packets without callbacks are passed through
+		let mut output = HandlerOutputBuilder::new();
+		let packet = send_transfer(module.inner_mut(), &mut output, msg_transfer(""), policy);
+		module
+			.on_timeout_packet(&mut ModuleOutputBuilder::new(), &packet.unwrap(), &relayer)
+			.unwrap();
+		assert_eq!(invoked(&module).len(), 3);
+	}
+
+	#[test]
+	fn test_failing_src_callbacks() {
+		let relayer = get_dummy_account_id();
+		let memo = r#"{"src_callback":{"address":"alice"}}"#;
+
+		for policy in [CallbackFailurePolicy::Ignore, CallbackFailurePolicy::Revert] {
+			let mut module = callbacks_module(policy);
+			let mut output = HandlerOutputBuilder::new();
+			let packet = send_transfer(module.inner_mut(), &mut output, msg_transfer(memo), policy);
+			let packet = packet.unwrap();
+
+			module.inner_mut().state_mut().failing = true;
+			let sent = send_transfer(module.inner_mut(), &mut output, msg_transfer(memo), policy);
+			let timed_out =
+				module.on_timeout_packet(&mut ModuleOutputBuilder::new(), &packet, &relayer);
+			match policy {
+				CallbackFailurePolicy::Ignore => assert!(sent.is_ok() && timed_out.is_ok()),
+				CallbackFailurePolicy::Revert => assert!(sent.is_err() && timed_out.is_err()),
+			}
+		}
+	}
+
+	#[test]
+	fn test_dest_callback() {
+		let mut module = callbacks_module(CallbackFailurePolicy::Revert);
+		let relayer = get_dummy_account_id();
+		let packet = recv_packet("uosmo", r#"{"dest_callback":{"address":"bob"}}"#);
+
+		// the callback runs, and the acknowledgement is written, once the packet is written
+		let ack = module.on_recv_packet(&mut ModuleOutputBuilder::new(), &packet, &relayer);
+		assert!(invoked(&module).is_empty());
+		match ack {
+			OnRecvPacketAck::Nil(write_fn) => write_fn(&mut module).unwrap(),
+			_ => panic!("packets with a destination callback are acknowledged by the module"),
+		}
+		assert_eq!(invoked(&module), vec![(CallbackType::ReceivePacket, "bob".to_string())]);
+		let (ack, results) = written_ack(&module);
+		assert_eq!(ack, Acknowledgement::success().as_ref().to_vec());
+		assert_eq!(results, vec!["success"]);
+
+		// packets without a destination callback are acknowledged by the host
+		let packet = recv_packet("uosmo", "");
+		let ack = module.on_recv_packet(&mut ModuleOutputBuilder::new(), &packet, &relayer);
+		assert!(matches!(ack, OnRecvPacketAck::Successful(_, _)));
+
+		// malformed callbacks fail the packet
+		let packet = recv_packet("uosmo", r#"{"dest_callback":{"address":""}}"#);
+		let ack = module.on_recv_packet(&mut ModuleOutputBuilder::new(), &packet, &relayer);
+		assert!(matches!(ack, OnRecvPacketAck::Failed(_)));
+	}
+
+	#[test]
+	fn test_failing_dest_callback() {
+		let relayer = get_dummy_account_id();
+		// the tokens come back to their source, they are unescrowed on receipt
+		let packet =
+			recv_packet("transfer/channel-1/uatom", r#"{"dest_callback":{"address":"bob"}}"#);
+		let escrow_denom = "uatom".parse().unwrap();
+
+		for policy in [CallbackFailurePolicy::Ignore, CallbackFailurePolicy::Revert] {
+			let mut module = callbacks_module(policy);
+			module.inner_mut().store_total_escrow(&escrow_denom, 100u64.into()).unwrap();
+			module.inner_mut().state_mut().failing = true;
+
+			let ack = module.on_recv_packet(&mut ModuleOutputBuilder::new(), &packet, &relayer);
+			match ack {
+				OnRecvPacketAck::Nil(write_fn) => write_fn(&mut module).unwrap(),
+				_ => panic!("packets with a destination callback are acknowledged by the module"),
+			}
+
+			let (ack, results) = written_ack(&module);
+			assert_eq!(results, vec!["failure"]);
+			let total_escrow = module.inner().get_total_escrow(&escrow_denom).unwrap();
+			match policy {
+				CallbackFailurePolicy::Ignore => {
+					assert_eq!(ack, Acknowledgement::success().as_ref().to_vec());
+					assert_eq!(total_escrow, 60u64.into());
+				},
+				CallbackFailurePolicy::Revert => {
+					// the receiver is not credited, and the sender gets refunded
+					assert_ne!(ack, Acknowledgement::success().as_ref().to_vec());
+					assert_eq!(total_escrow, 100u64.into());
+				},
+			}
+		}
+	}
+}
@@ -1,27 +1,34 @@
+use ibc_proto::cosmos::base::query::v1beta1::{PageRequest, PageResponse};
 use subtle_encoding::hex;
 
 use super::error::Error as Ics20Error;
 use crate::{
 	applications::transfer::{
 		acknowledgement::Acknowledgement,
+		enablement::EnablementKey,
+		escrow::{escrow_preimage, EscrowAddressScheme},
 		events::{AckEvent, AckStatusEvent, RecvEvent, TimeoutEvent},
+		metadata::DenomMetadata,
 		packet::PacketData,
 		relay::{
 			on_ack_packet::process_ack_packet, on_recv_packet::process_recv_packet,
 			on_timeout_packet::process_timeout_packet,
 		},
-		PrefixedCoin, PrefixedDenom, VERSION,
+		Amount, PrefixedCoin, PrefixedDenom, MODULE_ID_STR,
 	},
 	core::{
 		ics04_channel::{
 			channel::{Counterparty, Order},
 			context::{ChannelKeeper, ChannelReader},
+			error::Error as ChannelError,
 			msgs::acknowledgement::Acknowledgement as GenericAcknowledgement,
 			packet::Packet,
 			Version,
 		},
+		ics05_port::capabilities::{lookup_capability, Capability, CapabilityName},
+		ics23_commitment::ethereum::KeccakHostFunctions,
 		ics24_host::identifier::{ChannelId, ConnectionId, PortId},
-		ics26_routing::context::{ModuleOutputBuilder, OnRecvPacketAck, ReaderContext},
+		ics26_routing::context::{ModuleId, ModuleOutputBuilder, OnRecvPacketAck, ReaderContext},
 	},
 	prelude::*,
 	signer::Signer,
@@ -31,6 +38,51 @@ pub trait Ics20Keeper:
 	ChannelKeeper + BankKeeper<AccountId = <Self as Ics20Keeper>::AccountId>
 {
 	type AccountId;
+
+	/// Stores the trace of a newly minted IBC voucher denomination under its hash (see
+	/// [`Ics20Reader::denom_trace_hash`]).
+	fn store_denom_trace(
+		&mut self,
+		trace_hash: String,
+		denom: PrefixedDenom,
+	) -> Result<(), Ics20Error>;
+
+	/// Disables the transfers identified by `key` for the given reason, or enables them again if
+	/// `reason` is `None`.
+	fn store_transfer_disabled(
+		&mut self,
+		key: EnablementKey,
+		reason: Option<String>,
+	) -> Result<(), Ics20Error>;
+
+	/// Stores the total amount of `denom` held in escrow across all channels.
+	fn store_total_escrow(
+		&mut self,
+		denom: &PrefixedDenom,
+		amount: Amount,
+	) -> Result<(), Ics20Error>;
+
+	/// Registers the display metadata of the voucher of `denom` with the host, e.g. in its bank
+	/// module. Called when the voucher is minted for the first time, right after its trace is
+	/// stored. Returning an error fails the processing of the packet.
+	fn register_denom_metadata(
+		&mut self,
+		_denom: &PrefixedDenom,
+		_metadata: DenomMetadata,
+	) -> Result<(), Ics20Error> {
+		Ok(())
+	}
+
+	/// Hook invoked once the tokens carried by a received packet have been credited to the
+	/// receiver, e.g. to act on the packet `memo` with a contract call or an automatic swap.
+	/// Returning an error fails the processing of the packet.
+	fn on_recv_packet_hook(
+		&mut self,
+		_packet: &Packet,
+		_data: &PacketData,
+	) -> Result<(), Ics20Error> {
+		Ok(())
+	}
 }
 
 pub trait Ics20Reader: ChannelReader
@@ -38,6 +90,8 @@ where
 	Self: Sized,
 {
 	type AccountId: TryFrom<Signer>;
+	/// Host functions escrow accounts are derived with.
+	type HostFunctions: KeccakHostFunctions;
 
 	/// get_port returns the portID for the transfer module.
 	fn get_port(&self) -> Result<PortId, Ics20Error>;
@@ -48,9 +102,25 @@ where
 		port_id: &PortId,
 		channel_id: ChannelId,
 	) -> Result<<Self as Ics20Reader>::AccountId, Ics20Error> {
-		let hash = cosmos_adr028_escrow_address(self, port_id, channel_id);
+		let address = self
+			.escrow_address_scheme()
+			.escrow_address::<Self::HostFunctions>(self, port_id, channel_id);
+		self.escrow_account(address)
+	}
+
+	/// Returns the scheme escrow accounts are derived with, Cosmos ADR-028 by default.
+	fn escrow_address_scheme(&self) -> EscrowAddressScheme {
+		EscrowAddressScheme::default()
+	}
 
-		String::from_utf8(hex::encode_upper(hash))
+	/// Converts the raw bytes of an escrow account, as derived by
+	/// [`Ics20Reader::escrow_address_scheme`], into an account. By default, the upper-case hex
+	/// encoding of the bytes is parsed as a [`Signer`].
+	fn escrow_account(
+		&self,
+		address: Vec<u8>,
+	) -> Result<<Self as Ics20Reader>::AccountId, Ics20Error> {
+		String::from_utf8(hex::encode_upper(address))
 			.expect("hex encoded bytes are not valid UTF8")
 			.parse::<Signer>()
 			.map_err(Ics20Error::signer)?
@@ -64,25 +134,97 @@ where
 	/// Returns true iff receive is enabled.
 	fn is_receive_enabled(&self) -> bool;
 
-	/// Returns a hash of the prefixed denom.
-	/// Implement only if the host chain supports hashed denominations.
-	fn denom_hash_string(&self, _denom: &PrefixedDenom) -> Option<String> {
-		None
+	/// Returns the reason the transfers identified by `key` are disabled, or `None` if they are
+	/// not. See [`ensure_transfer_enabled`](super::enablement::ensure_transfer_enabled).
+	fn get_transfer_disabled(&self, key: &EnablementKey) -> Result<Option<String>, Ics20Error>;
+
+	/// Returns the total amount of `denom` held in escrow across all channels, zero if none. Like
+	/// `TotalEscrowForDenom` of ibc-go, totals are kept per denomination as held on this chain,
+	/// with its full trace, so vouchers sent on do not mix with their base denomination. Hosts
+	/// adding ICS20 to a chain with outstanding escrows must initialise these totals.
+	fn get_total_escrow(&self, denom: &PrefixedDenom) -> Result<Amount, Ics20Error>;
+
+	/// Returns the denom trace stored under `trace_hash`, if any.
+	fn get_denom_trace(&self, trace_hash: &str) -> Result<Option<PrefixedDenom>, Ics20Error>;
+
+	/// Returns up to `limit` stored denom traces along with their hashes, ordered by hash and
+	/// starting right after `start_after` (or from the first one if `None`).
+	fn get_denom_traces(
+		&self,
+		start_after: Option<&str>,
+		limit: u64,
+	) -> Result<Vec<(String, PrefixedDenom)>, Ics20Error>;
+
+	/// Returns the hash of the trace of the prefixed denom, as an uppercase hex string.
+	fn denom_trace_hash(&self, denom: &PrefixedDenom) -> String {
+		let hash = self.hash(denom.to_string().into_bytes());
+		String::from_utf8(hex::encode_upper(hash)).expect("hex encoded bytes are not valid UTF8")
+	}
+
+	/// Returns the `ibc/{hash}` denomination of the prefixed denom, or its base denomination if it
+	/// has no trace.
+	fn ibc_denom(&self, denom: &PrefixedDenom) -> String {
+		if denom.trace_path().is_empty() {
+			denom.base_denom().to_string()
+		} else {
+			format!("{}{}", DENOM_HASH_PREFIX, self.denom_trace_hash(denom))
+		}
+	}
+
+	/// Parses a denomination, resolving `ibc/{hash}` denominations to their stored trace.
+	fn resolve_denom(&self, denom: &str) -> Result<PrefixedDenom, Ics20Error> {
+		match denom.strip_prefix(DENOM_HASH_PREFIX) {
+			Some(hash) => {
+				if hash.is_empty() || hash.contains('/') {
+					return Err(Ics20Error::malformed_hash_denom())
+				}
+				self.get_denom_trace(&hash.to_uppercase())?.ok_or_else(Ics20Error::trace_not_found)
+			},
+			None => denom.parse(),
+		}
 	}
 }
 
+/// The prefix of hashed IBC voucher denominations.
+pub const DENOM_HASH_PREFIX: &str = "ibc/";
+
+/// Number of denom traces returned by [`denom_traces`] when the request sets no limit.
+pub const DEFAULT_DENOM_TRACES_LIMIT: u64 = 100;
+
+/// Returns a page of the stored denom traces. Follows the cosmos-sdk key based pagination, i.e.
+/// the `next_key` of a page is the hash of its last trace.
+pub fn denom_traces(
+	ctx: &impl Ics20Reader,
+	pagination: Option<PageRequest>,
+) -> Result<(Vec<PrefixedDenom>, PageResponse), Ics20Error> {
+	let pagination = pagination.unwrap_or_default();
+	let limit = if pagination.limit == 0 { DEFAULT_DENOM_TRACES_LIMIT } else { pagination.limit };
+	let start_after = if pagination.key.is_empty() {
+		None
+	} else {
+		Some(String::from_utf8(pagination.key).map_err(Ics20Error::utf8)?)
+	};
+
+	// Fetch one extra trace to find out whether there is a next page.
+	let mut traces = ctx.get_denom_traces(start_after.as_deref(), limit.saturating_add(1))?;
+	let next_key = if traces.len() as u64 > limit {
+		traces.truncate(limit as usize);
+		traces.last().map(|(hash, _)| hash.clone().into_bytes()).unwrap_or_default()
+	} else {
+		vec![]
+	};
+
+	let traces = traces.into_iter().map(|(_, denom)| denom).collect();
+	Ok((traces, PageResponse { next_key, total: 0 }))
+}
+
 // https://github.com/cosmos/cosmos-sdk/blob/master/docs/architecture/adr-028-public-key-addresses.md
-fn cosmos_adr028_escrow_address(
+pub(crate) fn cosmos_adr028_escrow_address(
 	ctx: &dyn ChannelReader,
 	port_id: &PortId,
 	channel_id: ChannelId,
 ) -> Vec<u8> {
-	let contents = format!("{}/{}", port_id, channel_id);
-	let mut data = VERSION.as_bytes().to_vec();
-	data.extend_from_slice(&[0]);
-	data.extend_from_slice(contents.as_bytes());
-
-	let mut hash = ctx.hash(data);
+	let mut hash = ctx.hash(escrow_preimage(port_id, channel_id));
 	hash.truncate(20);
 	hash
 }
@@ -113,6 +255,20 @@ pub trait BankKeeper {
 	) -> Result<(), Ics20Error>;
 }
 
+/// Read access to the balances of the bank, for contexts which must know whether tokens can be
+/// moved before moving them, such as a
+/// [`CachedContext`](crate::core::ics26_routing::cache::CachedContext).
+pub trait BankReader {
+	type AccountId;
+
+	/// Returns the amount of `denom` held by `account`.
+	fn get_balance(
+		&self,
+		account: &Self::AccountId,
+		denom: &PrefixedDenom,
+	) -> Result<Amount, Ics20Error>;
+}
+
 /// Captures all the dependencies which the ICS20 module requires to be able to dispatch and
 /// process IBC messages.
 pub trait Ics20Context:
@@ -123,8 +279,21 @@ pub trait Ics20Context:
 	type AccountId: TryFrom<Signer>;
 }
 
+/// Returns the capability of a channel opened by the transfer module, required to send packets on
+/// the channel and to write their acknowledgements. The capabilities of the channels of the module
+/// are issued to it under [`MODULE_ID_STR`].
+pub(crate) fn channel_capability(
+	ctx: &impl Ics20Context,
+	port_id: &PortId,
+	channel_id: ChannelId,
+) -> Result<Capability, Ics20Error> {
+	let module_id: ModuleId = MODULE_ID_STR.parse().expect("invalid ModuleId");
+	lookup_capability(ctx, &CapabilityName::channel(port_id, channel_id), &module_id)
+		.map_err(|e| Ics20Error::ics04_channel(ChannelError::ics05_port(e)))
+}
+
 fn validate_transfer_channel_params(
-	ctx: &mut impl Ics20Context,
+	ctx: &impl Ics20Context,
 	order: Order,
 	port_id: &PortId,
 	channel_id: &ChannelId,
@@ -158,6 +327,18 @@ fn validate_counterparty_version(counterparty_version: &Version) -> Result<(), I
 	}
 }
 
+pub fn on_chan_open_init_validate(
+	ctx: &impl Ics20Context,
+	order: Order,
+	_connection_hops: &[ConnectionId],
+	port_id: &PortId,
+	channel_id: &ChannelId,
+	_counterparty: &Counterparty,
+	version: &Version,
+) -> Result<(), Ics20Error> {
+	validate_transfer_channel_params(ctx, order, port_id, channel_id, version)
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn on_chan_open_init(
 	ctx: &mut impl Ics20Context,
@@ -172,6 +353,21 @@ pub fn on_chan_open_init(
 	validate_transfer_channel_params(ctx, order, port_id, channel_id, version)
 }
 
+#[allow(clippy::too_many_arguments)]
+pub fn on_chan_open_try_validate(
+	ctx: &impl Ics20Context,
+	order: Order,
+	_connection_hops: &[ConnectionId],
+	port_id: &PortId,
+	channel_id: &ChannelId,
+	_counterparty: &Counterparty,
+	version: &Version,
+	counterparty_version: &Version,
+) -> Result<(), Ics20Error> {
+	validate_transfer_channel_params(ctx, order, port_id, channel_id, version)?;
+	validate_counterparty_version(counterparty_version)
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn on_chan_open_try(
 	ctx: &mut impl Ics20Context,
@@ -189,6 +385,15 @@ pub fn on_chan_open_try(
 	Ok(Version::ics20())
 }
 
+pub fn on_chan_open_ack_validate(
+	_ctx: &impl Ics20Context,
+	_port_id: &PortId,
+	_channel_id: &ChannelId,
+	counterparty_version: &Version,
+) -> Result<(), Ics20Error> {
+	validate_counterparty_version(counterparty_version)
+}
+
 pub fn on_chan_open_ack(
 	_ctx: &mut impl Ics20Context,
 	_output: &mut ModuleOutputBuilder,
@@ -209,6 +414,14 @@ pub fn on_chan_open_confirm(
 	Ok(())
 }
 
+pub fn on_chan_close_init_validate(
+	_ctx: &impl Ics20Context,
+	_port_id: &PortId,
+	_channel_id: &ChannelId,
+) -> Result<(), Ics20Error> {
+	Err(Ics20Error::cant_close_channel())
+}
+
 pub fn on_chan_close_init(
 	_ctx: &mut impl Ics20Context,
 	_output: &mut ModuleOutputBuilder,
@@ -251,6 +464,7 @@ pub fn on_recv_packet<Ctx: 'static + Ics20Context>(
 		receiver: data.receiver,
 		denom: data.token.denom,
 		amount: data.token.amount,
+		memo: data.memo,
 		success: ack.is_successful(),
 	};
 	output.emit(recv_event.into());
@@ -258,6 +472,19 @@ pub fn on_recv_packet<Ctx: 'static + Ics20Context>(
 	ack
 }
 
+pub fn on_acknowledgement_packet_validate(
+	_ctx: &impl Ics20Context,
+	packet: &Packet,
+	acknowledgement: &GenericAcknowledgement,
+	_relayer: &Signer,
+) -> Result<(), Ics20Error> {
+	serde_json::from_slice::<PacketData>(&packet.data)
+		.map_err(|_| Ics20Error::packet_data_deserialization())?;
+	serde_json::from_slice::<Acknowledgement>(acknowledgement.as_ref())
+		.map_err(|_| Ics20Error::ack_deserialization())?;
+	Ok(())
+}
+
 pub fn on_acknowledgement_packet(
 	ctx: &mut impl Ics20Context,
 	output: &mut ModuleOutputBuilder,
@@ -277,6 +504,7 @@ pub fn on_acknowledgement_packet(
 		receiver: data.receiver,
 		denom: data.token.denom,
 		amount: data.token.amount,
+		memo: data.memo,
 		acknowledgement: acknowledgement.clone(),
 	};
 	output.emit(ack_event.into());
@@ -285,6 +513,16 @@ pub fn on_acknowledgement_packet(
 	Ok(())
 }
 
+pub fn on_timeout_packet_validate(
+	_ctx: &impl Ics20Context,
+	packet: &Packet,
+	_relayer: &Signer,
+) -> Result<(), Ics20Error> {
+	serde_json::from_slice::<PacketData>(&packet.data)
+		.map_err(|_| Ics20Error::packet_data_deserialization())?;
+	Ok(())
+}
+
 pub fn on_timeout_packet(
 	ctx: &mut impl Ics20Context,
 	output: &mut ModuleOutputBuilder,
@@ -315,22 +553,36 @@ pub(crate) mod test {
 
 	use crate::{
 		applications::transfer::{
-			context::cosmos_adr028_escrow_address, error::Error as Ics20Error,
-			msgs::transfer::MsgTransfer, relay::send_transfer::send_transfer, PrefixedCoin,
+			context::{cosmos_adr028_escrow_address, denom_traces, Ics20Keeper, Ics20Reader},
+			error::Error as Ics20Error,
+			msgs::transfer::MsgTransfer,
+			packet::PacketData,
+			relay::{
+				on_recv_packet::process_recv_packet, on_timeout_packet::process_timeout_packet,
+				send_transfer::send_transfer,
+			},
+			Amount, PrefixedCoin, PrefixedDenom,
+		},
+		core::{
+			ics04_channel::{error::Error, packet::Packet},
+			ics24_host::identifier::{ChannelId, PortId},
+			ics26_routing::context::ModuleOutputBuilder,
 		},
-		core::ics04_channel::error::Error,
 		handler::HandlerOutputBuilder,
 		mock::context::{HostBlockType, MockClientTypes, MockIbcStore},
 		prelude::*,
-		test_utils::DummyTransferModule,
+		test_utils::{get_dummy_account_id, DummyTransferModule},
 	};
+	use ibc_proto::cosmos::base::query::v1beta1::PageRequest;
 
 	pub(crate) fn deliver<C: HostBlockType>(
 		ctx: &mut DummyTransferModule<C>,
 		output: &mut HandlerOutputBuilder<()>,
 		msg: MsgTransfer<PrefixedCoin>,
 	) -> Result<(), Error> {
-		send_transfer(ctx, output, msg).map_err(|e: Ics20Error| Error::app_module(e.to_string()))
+		send_transfer(ctx, output, msg)
+			.map(|_| ())
+			.map_err(|e: Ics20Error| Error::app_module(e.to_string()))
 	}
 
 	#[test]
@@ -364,4 +616,117 @@ pub(crate) mod test {
 			"cosmos177x69sver58mcfs74x6dg0tv6ls4s3xmmcaw53",
 		);
 	}
+
+	#[test]
+	fn test_denom_traces() -> Result<(), Ics20Error> {
+		let ibc_store = MockIbcStore::<MockClientTypes>::default();
+		let mut ctx = DummyTransferModule::new(Arc::new(Mutex::new(ibc_store)));
+
+		// hash obtained using `gaiad query ibc-transfer denom-hash [trace]`
+		let denom: PrefixedDenom = "transfer/channel-0/uatom".parse()?;
+		let hash = ctx.denom_trace_hash(&denom);
+		assert_eq!(hash, "27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2");
+		assert_eq!(ctx.ibc_denom(&denom), format!("ibc/{}", hash));
+		assert_eq!(ctx.ibc_denom(&"uatom".parse::<PrefixedDenom>()?), "uatom");
+
+		assert!(ctx.resolve_denom(&format!("ibc/{}", hash)).is_err(), "unknown trace");
+		ctx.store_denom_trace(hash.clone(), denom.clone())?;
+		assert_eq!(ctx.resolve_denom(&format!("ibc/{}", hash))?, denom);
+		assert_eq!(ctx.resolve_denom(&format!("ibc/{}", hash.to_lowercase()))?, denom);
+		assert_eq!(ctx.resolve_denom("transfer/channel-0/uatom")?, denom);
+		assert!(ctx.resolve_denom("ibc/").is_err(), "missing hash");
+
+		for channel in 1..5 {
+			let denom: PrefixedDenom = format!("transfer/channel-{}/uatom", channel).parse()?;
+			ctx.store_denom_trace(ctx.denom_trace_hash(&denom), denom)?;
+		}
+		let request = |key: Vec<u8>| Some(PageRequest { key, limit: 2, ..Default::default() });
+		let (first_page, page) = denom_traces(&ctx, request(vec![]))?;
+		assert_eq!(first_page.len(), 2);
+		let (second_page, page) = denom_traces(&ctx, request(page.next_key))?;
+		assert_eq!(second_page.len(), 2);
+		let (last_page, page) = denom_traces(&ctx, request(page.next_key))?;
+		assert_eq!(last_page.len(), 1);
+		assert!(page.next_key.is_empty());
+		assert!(!first_page.iter().chain(&second_page).any(|denom| last_page.contains(denom)));
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_total_escrow_refund() -> Result<(), Ics20Error> {
+		let ibc_store = MockIbcStore::<MockClientTypes>::default();
+		let mut ctx = DummyTransferModule::new(Arc::new(Mutex::new(ibc_store)));
+
+		let denom: PrefixedDenom = "uatom".parse()?;
+		let data = PacketData {
+			token: PrefixedCoin { denom: denom.clone(), amount: 10u64.into() },
+			sender: get_dummy_account_id(),
+			receiver: get_dummy_account_id(),
+			memo: String::new(),
+		};
+		let packet = Packet {
+			source_port: PortId::transfer(),
+			destination_port: PortId::transfer(),
+			data: serde_json::to_vec(&data).unwrap(),
+			..Default::default()
+		};
+
+		// refunding more than the total escrow violates the escrow invariant
+		assert!(process_timeout_packet(&mut ctx, &packet, &data).is_err());
+		assert_eq!(ctx.get_total_escrow(&denom)?, Amount::from(0u64));
+
+		ctx.store_total_escrow(&denom, 100u64.into())?;
+		process_timeout_packet(&mut ctx, &packet, &data)?;
+		assert_eq!(ctx.get_total_escrow(&denom)?, Amount::from(90u64));
+
+		// the total escrow is tracked per denomination trace
+		let voucher: PrefixedDenom = "transfer/channel-5/uatom".parse()?;
+		let mut data = data;
+		data.token.denom = voucher.clone();
+		let packet = Packet { data: serde_json::to_vec(&data).unwrap(), ..packet };
+		assert!(process_timeout_packet(&mut ctx, &packet, &data).is_err());
+		ctx.store_total_escrow(&voucher, 50u64.into())?;
+		process_timeout_packet(&mut ctx, &packet, &data)?;
+		assert_eq!(ctx.get_total_escrow(&voucher)?, Amount::from(40u64));
+		assert_eq!(ctx.get_total_escrow(&denom)?, Amount::from(90u64));
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_total_escrow_recv() -> Result<(), Ics20Error> {
+		let ibc_store = MockIbcStore::<MockClientTypes>::default();
+		let mut ctx = DummyTransferModule::new(Arc::new(Mutex::new(ibc_store)));
+
+		let denom: PrefixedDenom = "uatom".parse()?;
+		ctx.store_total_escrow(&denom, 100u64.into())?;
+		let data = PacketData {
+			token: PrefixedCoin {
+				denom: "transfer/channel-1/uatom".parse()?,
+				amount: 10u64.into(),
+			},
+			sender: get_dummy_account_id(),
+			receiver: get_dummy_account_id(),
+			memo: String::new(),
+		};
+		let packet = Packet {
+			source_port: PortId::transfer(),
+			source_channel: ChannelId::new(1),
+			destination_port: PortId::transfer(),
+			destination_channel: ChannelId::new(0),
+			data: serde_json::to_vec(&data).unwrap(),
+			..Default::default()
+		};
+
+		// packets received before either is written all decrease the total escrow
+		let mut output = ModuleOutputBuilder::new();
+		let first = process_recv_packet(&ctx, &mut output, &packet, data.clone())?;
+		let second = process_recv_packet(&ctx, &mut output, &packet, data)?;
+		first(&mut ctx).unwrap();
+		second(&mut ctx).unwrap();
+		assert_eq!(ctx.get_total_escrow(&denom)?, Amount::from(80u64));
+
+		Ok(())
+	}
 }
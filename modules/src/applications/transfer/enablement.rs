@@ -0,0 +1,135 @@
+//! Per-denomination enablement of ICS20 transfers.
+//!
+//! On top of the global [`Ics20Reader::is_send_enabled`] and
+//! [`Ics20Reader::is_receive_enabled`] switches, transfers of a single denomination can be
+//! disabled, either on every channel or on a given one, e.g. to pause a compromised asset.
+
+use core::fmt;
+
+use crate::{
+	applications::transfer::{
+		context::{Ics20Context, Ics20Reader},
+		error::Error as Ics20Error,
+		PrefixedDenom,
+	},
+	core::ics24_host::identifier::ChannelId,
+	prelude::*,
+};
+
+/// The direction of a transfer, from the point of view of the host chain.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TransferDirection {
+	Send,
+	Receive,
+}
+
+impl fmt::Display for TransferDirection {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			TransferDirection::Send => write!(f, "send"),
+			TransferDirection::Receive => write!(f, "receive"),
+		}
+	}
+}
+
+/// Identifies the transfers an enablement flag applies to. The denomination is the one used on
+/// the host chain, i.e. as it appears in outgoing packets and after trace manipulation for
+/// incoming ones. A `channel_id` of `None` applies to all channels.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct EnablementKey {
+	pub direction: TransferDirection,
+	pub denom: PrefixedDenom,
+	pub channel_id: Option<ChannelId>,
+}
+
+impl EnablementKey {
+	pub fn new(
+		direction: TransferDirection,
+		denom: PrefixedDenom,
+		channel_id: Option<ChannelId>,
+	) -> Self {
+		Self { direction, denom, channel_id }
+	}
+}
+
+/// Fails with the stored reason if transfers of `denom` through `channel_id` in the `direction`
+/// direction are disabled, either on this channel or on all channels.
+pub fn ensure_transfer_enabled(
+	ctx: &impl Ics20Reader,
+	direction: TransferDirection,
+	denom: &PrefixedDenom,
+	channel_id: ChannelId,
+) -> Result<(), Ics20Error> {
+	for channel_id in [Some(channel_id), None] {
+		let key = EnablementKey::new(direction, denom.clone(), channel_id);
+		if let Some(reason) = ctx.get_transfer_disabled(&key)? {
+			return Err(Ics20Error::denom_transfer_disabled(
+				direction.to_string(),
+				denom.to_string(),
+				reason,
+			))
+		}
+	}
+	Ok(())
+}
+
+/// Disables the transfers identified by `key`, `reason` being reported in the resulting errors
+/// and error acknowledgements. Meant to be called from a governance proposal.
+pub fn disable_transfers(
+	ctx: &mut impl Ics20Context,
+	key: EnablementKey,
+	reason: String,
+) -> Result<(), Ics20Error> {
+	if reason.trim().is_empty() {
+		return Err(Ics20Error::empty_disable_reason())
+	}
+	ctx.store_transfer_disabled(key, Some(reason))
+}
+
+/// Lifts a flag previously set with [`disable_transfers`]. Meant to be called from a governance
+/// proposal.
+pub fn enable_transfers(ctx: &mut impl Ics20Context, key: EnablementKey) -> Result<(), Ics20Error> {
+	ctx.store_transfer_disabled(key, None)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::{Arc, Mutex};
+
+	use super::*;
+	use crate::{
+		mock::context::{MockClientTypes, MockIbcStore},
+		test_utils::DummyTransferModule,
+	};
+
+	#[test]
+	fn test_denom_enablement() -> Result<(), Ics20Error> {
+		let ibc_store = MockIbcStore::<MockClientTypes>::default();
+		let mut ctx = DummyTransferModule::new(Arc::new(Mutex::new(ibc_store)));
+
+		let (send, receive) = (TransferDirection::Send, TransferDirection::Receive);
+		let denom: PrefixedDenom = "uatom".parse()?;
+		let other_denom: PrefixedDenom = "uosmo".parse()?;
+		let (channel_0, channel_1) = (ChannelId::new(0), ChannelId::new(1));
+
+		let channel_key = EnablementKey::new(send, denom.clone(), Some(channel_1));
+		assert!(disable_transfers(&mut ctx, channel_key.clone(), " ".to_string()).is_err());
+		disable_transfers(&mut ctx, channel_key.clone(), "exploit".to_string())?;
+		assert!(ensure_transfer_enabled(&ctx, send, &denom, channel_0).is_ok());
+		let err = ensure_transfer_enabled(&ctx, send, &denom, channel_1).unwrap_err();
+		assert!(err.to_string().contains("exploit"));
+		assert!(ensure_transfer_enabled(&ctx, receive, &denom, channel_1).is_ok());
+
+		let denom_key = EnablementKey::new(send, denom.clone(), None);
+		disable_transfers(&mut ctx, denom_key.clone(), "exploit".to_string())?;
+		assert!(ensure_transfer_enabled(&ctx, send, &denom, channel_0).is_err());
+		assert!(ensure_transfer_enabled(&ctx, send, &other_denom, channel_0).is_ok());
+
+		enable_transfers(&mut ctx, denom_key)?;
+		enable_transfers(&mut ctx, channel_key)?;
+		assert!(ensure_transfer_enabled(&ctx, send, &denom, channel_0).is_ok());
+		assert!(ensure_transfer_enabled(&ctx, send, &denom, channel_1).is_ok());
+
+		Ok(())
+	}
+}
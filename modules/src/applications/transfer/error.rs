@@ -123,6 +123,13 @@ define_error! {
 		SendDisabled
 			| _ | { "send is not enabled" },
 
+		DenomTransferDisabled
+			{ direction: String, denom: String, reason: String }
+			| e | { format_args!("{0} of {1} is disabled: {2}", e.direction, e.denom, e.reason) },
+
+		EmptyDisableReason
+			| _ | { "a reason must be given to disable transfers" },
+
 		ParseAccountFailure
 			| _ | { "failed to parse as AccountId" },
 
@@ -140,5 +147,79 @@ define_error! {
 		UnknownMsgType
 			{ msg_type: String }
 			| e | { format_args!("unknown msg type: {0}", e.msg_type) },
+
+		NegativeTotalEscrow
+			{ denom: String, total_escrow: String, amount: String }
+			| e | { format_args!("total escrow of {0} ({1}) is lower than the unescrowed amount {2}", e.denom, e.total_escrow, e.amount) },
+
+		TotalEscrowOverflow
+			{ denom: String }
+			| e | { format_args!("total escrow of {0} overflows", e.denom) },
+
+		InsufficientFunds
+			{ denom: String, balance: String, amount: String }
+			| e | { format_args!("insufficient funds: balance of {0} is {1}, lower than {2}", e.denom, e.balance, e.amount) },
+
+		BalanceOverflow
+			{ denom: String }
+			| e | { format_args!("balance of {0} overflows", e.denom) },
+
+		RateLimitExceeded
+			{ channel_id: ChannelId, denom: String, quota: String }
+			| e | { format_args!("rate limit quota '{0}' exceeded for denom {1} on channel {2}", e.quota, e.denom, e.channel_id) },
+
+		RateLimitQuotaAlreadyExists
+			{ quota: String }
+			| e | { format_args!("rate limit quota '{0}' already exists", e.quota) },
+
+		RateLimitQuotaNotFound
+			{ quota: String }
+			| e | { format_args!("rate limit quota '{0}' not found", e.quota) },
+
+		InvalidRateLimitQuota
+			{ reason: String }
+			| e | { format_args!("invalid rate limit quota: {0}", e.reason) },
+
+		RateLimitOverflow
+			| _ | { "overflow while updating rate limit flow" },
+
+		InvalidCallback
+			{ reason: String }
+			| e | { format_args!("invalid packet callback in memo: {0}", e.reason) },
+
+		CallbackFailed
+			{ address: String, reason: String }
+			| e | { format_args!("callback to {0} failed: {1}", e.address, e.reason) },
+
+		CallbackOutOfGas
+			{ address: String, gas_limit: u64 }
+			| e | { format_args!("callback to {0} ran out of gas (limit: {1})", e.address, e.gas_limit) },
+
+		InvalidForwardMetadata
+			{ reason: String }
+			| e | { format_args!("invalid forward metadata in memo: {0}", e.reason) },
+
+		ForwardTimeoutOverflow
+			| _ | { "timeout of forwarded transfer overflows" },
+
+		ForwardFailed
+			{ reason: String }
+			| e | { format_args!("forwarded transfer failed: {0}", e.reason) },
+
+		InvalidTransferAuthorization
+			{ reason: String }
+			| e | { format_args!("invalid transfer authorization: {0}", e.reason) },
+
+		UnauthorizedTransfer
+			{ reason: String }
+			| e | { format_args!("transfer not authorized: {0}", e.reason) },
+
+		SpendLimitExceeded
+			{ denom: String }
+			| e | { format_args!("spend limit exceeded for denom {0}", e.denom) },
+
+		TransferAuthorizationNotFound
+			{ granter: String, grantee: String }
+			| e | { format_args!("no transfer authorization from {0} to {1}", e.granter, e.grantee) },
 	}
 }
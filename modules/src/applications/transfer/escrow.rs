@@ -0,0 +1,123 @@
+//! Derivation of the accounts holding the tokens escrowed by ICS20 channels.
+//!
+//! Escrow accounts are derived deterministically from the port and channel identifiers, so that
+//! anyone, e.g. a counterparty auditing the escrowed supply, can compute them. The schemes below
+//! return the raw account bytes, which [`Ics20Reader::escrow_account`] turns into a host account.
+//!
+//! [`Ics20Reader::escrow_account`]: crate::applications::transfer::context::Ics20Reader::escrow_account
+
+use codec::Encode;
+
+use crate::{
+	applications::transfer::{context::cosmos_adr028_escrow_address, VERSION},
+	core::{
+		ics04_channel::context::ChannelReader,
+		ics23_commitment::ethereum::KeccakHostFunctions,
+		ics24_host::identifier::{ChannelId, PortId},
+	},
+	prelude::*,
+};
+
+/// Length of Substrate `AccountId32` accounts.
+const SUBSTRATE_ACCOUNT_LEN: usize = 32;
+
+/// Length of EVM addresses.
+const EVM_ADDRESS_LEN: usize = 20;
+
+/// The ways escrow accounts can be derived from a port and a channel identifier.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum EscrowAddressScheme {
+	/// Cosmos SDK module account address, as used by ibc-go: the first 20 bytes of
+	/// `sha256("ics20-1" || 0x00 || "{port}/{channel}")`, `sha256` being
+	/// [`ChannelReader::hash`].
+	CosmosAdr028,
+	/// Substrate module sub-account, as derived by `PalletId::into_sub_account_truncating`:
+	/// `"modl" || pallet_id || SCALE((channel sequence, port))`, zero-padded or truncated to 32
+	/// bytes. Channel sequences being unique on a chain, truncated ports cannot collide.
+	SubstrateModuleAccount { pallet_id: [u8; 8] },
+	/// EVM address: the last 20 bytes of `keccak256("ics20-1" || 0x00 || "{port}/{channel}")`,
+	/// `keccak256` being [`KeccakHostFunctions::keccak_256`].
+	Evm,
+}
+
+impl Default for EscrowAddressScheme {
+	fn default() -> Self {
+		EscrowAddressScheme::CosmosAdr028
+	}
+}
+
+impl EscrowAddressScheme {
+	/// Returns the raw bytes of the escrow account of `channel_id` on `port_id`.
+	pub fn escrow_address<H: KeccakHostFunctions>(
+		&self,
+		ctx: &dyn ChannelReader,
+		port_id: &PortId,
+		channel_id: ChannelId,
+	) -> Vec<u8> {
+		match self {
+			EscrowAddressScheme::CosmosAdr028 =>
+				cosmos_adr028_escrow_address(ctx, port_id, channel_id),
+			EscrowAddressScheme::SubstrateModuleAccount { pallet_id } => {
+				let mut address =
+					(*b"modl", *pallet_id, channel_id.sequence(), port_id.as_str()).encode();
+				address.resize(SUBSTRATE_ACCOUNT_LEN, 0);
+				address
+			},
+			EscrowAddressScheme::Evm => {
+				let hash = H::keccak_256(&escrow_preimage(port_id, channel_id));
+				hash[hash.len() - EVM_ADDRESS_LEN..].to_vec()
+			},
+		}
+	}
+}
+
+/// The data hashed by the ADR-028 and EVM schemes.
+pub(crate) fn escrow_preimage(port_id: &PortId, channel_id: ChannelId) -> Vec<u8> {
+	let contents = format!("{}/{}", port_id, channel_id);
+	let mut data = VERSION.as_bytes().to_vec();
+	data.extend_from_slice(&[0]);
+	data.extend_from_slice(contents.as_bytes());
+	data
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::{Arc, Mutex};
+
+	use subtle_encoding::hex;
+
+	use super::*;
+	use crate::{
+		mock::context::{MockClientTypes, MockIbcStore},
+		test_utils::{Crypto, DummyTransferModule},
+	};
+
+	#[test]
+	fn test_escrow_address_schemes() {
+		let ctx = DummyTransferModule::new(Arc::new(Mutex::new(
+			MockIbcStore::<MockClientTypes>::default(),
+		)));
+		let port_id = PortId::transfer();
+		let address = |scheme: EscrowAddressScheme, channel| {
+			scheme.escrow_address::<Crypto>(&ctx, &port_id, channel)
+		};
+
+		// the escrow account of the transfer/channel-141 channel of the Cosmos Hub, i.e.
+		// cosmos1x54ltnyg88k0ejmk8ytwrhd3ltm84xehrnlslf
+		let adr028 = address(EscrowAddressScheme::default(), ChannelId::new(141));
+		assert_eq!(hex::encode_upper(adr028), b"352BF5CC8839ECFCCB763916E1DDB1FAF67A9B37");
+
+		let substrate = EscrowAddressScheme::SubstrateModuleAccount { pallet_id: *b"ics20-1 " };
+		let account = address(substrate, ChannelId::new(1));
+		assert_eq!(account.len(), SUBSTRATE_ACCOUNT_LEN);
+		assert_eq!(&account[..12], b"modlics20-1 ");
+		assert_eq!(&account[12..20], &1u64.to_le_bytes());
+		assert_ne!(account, address(substrate, ChannelId::new(10)));
+
+		let evm_address = address(EscrowAddressScheme::Evm, ChannelId::new(1));
+		assert_eq!(evm_address.len(), EVM_ADDRESS_LEN);
+		let hash = Crypto::keccak_256(&escrow_preimage(&port_id, ChannelId::new(1)));
+		assert_eq!(evm_address, hash[12..].to_vec());
+		assert_ne!(evm_address, address(EscrowAddressScheme::Evm, ChannelId::new(2)));
+	}
+}
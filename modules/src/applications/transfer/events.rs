@@ -1,7 +1,10 @@
 use crate::{
 	applications::transfer::{
-		acknowledgement::Acknowledgement, Amount, PrefixedDenom, MODULE_ID_STR,
+		acknowledgement::Acknowledgement,
+		callbacks::{CallbackTarget, CallbackType},
+		Amount, PrefixedDenom, MODULE_ID_STR,
 	},
+	core::ics04_channel::packet::Sequence,
 	events::ModuleEvent,
 	prelude::*,
 	signer::Signer,
@@ -11,6 +14,8 @@ const EVENT_TYPE_PACKET: &str = "fungible_token_packet";
 const EVENT_TYPE_TIMEOUT: &str = "timeout";
 const EVENT_TYPE_DENOM_TRACE: &str = "denomination_trace";
 const EVENT_TYPE_TRANSFER: &str = "ibc_transfer";
+const EVENT_TYPE_SOURCE_CALLBACK: &str = "ibc_src_callback";
+const EVENT_TYPE_DESTINATION_CALLBACK: &str = "ibc_dest_callback";
 
 pub enum Event {
 	Recv(RecvEvent),
@@ -19,18 +24,20 @@ pub enum Event {
 	Timeout(TimeoutEvent),
 	DenomTrace(DenomTraceEvent),
 	Transfer(TransferEvent),
+	Callback(CallbackEvent),
 }
 
 pub struct RecvEvent {
 	pub receiver: Signer,
 	pub denom: PrefixedDenom,
 	pub amount: Amount,
+	pub memo: String,
 	pub success: bool,
 }
 
 impl From<RecvEvent> for ModuleEvent {
 	fn from(ev: RecvEvent) -> Self {
-		let RecvEvent { receiver, denom, amount, success } = ev;
+		let RecvEvent { receiver, denom, amount, memo, success } = ev;
 		Self {
 			kind: EVENT_TYPE_PACKET.to_string(),
 			module_name: MODULE_ID_STR.parse().expect("invalid ModuleId"),
@@ -38,6 +45,7 @@ impl From<RecvEvent> for ModuleEvent {
 				("receiver", receiver).into(),
 				("denom", denom).into(),
 				("amount", amount).into(),
+				("memo", memo).into(),
 				("success", success).into(),
 			],
 		}
@@ -48,12 +56,13 @@ pub struct AckEvent {
 	pub receiver: Signer,
 	pub denom: PrefixedDenom,
 	pub amount: Amount,
+	pub memo: String,
 	pub acknowledgement: Acknowledgement,
 }
 
 impl From<AckEvent> for ModuleEvent {
 	fn from(ev: AckEvent) -> Self {
-		let AckEvent { receiver, denom, amount, acknowledgement } = ev;
+		let AckEvent { receiver, denom, amount, memo, acknowledgement } = ev;
 		Self {
 			kind: EVENT_TYPE_PACKET.to_string(),
 			module_name: MODULE_ID_STR.parse().expect("invalid ModuleId"),
@@ -61,6 +70,7 @@ impl From<AckEvent> for ModuleEvent {
 				("receiver", receiver).into(),
 				("denom", denom).into(),
 				("amount", amount).into(),
+				("memo", memo).into(),
 				("acknowledgement", acknowledgement).into(),
 			],
 		}
@@ -110,22 +120,18 @@ impl From<TimeoutEvent> for ModuleEvent {
 }
 
 pub struct DenomTraceEvent {
-	pub trace_hash: Option<String>,
+	pub trace_hash: String,
 	pub denom: PrefixedDenom,
 }
 
 impl From<DenomTraceEvent> for ModuleEvent {
 	fn from(ev: DenomTraceEvent) -> Self {
 		let DenomTraceEvent { trace_hash, denom } = ev;
-		let mut ev = Self {
+		Self {
 			kind: EVENT_TYPE_DENOM_TRACE.to_string(),
 			module_name: MODULE_ID_STR.parse().expect("invalid ModuleId"),
-			attributes: vec![("denom", denom).into()],
-		};
-		if let Some(hash) = trace_hash {
-			ev.attributes.push(("trace_hash", hash).into());
+			attributes: vec![("trace_hash", trace_hash).into(), ("denom", denom).into()],
 		}
-		ev
 	}
 }
 
@@ -145,6 +151,42 @@ impl From<TransferEvent> for ModuleEvent {
 	}
 }
 
+pub struct CallbackEvent {
+	pub callback_type: CallbackType,
+	pub target: CallbackTarget,
+	pub sequence: Sequence,
+	/// The error returned by the callback, if any.
+	pub error: Option<String>,
+}
+
+impl From<CallbackEvent> for ModuleEvent {
+	fn from(ev: CallbackEvent) -> Self {
+		let CallbackEvent { callback_type, target, sequence, error } = ev;
+		let kind = match callback_type {
+			CallbackType::ReceivePacket => EVENT_TYPE_DESTINATION_CALLBACK,
+			_ => EVENT_TYPE_SOURCE_CALLBACK,
+		};
+		let mut event = Self {
+			kind: kind.to_string(),
+			module_name: MODULE_ID_STR.parse().expect("invalid ModuleId"),
+			attributes: vec![
+				("callback_type", callback_type).into(),
+				("callback_address", target.address).into(),
+				("callback_exec_gas_limit", target.gas_limit).into(),
+				("packet_sequence", sequence).into(),
+			],
+		};
+		match error {
+			None => event.attributes.push(("callback_result", "success").into()),
+			Some(error) => {
+				event.attributes.push(("callback_result", "failure").into());
+				event.attributes.push(("callback_error", error).into());
+			},
+		}
+		event
+	}
+}
+
 impl From<Event> for ModuleEvent {
 	fn from(ev: Event) -> Self {
 		match ev {
@@ -154,6 +196,7 @@ impl From<Event> for ModuleEvent {
 			Event::Timeout(ev) => ev.into(),
 			Event::DenomTrace(ev) => ev.into(),
 			Event::Transfer(ev) => ev.into(),
+			Event::Callback(ev) => ev.into(),
 		}
 	}
 }
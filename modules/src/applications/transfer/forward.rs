@@ -0,0 +1,794 @@
+//! Packet forwarding middleware, routing ICS20 transfers through the host chain on their way to
+//! another chain.
+//!
+//! An incoming transfer is forwarded when its memo is a JSON object with a `forward` entry:
+//!
+//! ```json
+//! {
+//!   "forward": {
+//!     "receiver": "<address on the next chain>",
+//!     "port": "transfer",
+//!     "channel": "channel-1",
+//!     "timeout": "10m",
+//!     "retries": 2,
+//!     "next": { "forward": { ... } }
+//!   }
+//! }
+//! ```
+//!
+//! where `timeout`, `retries` and `next`, the memo of the forwarded transfer, are optional. The
+//! tokens are credited to an intermediate account provided by the host, then sent to the next hop.
+//! The acknowledgement of the incoming packet is withheld until the forwarded packet is
+//! acknowledged, and the forwarded transfer is resent if it times out while retries are left.
+//! When the forwarded transfer fails, the tokens received are burnt or escrowed back and the
+//! incoming packet is answered with an error acknowledgement, so that every chain along the path
+//! refunds its own sender.
+
+use core::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+	applications::transfer::{
+		acknowledgement::Acknowledgement,
+		context::Ics20Context,
+		error::Error as Ics20Error,
+		msgs::transfer::MsgTransfer,
+		packet::PacketData,
+		relay::{
+			received_denom, revert_received_token, send_transfer::send_transfer,
+			write_packet_acknowledgement,
+		},
+		PrefixedCoin,
+	},
+	core::{
+		ics02_client::height::Height,
+		ics04_channel::{
+			channel::{Counterparty, Order},
+			error::Error as Ics04Error,
+			msgs::acknowledgement::Acknowledgement as GenericAcknowledgement,
+			packet::{Packet, Sequence},
+			Version,
+		},
+		ics24_host::identifier::{ChannelId, ConnectionId, PortId},
+		ics26_routing::context::{Module, ModuleOutputBuilder, OnRecvPacketAck, WriteFn},
+	},
+	events::IbcEvent,
+	handler::HandlerOutputBuilder,
+	prelude::*,
+	signer::Signer,
+};
+
+/// Timeout of forwarded transfers whose memo does not specify one.
+pub const DEFAULT_FORWARD_TIMEOUT: Duration = Duration::from_secs(28 * 24 * 60 * 60);
+
+/// Number of times a forwarded transfer is resent after a timeout when its memo does not specify
+/// it.
+pub const DEFAULT_FORWARD_RETRIES: u8 = 1;
+
+/// The `forward` directive of a transfer memo.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ForwardMetadata {
+	/// The receiver on the next chain.
+	pub receiver: Signer,
+	/// The port the transfer is forwarded on.
+	pub port: PortId,
+	/// The channel the transfer is forwarded on.
+	pub channel: ChannelId,
+	/// Timeout of the forwarded transfer, relative to the time it is sent.
+	pub timeout: Duration,
+	/// Number of times the forwarded transfer is resent after a timeout.
+	pub retries: u8,
+	/// Memo of the forwarded transfer.
+	pub next: String,
+}
+
+#[derive(Deserialize)]
+struct RawForwardMetadata {
+	receiver: String,
+	port: String,
+	channel: String,
+	#[serde(default)]
+	timeout: Option<Value>,
+	#[serde(default)]
+	retries: Option<u8>,
+	#[serde(default)]
+	next: Option<Value>,
+}
+
+impl ForwardMetadata {
+	/// Parses the `forward` directive of `memo`. Memos that are not JSON objects, or that have no
+	/// such directive, are not forwarded.
+	pub fn from_memo(memo: &str) -> Result<Option<Self>, Ics20Error> {
+		let raw = match serde_json::from_str::<Value>(memo) {
+			Ok(Value::Object(mut memo)) => match memo.remove("forward") {
+				Some(raw) => raw,
+				None => return Ok(None),
+			},
+			_ => return Ok(None),
+		};
+		let raw = serde_json::from_value::<RawForwardMetadata>(raw)
+			.map_err(|e| Ics20Error::invalid_forward_metadata(e.to_string()))?;
+
+		let invalid =
+			|e: &dyn core::fmt::Display| Ics20Error::invalid_forward_metadata(e.to_string());
+		let timeout = match raw.timeout {
+			Some(timeout) => parse_timeout(&timeout)?,
+			None => DEFAULT_FORWARD_TIMEOUT,
+		};
+		let next = match raw.next {
+			None | Some(Value::Null) => String::new(),
+			Some(Value::String(next)) => next,
+			Some(next) => next.to_string(),
+		};
+
+		Ok(Some(Self {
+			receiver: raw.receiver.parse().map_err(|e| invalid(&e))?,
+			port: raw.port.parse().map_err(|e| invalid(&e))?,
+			channel: raw.channel.parse().map_err(|e| invalid(&e))?,
+			timeout,
+			retries: raw.retries.unwrap_or(DEFAULT_FORWARD_RETRIES),
+			next,
+		}))
+	}
+}
+
+/// Parses a timeout given either as a number of nanoseconds, or as a string made of a number
+/// followed by one of the `ns`, `s`, `m` or `h` units.
+fn parse_timeout(timeout: &Value) -> Result<Duration, Ics20Error> {
+	let invalid = || Ics20Error::invalid_forward_metadata(format!("invalid timeout {}", timeout));
+	let duration = match timeout {
+		Value::Number(nanos) => nanos.as_u64().map(Duration::from_nanos),
+		Value::String(timeout) => {
+			let split = timeout.find(|c: char| !c.is_ascii_digit()).unwrap_or(timeout.len());
+			let (value, unit) = timeout.split_at(split);
+			let value = value.parse::<u64>().map_err(|_| invalid())?;
+			match unit {
+				"" | "ns" => Some(Duration::from_nanos(value)),
+				"s" => Some(Duration::from_secs(value)),
+				"m" => value.checked_mul(60).map(Duration::from_secs),
+				"h" => value.checked_mul(60 * 60).map(Duration::from_secs),
+				_ => None,
+			}
+		},
+		_ => None,
+	};
+	duration.filter(|duration| !duration.is_zero()).ok_or_else(invalid)
+}
+
+/// Identifies a forwarded packet by its source port, source channel and sequence.
+pub type InFlightKey = (PortId, ChannelId, Sequence);
+
+/// A forwarded transfer waiting for its acknowledgement.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InFlightPacket {
+	/// The incoming packet whose acknowledgement is withheld.
+	pub original_packet: Packet,
+	/// The intermediate account the tokens are forwarded from.
+	pub sender: Signer,
+	/// The tokens forwarded, in their denomination on the host chain.
+	pub token: PrefixedCoin,
+	pub forward: ForwardMetadata,
+	/// Number of times the forwarded transfer may still be resent after a timeout.
+	pub retries_left: u8,
+}
+
+/// A context supplying the read-only dependencies of the forward middleware.
+pub trait ForwardReader {
+	/// Returns the account credited with the tokens of a transfer received from `sender` on
+	/// `channel_id` before they are forwarded.
+	fn intermediate_account(
+		&self,
+		channel_id: &ChannelId,
+		sender: &Signer,
+	) -> Result<Signer, Ics20Error>;
+
+	/// Returns the forwarded transfer that went out as `key`, if any.
+	fn get_in_flight_packet(&self, key: &InFlightKey)
+		-> Result<Option<InFlightPacket>, Ics20Error>;
+}
+
+/// A context supplying the write dependencies of the forward middleware.
+pub trait ForwardKeeper {
+	/// Stores the forwarded transfer that went out as `key`. Storing `None` removes it.
+	fn store_in_flight_packet(
+		&mut self,
+		key: InFlightKey,
+		in_flight: Option<InFlightPacket>,
+	) -> Result<(), Ics20Error>;
+
+	/// Reports the events of the packets sent and acknowledgements written by the middleware,
+	/// which cannot go through the output of module callbacks. Relayers rely on these events.
+	fn emit_ibc_events(&mut self, events: Vec<IbcEvent>);
+}
+
+/// Captures all the dependencies of a forwarding ICS20 module.
+pub trait ForwardContext: Ics20Context + ForwardReader + ForwardKeeper {}
+
+/// Sends the tokens of `in_flight` to the next hop and records it under the key of the outgoing
+/// packet.
+fn forward_transfer<Ctx: ForwardContext>(
+	ctx: &mut Ctx,
+	in_flight: InFlightPacket,
+) -> Result<(), Ics20Error> {
+	let timeout_timestamp = (ctx.host_timestamp() + in_flight.forward.timeout)
+		.map_err(|_| Ics20Error::forward_timeout_overflow())?;
+	let msg = MsgTransfer {
+		source_port: in_flight.forward.port.clone(),
+		source_channel: in_flight.forward.channel,
+		token: in_flight.token.clone(),
+		sender: in_flight.sender.clone(),
+		receiver: in_flight.forward.receiver.clone(),
+		timeout_height: Height::zero(),
+		timeout_timestamp,
+		memo: in_flight.forward.next.clone(),
+	};
+
+	let mut output = HandlerOutputBuilder::new();
+	let packet = send_transfer(ctx, &mut output, msg)?;
+	ctx.emit_ibc_events(output.with_result(()).events);
+
+	let key = (packet.source_port, packet.source_channel, packet.sequence);
+	ctx.store_in_flight_packet(key, Some(in_flight))
+}
+
+fn write_ack<Ctx: ForwardContext>(
+	ctx: &mut Ctx,
+	packet: Packet,
+	ack: Acknowledgement,
+) -> Result<(), Ics20Error> {
+	let events = write_packet_acknowledgement(ctx, packet, ack.as_ref().to_vec())?;
+	ctx.emit_ibc_events(events);
+	Ok(())
+}
+
+/// Takes back the tokens credited to the intermediate account when `in_flight` was received, the
+/// forwarded transfer having been refunded to it, and answers the incoming packet with an error
+/// acknowledgement.
+fn revert_forward<Ctx: ForwardContext>(
+	ctx: &mut Ctx,
+	in_flight: InFlightPacket,
+	reason: String,
+) -> Result<(), Ics20Error> {
+	let InFlightPacket { original_packet: packet, sender, .. } = in_flight;
+	revert_received_token(ctx, &packet, sender)?;
+	write_ack(ctx, packet, Acknowledgement::from_error(Ics20Error::forward_failed(reason)))
+}
+
+/// Wraps an ICS20 transfer `Module`, forwarding incoming transfers whose memo carries a `forward`
+/// directive. The acknowledgement of a forwarded transfer is written asynchronously by this
+/// module, the host must therefore not write one when the inner module returns
+/// [`OnRecvPacketAck::Nil`].
+///
+/// A transfer that cannot be forwarded, or resent after a timeout, is reverted like one whose
+/// forwarded transfer failed: its original sender is refunded through an error acknowledgement.
+#[derive(Clone, Debug)]
+pub struct ForwardModule<M> {
+	inner: M,
+}
+
+impl<M> ForwardModule<M> {
+	pub fn new(inner: M) -> Self {
+		Self { inner }
+	}
+
+	pub fn inner(&self) -> &M {
+		&self.inner
+	}
+
+	pub fn inner_mut(&mut self) -> &mut M {
+		&mut self.inner
+	}
+}
+
+impl<M: Module + ForwardContext + 'static> ForwardModule<M> {
+	fn wrap_write_fn(write_fn: Box<WriteFn>, in_flight: InFlightPacket) -> Box<WriteFn> {
+		Box::new(move |module| {
+			let module = module.downcast_mut::<Self>().unwrap();
+			write_fn(&mut module.inner)?;
+			match forward_transfer(&mut module.inner, in_flight.clone()) {
+				Ok(()) => Ok(()),
+				Err(e) => revert_forward(&mut module.inner, in_flight, e.to_string())
+					.map_err(|e| e.to_string()),
+			}
+		})
+	}
+
+	/// Removes and returns the forwarded transfer `packet` belongs to, if any.
+	fn take_in_flight_packet(
+		&mut self,
+		packet: &Packet,
+	) -> Result<Option<InFlightPacket>, Ics20Error> {
+		let key = (packet.source_port.clone(), packet.source_channel, packet.sequence);
+		let in_flight = self.inner.get_in_flight_packet(&key)?;
+		if in_flight.is_some() {
+			self.inner.store_in_flight_packet(key, None)?;
+		}
+		Ok(in_flight)
+	}
+}
+
+impl<M: Module + ForwardContext + 'static> Module for ForwardModule<M> {
+	fn inner_module(&self) -> Option<&dyn Module> {
+		Some(&self.inner)
+	}
+
+	fn on_chan_open_init(
+		&mut self,
+		output: &mut ModuleOutputBuilder,
+		order: Order,
+		connection_hops: &[ConnectionId],
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		counterparty: &Counterparty,
+		version: &Version,
+	) -> Result<(), Ics04Error> {
+		self.inner.on_chan_open_init(
+			output,
+			order,
+			connection_hops,
+			port_id,
+			channel_id,
+			counterparty,
+			version,
+		)
+	}
+
+	fn on_chan_open_try(
+		&mut self,
+		output: &mut ModuleOutputBuilder,
+		order: Order,
+		connection_hops: &[ConnectionId],
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		counterparty: &Counterparty,
+		version: &Version,
+		counterparty_version: &Version,
+	) -> Result<Version, Ics04Error> {
+		self.inner.on_chan_open_try(
+			output,
+			order,
+			connection_hops,
+			port_id,
+			channel_id,
+			counterparty,
+			version,
+			counterparty_version,
+		)
+	}
+
+	fn on_chan_open_ack(
+		&mut self,
+		output: &mut ModuleOutputBuilder,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		counterparty_version: &Version,
+	) -> Result<(), Ics04Error> {
+		self.inner.on_chan_open_ack(output, port_id, channel_id, counterparty_version)
+	}
+
+	fn on_chan_open_confirm(
+		&mut self,
+		output: &mut ModuleOutputBuilder,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+	) -> Result<(), Ics04Error> {
+		self.inner.on_chan_open_confirm(output, port_id, channel_id)
+	}
+
+	fn on_chan_close_init(
+		&mut self,
+		output: &mut ModuleOutputBuilder,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+	) -> Result<(), Ics04Error> {
+		self.inner.on_chan_close_init(output, port_id, channel_id)
+	}
+
+	fn on_chan_close_confirm(
+		&mut self,
+		output: &mut ModuleOutputBuilder,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+	) -> Result<(), Ics04Error> {
+		self.inner.on_chan_close_confirm(output, port_id, channel_id)
+	}
+
+	fn on_recv_packet(
+		&self,
+		output: &mut ModuleOutputBuilder,
+		packet: &Packet,
+		relayer: &Signer,
+	) -> OnRecvPacketAck {
+		let data = match serde_json::from_slice::<PacketData>(&packet.data) {
+			Ok(data) => data,
+			Err(_) => return self.inner.on_recv_packet(output, packet, relayer),
+		};
+		let forward = match ForwardMetadata::from_memo(&data.memo) {
+			Ok(Some(forward)) => forward,
+			Ok(None) => return self.inner.on_recv_packet(output, packet, relayer),
+			Err(e) => return OnRecvPacketAck::Failed(Box::new(Acknowledgement::from_error(e))),
+		};
+		let intermediate =
+			match self.inner.intermediate_account(&packet.destination_channel, &data.sender) {
+				Ok(intermediate) => intermediate,
+				Err(e) => return OnRecvPacketAck::Failed(Box::new(Acknowledgement::from_error(e))),
+			};
+		let token = PrefixedCoin {
+			denom: received_denom(packet, &data.token.denom),
+			amount: data.token.amount,
+		};
+
+		// the inner module credits the intermediate account and must not act on the memo, which
+		// is meant for the next hop
+		let inner_packet = {
+			let data = PacketData { receiver: intermediate.clone(), memo: String::new(), ..data };
+			let data =
+				serde_json::to_vec(&data).expect("PacketData's infallible Serialize impl failed");
+			Packet { data, ..packet.clone() }
+		};
+
+		match self.inner.on_recv_packet(output, &inner_packet, relayer) {
+			OnRecvPacketAck::Nil(write_fn) | OnRecvPacketAck::Successful(_, write_fn) => {
+				let in_flight = InFlightPacket {
+					original_packet: packet.clone(),
+					sender: intermediate,
+					token,
+					retries_left: forward.retries,
+					forward,
+				};
+				OnRecvPacketAck::Nil(Self::wrap_write_fn(write_fn, in_flight))
+			},
+			ack @ OnRecvPacketAck::Failed(_) => ack,
+		}
+	}
+
+	fn on_acknowledgement_packet(
+		&mut self,
+		output: &mut ModuleOutputBuilder,
+		packet: &Packet,
+		acknowledgement: &GenericAcknowledgement,
+		relayer: &Signer,
+	) -> Result<(), Ics04Error> {
+		self.inner.on_acknowledgement_packet(output, packet, acknowledgement, relayer)?;
+
+		let in_flight = match self.take_in_flight_packet(packet) {
+			Ok(Some(in_flight)) => in_flight,
+			Ok(None) => return Ok(()),
+			Err(e) => return Err(Ics04Error::app_module(e.to_string())),
+		};
+		let result = match serde_json::from_slice::<Acknowledgement>(acknowledgement.as_ref()) {
+			Ok(Acknowledgement::Success(_)) =>
+				write_ack(&mut self.inner, in_flight.original_packet, Acknowledgement::success()),
+			Ok(Acknowledgement::Error(e)) => revert_forward(&mut self.inner, in_flight, e),
+			Err(_) => revert_forward(
+				&mut self.inner,
+				in_flight,
+				Ics20Error::ack_deserialization().to_string(),
+			),
+		};
+		result.map_err(|e| Ics04Error::app_module(e.to_string()))
+	}
+
+	fn on_timeout_packet(
+		&mut self,
+		output: &mut ModuleOutputBuilder,
+		packet: &Packet,
+		relayer: &Signer,
+	) -> Result<(), Ics04Error> {
+		self.inner.on_timeout_packet(output, packet, relayer)?;
+
+		let in_flight = match self.take_in_flight_packet(packet) {
+			Ok(Some(in_flight)) => in_flight,
+			Ok(None) => return Ok(()),
+			Err(e) => return Err(Ics04Error::app_module(e.to_string())),
+		};
+		let result = if in_flight.retries_left > 0 {
+			let retries_left = in_flight.retries_left - 1;
+			let retry = InFlightPacket { retries_left, ..in_flight.clone() };
+			forward_transfer(&mut self.inner, retry)
+				.or_else(|e| revert_forward(&mut self.inner, in_flight, e.to_string()))
+		} else {
+			revert_forward(&mut self.inner, in_flight, "forwarded packet timed out".to_string())
+		};
+		result.map_err(|e| Ics04Error::app_module(e.to_string()))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		applications::transfer::{
+			acknowledgement::ACK_SUCCESS_B64,
+			context::{Ics20Keeper, Ics20Reader},
+		},
+		core::{
+			ics03_connection::{
+				connection::{
+					ConnectionEnd, Counterparty as ConnectionCounterparty, State as ConnectionState,
+				},
+				version::get_compatible_versions,
+			},
+			ics04_channel::channel::{ChannelEnd, State},
+			ics24_host::identifier::ClientId,
+		},
+		mock::context::{HostBlockType, MockClientTypes, MockContext},
+		test_utils::{get_dummy_account_id, DummyTransferModule},
+		timestamp::{Timestamp, ZERO_DURATION},
+	};
+	use alloc::collections::BTreeMap;
+
+	/// The forwarding state of the transfer module wrapped by the tested one.
+	#[derive(Clone, Debug, Default)]
+	struct Forwards {
+		in_flight_packets: BTreeMap<InFlightKey, InFlightPacket>,
+		/// The events emitted outside of module callbacks so far.
+		ibc_events: Vec<IbcEvent>,
+	}
+
+	type TestModule = ForwardModule<DummyTransferModule<MockClientTypes, Forwards>>;
+
+	impl<C: HostBlockType> ForwardReader for DummyTransferModule<C, Forwards> {
+		fn intermediate_account(
+			&self,
+			_channel_id: &ChannelId,
+			_sender: &Signer,
+		) -> Result<Signer, Ics20Error> {
+			Ok(get_dummy_account_id())
+		}
+
+		fn get_in_flight_packet(
+			&self,
+			key: &InFlightKey,
+		) -> Result<Option<InFlightPacket>, Ics20Error> {
+			Ok(self.state().in_flight_packets.get(key).cloned())
+		}
+	}
+
+	impl<C: HostBlockType> ForwardKeeper for DummyTransferModule<C, Forwards> {
+		fn store_in_flight_packet(
+			&mut self,
+			key: InFlightKey,
+			in_flight: Option<InFlightPacket>,
+		) -> Result<(), Ics20Error> {
+			let in_flight_packets = &mut self.state_mut().in_flight_packets;
+			match in_flight {
+				Some(in_flight) => in_flight_packets.insert(key, in_flight),
+				None => in_flight_packets.remove(&key),
+			};
+			Ok(())
+		}
+
+		fn emit_ibc_events(&mut self, events: Vec<IbcEvent>) {
+			self.state_mut().ibc_events.extend(events);
+		}
+	}
+
+	impl<C: HostBlockType> ForwardContext for DummyTransferModule<C, Forwards> {}
+
+	#[test]
+	fn test_forward_metadata_from_memo() {
+		assert_eq!(ForwardMetadata::from_memo("").unwrap(), None);
+		assert_eq!(ForwardMetadata::from_memo("hello").unwrap(), None);
+		assert_eq!(ForwardMetadata::from_memo(r#"{"wasm":{}}"#).unwrap(), None);
+
+		let memo =
+			r#"{"forward":{"receiver":"cosmos1receiver","port":"transfer","channel":"channel-1"}}"#;
+		let forward = ForwardMetadata::from_memo(memo).unwrap().unwrap();
+		assert_eq!(forward.receiver.as_ref(), "cosmos1receiver");
+		assert_eq!(forward.port, PortId::transfer());
+		assert_eq!(forward.channel, ChannelId::new(1));
+		assert_eq!(forward.timeout, DEFAULT_FORWARD_TIMEOUT);
+		assert_eq!(forward.retries, DEFAULT_FORWARD_RETRIES);
+		assert_eq!(forward.next, "");
+
+		let memo = r#"{"forward":{"receiver":"r","port":"transfer","channel":"channel-1","timeout":"10m","retries":3,"next":{"forward":{}}}}"#;
+		let forward = ForwardMetadata::from_memo(memo).unwrap().unwrap();
+		assert_eq!(forward.timeout, Duration::from_secs(600));
+		assert_eq!(forward.retries, 3);
+		assert_eq!(forward.next, r#"{"forward":{}}"#);
+
+		let memo = r#"{"forward":{"receiver":"r","port":"transfer","channel":"channel-1"#;
+		assert_eq!(ForwardMetadata::from_memo(memo).unwrap(), None);
+		let memo = r#"{"forward":{"receiver":"r","port":"transfer"}}"#;
+		assert!(ForwardMetadata::from_memo(memo).is_err());
+		let memo = r#"{"forward":{"receiver":"r","port":"transfer","channel":"chan"}}"#;
+		assert!(ForwardMetadata::from_memo(memo).is_err());
+	}
+
+	#[test]
+	fn test_parse_timeout() {
+		let parse = |timeout: &str| parse_timeout(&serde_json::from_str(timeout).unwrap()).ok();
+		assert_eq!(parse("1000"), Some(Duration::from_nanos(1000)));
+		assert_eq!(parse(r#""1000""#), Some(Duration::from_nanos(1000)));
+		assert_eq!(parse(r#""30s""#), Some(Duration::from_secs(30)));
+		assert_eq!(parse(r#""2h""#), Some(Duration::from_secs(7200)));
+		assert_eq!(parse("0"), None);
+		assert_eq!(parse(r#""10d""#), None);
+		assert_eq!(parse(r#""m""#), None);
+		assert_eq!(parse("-1"), None);
+	}
+
+	/// A transfer module receiving transfers on channel-0 and forwarding them on channel-1, the
+	/// channel-1 of the sending chain being the counterparty of channel-0.
+	fn forward_module() -> TestModule {
+		let connection_end = ConnectionEnd::new(
+			ConnectionState::Open,
+			ClientId::default(),
+			ConnectionCounterparty::new(
+				ClientId::default(),
+				Some(ConnectionId::default()),
+				Default::default(),
+			),
+			get_compatible_versions(),
+			ZERO_DURATION,
+		);
+		let channel_end = |counterparty| {
+			ChannelEnd::new(
+				State::Open,
+				Order::Unordered,
+				Counterparty::new(PortId::transfer(), Some(ChannelId::new(counterparty))),
+				vec![ConnectionId::default()],
+				Version::ics20(),
+			)
+		};
+		let ctx = MockContext::<MockClientTypes>::default()
+			.with_client(&ClientId::default(), Height::new(0, 10))
+			.with_connection(ConnectionId::default(), connection_end)
+			.with_channel(PortId::transfer(), ChannelId::new(0), channel_end(1))
+			.with_channel(PortId::transfer(), ChannelId::new(1), channel_end(7))
+			.with_send_sequence(PortId::transfer(), ChannelId::new(1), 1.into());
+
+		let mut inner = DummyTransferModule::with_state(ctx.ibc_store_share(), Forwards::default());
+		inner.set_host_timestamp(Timestamp::now());
+		ForwardModule::new(inner)
+	}
+
+	/// A packet received on channel-0 carrying 40 `denom`, to be forwarded on `channel`.
+	fn recv_packet(denom: &str, channel: &str, retries: u8) -> Packet {
+		let memo = format!(
+			r#"{{"forward":{{"receiver":"cosmos1receiver","port":"transfer","channel":"{}","retries":{}}}}}"#,
+			channel, retries
+		);
+		let data = PacketData {
+			token: PrefixedCoin { denom: denom.parse().unwrap(), amount: 40u64.into() },
+			sender: get_dummy_account_id(),
+			receiver: get_dummy_account_id(),
+			memo,
+		};
+		Packet {
+			source_port: PortId::transfer(),
+			source_channel: ChannelId::new(1),
+			destination_port: PortId::transfer(),
+			destination_channel: ChannelId::new(0),
+			data: serde_json::to_vec(&data).unwrap(),
+			..Default::default()
+		}
+	}
+
+	fn ack(ack: Acknowledgement) -> GenericAcknowledgement {
+		serde_json::to_vec(&ack.to_string()).unwrap().into()
+	}
+
+	/// Receives `packet` and returns the packets sent by the module so far.
+	fn recv(module: &mut TestModule, packet: &Packet) -> Vec<Packet> {
+		let relayer = get_dummy_account_id();
+		match module.on_recv_packet(&mut ModuleOutputBuilder::new(), packet, &relayer) {
+			OnRecvPacketAck::Nil(write_fn) => write_fn(module).unwrap(),
+			_ => panic!("forwarded packets are acknowledged by the module"),
+		}
+		sent_packets(module)
+	}
+
+	fn sent_packets(module: &TestModule) -> Vec<Packet> {
+		let events = module.inner().state().ibc_events.iter();
+		events
+			.filter_map(|event| match event {
+				IbcEvent::SendPacket(event) => Some(event.packet.clone()),
+				_ => None,
+			})
+			.collect()
+	}
+
+	/// Returns whether each acknowledgement written by the module so far is successful.
+	fn written_acks(module: &TestModule) -> Vec<bool> {
+		let events = module.inner().state().ibc_events.iter();
+		events
+			.filter_map(|event| match event {
+				IbcEvent::WriteAcknowledgement(event) => Some(event.ack == ACK_SUCCESS_B64),
+				_ => None,
+			})
+			.collect()
+	}
+
+	fn in_flight(module: &TestModule, sequence: u64) -> Option<InFlightPacket> {
+		let key = (PortId::transfer(), ChannelId::new(1), sequence.into());
+		module.inner().get_in_flight_packet(&key).unwrap()
+	}
+
+	#[test]
+	fn test_forward_ack() {
+		let mut module = forward_module();
+		let original = recv_packet("uosmo", "channel-1", 0);
+
+		// the tokens received are sent to the next hop, the acknowledgement being withheld
+		let sent = recv(&mut module, &original);
+		assert_eq!(sent.len(), 1);
+		let data = serde_json::from_slice::<PacketData>(&sent[0].data).unwrap();
+		assert_eq!(data.receiver.as_ref(), "cosmos1receiver");
+		assert_eq!(data.token.denom, "transfer/channel-0/uosmo".parse().unwrap());
+		assert_eq!(sent[0].source_channel, ChannelId::new(1));
+		assert_eq!(in_flight(&module, 1).unwrap().original_packet, original);
+		assert!(written_acks(&module).is_empty());
+
+		// the acknowledgement of the forwarded packet is passed on to the original one
+		let success = ack(Acknowledgement::success());
+		let relayer = get_dummy_account_id();
+		let mut output = ModuleOutputBuilder::new();
+		module
+			.on_acknowledgement_packet(&mut output, &sent[0], &success, &relayer)
+			.unwrap();
+		assert_eq!(written_acks(&module), vec![true]);
+		assert_eq!(in_flight(&module, 1), None);
+
+		// acknowledgements of packets the module did not forward are left to the inner module
+		module
+			.on_acknowledgement_packet(&mut output, &sent[0], &success, &relayer)
+			.unwrap();
+		assert_eq!(written_acks(&module).len(), 1);
+	}
+
+	#[test]
+	fn test_forward_error_ack() {
+		let mut module = forward_module();
+		let sent = recv(&mut module, &recv_packet("uosmo", "channel-1", 0));
+
+		let error = ack(Acknowledgement::Error("out of gas".to_string()));
+		let relayer = get_dummy_account_id();
+		let mut output = ModuleOutputBuilder::new();
+		module
+			.on_acknowledgement_packet(&mut output, &sent[0], &error, &relayer)
+			.unwrap();
+		assert_eq!(written_acks(&module), vec![false]);
+		assert_eq!(in_flight(&module, 1), None);
+	}
+
+	#[test]
+	fn test_forward_timeout() {
+		let mut module = forward_module();
+		let sent = recv(&mut module, &recv_packet("uosmo", "channel-1", 1));
+		let relayer = get_dummy_account_id();
+		let mut output = ModuleOutputBuilder::new();
+
+		// the forwarded transfer is resent while retries are left
+		module.on_timeout_packet(&mut output, &sent[0], &relayer).unwrap();
+		let sent = sent_packets(&module);
+		assert_eq!(sent.len(), 2);
+		assert_eq!(in_flight(&module, 1), None);
+		assert_eq!(in_flight(&module, 2).unwrap().retries_left, 0);
+		assert!(written_acks(&module).is_empty());
+
+		// then the original sender is refunded
+		module.on_timeout_packet(&mut output, &sent[1], &relayer).unwrap();
+		assert_eq!(sent_packets(&module).len(), 2);
+		assert_eq!(in_flight(&module, 2), None);
+		assert_eq!(written_acks(&module), vec![false]);
+	}
+
+	#[test]
+	fn test_forward_failure() {
+		let mut module = forward_module();
+		// the tokens come back to their source, they are unescrowed on receipt
+		let escrow_denom = "uatom".parse().unwrap();
+		module.inner_mut().store_total_escrow(&escrow_denom, 100u64.into()).unwrap();
+
+		// there is no channel-5 to forward the tokens on, the original sender is refunded and the
+		// tokens received escrowed back
+		let sent = recv(&mut module, &recv_packet("transfer/channel-1/uatom", "channel-5", 1));
+		assert!(sent.is_empty());
+		assert_eq!(written_acks(&module), vec![false]);
+		assert_eq!(module.inner().get_total_escrow(&escrow_denom).unwrap(), 100u64.into());
+	}
+}
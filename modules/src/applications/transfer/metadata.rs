@@ -0,0 +1,161 @@
+//! Display metadata of IBC vouchers, registered with the host through
+//! [`Ics20Keeper::register_denom_metadata`] when a voucher denomination is minted for the first
+//! time.
+//!
+//! The metadata is generated from the denom trace, and can be completed by the sender through a
+//! `denom_metadata` entry in the memo of the transfer:
+//!
+//! ```json
+//! {
+//!   "denom_metadata": {
+//!     "name": "Cosmos Hub Atom",
+//!     "symbol": "ATOM",
+//!     "display": "atom",
+//!     "decimals": 6,
+//!     "description": "The native staking token of the Cosmos Hub"
+//!   }
+//! }
+//! ```
+//!
+//! where all fields are optional. Malformed entries are ignored.
+//!
+//! [`Ics20Keeper::register_denom_metadata`]: crate::applications::transfer::context::Ics20Keeper::register_denom_metadata
+
+use ibc_proto::cosmos::bank::v1beta1::{DenomUnit as RawDenomUnit, Metadata as RawMetadata};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{applications::transfer::PrefixedDenom, prelude::*};
+
+/// A unit of a denomination, expressed as a power of ten of its base unit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DenomUnit {
+	pub denom: String,
+	pub exponent: u32,
+	pub aliases: Vec<String>,
+}
+
+impl From<DenomUnit> for RawDenomUnit {
+	fn from(unit: DenomUnit) -> Self {
+		Self { denom: unit.denom, exponent: unit.exponent, aliases: unit.aliases }
+	}
+}
+
+/// Display metadata of a denomination, mirroring the cosmos-sdk bank `Metadata`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DenomMetadata {
+	pub description: String,
+	/// The units of the denomination, the first one being its base unit.
+	pub denom_units: Vec<DenomUnit>,
+	/// The base unit, i.e. the `ibc/{hash}` denomination of the voucher.
+	pub base: String,
+	/// The unit clients should display amounts in.
+	pub display: String,
+	pub name: String,
+	pub symbol: String,
+}
+
+#[derive(Default, Deserialize)]
+struct MemoMetadata {
+	#[serde(default)]
+	name: Option<String>,
+	#[serde(default)]
+	symbol: Option<String>,
+	#[serde(default)]
+	display: Option<String>,
+	#[serde(default)]
+	decimals: Option<u32>,
+	#[serde(default)]
+	description: Option<String>,
+}
+
+impl MemoMetadata {
+	fn from_memo(memo: &str) -> Self {
+		serde_json::from_str::<Value>(memo)
+			.ok()
+			.and_then(|mut memo| memo.get_mut("denom_metadata").map(Value::take))
+			.and_then(|metadata| serde_json::from_value(metadata).ok())
+			.unwrap_or_default()
+	}
+}
+
+impl DenomMetadata {
+	/// Generates the metadata of the voucher of `denom`, whose `ibc/{hash}` denomination is
+	/// `base`, completed with the `denom_metadata` entry of `memo`, if any.
+	pub fn new(denom: &PrefixedDenom, base: String, memo: &str) -> Self {
+		let hints = MemoMetadata::from_memo(memo);
+		let base_denom = denom.base_denom().as_str();
+
+		let mut denom_units =
+			vec![DenomUnit { denom: base.clone(), exponent: 0, aliases: vec![base_denom.into()] }];
+		let display = match (hints.display, hints.decimals) {
+			(Some(display), Some(decimals)) if decimals > 0 && display != base => {
+				denom_units.push(DenomUnit {
+					denom: display.clone(),
+					exponent: decimals,
+					aliases: vec![],
+				});
+				display
+			},
+			_ => base.clone(),
+		};
+
+		Self {
+			description: hints.description.unwrap_or_else(|| format!("IBC token from {}", denom)),
+			denom_units,
+			base,
+			display,
+			name: hints.name.unwrap_or_else(|| format!("{} IBC token", denom)),
+			symbol: hints.symbol.unwrap_or_else(|| base_denom.to_uppercase()),
+		}
+	}
+}
+
+impl From<DenomMetadata> for RawMetadata {
+	fn from(metadata: DenomMetadata) -> Self {
+		Self {
+			description: metadata.description,
+			denom_units: metadata.denom_units.into_iter().map(Into::into).collect(),
+			base: metadata.base,
+			display: metadata.display,
+			name: metadata.name,
+			symbol: metadata.symbol,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const BASE: &str = "ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2";
+
+	#[test]
+	fn test_denom_metadata() {
+		let denom: PrefixedDenom = "transfer/channel-0/uatom".parse().unwrap();
+
+		let metadata = DenomMetadata::new(&denom, BASE.to_string(), "");
+		assert_eq!(metadata.base, BASE);
+		assert_eq!(metadata.display, BASE);
+		assert_eq!(metadata.symbol, "UATOM");
+		assert_eq!(metadata.name, "transfer/channel-0/uatom IBC token");
+		assert_eq!(
+			metadata.denom_units,
+			vec![DenomUnit { denom: BASE.to_string(), exponent: 0, aliases: vec!["uatom".into()] }]
+		);
+
+		let memo = r#"{"denom_metadata":{"symbol":"ATOM","display":"atom","decimals":6}}"#;
+		let metadata = DenomMetadata::new(&denom, BASE.to_string(), memo);
+		assert_eq!(metadata.symbol, "ATOM");
+		assert_eq!(metadata.display, "atom");
+		assert_eq!(metadata.denom_units.len(), 2);
+		assert_eq!(metadata.denom_units[1].exponent, 6);
+
+		// malformed metadata is ignored
+		let memo = r#"{"denom_metadata":{"decimals":"six"}}"#;
+		assert_eq!(
+			DenomMetadata::new(&denom, BASE.to_string(), memo),
+			DenomMetadata::new(&denom, BASE.to_string(), "")
+		);
+	}
+}
@@ -2,12 +2,19 @@
 //! constitutes a "fungible token transfer bridge module" between the IBC routing module and an
 //! asset tracking module.
 pub mod acknowledgement;
+pub mod authz;
+pub mod callbacks;
 pub mod context;
 pub mod denom;
+pub mod enablement;
 pub mod error;
+pub mod escrow;
 pub mod events;
+pub mod forward;
+pub mod metadata;
 pub mod msgs;
 pub mod packet;
+pub mod rate_limit;
 pub mod relay;
 
 pub use denom::*;
@@ -46,6 +46,8 @@ pub struct MsgTransfer<C = Coin> {
 	/// Timeout timestamp relative to the current block timestamp.
 	/// The timeout is disabled when set to 0.
 	pub timeout_timestamp: Timestamp,
+	/// Optional memo forwarded verbatim in the packet data.
+	pub memo: String,
 }
 
 impl Msg for MsgTransfer {
@@ -89,6 +91,7 @@ impl TryFrom<RawMsgTransfer> for MsgTransfer {
 			receiver: raw_msg.receiver.parse().map_err(Error::signer)?,
 			timeout_height,
 			timeout_timestamp,
+			memo: raw_msg.memo,
 		})
 	}
 }
@@ -103,6 +106,7 @@ impl From<MsgTransfer> for RawMsgTransfer {
 			receiver: domain_msg.receiver.to_string(),
 			timeout_height: Some(domain_msg.timeout_height.into()),
 			timeout_timestamp: domain_msg.timeout_timestamp.nanoseconds(),
+			memo: domain_msg.memo,
 		}
 	}
 }
@@ -153,6 +157,7 @@ pub mod test_util {
 			receiver: address,
 			timeout_timestamp: Timestamp::now().add(Duration::from_secs(10)).unwrap(),
 			timeout_height: Height { revision_number: 0, revision_height: height },
+			memo: String::new(),
 		}
 	}
 }
@@ -1,4 +1,4 @@
-use alloc::string::ToString;
+use alloc::string::{String, ToString};
 use core::{convert::TryFrom, str::FromStr};
 
 use ibc_proto::ibc::applications::transfer::v2::FungibleTokenPacketData as RawPacketData;
@@ -12,6 +12,10 @@ pub struct PacketData {
 	pub token: PrefixedCoin,
 	pub sender: Signer,
 	pub receiver: Signer,
+	/// Optional memo, only serialized when non-empty so that packets remain compatible with
+	/// counterparties that do not know about this field.
+	#[serde(default, skip_serializing_if = "String::is_empty")]
+	pub memo: String,
 }
 
 impl TryFrom<RawPacketData> for PacketData {
@@ -25,6 +29,7 @@ impl TryFrom<RawPacketData> for PacketData {
 			token: PrefixedCoin { denom, amount },
 			sender: raw_pkt_data.sender.parse().map_err(Error::signer)?,
 			receiver: raw_pkt_data.receiver.parse().map_err(Error::signer)?,
+			memo: raw_pkt_data.memo,
 		})
 	}
 }
@@ -36,6 +41,36 @@ impl From<PacketData> for RawPacketData {
 			amount: pkt_data.token.amount.to_string(),
 			sender: pkt_data.sender.to_string(),
 			receiver: pkt_data.receiver.to_string(),
+			memo: pkt_data.memo,
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::get_dummy_bech32_account;
+
+	fn dummy_packet_data(memo: &str) -> PacketData {
+		let address: Signer = get_dummy_bech32_account().parse().unwrap();
+		PacketData {
+			token: PrefixedCoin { denom: "uatom".parse().unwrap(), amount: 10u64.into() },
+			sender: address.clone(),
+			receiver: address,
+			memo: memo.to_string(),
+		}
+	}
+
+	#[test]
+	fn test_packet_data_memo_serde() {
+		let data = dummy_packet_data("");
+		let json = serde_json::to_string(&data).unwrap();
+		assert!(!json.contains("memo"), "empty memo must not be serialized");
+		assert_eq!(serde_json::from_str::<PacketData>(&json).unwrap(), data);
+
+		let data = dummy_packet_data(r#"{"wasm":{"contract":"foo"}}"#);
+		let json = serde_json::to_string(&data).unwrap();
+		assert!(json.contains("memo"), "non-empty memo must be serialized");
+		assert_eq!(serde_json::from_str::<PacketData>(&json).unwrap(), data);
+	}
+}
@@ -0,0 +1,979 @@
+//! Rate limiting of ICS20 token flows.
+//!
+//! Quotas are attached to a `(channel, denom)` pair and bound the net flow of that denomination
+//! through the channel over a rolling window, expressed as a percentage of a value provided by the
+//! host (see [`RateLimitReader::channel_value`]). Outgoing transfers exceeding a quota are rejected
+//! by [`send_transfer`], incoming ones are answered with an error acknowledgement by
+//! [`RateLimitModule`], which also removes refunded transfers from the flows they were counted in.
+
+use core::time::Duration;
+
+use ibc_proto::cosmos::base::v1beta1::Coin as RawCoin;
+use serde::{Deserialize, Serialize};
+
+use super::relay::{
+	received_denom,
+	send_transfer::{
+		resolve_coin, send_transfer as inner_send_transfer,
+		validate_transfer as inner_validate_transfer,
+	},
+};
+use crate::{
+	applications::transfer::{
+		acknowledgement::Acknowledgement, context::Ics20Context, error::Error as Ics20Error,
+		msgs::transfer::MsgTransfer, packet::PacketData, Amount, PrefixedCoin, PrefixedDenom,
+	},
+	bigint::U256,
+	core::{
+		ics04_channel::{
+			channel::{Counterparty, Order},
+			error::Error as Ics04Error,
+			msgs::acknowledgement::Acknowledgement as GenericAcknowledgement,
+			packet::{Packet, Sequence},
+			Version,
+		},
+		ics24_host::identifier::{ChannelId, ConnectionId, PortId},
+		ics26_routing::context::{
+			Acknowledgement as AckTrait, Module, ModuleOutputBuilder, OnRecvPacketAck, WriteFn,
+		},
+	},
+	handler::{HandlerOutput, HandlerOutputBuilder},
+	prelude::*,
+	signer::Signer,
+	timestamp::{Expiry, Timestamp},
+};
+
+/// Direction of a token flow, from the point of view of the host chain.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FlowType {
+	In,
+	Out,
+}
+
+/// The `(channel, denom)` pair a set of quotas applies to. The denomination is the one used on the
+/// host chain, i.e. as it appears in outgoing packets and after trace manipulation for incoming
+/// ones.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct RateLimitPath {
+	pub channel_id: ChannelId,
+	pub denom: PrefixedDenom,
+}
+
+impl RateLimitPath {
+	pub fn new(channel_id: ChannelId, denom: PrefixedDenom) -> Self {
+		Self { channel_id, denom }
+	}
+}
+
+/// A bound on the net flow of tokens through a channel over a period of time.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Quota {
+	/// Name used by governance to refer to this quota.
+	pub name: String,
+	/// Maximum net outflow over a period, in percent of the channel value.
+	pub max_percent_send: u32,
+	/// Maximum net inflow over a period, in percent of the channel value.
+	pub max_percent_recv: u32,
+	/// Length of a period.
+	pub duration: Duration,
+}
+
+impl Quota {
+	pub fn validate_basic(&self) -> Result<(), Ics20Error> {
+		if self.name.trim().is_empty() {
+			return Err(Ics20Error::invalid_rate_limit_quota("empty quota name".to_string()))
+		}
+		if self.max_percent_send > 100 || self.max_percent_recv > 100 {
+			return Err(Ics20Error::invalid_rate_limit_quota(
+				"percentages must not exceed 100".to_string(),
+			))
+		}
+		if self.duration.is_zero() {
+			return Err(Ics20Error::invalid_rate_limit_quota("zero duration".to_string()))
+		}
+		Ok(())
+	}
+
+	/// Returns the maximum net flow allowed in the `flow_type` direction.
+	fn capacity(&self, flow_type: FlowType, channel_value: Amount) -> U256 {
+		let percent = match flow_type {
+			FlowType::In => U256::from(self.max_percent_recv),
+			FlowType::Out => U256::from(self.max_percent_send),
+		};
+		let value = channel_value.as_u256();
+		let hundred = U256::from(100u32);
+		value
+			.checked_mul(percent)
+			.map(|v| v / hundred)
+			.unwrap_or_else(|| (value / hundred).saturating_mul(percent))
+	}
+}
+
+/// The tokens that went through a channel during the current period of a quota.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Flow {
+	pub inflow: Amount,
+	pub outflow: Amount,
+	pub period_end: Timestamp,
+}
+
+impl Flow {
+	fn new(now: Timestamp, duration: Duration) -> Result<Self, Ics20Error> {
+		let period_end = (now + duration).map_err(|_| Ics20Error::rate_limit_overflow())?;
+		Ok(Self { inflow: 0u64.into(), outflow: 0u64.into(), period_end })
+	}
+
+	fn is_expired(&self, now: Timestamp) -> bool {
+		now.check_expiry(&self.period_end) == Expiry::Expired
+	}
+
+	/// Returns the net flow in the `flow_type` direction, zero if the flow is in the opposite
+	/// direction.
+	fn net(&self, flow_type: FlowType) -> U256 {
+		let (flow, counter_flow) = match flow_type {
+			FlowType::In => (self.inflow, self.outflow),
+			FlowType::Out => (self.outflow, self.inflow),
+		};
+		flow.as_u256().saturating_sub(counter_flow.as_u256())
+	}
+
+	fn add(&mut self, flow_type: FlowType, amount: Amount) -> Result<(), Ics20Error> {
+		let flow = match flow_type {
+			FlowType::In => &mut self.inflow,
+			FlowType::Out => &mut self.outflow,
+		};
+		*flow = flow.checked_add(amount).ok_or_else(Ics20Error::rate_limit_overflow)?;
+		Ok(())
+	}
+
+	fn undo(&mut self, flow_type: FlowType, amount: Amount) {
+		let flow = match flow_type {
+			FlowType::In => &mut self.inflow,
+			FlowType::Out => &mut self.outflow,
+		};
+		*flow = flow.checked_sub(amount).unwrap_or_else(|| 0u64.into());
+	}
+}
+
+/// A quota along with the flow tracked against it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimit {
+	pub quota: Quota,
+	pub flow: Flow,
+}
+
+impl RateLimit {
+	pub fn new(quota: Quota, now: Timestamp) -> Result<Self, Ics20Error> {
+		let flow = Flow::new(now, quota.duration)?;
+		Ok(Self { quota, flow })
+	}
+
+	/// Records `amount` flowing in the `flow_type` direction at time `now`. Returns false, leaving
+	/// the flow untouched, if this would exceed the quota.
+	pub fn allow(
+		&mut self,
+		flow_type: FlowType,
+		amount: Amount,
+		channel_value: Amount,
+		now: Timestamp,
+	) -> Result<bool, Ics20Error> {
+		let mut flow = if self.flow.is_expired(now) {
+			Flow::new(now, self.quota.duration)?
+		} else {
+			self.flow.clone()
+		};
+		flow.add(flow_type, amount)?;
+
+		if flow.net(flow_type) > self.quota.capacity(flow_type, channel_value) {
+			return Ok(false)
+		}
+
+		self.flow = flow;
+		Ok(true)
+	}
+
+	/// Removes `amount` from the flow in the `flow_type` direction, e.g. when a transfer is
+	/// refunded, if it was counted in the current period, which ends at `period_end`. Amounts
+	/// counted in a previous period are already out of the flow.
+	pub fn undo(&mut self, flow_type: FlowType, amount: Amount, period_end: Timestamp) {
+		if self.flow.period_end == period_end {
+			self.flow.undo(flow_type, amount)
+		}
+	}
+
+	/// Clears the flow and starts a new period at `now`.
+	pub fn reset(&mut self, now: Timestamp) -> Result<(), Ics20Error> {
+		self.flow = Flow::new(now, self.quota.duration)?;
+		Ok(())
+	}
+}
+
+/// An outgoing transfer counted in the flows of its pair, until it is acknowledged or times out.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingSend {
+	pub path: RateLimitPath,
+	pub amount: Amount,
+	/// The end of the period the transfer was counted in, for each quota of the pair by name.
+	pub periods: Vec<(String, Timestamp)>,
+}
+
+impl PendingSend {
+	fn new(path: RateLimitPath, amount: Amount, rate_limits: &[RateLimit]) -> Self {
+		let periods = rate_limits
+			.iter()
+			.map(|rate_limit| (rate_limit.quota.name.clone(), rate_limit.flow.period_end))
+			.collect();
+		Self { path, amount, periods }
+	}
+
+	fn period_end(&self, quota_name: &str) -> Option<Timestamp> {
+		self.periods.iter().find(|(name, _)| name == quota_name).map(|(_, end)| *end)
+	}
+}
+
+/// A context supplying the read-only dependencies of the rate limiter.
+pub trait RateLimitReader {
+	/// Returns the quotas, and their flows, that apply to `path`. An empty list means the path
+	/// is not rate limited.
+	fn rate_limits(&self, path: &RateLimitPath) -> Result<Vec<RateLimit>, Ics20Error>;
+
+	/// Returns the value the quotas of `path` are a percentage of, typically the total supply of
+	/// the denomination on the host chain.
+	fn channel_value(&self, path: &RateLimitPath) -> Result<Amount, Ics20Error>;
+
+	/// Returns the outgoing transfer sent on `channel_id` with `sequence`, if it is counted in
+	/// the flows of its pair.
+	fn pending_send(
+		&self,
+		channel_id: &ChannelId,
+		sequence: Sequence,
+	) -> Result<Option<PendingSend>, Ics20Error>;
+}
+
+/// A context supplying the write dependencies of the rate limiter.
+pub trait RateLimitKeeper {
+	/// Stores the quotas, and their flows, that apply to `path`. Storing an empty list removes
+	/// the rate limit on the path.
+	fn store_rate_limits(
+		&mut self,
+		path: RateLimitPath,
+		rate_limits: Vec<RateLimit>,
+	) -> Result<(), Ics20Error>;
+
+	/// Stores the outgoing transfer sent on `channel_id` with `sequence`, or removes it if
+	/// `pending_send` is `None`.
+	fn store_pending_send(
+		&mut self,
+		channel_id: ChannelId,
+		sequence: Sequence,
+		pending_send: Option<PendingSend>,
+	) -> Result<(), Ics20Error>;
+}
+
+/// Captures all the dependencies of a rate limited ICS20 module.
+pub trait RateLimitContext: Ics20Context + RateLimitReader + RateLimitKeeper {}
+
+/// Checks `amount` flowing in the `flow_type` direction against every quota of `path` and returns
+/// the updated rate limits, which must be stored once the transfer is known to succeed.
+fn check_flow<Ctx: RateLimitContext>(
+	ctx: &Ctx,
+	path: &RateLimitPath,
+	flow_type: FlowType,
+	amount: Amount,
+) -> Result<Vec<RateLimit>, Ics20Error> {
+	let mut rate_limits = ctx.rate_limits(path)?;
+	if rate_limits.is_empty() {
+		return Ok(rate_limits)
+	}
+
+	let channel_value = ctx.channel_value(path)?;
+	let now = ctx.host_timestamp();
+	for rate_limit in rate_limits.iter_mut() {
+		if !rate_limit.allow(flow_type, amount, channel_value, now)? {
+			return Err(Ics20Error::rate_limit_exceeded(
+				path.channel_id,
+				path.denom.to_string(),
+				rate_limit.quota.name.clone(),
+			))
+		}
+	}
+
+	Ok(rate_limits)
+}
+
+fn store_flow<Ctx: RateLimitContext>(
+	ctx: &mut Ctx,
+	path: RateLimitPath,
+	rate_limits: Vec<RateLimit>,
+) -> Result<(), Ics20Error> {
+	if rate_limits.is_empty() {
+		return Ok(())
+	}
+	ctx.store_rate_limits(path, rate_limits)
+}
+
+/// Settles the outgoing transfer sent on `channel_id` with `sequence`, removing its tokens from
+/// the flows they were counted in if it was `refunded`.
+fn settle_send<Ctx: RateLimitContext>(
+	ctx: &mut Ctx,
+	channel_id: ChannelId,
+	sequence: Sequence,
+	refunded: bool,
+) -> Result<(), Ics20Error> {
+	let pending_send = match ctx.pending_send(&channel_id, sequence)? {
+		Some(pending_send) => pending_send,
+		None => return Ok(()),
+	};
+	ctx.store_pending_send(channel_id, sequence, None)?;
+	if !refunded {
+		return Ok(())
+	}
+
+	let mut rate_limits = ctx.rate_limits(&pending_send.path)?;
+	for rate_limit in rate_limits.iter_mut() {
+		if let Some(period_end) = pending_send.period_end(&rate_limit.quota.name) {
+			rate_limit.undo(FlowType::Out, pending_send.amount, period_end);
+		}
+	}
+	store_flow(ctx, pending_send.path, rate_limits)
+}
+
+/// Rate limited counterpart of
+/// [`send_transfer`](crate::applications::transfer::relay::send_transfer::send_transfer).
+/// The transfer is rejected if it exceeds any quota of its `(source channel, denom)` pair.
+pub fn send_transfer<Ctx, C>(
+	ctx: &mut Ctx,
+	output: &mut HandlerOutputBuilder<()>,
+	msg: MsgTransfer<C>,
+) -> Result<Packet, Ics20Error>
+where
+	Ctx: RateLimitContext,
+	C: Into<RawCoin>,
+{
+	let (msg, path, rate_limits) = check_send(ctx, msg)?;
+	let amount = msg.token.amount;
+	let packet = inner_send_transfer(ctx, output, msg)?;
+
+	if !rate_limits.is_empty() {
+		let pending_send = PendingSend::new(path.clone(), amount, &rate_limits);
+		ctx.store_pending_send(packet.source_channel, packet.sequence, Some(pending_send))?;
+	}
+	store_flow(ctx, path, rate_limits)?;
+	Ok(packet)
+}
+
+/// Rate limited counterpart of
+/// [`validate_transfer`](crate::applications::transfer::relay::send_transfer::validate_transfer).
+pub fn validate_transfer<Ctx, C>(
+	ctx: &Ctx,
+	msg: MsgTransfer<C>,
+) -> Result<HandlerOutput<Packet>, Ics20Error>
+where
+	Ctx: RateLimitContext,
+	C: Into<RawCoin>,
+{
+	let (msg, _, _) = check_send(ctx, msg)?;
+	inner_validate_transfer(ctx, msg)
+}
+
+/// Checks an outgoing transfer against the quotas of its pair, returning the transfer with its
+/// denomination resolved, along with the pair and its updated rate limits.
+fn check_send<Ctx, C>(
+	ctx: &Ctx,
+	msg: MsgTransfer<C>,
+) -> Result<(MsgTransfer<PrefixedCoin>, RateLimitPath, Vec<RateLimit>), Ics20Error>
+where
+	Ctx: RateLimitContext,
+	C: Into<RawCoin>,
+{
+	let token = resolve_coin(ctx, msg.token.into())?;
+	let path = RateLimitPath::new(msg.source_channel, token.denom.clone());
+	let rate_limits = check_flow(ctx, &path, FlowType::Out, token.amount)?;
+
+	let msg = MsgTransfer {
+		source_port: msg.source_port,
+		source_channel: msg.source_channel,
+		token,
+		sender: msg.sender,
+		receiver: msg.receiver,
+		timeout_height: msg.timeout_height,
+		timeout_timestamp: msg.timeout_timestamp,
+		memo: msg.memo,
+	};
+	Ok((msg, path, rate_limits))
+}
+
+/// Adds `quota` to the quotas of `path`. Meant to be called from a governance proposal.
+pub fn add_rate_limit<Ctx: RateLimitContext>(
+	ctx: &mut Ctx,
+	path: RateLimitPath,
+	quota: Quota,
+) -> Result<(), Ics20Error> {
+	quota.validate_basic()?;
+
+	let mut rate_limits = ctx.rate_limits(&path)?;
+	if rate_limits.iter().any(|rate_limit| rate_limit.quota.name == quota.name) {
+		return Err(Ics20Error::rate_limit_quota_already_exists(quota.name))
+	}
+	rate_limits.push(RateLimit::new(quota, ctx.host_timestamp())?);
+
+	ctx.store_rate_limits(path, rate_limits)
+}
+
+/// Removes the quota named `quota_name` from the quotas of `path`. Meant to be called from a
+/// governance proposal.
+pub fn remove_rate_limit<Ctx: RateLimitContext>(
+	ctx: &mut Ctx,
+	path: RateLimitPath,
+	quota_name: &str,
+) -> Result<(), Ics20Error> {
+	let mut rate_limits = ctx.rate_limits(&path)?;
+	let len = rate_limits.len();
+	rate_limits.retain(|rate_limit| rate_limit.quota.name != quota_name);
+	if rate_limits.len() == len {
+		return Err(Ics20Error::rate_limit_quota_not_found(quota_name.to_string()))
+	}
+
+	ctx.store_rate_limits(path, rate_limits)
+}
+
+/// Clears the flow tracked against the quota named `quota_name` of `path`. Meant to be called
+/// from a governance proposal.
+pub fn reset_rate_limit<Ctx: RateLimitContext>(
+	ctx: &mut Ctx,
+	path: RateLimitPath,
+	quota_name: &str,
+) -> Result<(), Ics20Error> {
+	let mut rate_limits = ctx.rate_limits(&path)?;
+	let now = ctx.host_timestamp();
+	rate_limits
+		.iter_mut()
+		.find(|rate_limit| rate_limit.quota.name == quota_name)
+		.ok_or_else(|| Ics20Error::rate_limit_quota_not_found(quota_name.to_string()))?
+		.reset(now)?;
+
+	ctx.store_rate_limits(path, rate_limits)
+}
+
+/// Wraps an ICS20 transfer `Module`, answering incoming transfers that exceed a quota with an
+/// error acknowledgement, and removing outgoing transfers that get refunded from the flows.
+/// Outgoing transfers must go through this module's [`send_transfer`].
+#[derive(Clone, Debug)]
+pub struct RateLimitModule<M> {
+	inner: M,
+}
+
+impl<M> RateLimitModule<M> {
+	pub fn new(inner: M) -> Self {
+		Self { inner }
+	}
+
+	pub fn inner(&self) -> &M {
+		&self.inner
+	}
+
+	pub fn inner_mut(&mut self) -> &mut M {
+		&mut self.inner
+	}
+}
+
+impl<M: Module + RateLimitContext + 'static> RateLimitModule<M> {
+	/// Returns the flow update of a received packet, or `None` if the packet data cannot be
+	/// decoded, in which case the inner module is left to fail the packet.
+	fn recv_flow(
+		&self,
+		packet: &Packet,
+	) -> Result<Option<(RateLimitPath, Vec<RateLimit>)>, Ics20Error> {
+		let data = match serde_json::from_slice::<PacketData>(&packet.data) {
+			Ok(data) => data,
+			Err(_) => return Ok(None),
+		};
+
+		let denom = received_denom(packet, &data.token.denom);
+		let path = RateLimitPath::new(packet.destination_channel, denom);
+		let rate_limits = check_flow(&self.inner, &path, FlowType::In, data.token.amount)?;
+		Ok(Some((path, rate_limits)))
+	}
+
+	/// Settles an outgoing packet, removing its tokens from the flows if it was `refunded`.
+	fn settle_send(&mut self, packet: &Packet, refunded: bool) -> Result<(), Ics04Error> {
+		settle_send(&mut self.inner, packet.source_channel, packet.sequence, refunded)
+			.map_err(|e| Ics04Error::app_module(e.to_string()))
+	}
+
+	fn wrap_write_fn(
+		write_fn: Box<WriteFn>,
+		flow: Option<(RateLimitPath, Vec<RateLimit>)>,
+	) -> Box<WriteFn> {
+		Box::new(move |module| {
+			let module = module.downcast_mut::<Self>().unwrap();
+			write_fn(&mut module.inner)?;
+			if let Some((path, rate_limits)) = flow {
+				store_flow(&mut module.inner, path, rate_limits).map_err(|e| e.to_string())?;
+			}
+			Ok(())
+		})
+	}
+}
+
+impl<M: Module + RateLimitContext + 'static> Module for RateLimitModule<M> {
+	fn inner_module(&self) -> Option<&dyn Module> {
+		Some(&self.inner)
+	}
+
+	fn on_chan_open_init(
+		&mut self,
+		output: &mut ModuleOutputBuilder,
+		order: Order,
+		connection_hops: &[ConnectionId],
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		counterparty: &Counterparty,
+		version: &Version,
+	) -> Result<(), Ics04Error> {
+		self.inner.on_chan_open_init(
+			output,
+			order,
+			connection_hops,
+			port_id,
+			channel_id,
+			counterparty,
+			version,
+		)
+	}
+
+	fn on_chan_open_try(
+		&mut self,
+		output: &mut ModuleOutputBuilder,
+		order: Order,
+		connection_hops: &[ConnectionId],
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		counterparty: &Counterparty,
+		version: &Version,
+		counterparty_version: &Version,
+	) -> Result<Version, Ics04Error> {
+		self.inner.on_chan_open_try(
+			output,
+			order,
+			connection_hops,
+			port_id,
+			channel_id,
+			counterparty,
+			version,
+			counterparty_version,
+		)
+	}
+
+	fn on_chan_open_ack(
+		&mut self,
+		output: &mut ModuleOutputBuilder,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		counterparty_version: &Version,
+	) -> Result<(), Ics04Error> {
+		self.inner.on_chan_open_ack(output, port_id, channel_id, counterparty_version)
+	}
+
+	fn on_chan_open_confirm(
+		&mut self,
+		output: &mut ModuleOutputBuilder,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+	) -> Result<(), Ics04Error> {
+		self.inner.on_chan_open_confirm(output, port_id, channel_id)
+	}
+
+	fn on_chan_close_init(
+		&mut self,
+		output: &mut ModuleOutputBuilder,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+	) -> Result<(), Ics04Error> {
+		self.inner.on_chan_close_init(output, port_id, channel_id)
+	}
+
+	fn on_chan_close_confirm(
+		&mut self,
+		output: &mut ModuleOutputBuilder,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+	) -> Result<(), Ics04Error> {
+		self.inner.on_chan_close_confirm(output, port_id, channel_id)
+	}
+
+	fn on_recv_packet(
+		&self,
+		output: &mut ModuleOutputBuilder,
+		packet: &Packet,
+		relayer: &Signer,
+	) -> OnRecvPacketAck {
+		let flow = match self.recv_flow(packet) {
+			Ok(flow) => flow,
+			Err(e) => return OnRecvPacketAck::Failed(Box::new(Acknowledgement::from_error(e))),
+		};
+
+		match self.inner.on_recv_packet(output, packet, relayer) {
+			OnRecvPacketAck::Nil(write_fn) =>
+				OnRecvPacketAck::Nil(Self::wrap_write_fn(write_fn, flow)),
+			OnRecvPacketAck::Successful(ack, write_fn) =>
+				OnRecvPacketAck::Successful(ack, Self::wrap_write_fn(write_fn, flow)),
+			ack @ OnRecvPacketAck::Failed(_) => ack,
+		}
+	}
+
+	fn on_acknowledgement_packet(
+		&mut self,
+		output: &mut ModuleOutputBuilder,
+		packet: &Packet,
+		acknowledgement: &GenericAcknowledgement,
+		relayer: &Signer,
+	) -> Result<(), Ics04Error> {
+		self.inner.on_acknowledgement_packet(output, packet, acknowledgement, relayer)?;
+
+		// the inner module rejects acknowledgements that are not ICS20 ones
+		let is_successful = serde_json::from_slice::<Acknowledgement>(acknowledgement.as_ref())
+			.map_or(false, |ack| ack.is_successful());
+		self.settle_send(packet, !is_successful)
+	}
+
+	fn on_timeout_packet(
+		&mut self,
+		output: &mut ModuleOutputBuilder,
+		packet: &Packet,
+		relayer: &Signer,
+	) -> Result<(), Ics04Error> {
+		self.inner.on_timeout_packet(output, packet, relayer)?;
+		self.settle_send(packet, true)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		applications::transfer::{
+			error::ErrorDetail, msgs::transfer::test_util::get_dummy_msg_transfer,
+		},
+		core::{
+			ics03_connection::{
+				connection::{
+					ConnectionEnd, Counterparty as ConnectionCounterparty, State as ConnectionState,
+				},
+				version::get_compatible_versions,
+			},
+			ics04_channel::channel::{ChannelEnd, State},
+			ics24_host::identifier::ClientId,
+		},
+		mock::context::{HostBlockType, MockClientTypes, MockContext},
+		test_utils::{get_dummy_account_id, DummyTransferModule},
+		timestamp::ZERO_DURATION,
+		Height,
+	};
+	use alloc::collections::BTreeMap;
+
+	/// The state of the rate limits of the transfer module wrapped by the tested one.
+	#[derive(Clone, Debug, Default)]
+	struct RateLimits {
+		rate_limits: BTreeMap<RateLimitPath, Vec<RateLimit>>,
+		channel_values: BTreeMap<RateLimitPath, Amount>,
+		pending_sends: BTreeMap<(ChannelId, Sequence), PendingSend>,
+	}
+
+	type TestModule = RateLimitModule<DummyTransferModule<MockClientTypes, RateLimits>>;
+
+	impl<C: HostBlockType> RateLimitReader for DummyTransferModule<C, RateLimits> {
+		fn rate_limits(&self, path: &RateLimitPath) -> Result<Vec<RateLimit>, Ics20Error> {
+			Ok(self.state().rate_limits.get(path).cloned().unwrap_or_default())
+		}
+
+		fn channel_value(&self, path: &RateLimitPath) -> Result<Amount, Ics20Error> {
+			Ok(self.state().channel_values.get(path).copied().unwrap_or_else(|| 0u64.into()))
+		}
+
+		fn pending_send(
+			&self,
+			channel_id: &ChannelId,
+			sequence: Sequence,
+		) -> Result<Option<PendingSend>, Ics20Error> {
+			Ok(self.state().pending_sends.get(&(*channel_id, sequence)).cloned())
+		}
+	}
+
+	impl<C: HostBlockType> RateLimitKeeper for DummyTransferModule<C, RateLimits> {
+		fn store_rate_limits(
+			&mut self,
+			path: RateLimitPath,
+			rate_limits: Vec<RateLimit>,
+		) -> Result<(), Ics20Error> {
+			if rate_limits.is_empty() {
+				self.state_mut().rate_limits.remove(&path);
+			} else {
+				self.state_mut().rate_limits.insert(path, rate_limits);
+			}
+			Ok(())
+		}
+
+		fn store_pending_send(
+			&mut self,
+			channel_id: ChannelId,
+			sequence: Sequence,
+			pending_send: Option<PendingSend>,
+		) -> Result<(), Ics20Error> {
+			let pending_sends = &mut self.state_mut().pending_sends;
+			match pending_send {
+				Some(pending_send) => pending_sends.insert((channel_id, sequence), pending_send),
+				None => pending_sends.remove(&(channel_id, sequence)),
+			};
+			Ok(())
+		}
+	}
+
+	impl<C: HostBlockType> RateLimitContext for DummyTransferModule<C, RateLimits> {}
+
+	fn quota() -> Quota {
+		Quota {
+			name: "daily".to_string(),
+			max_percent_send: 10,
+			max_percent_recv: 20,
+			duration: Duration::from_secs(60),
+		}
+	}
+
+	fn amount(value: u64) -> Amount {
+		Amount::from(value)
+	}
+
+	fn timestamp(secs: u64) -> Timestamp {
+		Timestamp::from_nanoseconds(secs * 1_000_000_000).unwrap()
+	}
+
+	#[test]
+	fn test_quota_validation() {
+		assert!(quota().validate_basic().is_ok());
+		assert!(Quota { name: " ".to_string(), ..quota() }.validate_basic().is_err());
+		assert!(Quota { max_percent_send: 101, ..quota() }.validate_basic().is_err());
+		assert!(Quota { duration: Duration::ZERO, ..quota() }.validate_basic().is_err());
+	}
+
+	#[test]
+	fn test_rate_limit_net_flow() -> Result<(), Ics20Error> {
+		let channel_value = amount(1000);
+		let mut rate_limit = RateLimit::new(quota(), timestamp(1))?;
+
+		// 10% of 1000 may be sent
+		assert!(rate_limit.allow(FlowType::Out, amount(60), channel_value, timestamp(2))?);
+		assert!(!rate_limit.allow(FlowType::Out, amount(50), channel_value, timestamp(3))?);
+		assert_eq!(rate_limit.flow.outflow, amount(60));
+
+		// inflows offset outflows
+		assert!(rate_limit.allow(FlowType::In, amount(50), channel_value, timestamp(4))?);
+		assert!(rate_limit.allow(FlowType::Out, amount(50), channel_value, timestamp(5))?);
+
+		// refunds are removed from the flow
+		rate_limit.undo(FlowType::Out, amount(110), rate_limit.flow.period_end);
+		assert_eq!(rate_limit.flow.outflow, amount(0));
+
+		// 20% of 1000 may be received
+		assert!(!rate_limit.allow(FlowType::In, amount(200), channel_value, timestamp(6))?);
+		assert!(rate_limit.allow(FlowType::In, amount(150), channel_value, timestamp(7))?);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_rate_limit_period() -> Result<(), Ics20Error> {
+		let channel_value = amount(1000);
+		let mut rate_limit = RateLimit::new(quota(), timestamp(1))?;
+
+		assert!(rate_limit.allow(FlowType::Out, amount(100), channel_value, timestamp(2))?);
+		assert!(!rate_limit.allow(FlowType::Out, amount(1), channel_value, timestamp(60))?);
+
+		// a new period starts once the current one is over
+		assert!(rate_limit.allow(FlowType::Out, amount(100), channel_value, timestamp(61))?);
+		assert_eq!(rate_limit.flow.period_end, timestamp(121));
+
+		// refunds of a previous period are not removed from the current one
+		rate_limit.undo(FlowType::Out, amount(100), timestamp(61));
+		assert_eq!(rate_limit.flow.outflow, amount(100));
+
+		rate_limit.reset(timestamp(62))?;
+		assert_eq!(rate_limit.flow.outflow, amount(0));
+		assert_eq!(rate_limit.flow.period_end, timestamp(122));
+
+		Ok(())
+	}
+
+	/// A transfer module with an open channel, whose transfers are rate limited by `quota()` on
+	/// `path`, for a channel value of 1000.
+	fn rate_limited_transfer(path: &RateLimitPath) -> TestModule {
+		let connection_end = ConnectionEnd::new(
+			ConnectionState::Open,
+			ClientId::default(),
+			ConnectionCounterparty::new(
+				ClientId::default(),
+				Some(ConnectionId::default()),
+				Default::default(),
+			),
+			get_compatible_versions(),
+			ZERO_DURATION,
+		);
+		let channel_end = ChannelEnd::new(
+			State::Open,
+			Order::Unordered,
+			Counterparty::new(PortId::transfer(), Some(ChannelId::new(1))),
+			vec![ConnectionId::default()],
+			Version::ics20(),
+		);
+		let ctx = MockContext::<MockClientTypes>::default()
+			.with_client(&ClientId::default(), Height::new(0, 10))
+			.with_connection(ConnectionId::default(), connection_end)
+			.with_channel(PortId::transfer(), ChannelId::default(), channel_end)
+			.with_send_sequence(PortId::transfer(), ChannelId::default(), 1.into());
+
+		let mut inner =
+			DummyTransferModule::with_state(ctx.ibc_store_share(), RateLimits::default());
+		inner.set_host_timestamp(timestamp(1));
+		inner.state_mut().channel_values.insert(path.clone(), amount(1000));
+		add_rate_limit(&mut inner, path.clone(), quota()).unwrap();
+		RateLimitModule::new(inner)
+	}
+
+	fn msg_transfer(value: u64) -> MsgTransfer<PrefixedCoin> {
+		MsgTransfer {
+			source_port: PortId::transfer(),
+			source_channel: ChannelId::default(),
+			token: PrefixedCoin { denom: "uatom".parse().unwrap(), amount: amount(value) },
+			..get_dummy_msg_transfer(20)
+		}
+	}
+
+	fn recv_packet(value: u64) -> Packet {
+		let data = PacketData {
+			token: PrefixedCoin { denom: "uosmo".parse().unwrap(), amount: amount(value) },
+			sender: get_dummy_account_id(),
+			receiver: get_dummy_account_id(),
+			memo: String::new(),
+		};
+		Packet {
+			source_port: PortId::transfer(),
+			source_channel: ChannelId::new(1),
+			destination_port: PortId::transfer(),
+			destination_channel: ChannelId::default(),
+			data: serde_json::to_vec(&data).unwrap(),
+			..Default::default()
+		}
+	}
+
+	fn ack(success: bool) -> GenericAcknowledgement {
+		let ack = if success {
+			Acknowledgement::success()
+		} else {
+			Acknowledgement::from_error(Ics20Error::packet_data_deserialization())
+		};
+		serde_json::to_vec(&ack.to_string()).unwrap().into()
+	}
+
+	fn flow(module: &TestModule, path: &RateLimitPath) -> Flow {
+		module.inner().rate_limits(path).unwrap()[0].flow.clone()
+	}
+
+	#[test]
+	fn test_rate_limited_send() {
+		let path = RateLimitPath::new(ChannelId::default(), "uatom".parse().unwrap());
+		let mut module = rate_limited_transfer(&path);
+		let mut output = HandlerOutputBuilder::new();
+		let relayer = get_dummy_account_id();
+
+		// 10% of 1000 may be sent
+		let packet = send_transfer(module.inner_mut(), &mut output, msg_transfer(60)).unwrap();
+		assert!(validate_transfer(module.inner(), msg_transfer(40)).is_ok());
+		let err = validate_transfer(module.inner(), msg_transfer(50)).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::RateLimitExceeded(_)));
+		assert!(send_transfer(module.inner_mut(), &mut output, msg_transfer(50)).is_err());
+		assert_eq!(flow(&module, &path).outflow, amount(60));
+
+		// acknowledged transfers stay in the flow
+		module
+			.on_acknowledgement_packet(
+				&mut ModuleOutputBuilder::new(),
+				&packet,
+				&ack(true),
+				&relayer,
+			)
+			.unwrap();
+		assert_eq!(flow(&module, &path).outflow, amount(60));
+		assert_eq!(module.inner().pending_send(&path.channel_id, packet.sequence).unwrap(), None);
+
+		// refunded ones are removed from it
+		let packet = send_transfer(module.inner_mut(), &mut output, msg_transfer(40)).unwrap();
+		assert_eq!(flow(&module, &path).outflow, amount(100));
+		module
+			.on_acknowledgement_packet(
+				&mut ModuleOutputBuilder::new(),
+				&packet,
+				&ack(false),
+				&relayer,
+			)
+			.unwrap();
+		assert_eq!(flow(&module, &path).outflow, amount(60));
+
+		let packet = send_transfer(module.inner_mut(), &mut output, msg_transfer(40)).unwrap();
+		module
+			.on_timeout_packet(&mut ModuleOutputBuilder::new(), &packet, &relayer)
+			.unwrap();
+		assert_eq!(flow(&module, &path).outflow, amount(60));
+		assert_eq!(module.inner().pending_send(&path.channel_id, packet.sequence).unwrap(), None);
+	}
+
+	#[test]
+	fn test_rate_limited_recv() {
+		// received tokens are prefixed with the destination channel
+		let denom = "transfer/channel-0/uosmo".parse().unwrap();
+		let path = RateLimitPath::new(ChannelId::default(), denom);
+		let mut module = rate_limited_transfer(&path);
+		let relayer = get_dummy_account_id();
+
+		// 20% of 1000 may be received, and the flow is only updated once the packet is written
+		let ack =
+			module.on_recv_packet(&mut ModuleOutputBuilder::new(), &recv_packet(150), &relayer);
+		assert_eq!(flow(&module, &path).inflow, amount(0));
+		match ack {
+			OnRecvPacketAck::Successful(_, write_fn) => write_fn(&mut module).unwrap(),
+			_ => panic!("a packet within the quota is passed to the inner module"),
+		}
+		assert_eq!(flow(&module, &path).inflow, amount(150));
+
+		let ack =
+			module.on_recv_packet(&mut ModuleOutputBuilder::new(), &recv_packet(100), &relayer);
+		assert!(matches!(ack, OnRecvPacketAck::Failed(_)));
+		assert_eq!(flow(&module, &path).inflow, amount(150));
+	}
+
+	#[test]
+	fn test_rate_limited_rollover() {
+		let path = RateLimitPath::new(ChannelId::default(), "uatom".parse().unwrap());
+		let mut module = rate_limited_transfer(&path);
+		let mut output = HandlerOutputBuilder::new();
+		let relayer = get_dummy_account_id();
+
+		let first = send_transfer(module.inner_mut(), &mut output, msg_transfer(100)).unwrap();
+		assert!(send_transfer(module.inner_mut(), &mut output, msg_transfer(1)).is_err());
+
+		// the quota is available again once its period is over
+		module.inner_mut().set_host_timestamp(timestamp(70));
+		let second = send_transfer(module.inner_mut(), &mut output, msg_transfer(100)).unwrap();
+		assert_eq!(flow(&module, &path).outflow, amount(100));
+		assert_eq!(flow(&module, &path).period_end, timestamp(130));
+
+		// refunds are only removed from the period they were counted in
+		module
+			.on_timeout_packet(&mut ModuleOutputBuilder::new(), &first, &relayer)
+			.unwrap();
+		assert_eq!(flow(&module, &path).outflow, amount(100));
+		module
+			.on_timeout_packet(&mut ModuleOutputBuilder::new(), &second, &relayer)
+			.unwrap();
+		assert_eq!(flow(&module, &path).outflow, amount(0));
+	}
+}
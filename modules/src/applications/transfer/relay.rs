@@ -1,11 +1,17 @@
 //! This module implements the processing logic for ICS20 (token transfer) message.
 use crate::{
 	applications::transfer::{
-		context::Ics20Context, error::Error as Ics20Error, is_sender_chain_source,
+		context::{channel_capability, Ics20Context},
+		error::Error as Ics20Error,
+		is_receiver_chain_source, is_sender_chain_source,
 		packet::PacketData,
+		Amount, PrefixedCoin, PrefixedDenom, TracePrefix,
 	},
-	core::ics04_channel::packet::Packet,
+	core::ics04_channel::{handler::write_acknowledgement, packet::Packet},
+	events::IbcEvent,
+	handler::HandlerOutput,
 	prelude::*,
+	signer::Signer,
 };
 
 pub mod on_ack_packet;
@@ -30,10 +36,125 @@ fn refund_packet_token(
 		let escrow_address =
 			ctx.get_channel_escrow_address(&packet.source_port, packet.source_channel)?;
 
-		ctx.send_coins(&escrow_address, &sender, &data.token)
+		ctx.send_coins(&escrow_address, &sender, &data.token)?;
+		decrease_total_escrow(ctx, &data.token.denom, data.token.amount)
 	}
 	// mint vouchers back to sender
 	else {
 		ctx.mint_coins(&sender, &data.token)
 	}
 }
+
+/// Returns the denomination under which the tokens of `denom` sent in `packet` are credited on
+/// the receiving chain.
+pub(crate) fn received_denom(packet: &Packet, denom: &PrefixedDenom) -> PrefixedDenom {
+	let mut denom = denom.clone();
+	if is_receiver_chain_source(packet.source_port.clone(), packet.source_channel, &denom) {
+		denom.remove_trace_prefix(&TracePrefix::new(
+			packet.source_port.clone(),
+			packet.source_channel,
+		));
+	} else {
+		denom.add_trace_prefix(TracePrefix::new(
+			packet.destination_port.clone(),
+			packet.destination_channel,
+		));
+	}
+	denom
+}
+
+/// Returns the total escrow of `denom` once `amount` more has been escrowed.
+pub(crate) fn increased_total_escrow(
+	ctx: &impl Ics20Context,
+	denom: &PrefixedDenom,
+	amount: Amount,
+) -> Result<Amount, Ics20Error> {
+	ctx.get_total_escrow(denom)?
+		.checked_add(amount)
+		.ok_or_else(|| Ics20Error::total_escrow_overflow(denom.to_string()))
+}
+
+/// Returns the total escrow of `denom` once `amount` has been unescrowed, failing if more than
+/// the total escrow would be released.
+pub(crate) fn decreased_total_escrow(
+	ctx: &impl Ics20Context,
+	denom: &PrefixedDenom,
+	amount: Amount,
+) -> Result<Amount, Ics20Error> {
+	let total_escrow = ctx.get_total_escrow(denom)?;
+	total_escrow.checked_sub(amount).ok_or_else(|| {
+		Ics20Error::negative_total_escrow(
+			denom.to_string(),
+			total_escrow.to_string(),
+			amount.to_string(),
+		)
+	})
+}
+
+/// Adds `amount` to the total escrow of `denom`.
+pub(crate) fn increase_total_escrow(
+	ctx: &mut impl Ics20Context,
+	denom: &PrefixedDenom,
+	amount: Amount,
+) -> Result<(), Ics20Error> {
+	let total_escrow = increased_total_escrow(ctx, denom, amount)?;
+	ctx.store_total_escrow(denom, total_escrow)
+}
+
+/// Removes `amount` from the total escrow of `denom`, failing if more than the total escrow
+/// would be released.
+pub(crate) fn decrease_total_escrow(
+	ctx: &mut impl Ics20Context,
+	denom: &PrefixedDenom,
+	amount: Amount,
+) -> Result<(), Ics20Error> {
+	let total_escrow = decreased_total_escrow(ctx, denom, amount)?;
+	ctx.store_total_escrow(denom, total_escrow)
+}
+
+/// Takes back the tokens of the received `packet` credited to `receiver`, escrowing them back or
+/// burning the vouchers minted for them, e.g. when the packet is eventually answered with an error
+/// acknowledgement.
+pub(crate) fn revert_received_token(
+	ctx: &mut impl Ics20Context,
+	packet: &Packet,
+	receiver: Signer,
+) -> Result<(), Ics20Error> {
+	let data = serde_json::from_slice::<PacketData>(&packet.data)
+		.map_err(|_| Ics20Error::packet_data_deserialization())?;
+	let receiver = receiver.try_into().map_err(|_| Ics20Error::parse_account_failure())?;
+	let token = PrefixedCoin {
+		denom: received_denom(packet, &data.token.denom),
+		amount: data.token.amount,
+	};
+
+	if is_receiver_chain_source(
+		packet.source_port.clone(),
+		packet.source_channel,
+		&data.token.denom,
+	) {
+		// the tokens were unescrowed, escrow them back
+		let escrow_address =
+			ctx.get_channel_escrow_address(&packet.destination_port, packet.destination_channel)?;
+		ctx.send_coins(&receiver, &escrow_address, &token)?;
+		increase_total_escrow(ctx, &token.denom, token.amount)
+	} else {
+		// the tokens were minted as vouchers, burn them
+		ctx.burn_coins(&receiver, &token)
+	}
+}
+
+/// Writes `ack` for the received `packet`, for middlewares acknowledging packets themselves, and
+/// returns the events to report.
+pub(crate) fn write_packet_acknowledgement(
+	ctx: &mut impl Ics20Context,
+	packet: Packet,
+	ack: Vec<u8>,
+) -> Result<Vec<IbcEvent>, Ics20Error> {
+	let capability = channel_capability(ctx, &packet.destination_port, packet.destination_channel)?;
+	let HandlerOutput { result, events, .. } =
+		write_acknowledgement::process(ctx, &capability, packet, ack)
+			.map_err(Ics20Error::ics04_channel)?;
+	ctx.store_packet_result(result).map_err(Ics20Error::ics04_channel)?;
+	Ok(events)
+}
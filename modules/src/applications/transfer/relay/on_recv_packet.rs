@@ -1,7 +1,14 @@
 use crate::{
 	applications::transfer::{
-		context::Ics20Context, error::Error as Ics20Error, events::DenomTraceEvent,
-		is_receiver_chain_source, packet::PacketData, TracePrefix,
+		context::Ics20Context,
+		enablement::{ensure_transfer_enabled, TransferDirection},
+		error::Error as Ics20Error,
+		events::DenomTraceEvent,
+		is_receiver_chain_source,
+		metadata::DenomMetadata,
+		packet::PacketData,
+		relay::{decrease_total_escrow, decreased_total_escrow},
+		TracePrefix,
 	},
 	core::{
 		ics04_channel::packet::Packet,
@@ -34,37 +41,69 @@ pub fn process_recv_packet<Ctx: 'static + Ics20Context>(
 		// sender chain is not the source, unescrow tokens
 		let prefix = TracePrefix::new(packet.source_port.clone(), packet.source_channel);
 		let coin = {
-			let mut c = data.token;
+			let mut c = data.token.clone();
 			c.denom.remove_trace_prefix(&prefix);
 			c
 		};
+		ensure_transfer_enabled(
+			ctx,
+			TransferDirection::Receive,
+			&coin.denom,
+			packet.destination_channel,
+		)?;
 
 		let escrow_address =
 			ctx.get_channel_escrow_address(&packet.destination_port, packet.destination_channel)?;
+		// a packet releasing more than the total escrow is acknowledged with an error, the total
+		// escrow is only decreased once the packet is written, from its value at that time
+		decreased_total_escrow(ctx, &coin.denom, coin.amount)?;
 
+		let packet = packet.clone();
 		Ok(Box::new(move |ctx| {
 			let ctx = ctx.downcast_mut::<Ctx>().unwrap();
 			ctx.send_coins(&escrow_address, &receiver_account, &coin)
+				.and_then(|_| decrease_total_escrow(ctx, &coin.denom, coin.amount))
+				.and_then(|_| ctx.on_recv_packet_hook(&packet, &data))
 				.map_err(|e| e.to_string())
 		}))
 	} else {
 		// sender chain is the source, mint vouchers
 		let prefix = TracePrefix::new(packet.destination_port.clone(), packet.destination_channel);
 		let coin = {
-			let mut c = data.token;
+			let mut c = data.token.clone();
 			c.denom.add_trace_prefix(prefix);
 			c
 		};
+		ensure_transfer_enabled(
+			ctx,
+			TransferDirection::Receive,
+			&coin.denom,
+			packet.destination_channel,
+		)?;
 
-		let denom_trace_event = DenomTraceEvent {
-			trace_hash: ctx.denom_hash_string(&coin.denom),
-			denom: coin.denom.clone(),
+		// the trace is only stored, and announced, the first time the voucher is minted
+		let trace_hash = ctx.denom_trace_hash(&coin.denom);
+		let new_denom_trace = if ctx.get_denom_trace(&trace_hash)?.is_none() {
+			let denom_trace_event =
+				DenomTraceEvent { trace_hash: trace_hash.clone(), denom: coin.denom.clone() };
+			output.emit(denom_trace_event.into());
+			let metadata = DenomMetadata::new(&coin.denom, ctx.ibc_denom(&coin.denom), &data.memo);
+			Some((trace_hash, coin.denom.clone(), metadata))
+		} else {
+			None
 		};
-		output.emit(denom_trace_event.into());
 
+		let packet = packet.clone();
 		Ok(Box::new(move |ctx| {
 			let ctx = ctx.downcast_mut::<Ctx>().unwrap();
-			ctx.mint_coins(&receiver_account, &coin).map_err(|e| e.to_string())
+			if let Some((trace_hash, denom, metadata)) = new_denom_trace {
+				ctx.store_denom_trace(trace_hash, denom.clone())
+					.and_then(|_| ctx.register_denom_metadata(&denom, metadata))
+					.map_err(|e| e.to_string())?;
+			}
+			ctx.mint_coins(&receiver_account, &coin)
+				.and_then(|_| ctx.on_recv_packet_hook(&packet, &data))
+				.map_err(|e| e.to_string())
 		}))
 	}
 }
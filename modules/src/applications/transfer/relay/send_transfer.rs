@@ -1,25 +1,117 @@
+use core::str::FromStr;
+
+use ibc_proto::cosmos::base::v1beta1::Coin as RawCoin;
+
 use crate::{
 	applications::transfer::{
-		context::Ics20Context, error::Error, events::TransferEvent, is_sender_chain_source,
-		msgs::transfer::MsgTransfer, packet::PacketData, Coin, PrefixedCoin,
+		context::{channel_capability, Ics20Context},
+		enablement::{ensure_transfer_enabled, TransferDirection},
+		error::Error,
+		events::TransferEvent,
+		is_sender_chain_source,
+		msgs::transfer::MsgTransfer,
+		packet::PacketData,
+		relay::increased_total_escrow,
+		Amount, PrefixedCoin,
+	},
+	core::ics04_channel::{
+		handler::send_packet::send_packet,
+		packet::{Packet, PacketResult},
 	},
-	core::ics04_channel::{handler::send_packet::send_packet, packet::Packet},
 	events::ModuleEvent,
 	handler::{HandlerOutput, HandlerOutputBuilder},
 	prelude::*,
+	signer::Signer,
 };
 
 /// This function handles the transfer sending logic.
+/// The token denomination may be given as `ibc/{hash}`, in which case it is resolved to its full
+/// trace before being sent.
+/// Returns the packet that was sent.
 /// If this method returns an error, the runtime is expected to rollback all state modifications to
 /// the `Ctx` caused by all messages from the transaction that this `msg` is a part of.
 pub fn send_transfer<Ctx, C>(
 	ctx: &mut Ctx,
 	output: &mut HandlerOutputBuilder<()>,
 	msg: MsgTransfer<C>,
-) -> Result<(), Error>
+) -> Result<Packet, Error>
 where
 	Ctx: Ics20Context,
-	C: TryInto<PrefixedCoin>,
+	C: Into<RawCoin>,
+{
+	let transfer = prepare_transfer(ctx, msg)?;
+
+	match &transfer.escrow {
+		Some((escrow_address, total_escrow)) => {
+			ctx.send_coins(&transfer.sender, escrow_address, &transfer.token)?;
+			ctx.store_total_escrow(&transfer.token.denom, *total_escrow)?;
+		},
+		None => ctx.burn_coins(&transfer.sender, &transfer.token)?,
+	}
+
+	ctx.store_packet_result(transfer.send_packet.result.clone())
+		.map_err(Error::ics04_channel)?;
+
+	Ok(transfer.emit(output))
+}
+
+/// Runs all the checks of [`send_transfer`] without modifying the `Ctx`, and returns the packet
+/// the transfer would send along with the events it would emit, so that a transfer can be
+/// simulated before it is submitted.
+/// Whether the sender holds the tokens is left to the bank, and only checked on execution.
+pub fn validate_transfer<Ctx, C>(
+	ctx: &Ctx,
+	msg: MsgTransfer<C>,
+) -> Result<HandlerOutput<Packet>, Error>
+where
+	Ctx: Ics20Context,
+	C: Into<RawCoin>,
+{
+	let transfer = prepare_transfer(ctx, msg)?;
+	let mut output = HandlerOutputBuilder::new();
+	let packet = transfer.emit(&mut output);
+	let HandlerOutput { log, events, .. } = output.with_result(());
+	Ok(HandlerOutput { result: packet, log, events })
+}
+
+/// A transfer that passed all checks, and the state changes its execution consists of.
+struct PreparedTransfer<AccountId> {
+	sender: AccountId,
+	token: PrefixedCoin,
+	/// The escrow account and its new total escrow, if the tokens are escrowed rather than burnt.
+	escrow: Option<(AccountId, Amount)>,
+	send_packet: HandlerOutput<PacketResult>,
+	packet: Packet,
+	msg_sender: Signer,
+	msg_receiver: Signer,
+}
+
+impl<AccountId> PreparedTransfer<AccountId> {
+	fn emit(self, output: &mut HandlerOutputBuilder<()>) -> Packet {
+		let HandlerOutput { log, events, .. } = self.send_packet;
+		output.merge_output(
+			HandlerOutput::builder().with_log(log).with_events(events).with_result(()),
+		);
+
+		output.log(format!(
+			"IBC fungible token transfer: {} --({})--> {}",
+			self.msg_sender, self.token, self.msg_receiver
+		));
+
+		let transfer_event = TransferEvent { sender: self.msg_sender, receiver: self.msg_receiver };
+		output.emit(ModuleEvent::from(transfer_event).into());
+
+		self.packet
+	}
+}
+
+fn prepare_transfer<Ctx, C>(
+	ctx: &Ctx,
+	msg: MsgTransfer<C>,
+) -> Result<PreparedTransfer<<Ctx as Ics20Context>::AccountId>, Error>
+where
+	Ctx: Ics20Context,
+	C: Into<RawCoin>,
 {
 	if !ctx.is_send_enabled() {
 		return Err(Error::send_disabled())
@@ -39,23 +131,28 @@ where
 		.get_next_sequence_send(&(msg.source_port.clone(), msg.source_channel))
 		.map_err(Error::ics04_channel)?;
 
-	let token = msg.token.try_into().map_err(|_| Error::invalid_token())?;
-	let denom = token.denom.clone();
-	let coin = Coin { denom: denom.clone(), amount: token.amount };
+	let token = resolve_coin(ctx, msg.token.into())?;
+	ensure_transfer_enabled(ctx, TransferDirection::Send, &token.denom, msg.source_channel)?;
 
 	let sender = msg.sender.clone().try_into().map_err(|_| Error::parse_account_failure())?;
 
-	if is_sender_chain_source(msg.source_port.clone(), msg.source_channel, &denom) {
-		let escrow_address =
-			ctx.get_channel_escrow_address(&msg.source_port, msg.source_channel)?;
-		ctx.send_coins(&sender, &escrow_address, &coin)?;
-	} else {
-		ctx.burn_coins(&sender, &coin)?;
-	}
+	let escrow =
+		if is_sender_chain_source(msg.source_port.clone(), msg.source_channel, &token.denom) {
+			let escrow_address =
+				ctx.get_channel_escrow_address(&msg.source_port, msg.source_channel)?;
+			let total_escrow = increased_total_escrow(ctx, &token.denom, token.amount)?;
+			Some((escrow_address, total_escrow))
+		} else {
+			None
+		};
 
 	let data = {
-		let data =
-			PacketData { token: coin, sender: msg.sender.clone(), receiver: msg.receiver.clone() };
+		let data = PacketData {
+			token: token.clone(),
+			sender: msg.sender.clone(),
+			receiver: msg.receiver.clone(),
+			memo: msg.memo,
+		};
 		serde_json::to_vec(&data).expect("PacketData's infallible Serialize impl failed")
 	};
 
@@ -70,20 +167,24 @@ where
 		timeout_timestamp: msg.timeout_timestamp,
 	};
 
-	let HandlerOutput { result, log, events } =
-		send_packet(ctx, packet).map_err(Error::ics04_channel)?;
+	let capability = channel_capability(ctx, &packet.source_port, packet.source_channel)?;
+	let send_packet =
+		send_packet(ctx, &capability, packet.clone()).map_err(Error::ics04_channel)?;
 
-	ctx.store_packet_result(result).map_err(Error::ics04_channel)?;
-
-	output.merge_output(HandlerOutput::builder().with_log(log).with_events(events).with_result(()));
-
-	output.log(format!(
-		"IBC fungible token transfer: {} --({})--> {}",
-		msg.sender, token, msg.receiver
-	));
-
-	let transfer_event = TransferEvent { sender: msg.sender, receiver: msg.receiver };
-	output.emit(ModuleEvent::from(transfer_event).into());
+	Ok(PreparedTransfer {
+		sender,
+		token,
+		escrow,
+		send_packet,
+		packet,
+		msg_sender: msg.sender,
+		msg_receiver: msg.receiver,
+	})
+}
 
-	Ok(())
+/// Parses a coin, resolving its denomination if it is of the form `ibc/{hash}`.
+pub fn resolve_coin(ctx: &impl Ics20Context, coin: RawCoin) -> Result<PrefixedCoin, Error> {
+	let denom = ctx.resolve_denom(&coin.denom)?;
+	let amount = Amount::from_str(&coin.amount)?;
+	Ok(PrefixedCoin { denom, amount })
 }
@@ -216,4 +216,99 @@ pub trait ClientDef: Clone {
 		channel_id: &ChannelId,
 		sequence: Sequence,
 	) -> Result<(), Error>;
+
+	/// Verify a single `proof` that each of the packets sent on the channel with the given
+	/// sequences has been commited, to the given commitment.
+	///
+	/// By default, each commitment is verified by [`Self::verify_packet_data`] against the whole
+	/// `proof`, which suits proof formats in which a proof of several keys is a proof of each of
+	/// them, e.g. trie proofs.
+	#[allow(clippy::too_many_arguments)]
+	fn verify_packet_data_batch<Ctx: ReaderContext>(
+		&self,
+		ctx: &Ctx,
+		client_id: &ClientId,
+		client_state: &Self::ClientState,
+		height: Height,
+		connection_end: &ConnectionEnd,
+		proof: &CommitmentProofBytes,
+		root: &CommitmentRoot,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		commitments: Vec<(Sequence, PacketCommitment)>,
+	) -> Result<(), Error> {
+		commitments.into_iter().try_for_each(|(sequence, commitment)| {
+			self.verify_packet_data(
+				ctx,
+				client_id,
+				client_state,
+				height,
+				connection_end,
+				proof,
+				root,
+				port_id,
+				channel_id,
+				sequence,
+				commitment,
+			)
+		})
+	}
+
+	/// Verify a single `proof` that none of the packets with the given sequences has been received
+	/// on the channel.
+	///
+	/// By default, the absence of each receipt is verified by
+	/// [`Self::verify_packet_receipt_absence`] against the whole `proof`, see
+	/// [`Self::verify_packet_data_batch`].
+	#[allow(clippy::too_many_arguments)]
+	fn verify_packet_receipt_absence_batch<Ctx: ReaderContext>(
+		&self,
+		ctx: &Ctx,
+		client_id: &ClientId,
+		client_state: &Self::ClientState,
+		height: Height,
+		connection_end: &ConnectionEnd,
+		proof: &CommitmentProofBytes,
+		root: &CommitmentRoot,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		sequences: Vec<Sequence>,
+	) -> Result<(), Error> {
+		sequences.into_iter().try_for_each(|sequence| {
+			self.verify_packet_receipt_absence(
+				ctx,
+				client_id,
+				client_state,
+				height,
+				connection_end,
+				proof,
+				root,
+				port_id,
+				channel_id,
+				sequence,
+			)
+		})
+	}
+
+	/// Verify a `proof` that `value` is stored under `path`, in the store identified by
+	/// `prefix`, of the counterparty state committed to by `root`. Used to verify the results of
+	/// cross-chain queries. Clients which cannot verify arbitrary paths reject every proof.
+	#[allow(clippy::too_many_arguments)]
+	fn verify_membership<Ctx: ReaderContext>(
+		&self,
+		_ctx: &Ctx,
+		_client_id: &ClientId,
+		client_state: &Self::ClientState,
+		_height: Height,
+		_prefix: &CommitmentPrefix,
+		_proof: &CommitmentProofBytes,
+		_root: &CommitmentRoot,
+		_path: String,
+		_value: Vec<u8>,
+	) -> Result<(), Error> {
+		Err(Error::client_error(
+			client_state.client_type(),
+			"membership proofs of arbitrary paths are not supported".to_string(),
+		))
+	}
 }
@@ -1,5 +1,9 @@
 use crate::{
-	core::{ics02_client::client_def::ClientDef, ics24_host::identifier::ChainId},
+	core::{
+		ics02_client::client_def::ClientDef,
+		ics23_commitment::{specs::ProofSpecs, verify::ProofFormat},
+		ics24_host::identifier::ChainId,
+	},
 	prelude::*,
 	Height,
 };
@@ -51,6 +55,18 @@ pub trait ClientState: Clone + Debug + Send + Sync {
 	/// Helper function to verify the upgrade client procedure.
 	fn expired(&self, elapsed: Duration) -> bool;
 
+	/// The format of the commitment proofs of the chain, which the verification functions of the
+	/// client expect, see [`verify`](crate::core::ics23_commitment::verify).
+	fn proof_format(&self) -> ProofFormat {
+		ProofFormat::Ics23
+	}
+
+	/// The specs the ICS-23 proofs of the chain are verified against, from its IBC store to its
+	/// root store. Only meaningful if the [`proof_format`](Self::proof_format) is ICS-23.
+	fn proof_specs(&self) -> ProofSpecs {
+		ProofSpecs::default()
+	}
+
 	/// Performs downcast of the client state from an "AnyClientState" type to T, otherwise
 	/// panics. Downcast from `T` to `T` is always successful.
 	fn downcast<T: Clone + 'static>(self) -> Option<T>
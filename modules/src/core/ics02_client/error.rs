@@ -10,6 +10,7 @@ use crate::{
 		ics23_commitment::error::Error as Ics23Error,
 		ics24_host::{error::ValidationError, identifier::ClientId},
 	},
+	gas::GasError,
 	signer::SignerError,
 	timestamp::Timestamp,
 	Height,
@@ -281,5 +282,9 @@ define_error! {
 		Signer
 			[ SignerError ]
 			| _ | { "failed to parse signer" },
+
+		Gas
+			[ GasError ]
+			| _ | { "gas meter error" },
 	}
 }
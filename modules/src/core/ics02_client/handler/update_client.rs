@@ -57,7 +57,10 @@ where
 		return Err(Error::client_frozen(client_id));
 	}
 
+	ctx.gas_meter().charge_client_message().map_err(Error::gas)?;
+
 	// Read consensus state from the host chain store.
+	ctx.gas_meter().charge_consensus_state_read().map_err(Error::gas)?;
 	let latest_consensus_state =
 		ctx.consensus_state(&client_id, client_state.latest_height()).map_err(|_| {
 			Error::consensus_state_not_found(client_id.clone(), client_state.latest_height())
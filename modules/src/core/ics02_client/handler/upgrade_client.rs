@@ -57,6 +57,11 @@ where
 
 	let client_type = ctx.client_type(&client_id)?;
 
+	let gas = ctx.gas_meter();
+	gas.charge_proof_verification(&msg.proof_upgrade_client).map_err(Error::gas)?;
+	gas.charge_proof_verification(&msg.proof_upgrade_consensus_state)
+		.map_err(Error::gas)?;
+
 	let client_def = client_state.client_def();
 
 	let (new_client_state, new_consensus_state) = client_def
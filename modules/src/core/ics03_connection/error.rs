@@ -2,11 +2,13 @@ use crate::{
 	core::{
 		ics02_client::error as client_error,
 		ics03_connection::version::Version,
+		ics23_commitment::error::Error as Ics23Error,
 		ics24_host::{
 			error::ValidationError,
 			identifier::{ClientId, ConnectionId},
 		},
 	},
+	gas::GasError,
 	prelude::*,
 	proofs::ProofError,
 	signer::SignerError,
@@ -162,8 +164,20 @@ define_error! {
 					e.client_id)
 			},
 
+		InvalidProofSpecs
+			{ client_id: ClientId }
+			[ Ics23Error ]
+			| e | {
+				format_args!("the proof specs of client {0} are invalid",
+					e.client_id)
+			},
+
 		ImplementationSpecific
 			{ reason: String }
 			| e | { format_args!("implementation specific error: {}", e.reason) },
+
+		Gas
+			[ GasError ]
+			| _ | { "gas meter error" },
 	}
 }
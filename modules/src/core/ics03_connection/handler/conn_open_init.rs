@@ -6,7 +6,7 @@ use crate::{
 			connection::{ConnectionEnd, State},
 			error::Error,
 			events::Attributes,
-			handler::{ConnectionIdState, ConnectionResult},
+			handler::{verify::check_client_proof_specs, ConnectionIdState, ConnectionResult},
 			msgs::conn_open_init::MsgConnectionOpenInit,
 		},
 		ics24_host::identifier::ConnectionId,
@@ -23,8 +23,9 @@ pub(crate) fn process<Ctx: ReaderContext>(
 ) -> HandlerResult<ConnectionResult, Error> {
 	let mut output = HandlerOutput::builder();
 
-	// An IBC client running on the local (host) chain should exist.
-	ctx.client_state(&msg.client_id).map_err(Error::ics02_client)?;
+	// An IBC client running on the local (host) chain should exist, and be able to verify the
+	// proofs of the counterparty.
+	check_client_proof_specs(ctx, &msg.client_id)?;
 
 	let versions = match msg.version {
 		Some(version) =>
@@ -79,6 +80,7 @@ mod tests {
 			ics03_connection::{
 				connection::State,
 				context::ConnectionReader,
+				error::ErrorDetail,
 				handler::{dispatch, ConnectionResult},
 				msgs::{
 					conn_open_init::{
@@ -198,4 +200,17 @@ mod tests {
 			}
 		}
 	}
+
+	#[test]
+	fn conn_open_init_rejects_invalid_proof_specs() {
+		let msg = MsgConnectionOpenInit::try_from(get_dummy_raw_msg_conn_open_init()).unwrap();
+		let ctx = MockContext::<MockClientTypes>::default()
+			.with_client(&msg.client_id, Height::new(0, 10))
+			.with_invalid_client_proof_specs(&msg.client_id);
+
+		let err = dispatch(&ctx, ConnectionMsg::ConnectionOpenInit(msg.clone())).unwrap_err();
+		assert!(
+			matches!(err.detail(), ErrorDetail::InvalidProofSpecs(e) if e.client_id == msg.client_id)
+		);
+	}
 }
@@ -8,8 +8,8 @@ use crate::{
 			events::Attributes,
 			handler::{
 				verify::{
-					check_client_consensus_height, verify_client_proof, verify_connection_proof,
-					verify_consensus_proof,
+					check_client_consensus_height, check_client_proof_specs, verify_client_proof,
+					verify_connection_proof, verify_consensus_proof,
 				},
 				ConnectionIdState, ConnectionResult,
 			},
@@ -75,6 +75,7 @@ pub(crate) fn process<Ctx: ReaderContext>(
 	})?;
 
 	ctx.validate_self_client(&client_state).map_err(Error::ics02_client)?;
+	check_client_proof_specs(ctx, &msg.client_id)?;
 
 	verify_connection_proof::<_>(
 		ctx,
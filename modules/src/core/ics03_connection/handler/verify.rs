@@ -7,7 +7,8 @@ use crate::core::ics02_client::{
 use crate::{
 	core::{
 		ics03_connection::{connection::ConnectionEnd, error::Error},
-		ics23_commitment::commitment::CommitmentProofBytes,
+		ics23_commitment::{commitment::CommitmentProofBytes, verify::ProofFormat},
+		ics24_host::identifier::ClientId,
 		ics26_routing::context::ReaderContext,
 	},
 	proofs::ConsensusProof,
@@ -15,6 +16,22 @@ use crate::{
 };
 use alloc::{format, vec::Vec};
 
+/// Checks that the client `client_id` can verify the proofs of its counterparty, i.e. that the
+/// specs it verifies ICS-23 proofs against are structurally valid, before opening a connection.
+pub fn check_client_proof_specs<Ctx: ReaderContext>(
+	ctx: &Ctx,
+	client_id: &ClientId,
+) -> Result<(), Error> {
+	let client_state = ctx.client_state(client_id).map_err(Error::ics02_client)?;
+	if client_state.proof_format() == ProofFormat::Ics23 {
+		client_state
+			.proof_specs()
+			.validate()
+			.map_err(|e| Error::invalid_proof_specs(client_id.clone(), e))?;
+	}
+	Ok(())
+}
+
 /// Verifies the authenticity and semantic correctness of a commitment `proof`. The commitment
 /// claims to prove that an object of type connection exists on the source chain (i.e., the chain
 /// which created this proof). This object must match the state of `expected_conn`.
@@ -35,6 +52,9 @@ pub fn verify_connection_proof<Ctx: ReaderContext>(
 	}
 
 	// The client must have the consensus state for the height where this proof was created.
+	ctx.gas_meter()
+		.charge_proof_verification(proof.as_bytes())
+		.map_err(Error::gas)?;
 	let consensus_state = ctx
 		.consensus_state(connection_end.client_id(), proof_height)
 		.map_err(|e| Error::consensus_state_verification_failure(proof_height, e))?;
@@ -86,6 +106,9 @@ pub fn verify_client_proof<Ctx: ReaderContext>(
 		return Err(Error::frozen_client(connection_end.client_id().clone()))
 	}
 
+	ctx.gas_meter()
+		.charge_proof_verification(proof.as_bytes())
+		.map_err(Error::gas)?;
 	let consensus_state = ctx
 		.consensus_state(connection_end.client_id(), proof_height)
 		.map_err(|e| Error::consensus_state_verification_failure(proof_height, e))?;
@@ -121,6 +144,9 @@ pub fn verify_consensus_proof<Ctx: ReaderContext>(
 		return Err(Error::frozen_client(connection_end.client_id().clone()))
 	}
 
+	ctx.gas_meter()
+		.charge_proof_verification(proof.proof().as_bytes())
+		.map_err(Error::gas)?;
 	let consensus_state = ctx
 		.consensus_state(connection_end.client_id(), height)
 		.map_err(|e| Error::consensus_state_verification_failure(height, e))?;
@@ -0,0 +1,213 @@
+//! The acknowledgement envelope recommended by ICS 04, i.e. `ibc.core.channel.v1.Acknowledgement`.
+//!
+//! Applications are free to define the format of their acknowledgements, but those using this
+//! envelope can be told apart from failed ones by any module, e.g. by middleware. Both the JSON
+//! encoding, which ibc-go applications write, and the protobuf encoding are supported.
+
+use core::fmt::{self, Display};
+
+use ibc_proto::ibc::core::channel::v1::{
+	acknowledgement::Response as RawResponse, Acknowledgement as RawAcknowledgement,
+};
+use serde_json::{Map, Value};
+use subtle_encoding::base64;
+use tendermint_proto::Protobuf;
+
+use crate::{
+	core::ics04_channel::{
+		error::Error, msgs::acknowledgement::Acknowledgement as GenericAcknowledgement,
+	},
+	prelude::*,
+};
+
+/// The message of error acknowledgements, as written by ibc-go.
+/// NOTE: Changing this const is state machine breaking as acknowledgements are written into state.
+pub const ACK_ERR_STR: &str = "error handling packet: see events for details";
+
+const RESULT_KEY: &str = "result";
+const ERROR_KEY: &str = "error";
+
+/// An acknowledgement carrying either the result of a packet, or an error.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AcknowledgementEnvelope {
+	Result(Vec<u8>),
+	Error(String),
+}
+
+impl AcknowledgementEnvelope {
+	/// Builds an error acknowledgement. Acknowledgements are committed to, so they must be
+	/// identical on every node: the error itself is to be reported through events.
+	pub fn error() -> Self {
+		Self::Error(ACK_ERR_STR.to_string())
+	}
+
+	/// Builds an error acknowledgement from an application error code, in the format used by
+	/// ibc-go: `ABCI code: {code}: error handling packet: see events for details`.
+	pub fn from_error_code(code: u32) -> Self {
+		Self::Error(format!("ABCI code: {}: {}", code, ACK_ERR_STR))
+	}
+
+	/// Builds an error acknowledgement from `err`, keeping only the first line of its message, see
+	/// [`error_message`].
+	pub fn from_error(err: impl Display) -> Self {
+		match error_message(err) {
+			message if message.is_empty() => Self::error(),
+			message => Self::Error(format!("{}: {}", ACK_ERR_STR, message)),
+		}
+	}
+
+	pub fn is_successful(&self) -> bool {
+		matches!(self, AcknowledgementEnvelope::Result(_))
+	}
+
+	/// Encodes the acknowledgement as JSON, `{"result":"<base64>"}` or `{"error":"<message>"}`,
+	/// as ibc-go applications do.
+	pub fn to_json_bytes(&self) -> Vec<u8> {
+		let (key, value) = match self {
+			AcknowledgementEnvelope::Result(result) => (
+				RESULT_KEY,
+				String::from_utf8(base64::encode(result)).expect("base64 is valid UTF8"),
+			),
+			AcknowledgementEnvelope::Error(error) => (ERROR_KEY, error.clone()),
+		};
+		let mut map = Map::new();
+		map.insert(key.to_string(), Value::String(value));
+		serde_json::to_vec(&Value::Object(map)).expect("JSON values are serializable")
+	}
+
+	pub fn from_json_bytes(bytes: &[u8]) -> Result<Self, Error> {
+		let invalid = |reason: &str| Error::malformed_acknowledgement(reason.to_string());
+		let value = serde_json::from_slice::<Value>(bytes).map_err(|_| invalid("not JSON"))?;
+		let map = match value {
+			Value::Object(map) if map.len() == 1 => map,
+			_ => return Err(invalid("expected an object with a single key")),
+		};
+		match map.into_iter().next() {
+			Some((key, Value::String(result))) if key == RESULT_KEY => {
+				let result = base64::decode(result.as_bytes())
+					.map_err(|_| invalid("invalid base64 result"))?;
+				Ok(AcknowledgementEnvelope::Result(result))
+			},
+			Some((key, Value::String(error))) if key == ERROR_KEY =>
+				Ok(AcknowledgementEnvelope::Error(error)),
+			_ => Err(invalid("expected a result or an error")),
+		}
+	}
+
+	/// Decodes an acknowledgement in either the JSON or the protobuf encoding.
+	pub fn decode(bytes: &[u8]) -> Result<Self, Error> {
+		Self::from_json_bytes(bytes).or_else(|_| {
+			<Self as Protobuf<RawAcknowledgement>>::decode_vec(bytes)
+				.map_err(|_| Error::malformed_acknowledgement("unknown encoding".to_string()))
+		})
+	}
+}
+
+/// Returns the message of `err` to write in an error acknowledgement. Acknowledgements are
+/// committed to, so they must be identical on every node: only the first line of the message is
+/// kept, as the following ones may hold the chain of sources of the error, traces or backtraces.
+pub fn error_message(err: impl Display) -> String {
+	err.to_string().lines().next().unwrap_or_default().trim().to_string()
+}
+
+impl Display for AcknowledgementEnvelope {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			AcknowledgementEnvelope::Result(result) =>
+				write!(f, "result: {}", String::from_utf8_lossy(&base64::encode(result))),
+			AcknowledgementEnvelope::Error(error) => write!(f, "error: {}", error),
+		}
+	}
+}
+
+impl Protobuf<RawAcknowledgement> for AcknowledgementEnvelope {}
+
+impl TryFrom<RawAcknowledgement> for AcknowledgementEnvelope {
+	type Error = Error;
+
+	fn try_from(raw: RawAcknowledgement) -> Result<Self, Self::Error> {
+		match raw.response {
+			Some(RawResponse::Result(result)) if !result.is_empty() =>
+				Ok(AcknowledgementEnvelope::Result(result)),
+			Some(RawResponse::Error(error)) if !error.is_empty() =>
+				Ok(AcknowledgementEnvelope::Error(error)),
+			_ => Err(Error::malformed_acknowledgement("empty response".to_string())),
+		}
+	}
+}
+
+impl From<AcknowledgementEnvelope> for RawAcknowledgement {
+	fn from(ack: AcknowledgementEnvelope) -> Self {
+		let response = match ack {
+			AcknowledgementEnvelope::Result(result) => RawResponse::Result(result),
+			AcknowledgementEnvelope::Error(error) => RawResponse::Error(error),
+		};
+		RawAcknowledgement { response: Some(response) }
+	}
+}
+
+/// Acknowledgements are written in the JSON encoding.
+impl From<AcknowledgementEnvelope> for GenericAcknowledgement {
+	fn from(ack: AcknowledgementEnvelope) -> Self {
+		ack.to_json_bytes().into()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::core::ics26_routing::context::Acknowledgement as AckTrait;
+
+	#[test]
+	fn test_acknowledgement_encodings() {
+		let success = AcknowledgementEnvelope::Result(vec![1]);
+		assert_eq!(success.to_json_bytes(), br#"{"result":"AQ=="}"#.to_vec());
+		assert_eq!(AcknowledgementEnvelope::decode(br#"{"result":"AQ=="}"#).unwrap(), success);
+
+		// the protobuf encoding, with the result under the field 21
+		let proto = <AcknowledgementEnvelope as Protobuf<RawAcknowledgement>>::encode_vec(&success);
+		assert_eq!(proto, vec![0xaa, 0x01, 0x01, 0x01]);
+		assert_eq!(AcknowledgementEnvelope::decode(&proto).unwrap(), success);
+		let error = AcknowledgementEnvelope::error();
+		let proto = <AcknowledgementEnvelope as Protobuf<RawAcknowledgement>>::encode_vec(&error);
+		assert_eq!(AcknowledgementEnvelope::decode(&proto).unwrap(), error);
+
+		assert!(AcknowledgementEnvelope::decode(br#"{"result":"AQ==","error":"x"}"#).is_err());
+		assert!(AcknowledgementEnvelope::decode(b"AQ==").is_err());
+	}
+
+	#[test]
+	fn test_ibc_go_error_acknowledgement() {
+		// the acknowledgement written by ibc-go's `NewErrorAcknowledgement` for an error of ABCI
+		// code 5
+		let ibc_go_ack =
+			br#"{"error":"ABCI code: 5: error handling packet: see events for details"}"#;
+		let ack = AcknowledgementEnvelope::from_error_code(5);
+		assert_eq!(ack.to_json_bytes(), ibc_go_ack.to_vec());
+		assert_eq!(AcknowledgementEnvelope::decode(ibc_go_ack).unwrap(), ack);
+		assert!(!ack.is_successful());
+	}
+
+	#[test]
+	fn test_deterministic_error_acknowledgement() {
+		let ack = AcknowledgementEnvelope::from_error("insufficient funds\nat src/lib.rs:1");
+		let expected = format!("{}: insufficient funds", ACK_ERR_STR);
+		assert_eq!(ack, AcknowledgementEnvelope::Error(expected));
+		assert_eq!(AcknowledgementEnvelope::from_error(""), AcknowledgementEnvelope::error());
+	}
+
+	#[test]
+	fn test_acknowledgement_success() {
+		let ack = GenericAcknowledgement::from(AcknowledgementEnvelope::error());
+		assert!(!AckTrait::is_successful(&ack));
+		let ack = GenericAcknowledgement::from(AcknowledgementEnvelope::Result(vec![1]));
+		assert!(AckTrait::is_successful(&ack));
+		let proto = <AcknowledgementEnvelope as Protobuf<RawAcknowledgement>>::encode_vec(
+			&AcknowledgementEnvelope::Result(vec![1]),
+		);
+		assert!(AckTrait::is_successful(&GenericAcknowledgement::from(proto)));
+
+		// acknowledgements in an unknown format cannot be told successful
+		assert!(!AckTrait::is_successful(&GenericAcknowledgement::from(b"opaque".to_vec())));
+	}
+}
@@ -10,6 +10,7 @@ use crate::{
 			identifier::{ChannelId, ClientId, ConnectionId, PortId},
 		},
 	},
+	gas::GasError,
 	prelude::*,
 	proofs::ProofError,
 	signer::SignerError,
@@ -166,6 +167,37 @@ define_error! {
 					e.sequence)
 			},
 
+		PacketBatchVerificationFailed
+			{ sequences: Vec<Sequence> }
+			[ client_error::Error ]
+			| e | {
+				format_args!(
+					"Verification fails for the packets with the sequence numbers {0:?}",
+					e.sequences)
+			},
+
+		EmptyPacketBatch
+			| _ | { "a batch of packets cannot be empty" },
+
+		PacketBatchChannelMismatch
+			| _ | { "the packets of a batch must all go through the same channel" },
+
+		DuplicatePacketInBatch
+			{ sequence: Sequence }
+			| e | {
+				format_args!(
+					"the packet with the sequence number {0} appears more than once in the batch",
+					e.sequence)
+			},
+
+		OrderedPacketBatch
+			{ port_id: PortId, channel_id: ChannelId }
+			| e | {
+				format_args!(
+					"packets of the ordered channel {0}/{1} cannot be relayed in a batch",
+					e.port_id, e.channel_id)
+			},
+
 		VerifyChannelFailed
 			[ client_error::Error ]
 			| _ | {
@@ -175,6 +207,10 @@ define_error! {
 		InvalidAcknowledgement
 			| _ | { "Acknowledgment cannot be empty" },
 
+		MalformedAcknowledgement
+			{ reason: String }
+			| e | { format_args!("malformed acknowledgement: {0}", e.reason) },
+
 		AcknowledgementExists
 			{ sequence: Sequence }
 			| e | {
@@ -371,6 +407,10 @@ define_error! {
 					"application module error: {0}",
 					e.description)
 			},
+
+		Gas
+			[ GasError ]
+			| _ | { "gas meter error" },
 	}
 }
 
@@ -5,15 +5,22 @@ use crate::{
 		ics04_channel::{
 			channel::ChannelEnd,
 			error::Error,
-			msgs::{ChannelMsg, PacketMsg},
-			packet::PacketResult,
+			msgs::{
+				chan_open_init::MsgChannelOpenInit, chan_open_try::MsgChannelOpenTry, ChannelMsg,
+				PacketBatchMsg, PacketMsg,
+			},
+			packet::{Packet, PacketResult},
 		},
+		ics05_port::capabilities::{lookup_capability, CapabilityName},
 		ics24_host::identifier::{ChannelId, PortId},
 		ics26_routing::context::{
-			Ics26Context, ModuleId, ModuleOutputBuilder, OnRecvPacketAck, ReaderContext, Router,
+			Ics26Context, Module, ModuleId, ModuleOutputBuilder, OnRecvPacketAck, ReaderContext,
+			Router,
 		},
 	},
 	handler::{HandlerOutput, HandlerOutputBuilder},
+	prelude::*,
+	signer::Signer,
 };
 use core::fmt::Debug;
 
@@ -24,6 +31,7 @@ pub mod chan_open_ack;
 pub mod chan_open_confirm;
 pub mod chan_open_init;
 pub mod chan_open_try;
+pub mod packet_batch;
 pub mod recv_packet;
 pub mod send_packet;
 pub mod timeout;
@@ -55,11 +63,39 @@ where
 	Ctx: Ics26Context,
 {
 	let module_id = msg.lookup_module(ctx)?;
-	if ctx.router().has_route(&module_id) {
-		Ok(module_id)
-	} else {
-		Err(Error::route_not_found())
+	if !ctx.router().has_route(&module_id) {
+		return Err(Error::route_not_found())
 	}
+
+	// Channels are opened on bound ports, and issued a capability when they are. The handshake
+	// can only go on for channels whose capability is held by the module bound to their port.
+	let capability_name = match msg {
+		ChannelMsg::ChannelOpenInit(msg) => CapabilityName::port(&msg.port_id),
+		ChannelMsg::ChannelOpenTry(msg) => CapabilityName::port(&msg.port_id),
+		ChannelMsg::ChannelOpenAck(msg) => CapabilityName::channel(&msg.port_id, msg.channel_id),
+		ChannelMsg::ChannelOpenConfirm(msg) =>
+			CapabilityName::channel(&msg.port_id, msg.channel_id),
+		ChannelMsg::ChannelCloseInit(msg) => CapabilityName::channel(&msg.port_id, msg.channel_id),
+		ChannelMsg::ChannelCloseConfirm(msg) =>
+			CapabilityName::channel(&msg.port_id, msg.channel_id),
+	};
+	authenticate_module(ctx, &capability_name, &module_id)?;
+
+	Ok(module_id)
+}
+
+/// Authenticates the capability issued under `name`, as held by the module `module_id` which the
+/// message acting on the port or channel is routed to.
+fn authenticate_module<Ctx>(
+	ctx: &Ctx,
+	name: &CapabilityName,
+	module_id: &ModuleId,
+) -> Result<(), Error>
+where
+	Ctx: Ics26Context,
+{
+	let capability = lookup_capability(ctx, name, module_id).map_err(Error::ics05_port)?;
+	ctx.authenticate_capability(name, &capability).map_err(Error::ics05_port)
 }
 
 /// General entry point for processing any type of message related to the ICS4 channel open and
@@ -95,6 +131,7 @@ where
 	Ctx: Ics26Context,
 {
 	let cb = ctx.router_mut().get_route_mut(module_id).ok_or_else(Error::route_not_found)?;
+	validate_channel_callback(&*cb, msg, &result)?;
 
 	match msg {
 		ChannelMsg::ChannelOpenInit(msg) => cb.on_chan_open_init(
@@ -132,30 +169,110 @@ where
 		ChannelMsg::ChannelCloseConfirm(msg) =>
 			cb.on_chan_close_confirm(module_output, &msg.port_id, &result.channel_id)?,
 	}
+
+	// A new channel is issued a capability once accepted by the module it is opened by.
+	match msg {
+		ChannelMsg::ChannelOpenInit(MsgChannelOpenInit { port_id, .. }) |
+		ChannelMsg::ChannelOpenTry(MsgChannelOpenTry { port_id, .. }) => {
+			let name = CapabilityName::channel(port_id, result.channel_id);
+			ctx.new_capability(name, module_id.clone()).map_err(Error::ics05_port)?;
+		},
+		_ => {},
+	}
+
 	Ok(result)
 }
 
+/// Runs the checks of the module a channel handshake message is routed to, without modifying any
+/// state.
+pub fn channel_callback_validate<Ctx>(
+	ctx: &Ctx,
+	module_id: &ModuleId,
+	msg: &ChannelMsg,
+	result: &ChannelResult,
+) -> Result<(), Error>
+where
+	Ctx: Ics26Context,
+{
+	validate_channel_callback(validation_route(ctx, module_id)?, msg, result)
+}
+
+fn validate_channel_callback(
+	cb: &dyn Module,
+	msg: &ChannelMsg,
+	result: &ChannelResult,
+) -> Result<(), Error> {
+	match msg {
+		ChannelMsg::ChannelOpenInit(msg) => cb.on_chan_open_init_validate(
+			msg.channel.ordering,
+			&msg.channel.connection_hops,
+			&msg.port_id,
+			&result.channel_id,
+			msg.channel.counterparty(),
+			&msg.channel.version,
+		),
+		ChannelMsg::ChannelOpenTry(msg) => cb.on_chan_open_try_validate(
+			msg.channel.ordering,
+			&msg.channel.connection_hops,
+			&msg.port_id,
+			&result.channel_id,
+			msg.channel.counterparty(),
+			msg.channel.version(),
+			&msg.counterparty_version,
+		),
+		ChannelMsg::ChannelOpenAck(msg) => cb.on_chan_open_ack_validate(
+			&msg.port_id,
+			&result.channel_id,
+			&msg.counterparty_version,
+		),
+		ChannelMsg::ChannelOpenConfirm(msg) =>
+			cb.on_chan_open_confirm_validate(&msg.port_id, &result.channel_id),
+		ChannelMsg::ChannelCloseInit(msg) =>
+			cb.on_chan_close_init_validate(&msg.port_id, &result.channel_id),
+		ChannelMsg::ChannelCloseConfirm(msg) =>
+			cb.on_chan_close_confirm_validate(&msg.port_id, &result.channel_id),
+	}
+}
+
+/// Returns the module `module_id` to run the checks of.
+fn validation_route<'a, Ctx>(ctx: &'a Ctx, module_id: &ModuleId) -> Result<&'a dyn Module, Error>
+where
+	Ctx: Ics26Context,
+{
+	ctx.router().get_route(module_id).ok_or_else(Error::route_not_found)
+}
+
 pub fn get_module_for_packet_msg<Ctx>(ctx: &Ctx, msg: &PacketMsg) -> Result<ModuleId, Error>
 where
 	Ctx: Ics26Context,
 {
-	let module_id = match msg {
-		PacketMsg::RecvPacket(msg) => ctx
-			.lookup_module_by_port(&msg.packet.destination_port)
-			.map_err(Error::ics05_port)?,
-		PacketMsg::AckPacket(msg) =>
-			ctx.lookup_module_by_port(&msg.packet.source_port).map_err(Error::ics05_port)?,
-		PacketMsg::ToPacket(msg) =>
-			ctx.lookup_module_by_port(&msg.packet.source_port).map_err(Error::ics05_port)?,
-		PacketMsg::ToClosePacket(msg) =>
-			ctx.lookup_module_by_port(&msg.packet.source_port).map_err(Error::ics05_port)?,
+	let (port_id, channel_id) = match msg {
+		PacketMsg::RecvPacket(msg) =>
+			(&msg.packet.destination_port, msg.packet.destination_channel),
+		PacketMsg::AckPacket(msg) => (&msg.packet.source_port, msg.packet.source_channel),
+		PacketMsg::ToPacket(msg) => (&msg.packet.source_port, msg.packet.source_channel),
+		PacketMsg::ToClosePacket(msg) => (&msg.packet.source_port, msg.packet.source_channel),
 	};
+	get_module_for_channel(ctx, port_id, channel_id)
+}
 
-	if ctx.router().has_route(&module_id) {
-		Ok(module_id)
-	} else {
-		Err(Error::route_not_found())
+/// Returns the module bound to a port, which must hold the capability of the channel packets are
+/// relayed on.
+fn get_module_for_channel<Ctx>(
+	ctx: &Ctx,
+	port_id: &PortId,
+	channel_id: ChannelId,
+) -> Result<ModuleId, Error>
+where
+	Ctx: Ics26Context,
+{
+	let module_id = ctx.lookup_module_by_port(port_id).map_err(Error::ics05_port)?;
+	if !ctx.router().has_route(&module_id) {
+		return Err(Error::route_not_found())
 	}
+
+	authenticate_module(ctx, &CapabilityName::channel(port_id, channel_id), &module_id)?;
+	Ok(module_id)
 }
 
 /// Dispatcher for processing any type of message related to the ICS4 packet protocols.
@@ -187,17 +304,10 @@ where
 	Ctx: Ics26Context,
 {
 	let cb = ctx.router_mut().get_route_mut(module_id).ok_or_else(Error::route_not_found)?;
+	validate_packet_callback(&*cb, msg)?;
 
 	match msg {
-		PacketMsg::RecvPacket(msg) => {
-			let result = cb.on_recv_packet(module_output, &msg.packet, &msg.signer);
-			match result {
-				OnRecvPacketAck::Nil(write_fn) | OnRecvPacketAck::Successful(_, write_fn) => {
-					write_fn(cb.as_any_mut()).map_err(Error::app_module)?;
-				},
-				OnRecvPacketAck::Failed(_) => {},
-			}
-		},
+		PacketMsg::RecvPacket(msg) => on_recv_packet(cb, module_output, &msg.packet, &msg.signer)?,
 		PacketMsg::AckPacket(msg) => cb.on_acknowledgement_packet(
 			module_output,
 			&msg.packet,
@@ -211,3 +321,114 @@ where
 	};
 	Ok(())
 }
+
+/// Runs the checks of the module a packet message is routed to, without modifying any state.
+pub fn packet_callback_validate<Ctx>(
+	ctx: &Ctx,
+	module_id: &ModuleId,
+	msg: &PacketMsg,
+) -> Result<(), Error>
+where
+	Ctx: Ics26Context,
+{
+	validate_packet_callback(validation_route(ctx, module_id)?, msg)
+}
+
+fn validate_packet_callback(cb: &dyn Module, msg: &PacketMsg) -> Result<(), Error> {
+	match msg {
+		PacketMsg::RecvPacket(msg) => cb.on_recv_packet_validate(&msg.packet, &msg.signer),
+		PacketMsg::AckPacket(msg) =>
+			cb.on_acknowledgement_packet_validate(&msg.packet, &msg.acknowledgement, &msg.signer),
+		PacketMsg::ToPacket(msg) => cb.on_timeout_packet_validate(&msg.packet, &msg.signer),
+		PacketMsg::ToClosePacket(msg) => cb.on_timeout_packet_validate(&msg.packet, &msg.signer),
+	}
+}
+
+/// Runs the callback of a module receiving `packet`, applying the changes of state it returns
+/// unless it failed.
+fn on_recv_packet(
+	cb: &mut dyn Module,
+	module_output: &mut ModuleOutputBuilder,
+	packet: &Packet,
+	signer: &Signer,
+) -> Result<(), Error> {
+	match cb.on_recv_packet(module_output, packet, signer) {
+		OnRecvPacketAck::Nil(write_fn) | OnRecvPacketAck::Successful(_, write_fn) =>
+			write_fn(cb.as_any_mut()).map_err(Error::app_module),
+		OnRecvPacketAck::Failed(_) => Ok(()),
+	}
+}
+
+pub fn get_module_for_packet_batch_msg<Ctx>(
+	ctx: &Ctx,
+	msg: &PacketBatchMsg,
+) -> Result<ModuleId, Error>
+where
+	Ctx: Ics26Context,
+{
+	let packet = msg.packets().first().ok_or_else(Error::empty_packet_batch)?;
+	let (port_id, channel_id) = match msg {
+		PacketBatchMsg::RecvPackets(_) => (&packet.destination_port, packet.destination_channel),
+		PacketBatchMsg::ToPackets(_) => (&packet.source_port, packet.source_channel),
+	};
+	get_module_for_channel(ctx, port_id, channel_id)
+}
+
+/// Dispatcher for processing the messages relaying a batch of packets with a single proof. The
+/// results are in the order of the packets of the batch.
+pub fn packet_batch_dispatch<Ctx>(
+	ctx: &Ctx,
+	msg: &PacketBatchMsg,
+) -> Result<(HandlerOutputBuilder<()>, Vec<PacketResult>), Error>
+where
+	Ctx: ReaderContext,
+{
+	let HandlerOutput { result, log, events } = packet_batch::process::<_>(ctx, msg)?;
+	let builder = HandlerOutput::builder().with_log(log).with_events(events);
+	Ok((builder, result))
+}
+
+/// Runs the callback of the module a batch of packets is routed to, for its `packet`.
+pub fn packet_batch_callback<Ctx>(
+	ctx: &mut Ctx,
+	module_id: &ModuleId,
+	msg: &PacketBatchMsg,
+	packet: &Packet,
+	module_output: &mut ModuleOutputBuilder,
+) -> Result<(), Error>
+where
+	Ctx: Ics26Context,
+{
+	let cb = ctx.router_mut().get_route_mut(module_id).ok_or_else(Error::route_not_found)?;
+	validate_packet_batch_callback(&*cb, msg, packet)?;
+
+	match msg {
+		PacketBatchMsg::RecvPackets(msg) => on_recv_packet(cb, module_output, packet, msg.signer()),
+		PacketBatchMsg::ToPackets(msg) => cb.on_timeout_packet(module_output, packet, msg.signer()),
+	}
+}
+
+/// Runs the checks of the module a batch of packets is routed to, for its `packet`, without
+/// modifying any state.
+pub fn packet_batch_callback_validate<Ctx>(
+	ctx: &Ctx,
+	module_id: &ModuleId,
+	msg: &PacketBatchMsg,
+	packet: &Packet,
+) -> Result<(), Error>
+where
+	Ctx: Ics26Context,
+{
+	validate_packet_batch_callback(validation_route(ctx, module_id)?, msg, packet)
+}
+
+fn validate_packet_batch_callback(
+	cb: &dyn Module,
+	msg: &PacketBatchMsg,
+	packet: &Packet,
+) -> Result<(), Error> {
+	match msg {
+		PacketBatchMsg::RecvPackets(msg) => cb.on_recv_packet_validate(packet, msg.signer()),
+		PacketBatchMsg::ToPackets(msg) => cb.on_timeout_packet_validate(packet, msg.signer()),
+	}
+}
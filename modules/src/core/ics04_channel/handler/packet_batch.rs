@@ -0,0 +1,409 @@
+use crate::{
+	core::{
+		ics04_channel::{
+			channel::Order,
+			error::Error,
+			handler::{
+				recv_packet, timeout,
+				verify::{verify_packet_receipt_absence_batch, verify_packet_recv_batch_proofs},
+			},
+			msgs::PacketBatchMsg,
+			packet::PacketResult,
+		},
+		ics26_routing::context::ReaderContext,
+	},
+	handler::{HandlerOutput, HandlerResult},
+	prelude::*,
+};
+
+/// Processes each packet of a batch like the message relaying it on its own would, then verifies
+/// the single proof of the batch. The results are in the order of the packets of the batch.
+///
+/// Batches are only relayed over unordered channels, the packets of which are processed
+/// independently of each other.
+pub fn process<Ctx: ReaderContext>(
+	ctx: &Ctx,
+	msg: &PacketBatchMsg,
+) -> HandlerResult<Vec<PacketResult>, Error> {
+	let mut output = HandlerOutput::builder();
+
+	let first = msg.packets().first().ok_or_else(Error::empty_packet_batch)?;
+	let (port_id, channel_id) = match msg {
+		PacketBatchMsg::RecvPackets(_) => (&first.destination_port, first.destination_channel),
+		PacketBatchMsg::ToPackets(_) => (&first.source_port, first.source_channel),
+	};
+	let channel_end = ctx.channel_end(&(port_id.clone(), channel_id))?;
+	if channel_end.order_matches(&Order::Ordered) {
+		return Err(Error::ordered_packet_batch(port_id.clone(), channel_id))
+	}
+
+	let mut results = Vec::with_capacity(msg.packets().len());
+	for packet in msg.packets() {
+		let HandlerOutput { result, log, events } = match msg {
+			PacketBatchMsg::RecvPackets(_) => recv_packet::process_batched(ctx, packet)?,
+			PacketBatchMsg::ToPackets(msg) => timeout::process_batched(ctx, packet, msg.proofs())?,
+		};
+		output.merge_output(HandlerOutput { result: (), log, events });
+		results.push(result);
+	}
+
+	let connection_end = ctx
+		.connection_end(&channel_end.connection_hops()[0])
+		.map_err(Error::ics03_connection)?;
+	match msg {
+		PacketBatchMsg::RecvPackets(msg) => verify_packet_recv_batch_proofs(
+			ctx,
+			msg.proofs().height(),
+			msg.packets(),
+			&connection_end,
+			msg.proofs(),
+		)?,
+		PacketBatchMsg::ToPackets(msg) => verify_packet_receipt_absence_batch(
+			ctx,
+			msg.proofs().height(),
+			&connection_end,
+			msg.packets(),
+			msg.proofs(),
+		)?,
+	}
+
+	output.log(format!("success: batch of {} packets", results.len()));
+
+	Ok(output.with_result(results))
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::prelude::*;
+
+	use test_log::test;
+
+	use crate::{
+		core::{
+			ics03_connection::{
+				connection::{
+					ConnectionEnd, Counterparty as ConnectionCounterparty, State as ConnectionState,
+				},
+				version::get_compatible_versions,
+			},
+			ics04_channel::{
+				channel::{ChannelEnd, Counterparty, Order, State},
+				context::{ChannelKeeper, ChannelReader},
+				error::{Error, ErrorDetail},
+				handler::{
+					packet_batch::process, recv_packet::RecvPacketResult,
+					timeout::TimeoutPacketResult,
+				},
+				msgs::{
+					recv_packet_batch::{
+						test_util::get_dummy_raw_msg_recv_packet_batch, MsgRecvPacketBatch,
+					},
+					timeout_batch::{test_util::get_dummy_raw_msg_timeout_batch, MsgTimeoutBatch},
+					PacketBatchMsg,
+				},
+				packet::{Packet, PacketResult, Receipt, Sequence},
+				Version,
+			},
+			ics24_host::{
+				identifier::{ChannelId, ClientId, ConnectionId, PortId},
+				path::{CommitmentsPath, Path, ReceiptsPath},
+			},
+			ics26_routing::{
+				context::{
+					Ics26Context, Module, ModuleId, ModuleOutputBuilder, OnRecvPacketAck, Router,
+					RouterBuilder,
+				},
+				handler::{dispatch, validate},
+				msgs::Ics26Envelope,
+			},
+		},
+		events::IbcEvent,
+		mock::context::{MockClientTypes, MockContext, MockRouterBuilder},
+		proofs::Proofs,
+		signer::Signer,
+		test_utils::Crypto,
+		timestamp::ZERO_DURATION,
+		Height,
+	};
+
+	/// Returns a host whose client tracks the state of `counterparty` at the height returned, with
+	/// an `order`ed channel between the default ports and channels of both chains. The port of the
+	/// host is bound to the [`RecordingModule`], which the channel is opened by.
+	fn batch_context(
+		counterparty: &MockContext<MockClientTypes>,
+		order: Order,
+	) -> (MockContext<MockClientTypes>, Height) {
+		let mut context = MockContext::default();
+		context.scope_port_to_module(PortId::default(), recording_module_id());
+		let host_height = context.latest_height().increment();
+		let client_height = host_height.increment();
+		let store = counterparty.commitment_store::<Crypto>();
+
+		let connection_end = ConnectionEnd::new(
+			ConnectionState::Open,
+			ClientId::default(),
+			ConnectionCounterparty::new(
+				ClientId::default(),
+				Some(ConnectionId::default()),
+				store.prefix().clone(),
+			),
+			get_compatible_versions(),
+			ZERO_DURATION,
+		);
+		let channel_end = ChannelEnd::new(
+			State::Open,
+			order,
+			Counterparty::new(PortId::default(), Some(ChannelId::default())),
+			vec![ConnectionId::default()],
+			Version::ics20(),
+		);
+		let ctx = context
+			.with_client(&ClientId::default(), client_height)
+			.with_client_root(&ClientId::default(), client_height, store.root())
+			.with_connection(ConnectionId::default(), connection_end)
+			.with_channel(PortId::default(), ChannelId::default(), channel_end)
+			.with_height(host_height);
+		(ctx, client_height)
+	}
+
+	/// Returns the height of the client of [`batch_context`], which only depends on the host.
+	fn client_height() -> Height {
+		batch_context(&MockContext::default(), Order::Unordered).1
+	}
+
+	/// Stores the commitments of the `packets`, as sent by `ctx`.
+	fn with_commitments(
+		ctx: MockContext<MockClientTypes>,
+		packets: &[Packet],
+	) -> MockContext<MockClientTypes> {
+		packets.iter().fold(ctx, |ctx, packet| {
+			let commitment = ctx.packet_commitment(
+				packet.data.clone(),
+				packet.timeout_height,
+				packet.timeout_timestamp,
+			);
+			ctx.with_packet_commitment(
+				packet.source_port.clone(),
+				packet.source_channel,
+				packet.sequence,
+				commitment,
+			)
+		})
+	}
+
+	/// Returns the proof, at `height`, of the `paths` of the store of `counterparty`.
+	fn prove(
+		counterparty: &MockContext<MockClientTypes>,
+		paths: &[Path],
+		height: Height,
+	) -> Proofs {
+		let proof = counterparty.commitment_store::<Crypto>().prove_batch(paths).unwrap();
+		Proofs::new(proof, None, None, None, height).unwrap()
+	}
+
+	fn commitment_paths(packets: &[Packet]) -> Vec<Path> {
+		packets
+			.iter()
+			.map(|packet| {
+				let (port_id, channel_id) = (packet.source_port.clone(), packet.source_channel);
+				CommitmentsPath { port_id, channel_id, sequence: packet.sequence }.into()
+			})
+			.collect()
+	}
+
+	fn receipt_paths(packets: &[Packet]) -> Vec<Path> {
+		packets
+			.iter()
+			.map(|packet| {
+				let (port_id, channel_id) =
+					(packet.destination_port.clone(), packet.destination_channel);
+				ReceiptsPath { port_id, channel_id, sequence: packet.sequence }.into()
+			})
+			.collect()
+	}
+
+	/// Returns a batch of packets received from a counterparty which committed to them, along
+	/// with the counterparty.
+	fn recv_batch(sequences: &[u64]) -> (MsgRecvPacketBatch, MockContext<MockClientTypes>) {
+		let height = client_height();
+		let raw = get_dummy_raw_msg_recv_packet_batch(height.revision_height, sequences);
+		let msg = MsgRecvPacketBatch::try_from(raw).unwrap();
+		let counterparty = with_commitments(MockContext::default(), msg.packets());
+		let proofs = prove(&counterparty, &commitment_paths(msg.packets()), height);
+		let msg = MsgRecvPacketBatch::new(msg.packets().to_vec(), proofs, msg.signer().clone());
+		(msg.unwrap(), counterparty)
+	}
+
+	/// Returns a batch of packets timed out on a counterparty which never received them, along
+	/// with the counterparty.
+	fn timeout_batch(sequences: &[u64]) -> (MsgTimeoutBatch, MockContext<MockClientTypes>) {
+		let height = client_height();
+		let raw = get_dummy_raw_msg_timeout_batch(height.revision_height, 0, sequences);
+		let msg = MsgTimeoutBatch::try_from(raw).unwrap();
+		let counterparty = MockContext::default();
+		let proofs = prove(&counterparty, &receipt_paths(msg.packets()), height);
+		let msg = MsgTimeoutBatch::new(msg.packets().to_vec(), proofs, msg.signer().clone());
+		(msg.unwrap(), counterparty)
+	}
+
+	#[test]
+	fn recv_packet_batch_verifies_the_batch_proof() {
+		let (msg, counterparty) = recv_batch(&[1, 2, 3]);
+		let (ctx, _) = batch_context(&counterparty, Order::Unordered);
+
+		let output = process(&ctx, &PacketBatchMsg::RecvPackets(msg.clone())).unwrap();
+		assert_eq!(output.events.len(), 3);
+		assert!(output.events.iter().all(|e| matches!(e, IbcEvent::ReceivePacket(_))));
+		let sequences: Vec<_> = output
+			.result
+			.iter()
+			.map(|result| match result {
+				PacketResult::Recv(RecvPacketResult::Unordered { sequence, .. }) => *sequence,
+				result => panic!("unexpected result {:?}", result),
+			})
+			.collect();
+		assert_eq!(sequences, vec![1.into(), 2.into(), 3.into()]);
+
+		// the proof does not hold for a packet the counterparty did not commit to
+		let mut packets = msg.packets().to_vec();
+		packets[1].data = b"forged".to_vec();
+		let forged = MsgRecvPacketBatch::new(packets, msg.proofs().clone(), msg.signer().clone());
+		let err = process(&ctx, &PacketBatchMsg::RecvPackets(forged.unwrap())).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::PacketBatchVerificationFailed(_)));
+
+		// nor for a packet left out of the proof
+		let mut packets = msg.packets().to_vec();
+		let packet = Packet { sequence: 4.into(), ..packets[2].clone() };
+		packets.push(packet);
+		let extended = MsgRecvPacketBatch::new(packets, msg.proofs().clone(), msg.signer().clone());
+		let err = process(&ctx, &PacketBatchMsg::RecvPackets(extended.unwrap())).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::PacketBatchVerificationFailed(_)));
+
+		// packets of ordered channels are received one at a time
+		let (ordered_ctx, _) = batch_context(&counterparty, Order::Ordered);
+		let err = process(&ordered_ctx, &PacketBatchMsg::RecvPackets(msg)).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::OrderedPacketBatch(_)));
+	}
+
+	#[test]
+	fn timeout_batch_verifies_the_batch_proof() {
+		let (msg, mut counterparty) = timeout_batch(&[1, 2]);
+		let (ctx, client_height) = batch_context(&counterparty, Order::Unordered);
+		let ctx = with_commitments(ctx, msg.packets());
+
+		let output = process(&ctx, &PacketBatchMsg::ToPackets(msg.clone())).unwrap();
+		assert_eq!(output.events.len(), 2);
+		assert!(output.events.iter().all(|e| matches!(e, IbcEvent::TimeoutPacket(_))));
+		assert!(output.result.iter().all(|result| matches!(
+			result,
+			PacketResult::Timeout(TimeoutPacketResult { channel: None, .. })
+		)));
+
+		// the absence of the receipts cannot be proven once the counterparty received a packet
+		let packet = &msg.packets()[1];
+		let key = (packet.destination_port.clone(), packet.destination_channel, packet.sequence);
+		counterparty.store_packet_receipt(key, Receipt::Ok).unwrap();
+		let proofs = prove(&counterparty, &receipt_paths(msg.packets()), client_height);
+		let msg = MsgTimeoutBatch::new(msg.packets().to_vec(), proofs, msg.signer().clone());
+		let root = counterparty.commitment_store::<Crypto>().root();
+		let ctx = ctx.with_client_root(&ClientId::default(), client_height, root);
+		let err = process(&ctx, &PacketBatchMsg::ToPackets(msg.unwrap())).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::PacketBatchVerificationFailed(_)));
+	}
+
+	#[derive(Debug, Default)]
+	struct RecordingModule {
+		received: Vec<Sequence>,
+		timed_out: Vec<Sequence>,
+	}
+
+	impl Module for RecordingModule {
+		fn on_chan_open_try(
+			&mut self,
+			_output: &mut ModuleOutputBuilder,
+			_order: Order,
+			_connection_hops: &[ConnectionId],
+			_port_id: &PortId,
+			_channel_id: &ChannelId,
+			_counterparty: &Counterparty,
+			_version: &Version,
+			counterparty_version: &Version,
+		) -> Result<Version, Error> {
+			Ok(counterparty_version.clone())
+		}
+
+		fn on_recv_packet(
+			&self,
+			_output: &mut ModuleOutputBuilder,
+			packet: &Packet,
+			_relayer: &Signer,
+		) -> OnRecvPacketAck {
+			let sequence = packet.sequence;
+			OnRecvPacketAck::Nil(Box::new(move |module| {
+				module.downcast_mut::<RecordingModule>().unwrap().received.push(sequence);
+				Ok(())
+			}))
+		}
+
+		fn on_timeout_packet(
+			&mut self,
+			_output: &mut ModuleOutputBuilder,
+			packet: &Packet,
+			_relayer: &Signer,
+		) -> Result<(), Error> {
+			self.timed_out.push(packet.sequence);
+			Ok(())
+		}
+	}
+
+	fn recording_module_id() -> ModuleId {
+		"recording".parse().unwrap()
+	}
+
+	fn with_recording_module(ctx: MockContext<MockClientTypes>) -> MockContext<MockClientTypes> {
+		let router = MockRouterBuilder::default()
+			.add_route(recording_module_id(), RecordingModule::default())
+			.unwrap()
+			.build();
+		ctx.with_router(router)
+	}
+
+	fn recording_module(ctx: &mut MockContext<MockClientTypes>) -> &RecordingModule {
+		let module = ctx.router_mut().get_route_mut(&recording_module_id()).unwrap();
+		module.as_any_mut().downcast_mut::<RecordingModule>().unwrap()
+	}
+
+	#[test]
+	fn packet_batches_are_routed_packet_by_packet() {
+		let (msg, counterparty) = recv_batch(&[1, 2, 3]);
+		let mut ctx = with_recording_module(batch_context(&counterparty, Order::Unordered).0);
+		let envelope = Ics26Envelope::Ics4PacketBatchMsg(PacketBatchMsg::RecvPackets(msg.clone()));
+
+		validate(&ctx, envelope.clone()).unwrap();
+		let output = dispatch(&mut ctx, envelope.clone()).unwrap();
+		assert_eq!(output.events.len(), 3);
+		assert_eq!(recording_module(&mut ctx).received, vec![1.into(), 2.into(), 3.into()]);
+		for packet in msg.packets() {
+			let key =
+				(packet.destination_port.clone(), packet.destination_channel, packet.sequence);
+			assert!(matches!(ctx.get_packet_receipt(&key), Ok(Receipt::Ok)));
+		}
+
+		// packets received already are not handed to the module again
+		dispatch(&mut ctx, envelope).unwrap();
+		assert_eq!(recording_module(&mut ctx).received.len(), 3);
+
+		let (msg, counterparty) = timeout_batch(&[1, 2]);
+		let (ctx, _) = batch_context(&counterparty, Order::Unordered);
+		let mut ctx = with_recording_module(with_commitments(ctx, msg.packets()));
+		let envelope = Ics26Envelope::Ics4PacketBatchMsg(PacketBatchMsg::ToPackets(msg.clone()));
+
+		validate(&ctx, envelope.clone()).unwrap();
+		dispatch(&mut ctx, envelope).unwrap();
+		assert_eq!(recording_module(&mut ctx).timed_out, vec![1.into(), 2.into()]);
+		for packet in msg.packets() {
+			let key = (packet.source_port.clone(), packet.source_channel, packet.sequence);
+			assert!(ctx.get_packet_commitment(&key).is_err());
+		}
+	}
+}
@@ -15,6 +15,7 @@ use crate::{
 	events::IbcEvent,
 	handler::{HandlerOutput, HandlerResult},
 	prelude::*,
+	proofs::Proofs,
 	timestamp::Expiry,
 	Height,
 };
@@ -42,9 +43,26 @@ pub fn process<Ctx: ReaderContext>(
 	ctx: &Ctx,
 	msg: &MsgRecvPacket,
 ) -> HandlerResult<PacketResult, Error> {
-	let mut output = HandlerOutput::builder();
+	process_packet(ctx, &msg.packet, Some(&msg.proofs))
+}
 
-	let packet = &msg.packet;
+/// Processes a `packet` received in a batch, whose commitment is verified along with those of the
+/// rest of the batch, see [`packet_batch::process`](super::packet_batch::process).
+pub(super) fn process_batched<Ctx: ReaderContext>(
+	ctx: &Ctx,
+	packet: &Packet,
+) -> HandlerResult<PacketResult, Error> {
+	process_packet(ctx, packet, None)
+}
+
+/// Processes `packet`, verifying its commitment with `proofs` unless it has been verified along
+/// with the rest of its batch.
+fn process_packet<Ctx: ReaderContext>(
+	ctx: &Ctx,
+	packet: &Packet,
+	proofs: Option<&Proofs>,
+) -> HandlerResult<PacketResult, Error> {
+	let mut output = HandlerOutput::builder();
 
 	let dest_channel_end =
 		ctx.channel_end(&(packet.destination_port.clone(), packet.destination_channel))?;
@@ -80,13 +98,9 @@ pub fn process<Ctx: ReaderContext>(
 		return Err(Error::low_packet_timestamp())
 	}
 
-	verify_packet_recv_proofs::<Ctx>(
-		ctx,
-		msg.proofs.height(),
-		packet,
-		&connection_end,
-		&msg.proofs,
-	)?;
+	if let Some(proofs) = proofs {
+		verify_packet_recv_proofs::<Ctx>(ctx, proofs.height(), packet, &connection_end, proofs)?;
+	}
 
 	let result = if dest_channel_end.order_matches(&Order::Ordered) {
 		let next_seq_recv = ctx.get_next_sequence_recv(&(
@@ -97,7 +111,7 @@ pub fn process<Ctx: ReaderContext>(
 		if packet.sequence < next_seq_recv {
 			output.emit(IbcEvent::ReceivePacket(ReceivePacket {
 				height: Height::zero(),
-				packet: msg.packet.clone(),
+				packet: packet.clone(),
 			}));
 			return Ok(output.with_result(PacketResult::Recv(RecvPacketResult::NoOp)))
 		} else if packet.sequence != next_seq_recv {
@@ -121,7 +135,7 @@ pub fn process<Ctx: ReaderContext>(
 			Ok(_receipt) => {
 				output.emit(IbcEvent::ReceivePacket(ReceivePacket {
 					height: Height::zero(),
-					packet: msg.packet.clone(),
+					packet: packet.clone(),
 				}));
 				return Ok(output.with_result(PacketResult::Recv(RecvPacketResult::NoOp)))
 			},
@@ -143,7 +157,7 @@ pub fn process<Ctx: ReaderContext>(
 
 	output.emit(IbcEvent::ReceivePacket(ReceivePacket {
 		height: ctx.host_height(),
-		packet: msg.packet.clone(),
+		packet: packet.clone(),
 	}));
 
 	Ok(output.with_result(result))
@@ -166,16 +180,23 @@ mod tests {
 			},
 			ics04_channel::{
 				channel::{ChannelEnd, Counterparty, Order, State},
+				context::ChannelReader,
+				error::ErrorDetail,
 				handler::recv_packet::process,
 				msgs::recv_packet::{test_util::get_dummy_raw_msg_recv_packet, MsgRecvPacket},
 				packet::Packet,
 				Version,
 			},
-			ics24_host::identifier::{ChannelId, ClientId, ConnectionId, PortId},
+			ics23_commitment::commitment::CommitmentProofBytes,
+			ics24_host::{
+				identifier::{ChannelId, ClientId, ConnectionId, PortId},
+				path::CommitmentsPath,
+			},
 		},
 		events::IbcEvent,
 		mock::context::{MockClientTypes, MockContext},
-		test_utils::get_dummy_account_id,
+		proofs::Proofs,
+		test_utils::{get_dummy_account_id, Crypto},
 		timestamp::{Timestamp, ZERO_DURATION},
 	};
 
@@ -315,4 +336,76 @@ mod tests {
 			}
 		}
 	}
+
+	#[test]
+	fn recv_packet_verifies_the_commitment_proof() {
+		let context = MockContext::default();
+		let host_height = context.latest_height().increment();
+		let client_height = host_height.increment();
+		let msg =
+			MsgRecvPacket::try_from(get_dummy_raw_msg_recv_packet(client_height.revision_height))
+				.unwrap();
+		let packet = msg.packet.clone();
+
+		// the counterparty chain committed to the packet, and the client tracks its store
+		let commitment = context.packet_commitment(
+			packet.data.clone(),
+			packet.timeout_height,
+			packet.timeout_timestamp,
+		);
+		let counterparty = MockContext::<MockClientTypes>::default().with_packet_commitment(
+			packet.source_port.clone(),
+			packet.source_channel,
+			packet.sequence,
+			commitment,
+		);
+		let store = counterparty.commitment_store::<Crypto>();
+		let path = CommitmentsPath {
+			port_id: packet.source_port.clone(),
+			channel_id: packet.source_channel,
+			sequence: packet.sequence,
+		};
+		let proof = store.prove(&path.into()).unwrap();
+
+		let connection_end = ConnectionEnd::new(
+			ConnectionState::Open,
+			ClientId::default(),
+			ConnectionCounterparty::new(
+				ClientId::default(),
+				Some(ConnectionId::default()),
+				store.prefix().clone(),
+			),
+			get_compatible_versions(),
+			ZERO_DURATION,
+		);
+		let channel_end = ChannelEnd::new(
+			State::Open,
+			Order::default(),
+			Counterparty::new(packet.source_port.clone(), Some(packet.source_channel)),
+			vec![ConnectionId::default()],
+			Version::ics20(),
+		);
+		let ctx = context
+			.with_client(&ClientId::default(), client_height)
+			.with_client_root(&ClientId::default(), client_height, store.root())
+			.with_connection(ConnectionId::default(), connection_end)
+			.with_channel(packet.destination_port.clone(), packet.destination_channel, channel_end)
+			.with_height(host_height);
+
+		let msg_with_proof = |packet: Packet, proof: CommitmentProofBytes| {
+			let proofs = Proofs::new(proof, None, None, None, client_height).unwrap();
+			MsgRecvPacket::new(packet, proofs, get_dummy_account_id())
+		};
+
+		process(&ctx, &msg_with_proof(packet.clone(), proof.clone())).unwrap();
+
+		// the proof does not hold for a packet the counterparty did not commit to
+		let forged = Packet { data: b"forged".to_vec(), ..packet };
+		let err = process(&ctx, &msg_with_proof(forged, proof)).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::PacketVerificationFailed(_)));
+
+		// nor does a dummy proof
+		let err = process(&ctx, &msg).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::PacketVerificationFailed(_)));
+	}
 }
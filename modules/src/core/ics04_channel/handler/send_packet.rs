@@ -8,6 +8,7 @@ use crate::{
 			events::SendPacket,
 			packet::{Packet, PacketResult, Sequence},
 		},
+		ics05_port::capabilities::{Capability, CapabilityName},
 		ics24_host::identifier::{ChannelId, PortId},
 		ics26_routing::context::ReaderContext,
 	},
@@ -27,12 +28,20 @@ pub struct SendPacketResult {
 	pub packet: Packet,
 }
 
+/// Sends a packet on a channel, whose `capability` must be presented by the sending module.
 pub fn send_packet<Ctx: ReaderContext>(
 	ctx: &Ctx,
+	capability: &Capability,
 	packet: Packet,
 ) -> HandlerResult<PacketResult, Error> {
 	let mut output = HandlerOutput::builder();
 
+	ctx.authenticate_capability(
+		&CapabilityName::channel(&packet.source_port, packet.source_channel),
+		capability,
+	)
+	.map_err(Error::ics05_port)?;
+
 	let source_channel_end =
 		ctx.channel_end(&(packet.source_port.clone(), packet.source_channel))?;
 
@@ -128,6 +137,7 @@ mod tests {
 				packet::{test_utils::get_dummy_raw_packet, Packet},
 				Version,
 			},
+			ics05_port::capabilities::Capability,
 			ics24_host::identifier::{ChannelId, ClientId, ConnectionId, PortId},
 		},
 		events::IbcEvent,
@@ -141,6 +151,7 @@ mod tests {
 		struct Test {
 			name: String,
 			ctx: MockContext<MockClientTypes>,
+			capability: Capability,
 			packet: Packet,
 			want_pass: bool,
 		}
@@ -180,31 +191,54 @@ mod tests {
 
 		let client_height = Height::new(0, Height::default().revision_height + 1);
 
+		let good_context = context
+			.clone()
+			.with_client(&ClientId::default(), Height::default())
+			.with_connection(ConnectionId::default(), connection_end.clone())
+			.with_channel(PortId::default(), ChannelId::default(), channel_end.clone())
+			.with_channel(PortId::default(), ChannelId::new(1), channel_end.clone())
+			.with_send_sequence(PortId::default(), ChannelId::default(), 1.into());
+		let capability =
+			good_context.channel_capability(&PortId::default(), ChannelId::default()).unwrap();
+		let other_capability =
+			good_context.channel_capability(&PortId::default(), ChannelId::new(1)).unwrap();
+
+		let timeout_context = context
+			.clone()
+			.with_client(&ClientId::default(), client_height)
+			.with_connection(ConnectionId::default(), connection_end)
+			.with_channel(PortId::default(), ChannelId::default(), channel_end)
+			.with_send_sequence(PortId::default(), ChannelId::default(), 1.into());
+		let timeout_capability =
+			timeout_context.channel_capability(&PortId::default(), ChannelId::default()).unwrap();
+
 		let tests: Vec<Test> = vec![
 			Test {
 				name: "Processing fails because no channel exists in the context".to_string(),
-				ctx: context.clone(),
+				ctx: context,
+				capability: capability.clone(),
+				packet: packet.clone(),
+				want_pass: false,
+			},
+			Test {
+				name: "Processing fails because the capability is the one of another channel"
+					.to_string(),
+				ctx: good_context.clone(),
+				capability: other_capability,
 				packet: packet.clone(),
 				want_pass: false,
 			},
 			Test {
 				name: "Good parameters".to_string(),
-				ctx: context
-					.clone()
-					.with_client(&ClientId::default(), Height::default())
-					.with_connection(ConnectionId::default(), connection_end.clone())
-					.with_channel(PortId::default(), ChannelId::default(), channel_end.clone())
-					.with_send_sequence(PortId::default(), ChannelId::default(), 1.into()),
+				ctx: good_context,
+				capability,
 				packet,
 				want_pass: true,
 			},
 			Test {
 				name: "Packet timeout".to_string(),
-				ctx: context
-					.with_client(&ClientId::default(), client_height)
-					.with_connection(ConnectionId::default(), connection_end)
-					.with_channel(PortId::default(), ChannelId::default(), channel_end)
-					.with_send_sequence(PortId::default(), ChannelId::default(), 1.into()),
+				ctx: timeout_context,
+				capability: timeout_capability,
 				packet: packet_old,
 				want_pass: false,
 			},
@@ -213,7 +247,7 @@ mod tests {
 		.collect();
 
 		for test in tests {
-			let res = send_packet(&test.ctx, test.packet.clone());
+			let res = send_packet(&test.ctx, &test.capability, test.packet.clone());
 			// Additionally check the events and the output objects in the result.
 			match res {
 				Ok(proto_output) => {
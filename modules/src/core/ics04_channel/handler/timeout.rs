@@ -7,7 +7,7 @@ use crate::{
 			events::TimeoutPacket,
 			handler::verify::{verify_next_sequence_recv, verify_packet_receipt_absence},
 			msgs::timeout::MsgTimeout,
-			packet::{PacketResult, Sequence},
+			packet::{Packet, PacketResult, Sequence},
 		},
 		ics24_host::identifier::{ChannelId, PortId},
 		ics26_routing::context::ReaderContext,
@@ -15,6 +15,7 @@ use crate::{
 	events::IbcEvent,
 	handler::{HandlerOutput, HandlerResult},
 	prelude::*,
+	proofs::Proofs,
 };
 use core::fmt::Debug;
 
@@ -30,9 +31,30 @@ pub fn process<Ctx>(ctx: &Ctx, msg: &MsgTimeout) -> HandlerResult<PacketResult,
 where
 	Ctx: ReaderContext,
 {
-	let mut output = HandlerOutput::builder();
+	process_packet(ctx, &msg.packet, &msg.proofs, Some(msg.next_sequence_recv))
+}
 
-	let packet = &msg.packet;
+/// Processes a `packet` timed out in a batch, on an unordered channel, whose receipt is proven
+/// absent along with those of the rest of the batch, see
+/// [`packet_batch::process`](super::packet_batch::process).
+pub(super) fn process_batched<Ctx: ReaderContext>(
+	ctx: &Ctx,
+	packet: &Packet,
+	proofs: &Proofs,
+) -> HandlerResult<PacketResult, Error> {
+	process_packet(ctx, packet, proofs, None)
+}
+
+/// Processes the timeout of `packet`, as of the height of `proofs`. The `next_sequence_recv` of
+/// the counterparty is only given for packets timed out on their own, whose proofs are verified
+/// here, those of a batch being verified along with the rest of the batch.
+fn process_packet<Ctx: ReaderContext>(
+	ctx: &Ctx,
+	packet: &Packet,
+	proofs: &Proofs,
+	next_sequence_recv: Option<Sequence>,
+) -> HandlerResult<PacketResult, Error> {
+	let mut output = HandlerOutput::builder();
 
 	let mut source_channel_end =
 		ctx.channel_end(&(packet.source_port.clone(), packet.source_channel))?;
@@ -58,7 +80,7 @@ where
 	let client_id = connection_end.client_id().clone();
 
 	// check that timeout height or timeout timestamp has passed on the other end
-	let proof_height = msg.proofs.height();
+	let proof_height = proofs.height();
 
 	let consensus_state = ctx
 		.consensus_state(&client_id, proof_height)
@@ -89,16 +111,19 @@ where
 	}
 
 	let result = if source_channel_end.order_matches(&Order::Ordered) {
-		if packet.sequence < msg.next_sequence_recv {
-			return Err(Error::invalid_packet_sequence(packet.sequence, msg.next_sequence_recv))
+		let next_sequence_recv = next_sequence_recv.ok_or_else(|| {
+			Error::ordered_packet_batch(packet.source_port.clone(), packet.source_channel)
+		})?;
+		if packet.sequence < next_sequence_recv {
+			return Err(Error::invalid_packet_sequence(packet.sequence, next_sequence_recv))
 		}
 		verify_next_sequence_recv::<Ctx>(
 			ctx,
-			msg.proofs.height(),
+			proofs.height(),
 			&connection_end,
 			packet.clone(),
-			msg.next_sequence_recv,
-			&msg.proofs,
+			next_sequence_recv,
+			proofs,
 		)?;
 
 		source_channel_end.state = State::Closed;
@@ -109,13 +134,15 @@ where
 			channel: Some(source_channel_end),
 		})
 	} else {
-		verify_packet_receipt_absence::<Ctx>(
-			ctx,
-			msg.proofs.height(),
-			&connection_end,
-			packet.clone(),
-			&msg.proofs,
-		)?;
+		if next_sequence_recv.is_some() {
+			verify_packet_receipt_absence::<Ctx>(
+				ctx,
+				proofs.height(),
+				&connection_end,
+				packet.clone(),
+				proofs,
+			)?;
+		}
 
 		PacketResult::Timeout(TimeoutPacketResult {
 			port_id: packet.source_port.clone(),
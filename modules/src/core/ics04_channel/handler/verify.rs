@@ -40,6 +40,9 @@ where
 		return Err(Error::frozen_client(client_id))
 	}
 
+	ctx.gas_meter()
+		.charge_proof_verification(proof.as_bytes())
+		.map_err(Error::gas)?;
 	let consensus_state = ctx
 		.consensus_state(&client_id, height)
 		.map_err(|_| Error::error_invalid_consensus_state())?;
@@ -73,16 +76,7 @@ pub fn verify_packet_recv_proofs<Ctx: ReaderContext>(
 	proofs: &Proofs,
 ) -> Result<(), Error> {
 	let client_id = connection_end.client_id();
-	let client_state = ctx.client_state(client_id).map_err(Error::ics02_client)?;
-
-	// The client must not be frozen.
-	if client_state.is_frozen() {
-		return Err(Error::frozen_client(client_id.clone()))
-	}
-
-	let consensus_state = ctx
-		.consensus_state(client_id, proofs.height())
-		.map_err(|_| Error::error_invalid_consensus_state())?;
+	let (client_state, consensus_state) = packet_proof_client(ctx, connection_end, proofs)?;
 
 	let client_def = client_state.client_def();
 
@@ -119,16 +113,7 @@ pub fn verify_packet_acknowledgement_proofs<Ctx: ReaderContext>(
 	proofs: &Proofs,
 ) -> Result<(), Error> {
 	let client_id = connection_end.client_id();
-	let client_state = ctx.client_state(client_id).map_err(Error::ics02_client)?;
-
-	// The client must not be frozen.
-	if client_state.is_frozen() {
-		return Err(Error::frozen_client(client_id.clone()))
-	}
-
-	let consensus_state = ctx
-		.consensus_state(client_id, proofs.height())
-		.map_err(|_| Error::error_invalid_consensus_state())?;
+	let (client_state, consensus_state) = packet_proof_client(ctx, connection_end, proofs)?;
 
 	let ack_commitment = ctx.ack_commitment(acknowledgement);
 
@@ -167,16 +152,7 @@ where
 	Ctx: ReaderContext,
 {
 	let client_id = connection_end.client_id();
-	let client_state = ctx.client_state(client_id).map_err(Error::ics02_client)?;
-
-	// The client must not be frozen.
-	if client_state.is_frozen() {
-		return Err(Error::frozen_client(client_id.clone()))
-	}
-
-	let consensus_state = ctx
-		.consensus_state(client_id, proofs.height())
-		.map_err(|_| Error::error_invalid_consensus_state())?;
+	let (client_state, consensus_state) = packet_proof_client(ctx, connection_end, proofs)?;
 
 	let client_def = client_state.client_def();
 
@@ -210,16 +186,7 @@ where
 	Ctx: ReaderContext,
 {
 	let client_id = connection_end.client_id();
-	let client_state = ctx.client_state(client_id).map_err(Error::ics02_client)?;
-
-	// The client must not be frozen.
-	if client_state.is_frozen() {
-		return Err(Error::frozen_client(client_id.clone()))
-	}
-
-	let consensus_state = ctx
-		.consensus_state(client_id, proofs.height())
-		.map_err(|_| Error::error_invalid_consensus_state())?;
+	let (client_state, consensus_state) = packet_proof_client(ctx, connection_end, proofs)?;
 
 	let client_def = client_state.client_def();
 
@@ -241,3 +208,117 @@ where
 
 	Ok(())
 }
+
+/// Entry point for verifying the single proof of a batch of packets received together, that all the
+/// `packets`, sent on the same channel, have been committed to by the counterparty. See
+/// [`MsgRecvPacketBatch`](crate::core::ics04_channel::msgs::recv_packet_batch::MsgRecvPacketBatch).
+pub fn verify_packet_recv_batch_proofs<Ctx: ReaderContext>(
+	ctx: &Ctx,
+	height: Height,
+	packets: &[Packet],
+	connection_end: &ConnectionEnd,
+	proofs: &Proofs,
+) -> Result<(), Error> {
+	let first = packets.first().ok_or_else(Error::empty_packet_batch)?;
+
+	let client_id = connection_end.client_id();
+	let (client_state, consensus_state) = packet_proof_client(ctx, connection_end, proofs)?;
+
+	let client_def = client_state.client_def();
+
+	let commitments = packets
+		.iter()
+		.map(|packet| {
+			let commitment = ctx.packet_commitment(
+				packet.data.clone(),
+				packet.timeout_height,
+				packet.timeout_timestamp,
+			);
+			(packet.sequence, commitment)
+		})
+		.collect();
+
+	// Verify the proof for all the packets against the chain store.
+	client_def
+		.verify_packet_data_batch(
+			ctx,
+			client_id,
+			&client_state,
+			height,
+			connection_end,
+			proofs.object_proof(),
+			consensus_state.root(),
+			&first.source_port,
+			&first.source_channel,
+			commitments,
+		)
+		.map_err(|e| {
+			let sequences = packets.iter().map(|packet| packet.sequence).collect();
+			Error::packet_batch_verification_failed(sequences, e)
+		})?;
+
+	Ok(())
+}
+
+/// Entry point for verifying the single proof of a batch of packets timed out together, that none
+/// of the `packets`, sent on the same channel, has been received by the counterparty. See
+/// [`MsgTimeoutBatch`](crate::core::ics04_channel::msgs::timeout_batch::MsgTimeoutBatch).
+pub fn verify_packet_receipt_absence_batch<Ctx: ReaderContext>(
+	ctx: &Ctx,
+	height: Height,
+	connection_end: &ConnectionEnd,
+	packets: &[Packet],
+	proofs: &Proofs,
+) -> Result<(), Error> {
+	let first = packets.first().ok_or_else(Error::empty_packet_batch)?;
+
+	let client_id = connection_end.client_id();
+	let (client_state, consensus_state) = packet_proof_client(ctx, connection_end, proofs)?;
+
+	let client_def = client_state.client_def();
+
+	let sequences: Vec<Sequence> = packets.iter().map(|packet| packet.sequence).collect();
+
+	// Verify the proof for all the packets against the chain store.
+	client_def
+		.verify_packet_receipt_absence_batch(
+			ctx,
+			client_id,
+			&client_state,
+			height,
+			connection_end,
+			proofs.object_proof(),
+			consensus_state.root(),
+			&first.destination_port,
+			&first.destination_channel,
+			sequences.clone(),
+		)
+		.map_err(|e| Error::packet_batch_verification_failed(sequences, e))?;
+
+	Ok(())
+}
+
+/// Returns the state of the client verifying the proofs of the counterparty of `connection_end`,
+/// along with its consensus state at the height of `proofs`, once the verification of their object
+/// proof is charged to the gas meter.
+fn packet_proof_client<Ctx: ReaderContext>(
+	ctx: &Ctx,
+	connection_end: &ConnectionEnd,
+	proofs: &Proofs,
+) -> Result<(Ctx::AnyClientState, Ctx::AnyConsensusState), Error> {
+	let client_id = connection_end.client_id();
+	let client_state = ctx.client_state(client_id).map_err(Error::ics02_client)?;
+
+	// The client must not be frozen.
+	if client_state.is_frozen() {
+		return Err(Error::frozen_client(client_id.clone()))
+	}
+
+	let proof = proofs.object_proof().as_bytes();
+	ctx.gas_meter().charge_proof_verification(proof).map_err(Error::gas)?;
+	let consensus_state = ctx
+		.consensus_state(client_id, proofs.height())
+		.map_err(|_| Error::error_invalid_consensus_state())?;
+
+	Ok((client_state, consensus_state))
+}
@@ -7,6 +7,7 @@ use crate::{
 			events::WriteAcknowledgement,
 			packet::{Packet, PacketResult, Sequence},
 		},
+		ics05_port::capabilities::{Capability, CapabilityName},
 		ics24_host::identifier::{ChannelId, PortId},
 		ics26_routing::context::ReaderContext,
 	},
@@ -23,13 +24,22 @@ pub struct WriteAckPacketResult {
 	pub ack_commitment: AcknowledgementCommitment,
 }
 
+/// Writes the acknowledgement of a received packet, asynchronously to its reception. The
+/// `capability` of the channel the packet was received on must be presented by the module.
 pub fn process<Ctx: ReaderContext>(
 	ctx: &Ctx,
+	capability: &Capability,
 	packet: Packet,
 	ack: Vec<u8>,
 ) -> HandlerResult<PacketResult, Error> {
 	let mut output = HandlerOutput::builder();
 
+	ctx.authenticate_capability(
+		&CapabilityName::channel(&packet.destination_port, packet.destination_channel),
+		capability,
+	)
+	.map_err(Error::ics05_port)?;
+
 	let dest_channel_end =
 		ctx.channel_end(&(packet.destination_port.clone(), packet.destination_channel))?;
 
@@ -94,6 +104,7 @@ mod tests {
 				packet::{test_utils::get_dummy_raw_packet, Packet},
 				Version,
 			},
+			ics05_port::capabilities::Capability,
 			ics24_host::identifier::{ChannelId, ClientId, ConnectionId, PortId},
 		},
 		events::IbcEvent,
@@ -106,6 +117,7 @@ mod tests {
 		struct Test {
 			name: String,
 			ctx: MockContext<MockClientTypes>,
+			capability: Capability,
 			packet: Packet,
 			ack: Vec<u8>,
 			want_pass: bool,
@@ -142,35 +154,48 @@ mod tests {
 			ZERO_DURATION,
 		);
 
+		let good_context = context
+			.clone()
+			.with_client(&ClientId::default(), client_height)
+			.with_connection(ConnectionId::default(), connection_end.clone())
+			.with_channel(
+				packet.destination_port.clone(),
+				packet.destination_channel,
+				dest_channel_end.clone(),
+			);
+		let capability = good_context
+			.channel_capability(&packet.destination_port, packet.destination_channel)
+			.unwrap();
+
+		let zero_ack_context = context
+			.clone()
+			.with_client(&ClientId::default(), Height::default())
+			.with_connection(ConnectionId::default(), connection_end)
+			.with_channel(PortId::default(), ChannelId::default(), dest_channel_end);
+		let zero_ack_capability =
+			zero_ack_context.channel_capability(&PortId::default(), ChannelId::default()).unwrap();
+
 		let tests: Vec<Test> = vec![
 			Test {
 				name: "Processing fails because no channel exists in the context".to_string(),
-				ctx: context.clone(),
+				ctx: context,
+				capability: capability.clone(),
 				packet: packet.clone(),
 				ack: ack.clone(),
 				want_pass: false,
 			},
 			Test {
 				name: "Good parameters".to_string(),
-				ctx: context
-					.clone()
-					.with_client(&ClientId::default(), client_height)
-					.with_connection(ConnectionId::default(), connection_end.clone())
-					.with_channel(
-						packet.destination_port.clone(),
-						packet.destination_channel,
-						dest_channel_end.clone(),
-					),
+				ctx: good_context,
+				capability,
 				packet: packet.clone(),
 				ack,
 				want_pass: true,
 			},
 			Test {
 				name: "Zero ack".to_string(),
-				ctx: context
-					.with_client(&ClientId::default(), Height::default())
-					.with_connection(ConnectionId::default(), connection_end)
-					.with_channel(PortId::default(), ChannelId::default(), dest_channel_end),
+				ctx: zero_ack_context,
+				capability: zero_ack_capability,
 				packet,
 				ack: ack_null,
 				want_pass: false,
@@ -180,7 +205,7 @@ mod tests {
 		.collect();
 
 		for test in tests {
-			let res = process(&test.ctx, test.packet.clone(), test.ack);
+			let res = process(&test.ctx, &test.capability, test.packet.clone(), test.ack);
 			// Additionally check the events and the output objects in the result.
 			match res {
 				Ok(proto_output) => {
@@ -1,6 +1,7 @@
 //! ICS 04: Channel implementation that facilitates communication between
 //! applications and the chains those applications are built upon.
 
+pub mod acknowledgement;
 pub mod channel;
 pub mod context;
 pub mod error;
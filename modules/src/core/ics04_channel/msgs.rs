@@ -1,19 +1,26 @@
 //! Message definitions for all ICS4 domain types: channel open & close handshake datagrams, as well
 //! as packets.
 
-use crate::core::{
-	ics04_channel::{
-		error::Error,
-		msgs::{
-			acknowledgement::MsgAcknowledgement, chan_close_confirm::MsgChannelCloseConfirm,
-			chan_close_init::MsgChannelCloseInit, chan_open_ack::MsgChannelOpenAck,
-			chan_open_confirm::MsgChannelOpenConfirm, chan_open_init::MsgChannelOpenInit,
-			chan_open_try::MsgChannelOpenTry, recv_packet::MsgRecvPacket, timeout::MsgTimeout,
-			timeout_on_close::MsgTimeoutOnClose,
+use crate::{
+	core::{
+		ics04_channel::{
+			error::Error,
+			msgs::{
+				acknowledgement::MsgAcknowledgement, chan_close_confirm::MsgChannelCloseConfirm,
+				chan_close_init::MsgChannelCloseInit, chan_open_ack::MsgChannelOpenAck,
+				chan_open_confirm::MsgChannelOpenConfirm, chan_open_init::MsgChannelOpenInit,
+				chan_open_try::MsgChannelOpenTry, recv_packet::MsgRecvPacket,
+				recv_packet_batch::MsgRecvPacketBatch, timeout::MsgTimeout,
+				timeout_batch::MsgTimeoutBatch, timeout_on_close::MsgTimeoutOnClose,
+			},
+			packet::Packet,
 		},
+		ics26_routing::context::{Ics26Context, ModuleId},
 	},
-	ics26_routing::context::{Ics26Context, ModuleId},
+	prelude::*,
+	signer::Signer,
 };
+use alloc::collections::btree_set::BTreeSet;
 
 // Opening handshake messages.
 pub mod chan_open_ack;
@@ -31,6 +38,10 @@ pub mod recv_packet;
 pub mod timeout;
 pub mod timeout_on_close;
 
+// Messages relaying several packets with one proof.
+pub mod recv_packet_batch;
+pub mod timeout_batch;
+
 /// Enumeration of all possible messages that the ICS4 protocol processes.
 #[derive(Clone, Debug, PartialEq)]
 pub enum ChannelMsg {
@@ -69,3 +80,47 @@ pub enum PacketMsg {
 	ToPacket(MsgTimeout),
 	ToClosePacket(MsgTimeoutOnClose),
 }
+
+/// Enumeration of the messages relaying a batch of packets, sent over the same unordered channel,
+/// with a single proof for all of them.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PacketBatchMsg {
+	RecvPackets(MsgRecvPacketBatch),
+	ToPackets(MsgTimeoutBatch),
+}
+
+impl PacketBatchMsg {
+	pub fn packets(&self) -> &[Packet] {
+		match self {
+			PacketBatchMsg::RecvPackets(msg) => msg.packets(),
+			PacketBatchMsg::ToPackets(msg) => msg.packets(),
+		}
+	}
+
+	pub fn signer(&self) -> &Signer {
+		match self {
+			PacketBatchMsg::RecvPackets(msg) => msg.signer(),
+			PacketBatchMsg::ToPackets(msg) => msg.signer(),
+		}
+	}
+}
+
+/// Checks that the packets of a batch are sent over the same channel, and have distinct sequences.
+fn check_batch(packets: &[Packet]) -> Result<(), Error> {
+	let first = packets.first().ok_or_else(Error::empty_packet_batch)?;
+	if packets.iter().any(|packet| {
+		packet.source_port != first.source_port ||
+			packet.source_channel != first.source_channel ||
+			packet.destination_port != first.destination_port ||
+			packet.destination_channel != first.destination_channel
+	}) {
+		return Err(Error::packet_batch_channel_mismatch())
+	}
+
+	let mut sequences = BTreeSet::new();
+	if let Some(packet) = packets.iter().find(|packet| !sequences.insert(packet.sequence)) {
+		return Err(Error::duplicate_packet_in_batch(packet.sequence))
+	}
+
+	Ok(())
+}
@@ -0,0 +1,192 @@
+use crate::prelude::*;
+
+use tendermint_proto::Protobuf;
+
+use ibc_proto::ibc::core::channel::v1::MsgRecvPacketBatch as RawMsgRecvPacketBatch;
+
+use crate::{
+	core::ics04_channel::{error::Error, msgs::check_batch, packet::Packet},
+	proofs::Proofs,
+	signer::Signer,
+	tx_msg::Msg,
+};
+
+pub const TYPE_URL: &str = "/ibc.core.channel.v1.MsgRecvPacketBatch";
+
+///
+/// Message definition for the datagram receiving several packets sent over the same unordered
+/// channel, with a single proof of all their commitments.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MsgRecvPacketBatch {
+	packets: Vec<Packet>,
+	proofs: Proofs,
+	signer: Signer,
+}
+
+impl MsgRecvPacketBatch {
+	/// Builds the message of a batch of packets, which must be sent over the same channel, and have
+	/// distinct sequences.
+	pub fn new(
+		packets: Vec<Packet>,
+		proofs: Proofs,
+		signer: Signer,
+	) -> Result<MsgRecvPacketBatch, Error> {
+		check_batch(&packets)?;
+		Ok(Self { packets, proofs, signer })
+	}
+
+	pub fn packets(&self) -> &[Packet] {
+		&self.packets
+	}
+
+	pub fn proofs(&self) -> &Proofs {
+		&self.proofs
+	}
+
+	pub fn signer(&self) -> &Signer {
+		&self.signer
+	}
+}
+
+impl Msg for MsgRecvPacketBatch {
+	type ValidationError = Error;
+	type Raw = RawMsgRecvPacketBatch;
+
+	fn route(&self) -> String {
+		crate::keys::ROUTER_KEY.to_string()
+	}
+
+	fn type_url(&self) -> String {
+		TYPE_URL.to_string()
+	}
+}
+
+impl Protobuf<RawMsgRecvPacketBatch> for MsgRecvPacketBatch {}
+
+impl TryFrom<RawMsgRecvPacketBatch> for MsgRecvPacketBatch {
+	type Error = Error;
+
+	fn try_from(raw_msg: RawMsgRecvPacketBatch) -> Result<Self, Self::Error> {
+		let proofs = Proofs::new(
+			raw_msg.proof_commitment.try_into().map_err(Error::invalid_proof)?,
+			None,
+			None,
+			None,
+			raw_msg.proof_height.ok_or_else(Error::missing_height)?.into(),
+		)
+		.map_err(Error::invalid_proof)?;
+
+		let packets = raw_msg
+			.packets
+			.into_iter()
+			.map(Packet::try_from)
+			.collect::<Result<Vec<_>, _>>()?;
+		MsgRecvPacketBatch::new(packets, proofs, raw_msg.signer.parse().map_err(Error::signer)?)
+	}
+}
+
+impl From<MsgRecvPacketBatch> for RawMsgRecvPacketBatch {
+	fn from(domain_msg: MsgRecvPacketBatch) -> Self {
+		RawMsgRecvPacketBatch {
+			packets: domain_msg.packets.into_iter().map(Into::into).collect(),
+			proof_commitment: domain_msg.proofs.object_proof().clone().into(),
+			proof_height: Some(domain_msg.proofs.height().into()),
+			signer: domain_msg.signer.to_string(),
+		}
+	}
+}
+
+#[cfg(test)]
+pub mod test_util {
+	use ibc_proto::ibc::core::{
+		channel::v1::MsgRecvPacketBatch as RawMsgRecvPacketBatch, client::v1::Height as RawHeight,
+	};
+
+	use crate::{
+		core::ics04_channel::packet::test_utils::get_dummy_raw_packet,
+		test_utils::{get_dummy_bech32_account, get_dummy_proof},
+		timestamp::Timestamp,
+	};
+	use core::{ops::Add, time::Duration};
+
+	/// Returns a dummy `RawMsgRecvPacketBatch` of packets with the given sequences, for testing
+	/// only! The `height` parametrizes both the proof height as well as the timeout height.
+	pub fn get_dummy_raw_msg_recv_packet_batch(
+		height: u64,
+		sequences: &[u64],
+	) -> RawMsgRecvPacketBatch {
+		let timestamp = Timestamp::now().add(Duration::from_secs(9)).unwrap().nanoseconds();
+		let packets = sequences
+			.iter()
+			.map(|&sequence| {
+				let mut packet = get_dummy_raw_packet(height, timestamp);
+				packet.sequence = sequence;
+				packet
+			})
+			.collect();
+		RawMsgRecvPacketBatch {
+			packets,
+			proof_commitment: get_dummy_proof(),
+			proof_height: Some(RawHeight { revision_number: 0, revision_height: height }),
+			signer: get_dummy_bech32_account(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::prelude::*;
+
+	use test_log::test;
+
+	use ibc_proto::ibc::core::channel::v1::MsgRecvPacketBatch as RawMsgRecvPacketBatch;
+
+	use crate::core::ics04_channel::{
+		error::ErrorDetail,
+		msgs::recv_packet_batch::{
+			test_util::get_dummy_raw_msg_recv_packet_batch, MsgRecvPacketBatch,
+		},
+	};
+
+	#[test]
+	fn msg_recv_packet_batch_try_from_raw() {
+		let raw = get_dummy_raw_msg_recv_packet_batch(10, &[1, 2, 3]);
+		let msg = MsgRecvPacketBatch::try_from(raw.clone()).unwrap();
+		assert_eq!(msg.packets().len(), 3);
+
+		let raw_empty = RawMsgRecvPacketBatch { packets: vec![], ..raw.clone() };
+		let err = MsgRecvPacketBatch::try_from(raw_empty).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::EmptyPacketBatch(_)));
+
+		let mut raw_mismatch = raw.clone();
+		raw_mismatch.packets[1].source_channel = "channel-1".to_string();
+		let err = MsgRecvPacketBatch::try_from(raw_mismatch).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::PacketBatchChannelMismatch(_)));
+
+		let raw_duplicate = get_dummy_raw_msg_recv_packet_batch(10, &[1, 2, 1]);
+		let err = MsgRecvPacketBatch::try_from(raw_duplicate).unwrap_err();
+		assert!(
+			matches!(err.detail(), ErrorDetail::DuplicatePacketInBatch(e) if e.sequence == 1.into())
+		);
+
+		// the batch is checked the same when built directly
+		let mut packets = msg.packets().to_vec();
+		packets.push(packets[0].clone());
+		let err = MsgRecvPacketBatch::new(packets, msg.proofs().clone(), msg.signer().clone())
+			.unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::DuplicatePacketInBatch(_)));
+
+		let raw_no_height = RawMsgRecvPacketBatch { proof_height: None, ..raw };
+		assert!(MsgRecvPacketBatch::try_from(raw_no_height).is_err());
+	}
+
+	#[test]
+	fn to_and_from() {
+		let raw = get_dummy_raw_msg_recv_packet_batch(15, &[1, 2]);
+		let msg = MsgRecvPacketBatch::try_from(raw.clone()).unwrap();
+		let raw_back = RawMsgRecvPacketBatch::from(msg.clone());
+		let msg_back = MsgRecvPacketBatch::try_from(raw_back.clone()).unwrap();
+		assert_eq!(raw, raw_back);
+		assert_eq!(msg, msg_back);
+	}
+}
@@ -0,0 +1,179 @@
+use crate::prelude::*;
+
+use tendermint_proto::Protobuf;
+
+use ibc_proto::ibc::core::channel::v1::MsgTimeoutBatch as RawMsgTimeoutBatch;
+
+use crate::{
+	core::ics04_channel::{error::Error, msgs::check_batch, packet::Packet},
+	proofs::Proofs,
+	signer::Signer,
+	tx_msg::Msg,
+};
+
+pub const TYPE_URL: &str = "/ibc.core.channel.v1.MsgTimeoutBatch";
+
+///
+/// Message definition for the timeout of several packets sent over the same unordered channel, with
+/// a single proof that none of them has been received.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MsgTimeoutBatch {
+	packets: Vec<Packet>,
+	proofs: Proofs,
+	signer: Signer,
+}
+
+impl MsgTimeoutBatch {
+	/// Builds the message of a batch of packets, which must be sent over the same channel, and have
+	/// distinct sequences.
+	pub fn new(
+		packets: Vec<Packet>,
+		proofs: Proofs,
+		signer: Signer,
+	) -> Result<MsgTimeoutBatch, Error> {
+		check_batch(&packets)?;
+		Ok(Self { packets, proofs, signer })
+	}
+
+	pub fn packets(&self) -> &[Packet] {
+		&self.packets
+	}
+
+	pub fn proofs(&self) -> &Proofs {
+		&self.proofs
+	}
+
+	pub fn signer(&self) -> &Signer {
+		&self.signer
+	}
+}
+
+impl Msg for MsgTimeoutBatch {
+	type ValidationError = Error;
+	type Raw = RawMsgTimeoutBatch;
+
+	fn route(&self) -> String {
+		crate::keys::ROUTER_KEY.to_string()
+	}
+
+	fn type_url(&self) -> String {
+		TYPE_URL.to_string()
+	}
+}
+
+impl Protobuf<RawMsgTimeoutBatch> for MsgTimeoutBatch {}
+
+impl TryFrom<RawMsgTimeoutBatch> for MsgTimeoutBatch {
+	type Error = Error;
+
+	fn try_from(raw_msg: RawMsgTimeoutBatch) -> Result<Self, Self::Error> {
+		let proofs = Proofs::new(
+			raw_msg.proof_unreceived.try_into().map_err(Error::invalid_proof)?,
+			None,
+			None,
+			None,
+			raw_msg.proof_height.ok_or_else(Error::missing_height)?.into(),
+		)
+		.map_err(Error::invalid_proof)?;
+
+		let packets = raw_msg
+			.packets
+			.into_iter()
+			.map(Packet::try_from)
+			.collect::<Result<Vec<_>, _>>()?;
+		MsgTimeoutBatch::new(packets, proofs, raw_msg.signer.parse().map_err(Error::signer)?)
+	}
+}
+
+impl From<MsgTimeoutBatch> for RawMsgTimeoutBatch {
+	fn from(domain_msg: MsgTimeoutBatch) -> Self {
+		RawMsgTimeoutBatch {
+			packets: domain_msg.packets.into_iter().map(Into::into).collect(),
+			proof_unreceived: domain_msg.proofs.object_proof().clone().into(),
+			proof_height: Some(domain_msg.proofs.height().into()),
+			signer: domain_msg.signer.to_string(),
+		}
+	}
+}
+
+#[cfg(test)]
+pub mod test_util {
+	use ibc_proto::ibc::core::{
+		channel::v1::MsgTimeoutBatch as RawMsgTimeoutBatch, client::v1::Height as RawHeight,
+	};
+
+	use crate::{
+		core::ics04_channel::packet::test_utils::get_dummy_raw_packet,
+		test_utils::{get_dummy_bech32_account, get_dummy_proof},
+	};
+
+	/// Returns a dummy `RawMsgTimeoutBatch` of packets with the given sequences, for testing only!
+	/// The `height` parametrizes both the proof height as well as the timeout height.
+	pub fn get_dummy_raw_msg_timeout_batch(
+		height: u64,
+		timeout_timestamp: u64,
+		sequences: &[u64],
+	) -> RawMsgTimeoutBatch {
+		let packets = sequences
+			.iter()
+			.map(|&sequence| {
+				let mut packet = get_dummy_raw_packet(height, timeout_timestamp);
+				packet.sequence = sequence;
+				packet
+			})
+			.collect();
+		RawMsgTimeoutBatch {
+			packets,
+			proof_unreceived: get_dummy_proof(),
+			proof_height: Some(RawHeight { revision_number: 0, revision_height: height }),
+			signer: get_dummy_bech32_account(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::prelude::*;
+
+	use test_log::test;
+
+	use ibc_proto::ibc::core::channel::v1::MsgTimeoutBatch as RawMsgTimeoutBatch;
+
+	use crate::core::ics04_channel::{
+		error::ErrorDetail,
+		msgs::timeout_batch::{test_util::get_dummy_raw_msg_timeout_batch, MsgTimeoutBatch},
+	};
+
+	#[test]
+	fn msg_timeout_batch_try_from_raw() {
+		let raw = get_dummy_raw_msg_timeout_batch(10, 0, &[1, 2, 3]);
+		let msg = MsgTimeoutBatch::try_from(raw.clone()).unwrap();
+		assert_eq!(msg.packets().len(), 3);
+
+		let raw_empty = RawMsgTimeoutBatch { packets: vec![], ..raw.clone() };
+		let err = MsgTimeoutBatch::try_from(raw_empty).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::EmptyPacketBatch(_)));
+
+		let mut raw_mismatch = raw.clone();
+		raw_mismatch.packets[2].destination_port = "other".to_string();
+		let err = MsgTimeoutBatch::try_from(raw_mismatch).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::PacketBatchChannelMismatch(_)));
+
+		let raw_duplicate = get_dummy_raw_msg_timeout_batch(10, 0, &[2, 2]);
+		let err = MsgTimeoutBatch::try_from(raw_duplicate).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::DuplicatePacketInBatch(_)));
+
+		let raw_no_height = RawMsgTimeoutBatch { proof_height: None, ..raw };
+		assert!(MsgTimeoutBatch::try_from(raw_no_height).is_err());
+	}
+
+	#[test]
+	fn to_and_from() {
+		let raw = get_dummy_raw_msg_timeout_batch(15, 0, &[3, 4]);
+		let msg = MsgTimeoutBatch::try_from(raw.clone()).unwrap();
+		let raw_back = RawMsgTimeoutBatch::from(msg.clone());
+		let msg_back = MsgTimeoutBatch::try_from(raw_back.clone()).unwrap();
+		assert_eq!(raw, raw_back);
+		assert_eq!(msg, msg_back);
+	}
+}
@@ -0,0 +1,310 @@
+//! Object capabilities, as specified by ICS 05, guarding the ports and channels owned by modules.
+//!
+//! A [`Capability`] can only be created by a [`CapabilityKeeper`], which records the module it is
+//! issued to. Holding the capability of a port or a channel is what entitles a module to act on
+//! it, e.g. to send packets on the channel. Modules keep the capabilities of the ports they bind,
+//! from which they recover the ones of their channels with
+//! [`CapabilityReader::lookup_channel_capability`].
+
+use core::fmt::{Display, Formatter};
+
+use crate::{
+	core::{
+		ics05_port::error::Error,
+		ics24_host::identifier::{ChannelId, PortId},
+		ics26_routing::context::ModuleId,
+	},
+	prelude::*,
+};
+
+/// An unforgeable handle to a port or a channel. Capabilities cannot be built, serialized or
+/// deserialized outside of this crate, they can only be obtained from
+/// [`CapabilityKeeper::new_capability`] and [`CapabilityReader::lookup_channel_capability`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Capability {
+	index: u64,
+}
+
+impl Capability {
+	pub(crate) fn new(index: u64) -> Self {
+		Self { index }
+	}
+}
+
+/// The name under which a capability is stored.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CapabilityName(String);
+
+impl CapabilityName {
+	/// The name of the capability of a port: `ports/{port_id}`.
+	pub fn port(port_id: &PortId) -> Self {
+		Self(format!("ports/{}", port_id))
+	}
+
+	/// The name of the capability of the ports starting with a claimed prefix:
+	/// `portPrefixes/{prefix}`.
+	pub fn port_prefix(prefix: &str) -> Self {
+		Self(format!("portPrefixes/{}", prefix))
+	}
+
+	/// The name of the capability of a channel:
+	/// `capabilities/ports/{port_id}/channels/{channel_id}`.
+	pub fn channel(port_id: &PortId, channel_id: ChannelId) -> Self {
+		Self(format!("capabilities/ports/{}/channels/{}", port_id, channel_id))
+	}
+
+	pub fn as_str(&self) -> &str {
+		&self.0
+	}
+}
+
+impl Display for CapabilityName {
+	fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+		write!(f, "{}", self.0)
+	}
+}
+
+/// A context supplying the capabilities issued by the host.
+pub trait CapabilityReader {
+	/// Returns the index of the capability stored under `name`, if any.
+	fn get_capability_index(&self, name: &CapabilityName) -> Result<Option<u64>, Error>;
+
+	/// Returns the module the capability stored under `name` was issued to, if any.
+	fn get_capability_owner(&self, name: &CapabilityName) -> Result<Option<ModuleId>, Error>;
+
+	/// Returns the index the next capability will be created with. Indices must never be reused,
+	/// even after the release of their capability.
+	fn get_next_capability_index(&self) -> Result<u64, Error>;
+
+	/// Checks that a capability was issued under `name`.
+	fn ensure_capability_exists(&self, name: &CapabilityName) -> Result<(), Error> {
+		match self.get_capability_index(name)? {
+			Some(_) => Ok(()),
+			None => Err(Error::capability_not_found(name.clone())),
+		}
+	}
+
+	/// Checks that `capability` is the capability issued under `name`.
+	fn authenticate_capability(
+		&self,
+		name: &CapabilityName,
+		capability: &Capability,
+	) -> Result<(), Error> {
+		match self.get_capability_index(name)? {
+			Some(index) if index == capability.index => Ok(()),
+			Some(_) => Err(Error::unauthenticated_capability(name.clone())),
+			None => Err(Error::capability_not_found(name.clone())),
+		}
+	}
+
+	/// Returns the module holding `capability`, which must be the capability issued under `name`.
+	fn authenticate_owner(
+		&self,
+		name: &CapabilityName,
+		capability: &Capability,
+	) -> Result<ModuleId, Error> {
+		self.authenticate_capability(name, capability)?;
+		self.get_capability_owner(name)?
+			.ok_or_else(|| Error::capability_not_found(name.clone()))
+	}
+
+	/// Returns the capability of a channel opened on a port by the module holding
+	/// `port_capability`, the capability of the port.
+	fn lookup_channel_capability(
+		&self,
+		port_id: &PortId,
+		channel_id: ChannelId,
+		port_capability: &Capability,
+	) -> Result<Capability, Error> {
+		let owner = self.authenticate_owner(&CapabilityName::port(port_id), port_capability)?;
+		lookup_capability(self, &CapabilityName::channel(port_id, channel_id), &owner)
+	}
+}
+
+/// Returns the capability issued under `name`, which must have been issued to `module_id`. Only
+/// the handlers and the modules of this crate, which know the module they act for, look up
+/// capabilities by the name of their owner.
+pub(crate) fn lookup_capability<Ctx: CapabilityReader + ?Sized>(
+	ctx: &Ctx,
+	name: &CapabilityName,
+	module_id: &ModuleId,
+) -> Result<Capability, Error> {
+	let index = ctx
+		.get_capability_index(name)?
+		.ok_or_else(|| Error::capability_not_found(name.clone()))?;
+	match ctx.get_capability_owner(name)? {
+		Some(owner) if &owner == module_id => Ok(Capability::new(index)),
+		_ => Err(Error::capability_not_owned(name.clone(), module_id.clone())),
+	}
+}
+
+/// A context supplying the storage of the capabilities issued by the host.
+pub trait CapabilityKeeper: CapabilityReader {
+	/// Stores the index of the capability issued under `name`, or deletes it if `index` is
+	/// `None`.
+	fn store_capability_index(
+		&mut self,
+		name: CapabilityName,
+		index: Option<u64>,
+	) -> Result<(), Error>;
+
+	/// Stores the module the capability under `name` is issued to, or deletes it if `owner` is
+	/// `None`.
+	fn store_capability_owner(
+		&mut self,
+		name: CapabilityName,
+		owner: Option<ModuleId>,
+	) -> Result<(), Error>;
+
+	fn store_next_capability_index(&mut self, index: u64) -> Result<(), Error>;
+
+	/// Issues a new capability under `name` to the module `owner`. The name must not be taken.
+	fn new_capability(
+		&mut self,
+		name: CapabilityName,
+		owner: ModuleId,
+	) -> Result<Capability, Error> {
+		if self.get_capability_index(&name)?.is_some() {
+			return Err(Error::capability_already_exists(name))
+		}
+
+		let index = self.get_next_capability_index()?;
+		self.store_next_capability_index(index.checked_add(1).ok_or_else(|| {
+			Error::implementation_specific("capability indices exhausted".to_string())
+		})?)?;
+		self.store_capability_index(name.clone(), Some(index))?;
+		self.store_capability_owner(name, Some(owner))?;
+
+		Ok(Capability::new(index))
+	}
+
+	/// Revokes the capability issued under `name`, which `capability` must authenticate.
+	fn release_capability(
+		&mut self,
+		name: CapabilityName,
+		capability: Capability,
+	) -> Result<(), Error> {
+		self.authenticate_capability(&name, &capability)?;
+		self.store_capability_index(name.clone(), None)?;
+		self.store_capability_owner(name, None)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::core::ics05_port::error::ErrorDetail;
+
+	#[derive(Default)]
+	struct Store {
+		capabilities: BTreeMap<CapabilityName, u64>,
+		owners: BTreeMap<CapabilityName, ModuleId>,
+		next_index: u64,
+	}
+
+	impl CapabilityReader for Store {
+		fn get_capability_index(&self, name: &CapabilityName) -> Result<Option<u64>, Error> {
+			Ok(self.capabilities.get(name).copied())
+		}
+
+		fn get_capability_owner(&self, name: &CapabilityName) -> Result<Option<ModuleId>, Error> {
+			Ok(self.owners.get(name).cloned())
+		}
+
+		fn get_next_capability_index(&self) -> Result<u64, Error> {
+			Ok(self.next_index)
+		}
+	}
+
+	impl CapabilityKeeper for Store {
+		fn store_capability_index(
+			&mut self,
+			name: CapabilityName,
+			index: Option<u64>,
+		) -> Result<(), Error> {
+			match index {
+				Some(index) => self.capabilities.insert(name, index),
+				None => self.capabilities.remove(&name),
+			};
+			Ok(())
+		}
+
+		fn store_capability_owner(
+			&mut self,
+			name: CapabilityName,
+			owner: Option<ModuleId>,
+		) -> Result<(), Error> {
+			match owner {
+				Some(owner) => self.owners.insert(name, owner),
+				None => self.owners.remove(&name),
+			};
+			Ok(())
+		}
+
+		fn store_next_capability_index(&mut self, index: u64) -> Result<(), Error> {
+			self.next_index = index;
+			Ok(())
+		}
+	}
+
+	#[test]
+	fn test_capability_authentication() {
+		let mut store = Store::default();
+		let port = CapabilityName::port(&PortId::transfer());
+		let channel = CapabilityName::channel(&PortId::transfer(), ChannelId::new(0));
+		let transfer: ModuleId = "transfer".parse().unwrap();
+
+		let port_capability = store.new_capability(port.clone(), transfer.clone()).unwrap();
+		assert!(store.new_capability(port.clone(), transfer.clone()).is_err());
+		let channel_capability = store.new_capability(channel.clone(), transfer.clone()).unwrap();
+
+		assert!(store.authenticate_capability(&port, &port_capability).is_ok());
+		assert!(store.authenticate_capability(&channel, &port_capability).is_err());
+
+		// a released capability is revoked, and its name is not reissued with the same index
+		store.release_capability(channel.clone(), channel_capability.clone()).unwrap();
+		assert!(store.authenticate_capability(&channel, &channel_capability).is_err());
+		assert!(lookup_capability(&store, &channel, &transfer).is_err());
+		let reissued = store.new_capability(channel.clone(), transfer.clone()).unwrap();
+		assert!(store.authenticate_capability(&channel, &channel_capability).is_err());
+		assert!(store.authenticate_capability(&channel, &reissued).is_ok());
+	}
+
+	#[test]
+	fn test_capability_lookup() {
+		let mut store = Store::default();
+		let transfer_port = CapabilityName::port(&PortId::transfer());
+		let channel = CapabilityName::channel(&PortId::transfer(), ChannelId::new(0));
+		let other_port: PortId = "other".parse().unwrap();
+		let transfer: ModuleId = "transfer".parse().unwrap();
+		let other: ModuleId = "other".parse().unwrap();
+
+		let port_capability = store.new_capability(transfer_port, transfer.clone()).unwrap();
+		let other_capability =
+			store.new_capability(CapabilityName::port(&other_port), other.clone()).unwrap();
+		assert!(store
+			.lookup_channel_capability(&PortId::transfer(), ChannelId::new(0), &port_capability)
+			.is_err());
+		let capability = store.new_capability(channel.clone(), transfer.clone()).unwrap();
+
+		// the capability is recovered from the store by the holder of the capability of its port
+		assert_eq!(
+			store
+				.lookup_channel_capability(&PortId::transfer(), ChannelId::new(0), &port_capability)
+				.unwrap(),
+			capability
+		);
+		assert_eq!(lookup_capability(&store, &channel, &transfer).unwrap(), capability);
+		assert!(lookup_capability(&store, &channel, &other).is_err());
+
+		// the capability of the port of another module recovers none of the channels of the port
+		let err = store
+			.lookup_channel_capability(&PortId::transfer(), ChannelId::new(0), &other_capability)
+			.unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::UnauthenticatedCapability(_)));
+		let err = store
+			.lookup_channel_capability(&other_port, ChannelId::new(0), &other_capability)
+			.unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::CapabilityNotFound(_)));
+	}
+}
@@ -1,6 +1,11 @@
 use crate::{
 	core::{
-		ics05_port::error::Error, ics24_host::identifier::PortId, ics26_routing::context::ModuleId,
+		ics05_port::{
+			capabilities::{Capability, CapabilityKeeper, CapabilityName},
+			error::Error,
+		},
+		ics24_host::{identifier::PortId, validate::validate_identifier},
+		ics26_routing::context::ModuleId,
 	},
 	prelude::*,
 };
@@ -10,4 +15,179 @@ use crate::{
 pub trait PortReader {
 	/// Return the module_id associated with a given port_id
 	fn lookup_module_by_port(&self, port_id: &PortId) -> Result<ModuleId, Error>;
+
+	/// Returns the bound ports starting with `prefix`, along with the module bound to each.
+	fn lookup_ports_by_prefix(&self, prefix: &str) -> Result<Vec<(PortId, ModuleId)>, Error>;
+
+	/// Returns the port prefixes claimed by modules along with their owner, see
+	/// [`PortKeeper::claim_port_prefix`]. Hosts not supporting dynamic ports have none.
+	fn get_port_prefixes(&self) -> Result<Vec<(String, ModuleId)>, Error> {
+		Ok(Vec::new())
+	}
+
+	/// Returns the claimed prefix `port_id` starts with, if any, along with its owner.
+	fn lookup_port_prefix(&self, port_id: &PortId) -> Result<Option<(String, ModuleId)>, Error> {
+		let prefix = self
+			.get_port_prefixes()?
+			.into_iter()
+			.find(|(prefix, _)| port_id.as_str().starts_with(prefix.as_str()));
+		Ok(prefix)
+	}
+}
+
+/// A context supplying the binding of ports to modules.
+pub trait PortKeeper: PortReader + CapabilityKeeper {
+	/// Associates a port with the module bound to it, or dissociates it if `module_id` is `None`.
+	fn store_port_module(
+		&mut self,
+		port_id: PortId,
+		module_id: Option<ModuleId>,
+	) -> Result<(), Error>;
+
+	/// Stores a port prefix claimed by a module.
+	fn store_port_prefix(&mut self, prefix: String, module_id: ModuleId) -> Result<(), Error>;
+
+	/// Claims all the ports starting with `prefix` for a module, returning the capability of the
+	/// prefix, with which the module binds them at runtime, e.g. one `icacontroller-{owner}` port
+	/// per interchain account owner. The prefix must not overlap with the ones already claimed,
+	/// nor cover ports bound by other modules.
+	///
+	/// Prefixes are claimed when the module is initialised by the host, which hands the capability
+	/// to the module only. Claiming a prefix does not entitle anyone else to bind its ports.
+	fn claim_port_prefix(
+		&mut self,
+		prefix: String,
+		module_id: ModuleId,
+	) -> Result<Capability, Error> {
+		validate_identifier(&prefix, 1, 127)
+			.map_err(|e| Error::invalid_port_prefix(prefix.clone(), e))?;
+
+		let overlapping = self.get_port_prefixes()?.into_iter().find(|(claimed, _)| {
+			claimed.starts_with(prefix.as_str()) || prefix.starts_with(claimed.as_str())
+		});
+		if let Some((claimed_prefix, owner)) = overlapping {
+			return Err(Error::port_prefix_already_claimed(prefix, claimed_prefix, owner))
+		}
+		let bound = self
+			.lookup_ports_by_prefix(&prefix)?
+			.into_iter()
+			.find(|(_, owner)| *owner != module_id);
+		if let Some((port_id, owner)) = bound {
+			return Err(Error::port_prefix_covers_bound_port(prefix, port_id, owner))
+		}
+
+		let capability =
+			self.new_capability(CapabilityName::port_prefix(&prefix), module_id.clone())?;
+		self.store_port_prefix(prefix, module_id)?;
+		Ok(capability)
+	}
+
+	/// Binds a free port to a module, returning the capability of the port, which is required to
+	/// release the port.
+	///
+	/// Ports starting with a claimed prefix can only be bound with
+	/// [`PortKeeper::bind_prefixed_port`].
+	fn bind_port(&mut self, port_id: PortId, module_id: ModuleId) -> Result<Capability, Error> {
+		if let Some((prefix, owner)) = self.lookup_port_prefix(&port_id)? {
+			return Err(Error::port_prefix_claimed(port_id, prefix, owner))
+		}
+		bind(self, port_id, module_id)
+	}
+
+	/// Binds a free port starting with a claimed prefix to the owner of the prefix, which must
+	/// present `prefix_capability`, the capability of the prefix, as returned by
+	/// [`PortKeeper::claim_port_prefix`]. This is how modules bind ports at runtime.
+	fn bind_prefixed_port(
+		&mut self,
+		port_id: PortId,
+		prefix_capability: &Capability,
+	) -> Result<Capability, Error> {
+		let (prefix, _) = self
+			.lookup_port_prefix(&port_id)?
+			.ok_or_else(|| Error::port_prefix_not_claimed(port_id.clone()))?;
+		let owner =
+			self.authenticate_owner(&CapabilityName::port_prefix(&prefix), prefix_capability)?;
+		bind(self, port_id, owner)
+	}
+
+	/// Releases a port bound with [`PortKeeper::bind_port`], revoking its capability.
+	fn release_port(&mut self, port_id: PortId, capability: Capability) -> Result<(), Error> {
+		self.release_capability(CapabilityName::port(&port_id), capability)?;
+		self.store_port_module(port_id, None)
+	}
+}
+
+/// Binds a free port to a module, issuing the capability of the port to the module.
+fn bind<Ctx: PortKeeper + ?Sized>(
+	ctx: &mut Ctx,
+	port_id: PortId,
+	module_id: ModuleId,
+) -> Result<Capability, Error> {
+	if ctx.lookup_module_by_port(&port_id).is_ok() {
+		return Err(Error::port_already_bound(port_id))
+	}
+
+	let capability = ctx.new_capability(CapabilityName::port(&port_id), module_id.clone())?;
+	ctx.store_port_module(port_id, Some(module_id))?;
+	Ok(capability)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		core::ics05_port::error::ErrorDetail,
+		mock::context::{MockClientTypes, MockContext},
+	};
+
+	#[test]
+	fn test_dynamic_port_binding() {
+		let mut ctx = MockContext::<MockClientTypes>::default();
+		let controller: ModuleId = "icacontroller".parse().unwrap();
+		let other: ModuleId = "other".parse().unwrap();
+
+		let prefix_capability =
+			ctx.claim_port_prefix("icacontroller-".to_string(), controller.clone()).unwrap();
+		let err = ctx.claim_port_prefix("icacontroller".to_string(), other.clone()).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::PortPrefixAlreadyClaimed(_)));
+		let err = ctx
+			.claim_port_prefix("icacontroller-bob".to_string(), other.clone())
+			.unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::PortPrefixAlreadyClaimed(_)));
+		let err = ctx.claim_port_prefix("ica/".to_string(), other.clone()).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::InvalidPortPrefix(_)));
+
+		// ports starting with the prefix are bound to its owner, by the holder of its capability
+		let port_id: PortId = "icacontroller-alice".parse().unwrap();
+		let err = ctx.bind_port(port_id.clone(), controller.clone()).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::PortPrefixClaimed(_)));
+		let capability = ctx.bind_prefixed_port(port_id.clone(), &prefix_capability).unwrap();
+		assert_eq!(ctx.lookup_module_by_port(&port_id).unwrap(), controller);
+		let err = ctx.bind_prefixed_port(port_id.clone(), &prefix_capability).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::PortAlreadyBound(_)));
+
+		// ports outside of claimed prefixes are bound as usual
+		let transfer = ctx.bind_port("transfer".parse().unwrap(), other.clone()).unwrap();
+		let err = ctx.bind_prefixed_port("transfer-1".parse().unwrap(), &transfer).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::PortPrefixNotClaimed(_)));
+
+		// prefixes cannot be claimed over the ports of other modules
+		ctx.bind_port("icahost-1".parse().unwrap(), other.clone()).unwrap();
+		let err = ctx.claim_port_prefix("icahost-".to_string(), controller.clone()).unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::PortPrefixCoversBoundPort(_)));
+		let other_prefix_capability =
+			ctx.claim_port_prefix("icahost-".to_string(), other.clone()).unwrap();
+
+		// a module which is not the owner of a prefix cannot bind its ports, whichever of its own
+		// capabilities it presents
+		let bob: PortId = "icacontroller-bob".parse().unwrap();
+		for forged in [&other_prefix_capability, &transfer] {
+			let err = ctx.bind_prefixed_port(bob.clone(), forged).unwrap_err();
+			assert!(matches!(err.detail(), ErrorDetail::UnauthenticatedCapability(_)));
+		}
+		assert!(ctx.lookup_module_by_port(&bob).is_err());
+
+		ctx.release_port(port_id.clone(), capability).unwrap();
+		assert!(ctx.lookup_module_by_port(&port_id).is_err());
+	}
 }
@@ -1,4 +1,11 @@
-use crate::{core::ics24_host::identifier::PortId, prelude::*};
+use crate::{
+	core::{
+		ics05_port::capabilities::CapabilityName,
+		ics24_host::{error::ValidationError, identifier::PortId},
+		ics26_routing::context::ModuleId,
+	},
+	prelude::*,
+};
 use flex_error::define_error;
 
 define_error! {
@@ -16,6 +23,57 @@ define_error! {
 			{ port_id: PortId }
 			| e | { format_args!("could not retrieve module from port '{0}'", e.port_id) },
 
+		InvalidPortPrefix
+			{ prefix: String }
+			[ ValidationError ]
+			| e | { format_args!("invalid port prefix '{0}'", e.prefix) },
+
+		PortPrefixAlreadyClaimed
+			{ prefix: String, claimed_prefix: String, owner: ModuleId }
+			| e | {
+				format_args!(
+					"port prefix '{0}' overlaps with the prefix '{1}' claimed by module {2}",
+					e.prefix, e.claimed_prefix, e.owner)
+			},
+
+		PortPrefixClaimed
+			{ port_id: PortId, prefix: String, owner: ModuleId }
+			| e | {
+				format_args!(
+					"port '{0}' starts with the prefix '{1}' claimed by module {2}",
+					e.port_id, e.prefix, e.owner)
+			},
+
+		PortPrefixNotClaimed
+			{ port_id: PortId }
+			| e | { format_args!("port '{0}' does not start with a claimed prefix", e.port_id) },
+
+		PortPrefixCoversBoundPort
+			{ prefix: String, port_id: PortId, owner: ModuleId }
+			| e | {
+				format_args!(
+					"port prefix '{0}' covers the port '{1}' bound by module {2}",
+					e.prefix, e.port_id, e.owner)
+			},
+
+		CapabilityNotFound
+			{ name: CapabilityName }
+			| e | { format_args!("no capability was issued under '{0}'", e.name) },
+
+		CapabilityAlreadyExists
+			{ name: CapabilityName }
+			| e | { format_args!("a capability was already issued under '{0}'", e.name) },
+
+		UnauthenticatedCapability
+			{ name: CapabilityName }
+			| e | { format_args!("capability does not authenticate '{0}'", e.name) },
+
+		CapabilityNotOwned
+			{ name: CapabilityName, module_id: ModuleId }
+			| e | {
+				format_args!("capability '{0}' was not issued to module {1}", e.name, e.module_id)
+			},
+
 		ImplementationSpecific
 			{ reason: String }
 			| e | { format_args!("implementation specific error: {}", e.reason) },
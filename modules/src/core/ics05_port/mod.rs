@@ -1,5 +1,6 @@
 //! ICS 05: Port implementation specifies the allocation scheme used by modules to
 //! bind to uniquely named ports.
 
+pub mod capabilities;
 pub mod context;
 pub mod error;
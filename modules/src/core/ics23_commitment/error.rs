@@ -1,3 +1,4 @@
+use crate::{gas::GasError, prelude::*};
 use flex_error::{define_error, TraceError};
 use prost::DecodeError;
 
@@ -18,6 +19,13 @@ define_error! {
 		EmptyMerkleProof
 			|_| { "empty merkle proof" },
 
+		EmptyProofSpecs
+			|_| { "proof specs cannot be empty" },
+
+		InvalidProofSpec
+			{ index: usize, reason: String }
+			|e| { format_args!("invalid proof spec at index {}: {}", e.index, e.reason) },
+
 		EmptyMerkleRoot
 			|_| { "empty merkle root" },
 
@@ -33,7 +41,47 @@ define_error! {
 		InvalidMerkleProof
 			|_| { "invalid merkle proof" },
 
+		NonUtf8MerkleKey
+			{ key: Vec<u8> }
+			|e| { format_args!("merkle key {:?} is not valid UTF-8", e.key) },
+
 		VerificationFailure
-			|_| { "proof verification failed" }
+			|_| { "proof verification failed" },
+
+		EmptyBatch
+			|_| { "a batch proof must cover at least one key" },
+
+		BatchKeysMismatch
+			|_| { "the keys covered by a batch proof must only differ in the lowest subtree" },
+
+		EmptyStoreEntry
+			|_| { "keys and values of a commitment store cannot be empty" },
+
+		EmptyStore
+			|_| { "cannot prove the absence of a key from an empty store" },
+
+		TrieProofDecodingFailed
+			{ reason: String }
+			|e| { format_args!("failed to decode trie proof: {}", e.reason) },
+
+		InvalidTrieRoot
+			{ len: usize }
+			|e| { format_args!("invalid trie root: expected 32 bytes, got {}", e.len) },
+
+		InvalidRlp
+			{ reason: String }
+			|e| { format_args!("invalid RLP: {}", e.reason) },
+
+		InvalidTrieNode
+			{ reason: String }
+			|e| { format_args!("invalid trie node: {}", e.reason) },
+
+		UnsupportedProofFormat
+			{ format: String }
+			|e| { format_args!("the host does not verify {} proofs", e.format) },
+
+		Gas
+			[ GasError ]
+			|_| { "gas meter error" },
 	}
 }
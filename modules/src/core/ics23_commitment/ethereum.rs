@@ -0,0 +1,611 @@
+//! Verification of the Merkle-Patricia trie proofs of Ethereum, as returned by `eth_getProof`, for
+//! counterparties running their IBC handler as a Solidity contract.
+//!
+//! Such a contract keeps its IBC state in a `mapping(bytes32 => bytes32)`, whose keys are the
+//! `keccak256` of the ICS-24 paths and whose values are the `keccak256` of the values committed
+//! to, as yui-ibc-solidity does. A value is proven by the proof of the account of the contract in
+//! the state trie, followed by the proof of the slot of its path in the storage trie of the
+//! contract.
+
+use codec::{Decode, Encode};
+use primitive_types::U256;
+
+use crate::{
+	core::ics23_commitment::{
+		commitment::{CommitmentProofBytes, CommitmentRoot},
+		error::Error,
+	},
+	gas::GasMeter,
+	prelude::*,
+};
+
+/// Host functions needed to verify Ethereum proofs.
+pub trait KeccakHostFunctions {
+	fn keccak_256(input: &[u8]) -> [u8; 32];
+}
+
+/// The layout of the storage of the IBC contract.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StorageLayout {
+	/// The slot of the mapping of the commitments, i.e. the position of its declaration among the
+	/// state variables of the contract.
+	pub commitments_slot: U256,
+}
+
+impl StorageLayout {
+	/// Returns the storage slot of the commitment to the value of `key`, usually an ICS-24 path,
+	/// following the layout of mappings in Solidity: `keccak256(keccak256(key) . slot)`.
+	pub fn commitment_slot<H: KeccakHostFunctions>(&self, key: &[u8]) -> [u8; 32] {
+		let mut preimage = H::keccak_256(key).to_vec();
+		let mut slot = [0u8; 32];
+		self.commitments_slot.to_big_endian(&mut slot);
+		preimage.extend_from_slice(&slot);
+		H::keccak_256(&preimage)
+	}
+}
+
+/// An account of the state trie.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Account {
+	pub nonce: u64,
+	pub balance: U256,
+	pub storage_root: [u8; 32],
+	pub code_hash: [u8; 32],
+}
+
+/// The proofs of a slot of the storage of a contract, as returned by `eth_getProof`.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct EthereumProof {
+	pub account_proof: Vec<Vec<u8>>,
+	pub storage_proof: Vec<Vec<u8>>,
+}
+
+impl TryFrom<&CommitmentProofBytes> for EthereumProof {
+	type Error = Error;
+
+	fn try_from(proof: &CommitmentProofBytes) -> Result<Self, Self::Error> {
+		Self::decode(&mut proof.as_bytes())
+			.map_err(|e| Error::trie_proof_decoding_failed(e.to_string()))
+	}
+}
+
+impl From<EthereumProof> for CommitmentProofBytes {
+	fn from(proof: EthereumProof) -> Self {
+		proof.encode().try_into().expect("SCALE encodings are never empty")
+	}
+}
+
+/// Verifies a proof that the IBC contract at `contract` commits to `value` under `key`, in the
+/// state of root `root`, charging the nodes of the proof to `gas`.
+pub fn verify_membership<H: KeccakHostFunctions>(
+	layout: &StorageLayout,
+	root: &CommitmentRoot,
+	contract: &[u8; 20],
+	proof: &CommitmentProofBytes,
+	key: &[u8],
+	value: &[u8],
+	gas: &dyn GasMeter,
+) -> Result<(), Error> {
+	if value.is_empty() {
+		return Err(Error::empty_verified_value())
+	}
+	let commitment = verify_commitment::<H>(layout, root, contract, proof, key, gas)?;
+	if commitment != H::keccak_256(value) {
+		return Err(Error::verification_failure())
+	}
+	Ok(())
+}
+
+/// Verifies a proof that the IBC contract at `contract` commits to nothing under `key`, in the
+/// state of root `root`, charging the nodes of the proof to `gas`.
+pub fn verify_non_membership<H: KeccakHostFunctions>(
+	layout: &StorageLayout,
+	root: &CommitmentRoot,
+	contract: &[u8; 20],
+	proof: &CommitmentProofBytes,
+	key: &[u8],
+	gas: &dyn GasMeter,
+) -> Result<(), Error> {
+	let commitment = verify_commitment::<H>(layout, root, contract, proof, key, gas)?;
+	if commitment != [0; 32] {
+		return Err(Error::verification_failure())
+	}
+	Ok(())
+}
+
+fn verify_commitment<H: KeccakHostFunctions>(
+	layout: &StorageLayout,
+	root: &CommitmentRoot,
+	contract: &[u8; 20],
+	proof: &CommitmentProofBytes,
+	key: &[u8],
+	gas: &dyn GasMeter,
+) -> Result<[u8; 32], Error> {
+	let root: [u8; 32] = root
+		.as_bytes()
+		.try_into()
+		.map_err(|_| Error::invalid_trie_root(root.as_bytes().len()))?;
+	let proof = EthereumProof::try_from(proof)?;
+	let nodes = proof.account_proof.len() + proof.storage_proof.len();
+	gas.charge_trie_nodes(nodes).map_err(Error::gas)?;
+	let account = verify_account::<H>(&root, contract, &proof.account_proof)?
+		.ok_or_else(Error::verification_failure)?;
+	let slot = layout.commitment_slot::<H>(key);
+	verify_storage::<H>(&account.storage_root, &slot, &proof.storage_proof)
+}
+
+/// Verifies the proof of the account at `address` in the state trie of root `state_root`,
+/// returning the account, or `None` if the proof shows that there is none.
+pub fn verify_account<H: KeccakHostFunctions>(
+	state_root: &[u8; 32],
+	address: &[u8; 20],
+	proof: &[Vec<u8>],
+) -> Result<Option<Account>, Error> {
+	let account = match verify_trie_proof::<H>(state_root, &H::keccak_256(address), proof)? {
+		Some(account) => account,
+		None => return Ok(None),
+	};
+
+	let invalid = || Error::invalid_trie_node("invalid account".to_string());
+	let fields = match rlp::decode(&account)? {
+		rlp::Item::List(fields) if fields.len() == 4 => fields,
+		_ => return Err(invalid()),
+	};
+	let bytes: Vec<&[u8]> = fields
+		.into_iter()
+		.map(|field| match field {
+			rlp::Item::Bytes(bytes) => Ok(bytes),
+			rlp::Item::List(_) => Err(invalid()),
+		})
+		.collect::<Result<_, _>>()?;
+	if bytes[0].len() > 8 || bytes[1].len() > 32 {
+		return Err(invalid())
+	}
+
+	let mut nonce = [0u8; 8];
+	nonce[8 - bytes[0].len()..].copy_from_slice(bytes[0]);
+	Ok(Some(Account {
+		nonce: u64::from_be_bytes(nonce),
+		balance: U256::from_big_endian(bytes[1]),
+		storage_root: bytes[2].try_into().map_err(|_| invalid())?,
+		code_hash: bytes[3].try_into().map_err(|_| invalid())?,
+	}))
+}
+
+/// Verifies the proof of `slot` in the storage trie of root `storage_root`, returning the value of
+/// the slot, which is zero if the proof shows that the slot is not set.
+pub fn verify_storage<H: KeccakHostFunctions>(
+	storage_root: &[u8; 32],
+	slot: &[u8; 32],
+	proof: &[Vec<u8>],
+) -> Result<[u8; 32], Error> {
+	let mut value = [0u8; 32];
+	if let Some(encoded) = verify_trie_proof::<H>(storage_root, &H::keccak_256(slot), proof)? {
+		// values are stored as integers, without their leading zeros
+		match rlp::decode(&encoded)? {
+			rlp::Item::Bytes(bytes) if bytes.len() <= 32 =>
+				value[32 - bytes.len()..].copy_from_slice(bytes),
+			_ => return Err(Error::invalid_trie_node("invalid storage value".to_string())),
+		}
+	}
+	Ok(value)
+}
+
+/// A reference to a child node: its hash, or the node itself if its encoding is shorter than a
+/// hash.
+enum Child<'a> {
+	Hash(&'a [u8]),
+	Inline(rlp::Item<'a>),
+}
+
+/// Verifies the proof of `key` in the trie of root `root`, returning the value stored under `key`,
+/// or `None` if the proof shows that there is none. The nodes of the proof are ordered from the
+/// root, and `key` is the key of the trie, i.e. the hash of the key for secure tries.
+pub fn verify_trie_proof<H: KeccakHostFunctions>(
+	root: &[u8; 32],
+	key: &[u8],
+	proof: &[Vec<u8>],
+) -> Result<Option<Vec<u8>>, Error> {
+	let invalid = |reason: &str| Error::invalid_trie_node(reason.to_string());
+	let key: Vec<u8> = key.iter().flat_map(|byte| [byte >> 4, byte & 0x0f]).collect();
+	let mut key = key.as_slice();
+	let mut nodes = proof.iter();
+	let mut child = Child::Hash(root);
+
+	let value = loop {
+		let node = match child {
+			Child::Hash(hash) => {
+				let encoded = nodes.next().ok_or_else(|| invalid("missing node"))?;
+				if H::keccak_256(encoded) != hash {
+					return Err(Error::verification_failure())
+				}
+				rlp::decode(encoded)?
+			},
+			Child::Inline(node) => node,
+		};
+
+		let mut items = match node {
+			// the root of the empty trie
+			rlp::Item::Bytes(bytes) if bytes.is_empty() => break None,
+			rlp::Item::List(items) if items.len() == 17 || items.len() == 2 => items,
+			_ => return Err(invalid("unknown node")),
+		};
+		let reference = if items.len() == 17 {
+			match key.split_first() {
+				Some((nibble, rest)) => {
+					key = rest;
+					items.swap_remove(*nibble as usize)
+				},
+				None => match items.pop() {
+					Some(rlp::Item::Bytes(value)) if !value.is_empty() =>
+						break Some(value.to_vec()),
+					Some(rlp::Item::Bytes(_)) => break None,
+					_ => return Err(invalid("invalid branch value")),
+				},
+			}
+		} else {
+			let (path, is_leaf) = match &items[0] {
+				rlp::Item::Bytes(encoded) => decode_hex_prefix(encoded)?,
+				rlp::Item::List(_) => return Err(invalid("invalid node path")),
+			};
+			if !key.starts_with(&path) {
+				break None
+			}
+			key = &key[path.len()..];
+			let reference = items.pop().expect("the node has two items");
+			if is_leaf {
+				match reference {
+					rlp::Item::Bytes(value) if key.is_empty() => break Some(value.to_vec()),
+					rlp::Item::Bytes(_) => break None,
+					rlp::Item::List(_) => return Err(invalid("invalid leaf value")),
+				}
+			}
+			reference
+		};
+
+		child = match reference {
+			rlp::Item::Bytes(hash) if hash.len() == 32 => Child::Hash(hash),
+			rlp::Item::Bytes(hash) if hash.is_empty() => break None,
+			rlp::Item::List(_) => Child::Inline(reference),
+			rlp::Item::Bytes(_) => return Err(invalid("invalid child reference")),
+		};
+	};
+
+	if nodes.next().is_some() {
+		return Err(invalid("unused nodes"))
+	}
+	Ok(value)
+}
+
+/// Decodes the hex-prefix encoding of the path of a leaf or an extension, returning its nibbles
+/// and whether it is the path of a leaf.
+fn decode_hex_prefix(encoded: &[u8]) -> Result<(Vec<u8>, bool), Error> {
+	let (first, rest) = encoded
+		.split_first()
+		.ok_or_else(|| Error::invalid_trie_node("empty path".to_string()))?;
+	let flag = first >> 4;
+	if flag > 3 || (flag & 1 == 0 && first & 0x0f != 0) {
+		return Err(Error::invalid_trie_node("invalid path".to_string()))
+	}
+
+	let mut path = Vec::with_capacity(2 * encoded.len());
+	if flag & 1 == 1 {
+		path.push(first & 0x0f);
+	}
+	path.extend(rest.iter().flat_map(|byte| [byte >> 4, byte & 0x0f]));
+	Ok((path, flag & 2 == 2))
+}
+
+/// A decoder of the recursive length prefix encoding.
+mod rlp {
+	use super::Error;
+	use crate::prelude::*;
+
+	#[derive(Clone, Debug, PartialEq, Eq)]
+	pub enum Item<'a> {
+		Bytes(&'a [u8]),
+		List(Vec<Item<'a>>),
+	}
+
+	/// Decodes a single item, which must span the whole input.
+	pub fn decode(input: &[u8]) -> Result<Item<'_>, Error> {
+		match decode_item(input)? {
+			(item, []) => Ok(item),
+			_ => Err(invalid("trailing bytes")),
+		}
+	}
+
+	fn invalid(reason: &str) -> Error {
+		Error::invalid_rlp(reason.to_string())
+	}
+
+	fn decode_item(input: &[u8]) -> Result<(Item<'_>, &[u8]), Error> {
+		let (&prefix, rest) = input.split_first().ok_or_else(|| invalid("unexpected end"))?;
+		match prefix {
+			0x00..=0x7f => Ok((Item::Bytes(&input[..1]), rest)),
+			0x80..=0xbf => {
+				let (payload, rest) = split_payload(prefix, 0x80, rest)?;
+				if payload.len() == 1 && payload[0] < 0x80 {
+					return Err(invalid("non-canonical single byte"))
+				}
+				Ok((Item::Bytes(payload), rest))
+			},
+			0xc0..=0xff => {
+				let (mut payload, rest) = split_payload(prefix, 0xc0, rest)?;
+				let mut items = vec![];
+				while !payload.is_empty() {
+					let (item, remaining) = decode_item(payload)?;
+					items.push(item);
+					payload = remaining;
+				}
+				Ok((Item::List(items), rest))
+			},
+		}
+	}
+
+	/// Splits the payload of an item of prefix `prefix` from the rest of `input`, `offset` being
+	/// `0x80` for byte strings and `0xc0` for lists.
+	fn split_payload(prefix: u8, offset: u8, input: &[u8]) -> Result<(&[u8], &[u8]), Error> {
+		let (len, input) = match prefix - offset {
+			short @ 0..=55 => (short as usize, input),
+			long => {
+				let len_of_len = (long - 55) as usize;
+				if input.len() < len_of_len || len_of_len > 8 {
+					return Err(invalid("unexpected end"))
+				}
+				let (len, input) = input.split_at(len_of_len);
+				if len[0] == 0 {
+					return Err(invalid("non-canonical length"))
+				}
+				let len = len.iter().fold(0u64, |acc, byte| acc << 8 | u64::from(*byte));
+				if len < 56 {
+					return Err(invalid("non-canonical length"))
+				}
+				(usize::try_from(len).map_err(|_| invalid("unexpected end"))?, input)
+			},
+		};
+		if input.len() < len {
+			return Err(invalid("unexpected end"))
+		}
+		Ok(input.split_at(len))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		core::{
+			ics23_commitment::{
+				commitment::CommitmentPrefix,
+				merkle::MerklePath,
+				specs::ProofSpecs,
+				substrate::NoTrieHostFunctions,
+				verify::{self, ProofFormat},
+			},
+			ics24_host::{identifier::ConnectionId, path::ConnectionsPath, Path},
+		},
+		gas::{BasicGasMeter, InfiniteGasMeter},
+		test_utils::Crypto,
+	};
+	use subtle_encoding::hex;
+
+	// A state trie holding the IBC contract and five other accounts, and the storage trie of the
+	// contract, holding three commitments and a slot of another variable, in the format of the
+	// responses of `eth_getProof`. They are generated locally rather than captured from a node,
+	// which is why the verifier is also checked against a published trie in `test_published_trie`.
+	const STATE_ROOT: &str = "2ef192b4c249e8a2e1ff31090eaa2147344e653bf58f04b5677c8e8c1b0b172e";
+	const STORAGE_ROOT: &str = "f6fc09454640bf048f5a710bad2d70ffb5da7a6a6aeb2f5aa88b2744e66c1bbe";
+	const CODE_HASH: &str = "26d3f1d475390de85680826220dc167eaa9bfc8176c19e3c3246d7ec8dfb2a26";
+	const CONTRACT: &str = "a1b2c3d4e5f60718293a4b5c6d7e8f9011223344";
+	// of the contract, and of an account that does not exist, `0x0909..09`
+	const ACCOUNT_PROOF: [&str; 2] = [
+		concat!(
+			"f8d1a07d232b6461a290dbf94e2202f8bb30402dd095a3aa68078d209087be9220599180a012a933",
+			"b2d2e395bc9c98cc5a2fc9b528a67207295c81a7816db05953fbe7f75d808080a075dd40cc4f7833",
+			"e0e5b2d9a251220d8858b4a90320658dd5efffa862d8a604b58080a0a71ae5c79a0335a0aae929ad",
+			"53cfef23730139d132a76fb9c7c13c38b33a3da1a0a6e6baf83207fb710554017fa83cd4eaf9ee0b",
+			"75cfec2fcf0df0e5c7b6343f6e808080a0fdf41934ea1b05e156ee788c3fa0220fb9058c166f2f72",
+			"320129ae45efa0b99f8080",
+		),
+		concat!(
+			"f869a031ee3838c831d51764f9af236c21646e869a15c0fc58e6cb0c0434a5deea40beb846f84401",
+			"80a0f6fc09454640bf048f5a710bad2d70ffb5da7a6a6aeb2f5aa88b2744e66c1bbea026d3f1d475",
+			"390de85680826220dc167eaa9bfc8176c19e3c3246d7ec8dfb2a26",
+		),
+	];
+	const ABSENT_ACCOUNT_PROOF: [&str; 2] = [
+		concat!(
+			"f8d1a07d232b6461a290dbf94e2202f8bb30402dd095a3aa68078d209087be9220599180a012a933",
+			"b2d2e395bc9c98cc5a2fc9b528a67207295c81a7816db05953fbe7f75d808080a075dd40cc4f7833",
+			"e0e5b2d9a251220d8858b4a90320658dd5efffa862d8a604b58080a0a71ae5c79a0335a0aae929ad",
+			"53cfef23730139d132a76fb9c7c13c38b33a3da1a0a6e6baf83207fb710554017fa83cd4eaf9ee0b",
+			"75cfec2fcf0df0e5c7b6343f6e808080a0fdf41934ea1b05e156ee788c3fa0220fb9058c166f2f72",
+			"320129ae45efa0b99f8080",
+		),
+		concat!(
+			"f86ca032c2f498f37adab9c7a4bf0aae161bb929b33867f5b5976848450005f577b8cbb849f84703",
+			"83030000a056e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421a0c5d2",
+			"460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470",
+		),
+	];
+	// of the commitment to `connections/connection-0`, and of the absent `connections/connection-1`
+	const STORAGE_PROOF: [&str; 2] = [
+		concat!(
+			"f871808080808080808080a0a1aa11ee1891feb9b8aea8f9ab70830dc51dd7e39e5170f87f218066",
+			"afd8c5ff80a0e4449cb51d628e6e071cbba31d760efcf09715ce230ac380c7a239722c0f22118080",
+			"a08bc30385ef8f5f30f63e3d9372bb21dd45debe72610de3846efc29bb5494c45d8080",
+		),
+		concat!(
+			"f843a0353fc42ddf6c1b5bb218ce24e14c40af9e0eb127a5d76050d37d7369e2fc4a47a1a0729694",
+			"5801d89f047162ea64c6070a507db2e3049829c9d82fc1c84320693784",
+		),
+	];
+	const ABSENT_STORAGE_PROOF: [&str; 1] = [concat!(
+		"f871808080808080808080a0a1aa11ee1891feb9b8aea8f9ab70830dc51dd7e39e5170f87f218066",
+		"afd8c5ff80a0e4449cb51d628e6e071cbba31d760efcf09715ce230ac380c7a239722c0f22118080",
+		"a08bc30385ef8f5f30f63e3d9372bb21dd45debe72610de3846efc29bb5494c45d8080",
+	)];
+
+	// The nodes of the `puppy` trie of the trie tests of ethereum/tests, holding `do`, `dog`,
+	// `doge` and `horse`, on the way to `dog`. The last node inlines the nodes of `do` and below.
+	const PUPPY_ROOT: &str = "5991bb8c6514148a29db676a14ac506cd2cd5775ace63c30a4fe457715e9ac84";
+	const PUPPY_PROOF: [&str; 4] = [
+		"e216a0bd3ee507e6c67cfefca98f84be47c1bbc009315fabc4405db4ba32190374572a",
+		concat!(
+			"f84080808080a094a9f95bd89698e4da1812e0518053813b4d5b87caaf6b3c6fa57e9e50c0ff6880",
+			"8080cf85206f727365887374616c6c696f6e8080808080808080",
+		),
+		"e482006fa0d43b87fdcd4217013ccc92d04662e12d36e4cc25dc690077cd821a1956fc3e36",
+		concat!(
+			"f3808080808080de17dc808080808080c63584636f696e8080808080808080808570757070798080",
+			"808080808080808476657262",
+		),
+	];
+
+	fn decode_hex<const N: usize>(hex: &str) -> [u8; N] {
+		hex::decode(hex).unwrap().try_into().unwrap()
+	}
+
+	fn decode_proof(nodes: &[&str]) -> Vec<Vec<u8>> {
+		nodes.iter().map(|node| hex::decode(node).unwrap()).collect()
+	}
+
+	#[test]
+	fn test_trie_proofs() {
+		let root = decode_hex(STATE_ROOT);
+		let contract = decode_hex(CONTRACT);
+		let account = verify_account::<Crypto>(&root, &contract, &decode_proof(&ACCOUNT_PROOF))
+			.unwrap()
+			.unwrap();
+		let expected = Account {
+			nonce: 1,
+			balance: U256::zero(),
+			storage_root: decode_hex(STORAGE_ROOT),
+			code_hash: decode_hex(CODE_HASH),
+		};
+		assert_eq!(account, expected);
+
+		let absent = [9; 20];
+		let proof = decode_proof(&ABSENT_ACCOUNT_PROOF);
+		assert_eq!(verify_account::<Crypto>(&root, &absent, &proof).unwrap(), None);
+		assert!(verify_account::<Crypto>(&root, &contract, &proof).is_err());
+
+		// any change to the nodes is detected
+		let mut proof = decode_proof(&ACCOUNT_PROOF);
+		proof[1][40] ^= 1;
+		assert!(verify_account::<Crypto>(&root, &contract, &proof).is_err());
+		let proof = decode_proof(&ACCOUNT_PROOF[..1]);
+		assert!(verify_account::<Crypto>(&root, &contract, &proof).is_err());
+
+		let empty_root = Crypto::keccak_256(&[0x80]);
+		assert_eq!(verify_trie_proof::<Crypto>(&empty_root, &[1], &[vec![0x80]]).unwrap(), None);
+
+		assert!(rlp::decode(&[0x81, 0x7f]).is_err());
+		assert!(rlp::decode(&[0xb8, 0x01, 0x80]).is_err());
+		assert!(rlp::decode(&[0xc2, 0x80]).is_err());
+		assert!(rlp::decode(&[0x80, 0x80]).is_err());
+	}
+
+	#[test]
+	fn test_published_trie() {
+		let root = decode_hex(PUPPY_ROOT);
+		let proof = decode_proof(&PUPPY_PROOF);
+		let value = verify_trie_proof::<Crypto>(&root, b"dog", &proof).unwrap();
+		assert_eq!(value.as_deref(), Some(&b"puppy"[..]));
+		let value = verify_trie_proof::<Crypto>(&root, b"doge", &proof).unwrap();
+		assert_eq!(value.as_deref(), Some(&b"coin"[..]));
+		assert_eq!(verify_trie_proof::<Crypto>(&root, b"dot", &proof).unwrap(), None);
+		assert!(verify_trie_proof::<Crypto>(&root, b"horse", &proof).is_err());
+	}
+
+	#[test]
+	fn test_commitment_proofs() {
+		let layout = StorageLayout::default();
+		let root = CommitmentRoot::from_bytes(&decode_hex::<32>(STATE_ROOT));
+		let contract = decode_hex(CONTRACT);
+		let gas = &InfiniteGasMeter;
+		let stored = Path::from(ConnectionsPath(ConnectionId::new(0))).to_string().into_bytes();
+		let absent = Path::from(ConnectionsPath(ConnectionId::new(1))).to_string().into_bytes();
+
+		let proof = CommitmentProofBytes::from(EthereumProof {
+			account_proof: decode_proof(&ACCOUNT_PROOF),
+			storage_proof: decode_proof(&STORAGE_PROOF),
+		});
+		let verify = |key: &[u8], value: &[u8]| {
+			verify_membership::<Crypto>(&layout, &root, &contract, &proof, key, value, gas)
+		};
+		verify(&stored, b"connection end").unwrap();
+		assert!(verify(&stored, b"other connection end").is_err());
+		assert!(verify(&absent, b"connection end").is_err());
+		assert!(verify_non_membership::<Crypto>(&layout, &root, &contract, &proof, &stored, gas)
+			.is_err());
+
+		let proof = CommitmentProofBytes::from(EthereumProof {
+			account_proof: decode_proof(&ACCOUNT_PROOF),
+			storage_proof: decode_proof(&ABSENT_STORAGE_PROOF),
+		});
+		verify_non_membership::<Crypto>(&layout, &root, &contract, &proof, &absent, gas).unwrap();
+
+		// the commitments are looked up in the mapping declared at the given slot
+		let layout = StorageLayout { commitments_slot: U256::one() };
+		let proof = CommitmentProofBytes::from(EthereumProof {
+			account_proof: decode_proof(&ACCOUNT_PROOF),
+			storage_proof: decode_proof(&STORAGE_PROOF),
+		});
+		let value = b"connection end";
+		assert!(verify_membership::<Crypto>(
+			&layout, &root, &contract, &proof, &stored, value, gas
+		)
+		.is_err());
+	}
+
+	#[test]
+	fn test_evm_proof_format() {
+		let format =
+			ProofFormat::Evm { contract: decode_hex(CONTRACT), layout: StorageLayout::default() };
+		let prefix = CommitmentPrefix::try_from(b"ibc".to_vec()).unwrap();
+		let root = CommitmentRoot::from_bytes(&decode_hex::<32>(STATE_ROOT));
+		let specs = ProofSpecs::default();
+		let gas = &BasicGasMeter::new(u64::MAX);
+		let stored: Path = ConnectionsPath(ConnectionId::new(0)).into();
+		let absent: Path = ConnectionsPath(ConnectionId::new(1)).into();
+
+		// the contract holds no prefix, keys are the paths alone
+		let keys = format.merkle_path(&prefix, &stored);
+		assert_eq!(keys, MerklePath::new(vec![stored.to_string().into_bytes()]));
+
+		let proof = CommitmentProofBytes::from(EthereumProof {
+			account_proof: decode_proof(&ACCOUNT_PROOF),
+			storage_proof: decode_proof(&STORAGE_PROOF),
+		});
+		let verify_value = |value: &[u8]| {
+			let keys = keys.clone();
+			verify::verify_membership::<Crypto, NoTrieHostFunctions, Crypto>(
+				format,
+				&specs,
+				&proof,
+				&root,
+				keys,
+				value.to_vec(),
+				gas,
+			)
+		};
+		verify_value(b"connection end").unwrap();
+		let membership_gas = gas.gas_consumed();
+		assert!(verify_value(b"other connection end").is_err());
+
+		let proof = CommitmentProofBytes::from(EthereumProof {
+			account_proof: decode_proof(&ACCOUNT_PROOF),
+			storage_proof: decode_proof(&ABSENT_STORAGE_PROOF),
+		});
+		let keys = format.merkle_path(&prefix, &absent);
+		let consumed = gas.gas_consumed();
+		verify::verify_non_membership::<Crypto, NoTrieHostFunctions, Crypto>(
+			format, &specs, &proof, &root, keys, gas,
+		)
+		.unwrap();
+		// proofs are charged per node, and the slot is proven absent by fewer of them
+		assert!(gas.gas_consumed() - consumed < membership_gas);
+	}
+}
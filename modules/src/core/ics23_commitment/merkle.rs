@@ -4,21 +4,61 @@ use crate::{
 		error::Error,
 		specs::ProofSpecs,
 	},
+	gas::GasMeter,
 	prelude::*,
 };
 use core::marker::PhantomData;
-use ibc_proto::ibc::core::commitment::v1::{MerklePath, MerkleProof as RawMerkleProof, MerkleRoot};
+use ibc_proto::ibc::core::commitment::v1::{
+	MerklePath as RawMerklePath, MerkleProof as RawMerkleProof, MerkleRoot,
+};
 use ics23::{
-	calculate_existence_root, commitment_proof::Proof, verify_membership, verify_non_membership,
-	CommitmentProof, HostFunctionsProvider, NonExistenceProof,
+	batch_entry, calculate_existence_root, commitment_proof::Proof, decompress, verify_membership,
+	verify_non_membership, CommitmentProof, HostFunctionsProvider, NonExistenceProof,
 };
 
-pub fn apply_prefix(prefix: &CommitmentPrefix, mut path: Vec<String>) -> MerklePath {
-	let mut key_path: Vec<String> = vec![format!("{:?}", prefix)];
+/// Returns the keys of `path` in the store of prefix `prefix`, nested in the root store of a
+/// chain, the prefix being the key of the store in the root store.
+pub fn apply_prefix(prefix: &CommitmentPrefix, mut path: Vec<Vec<u8>>) -> MerklePath {
+	let mut key_path = vec![prefix.as_bytes().to_vec()];
 	key_path.append(&mut path);
 	MerklePath { key_path }
 }
 
+/// The keys of a value in each of the nested stores of a chain, from the root store to the store
+/// holding the value. Unlike those of [`RawMerklePath`], the keys are raw bytes, and need not be
+/// valid UTF-8.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MerklePath {
+	pub key_path: Vec<Vec<u8>>,
+}
+
+impl MerklePath {
+	pub fn new(key_path: Vec<Vec<u8>>) -> Self {
+		Self { key_path }
+	}
+}
+
+impl From<RawMerklePath> for MerklePath {
+	fn from(path: RawMerklePath) -> Self {
+		Self { key_path: path.key_path.into_iter().map(String::into_bytes).collect() }
+	}
+}
+
+impl TryFrom<MerklePath> for RawMerklePath {
+	type Error = Error;
+
+	fn try_from(path: MerklePath) -> Result<Self, Self::Error> {
+		let key_path = path
+			.key_path
+			.into_iter()
+			.map(|key| {
+				String::from_utf8(key).map_err(|e| Error::non_utf8_merkle_key(e.into_bytes()))
+			})
+			.collect::<Result<_, _>>()?;
+		Ok(Self { key_path })
+	}
+}
+
 impl From<CommitmentRoot> for MerkleRoot {
 	fn from(root: CommitmentRoot) -> Self {
 		Self { hash: root.into_vec() }
@@ -31,6 +71,13 @@ pub struct MerkleProof<H> {
 	_phantom: PhantomData<H>,
 }
 
+impl<H> MerkleProof<H> {
+	/// Builds a proof from the proofs of each key of a [`MerklePath`], from leaf to root.
+	pub fn new(proofs: Vec<CommitmentProof>) -> Self {
+		Self { proofs, _phantom: PhantomData }
+	}
+}
+
 /// Convert to ics23::CommitmentProof
 /// The encoding and decoding shouldn't fail since ics23::CommitmentProof and
 /// ibc_proto::ics23::CommitmentProof should be the same Ref. <https://github.com/informalsystems/ibc-rs/issues/853>
@@ -73,6 +120,7 @@ impl<H: HostFunctionsProvider> MerkleProof<H> {
 		keys: MerklePath,
 		value: Vec<u8>,
 		start_index: usize,
+		gas: &dyn GasMeter,
 	) -> Result<(), Error> {
 		// validate arguments
 		if self.proofs.is_empty() {
@@ -92,6 +140,7 @@ impl<H: HostFunctionsProvider> MerkleProof<H> {
 		if value.is_empty() {
 			return Err(Error::empty_verified_value())
 		}
+		gas.charge_merkle_ops(num.saturating_sub(start_index)).map_err(Error::gas)?;
 
 		let mut subroot = value.clone();
 		let mut value = value;
@@ -107,7 +156,7 @@ impl<H: HostFunctionsProvider> MerkleProof<H> {
 				Some(Proof::Exist(existence_proof)) => {
 					subroot = calculate_existence_root::<H>(existence_proof)
 						.map_err(|_| Error::invalid_merkle_proof())?;
-					if !verify_membership::<H>(proof, spec, &subroot, key.as_bytes(), &value) {
+					if !verify_membership::<H>(proof, spec, &subroot, key, &value) {
 						return Err(Error::verification_failure())
 					}
 					value = subroot.clone();
@@ -128,6 +177,7 @@ impl<H: HostFunctionsProvider> MerkleProof<H> {
 		specs: &ProofSpecs,
 		root: MerkleRoot,
 		keys: MerklePath,
+		gas: &dyn GasMeter,
 	) -> Result<(), Error> {
 		// validate arguments
 		if self.proofs.is_empty() {
@@ -145,6 +195,8 @@ impl<H: HostFunctionsProvider> MerkleProof<H> {
 			return Err(Error::number_of_keys_mismatch())
 		}
 
+		gas.charge_merkle_ops(1).map_err(Error::gas)?;
+
 		// verify the absence of key in lowest subtree
 		let proof = self.proofs.get(0).ok_or_else(Error::invalid_merkle_proof)?;
 		let spec = ics23_specs.get(0).ok_or_else(Error::invalid_merkle_proof)?;
@@ -153,15 +205,100 @@ impl<H: HostFunctionsProvider> MerkleProof<H> {
 		match &proof.proof {
 			Some(Proof::Nonexist(non_existence_proof)) => {
 				let subroot = calculate_non_existence_root::<H>(non_existence_proof)?;
-				if !verify_non_membership::<H>(proof, spec, &subroot, key.as_bytes()) {
+				if !verify_non_membership::<H>(proof, spec, &subroot, key) {
 					return Err(Error::verification_failure())
 				}
 				// verify membership proofs starting from index 1 with value = subroot
-				self.verify_membership(specs, root, keys, subroot, 1)
+				self.verify_membership(specs, root, keys, subroot, 1, gas)
 			},
 			_ => Err(Error::invalid_merkle_proof()),
 		}
 	}
+
+	/// Verifies that each value of `items` is stored under its keys, with a single proof whose
+	/// lowest subtree is proven by a batch proof, possibly compressed, covering all the keys. The
+	/// keys must therefore only differ in the lowest subtree.
+	pub fn verify_batch_membership(
+		&self,
+		specs: &ProofSpecs,
+		root: MerkleRoot,
+		items: Vec<(MerklePath, Vec<u8>)>,
+		gas: &dyn GasMeter,
+	) -> Result<(), Error> {
+		if items.iter().any(|(_, value)| value.is_empty()) {
+			return Err(Error::empty_verified_value())
+		}
+		let (keys, values): (Vec<_>, Vec<_>) = items.into_iter().unzip();
+		self.verify_batch(specs, root, keys, gas, |proof, spec, subroot, index, key| {
+			verify_membership::<H>(proof, spec, subroot, key, &values[index])
+		})
+	}
+
+	/// Verifies that nothing is stored under any of `keys`, with a single proof whose lowest
+	/// subtree is proven by a batch proof, possibly compressed, covering all the keys.
+	pub fn verify_batch_non_membership(
+		&self,
+		specs: &ProofSpecs,
+		root: MerkleRoot,
+		keys: Vec<MerklePath>,
+		gas: &dyn GasMeter,
+	) -> Result<(), Error> {
+		self.verify_batch(specs, root, keys, gas, |proof, spec, subroot, _, key| {
+			verify_non_membership::<H>(proof, spec, subroot, key)
+		})
+	}
+
+	/// Verifies each key of the lowest subtree with `verify`, given the decompressed batch proof,
+	/// the root of the subtree, the index of the key and the key, then the path from the subtree
+	/// to `root`.
+	fn verify_batch<F>(
+		&self,
+		specs: &ProofSpecs,
+		root: MerkleRoot,
+		keys: Vec<MerklePath>,
+		gas: &dyn GasMeter,
+		verify: F,
+	) -> Result<(), Error>
+	where
+		F: Fn(&CommitmentProof, &ics23::ProofSpec, &Vec<u8>, usize, &[u8]) -> bool,
+	{
+		let proof = self.proofs.get(0).ok_or_else(Error::empty_merkle_proof)?;
+		let num = self.proofs.len();
+		let first = keys.first().ok_or_else(Error::empty_batch)?;
+		if keys.iter().any(|key| key.key_path.len() != num) {
+			return Err(Error::number_of_keys_mismatch())
+		}
+		// keys are represented from root-to-leaf
+		if keys.iter().any(|key| key.key_path[..num - 1] != first.key_path[..num - 1]) {
+			return Err(Error::batch_keys_mismatch())
+		}
+		let ics23_specs = Vec::<ics23::ProofSpec>::from(specs.clone());
+		let spec = ics23_specs.get(0).ok_or_else(Error::number_of_specs_mismatch)?;
+		gas.charge_merkle_ops(keys.len()).map_err(Error::gas)?;
+
+		let proof = decompress(proof).map_err(|_| Error::invalid_merkle_proof())?;
+		// every entry of a valid batch leads to the same root
+		let subroot = match &proof.proof {
+			Some(Proof::Batch(batch)) =>
+				match batch.entries.first().and_then(|entry| entry.proof.as_ref()) {
+					Some(batch_entry::Proof::Exist(existence_proof)) =>
+						calculate_existence_root::<H>(existence_proof)
+							.map_err(|_| Error::invalid_merkle_proof())?,
+					Some(batch_entry::Proof::Nonexist(non_existence_proof)) =>
+						calculate_non_existence_root::<H>(non_existence_proof)?,
+					None => return Err(Error::invalid_merkle_proof()),
+				},
+			_ => return Err(Error::invalid_merkle_proof()),
+		};
+		for (index, key) in keys.iter().enumerate() {
+			if !verify(&proof, spec, &subroot, index, &key.key_path[num - 1]) {
+				return Err(Error::verification_failure())
+			}
+		}
+
+		// verify membership proofs starting from index 1 with value = subroot
+		self.verify_membership(specs, root, first.clone(), subroot, 1, gas)
+	}
 }
 
 // TODO move to ics23
@@ -221,3 +358,34 @@ fn calculate_non_existence_root<H: HostFunctionsProvider>(
 //         RawMerkleProof { proof: value.proof }
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::core::ics23_commitment::error::ErrorDetail;
+
+	#[test]
+	fn test_non_utf8_merkle_path() {
+		let prefix = CommitmentPrefix::try_from(vec![0xff, b'i', b'b', b'c']).unwrap();
+		let key = vec![b'k', 0xc3, 0x28];
+		let path = apply_prefix(&prefix, vec![key.clone()]);
+		assert_eq!(path.key_path, vec![vec![0xff, b'i', b'b', b'c'], key.clone()]);
+
+		// keys that are not valid UTF-8 have no protobuf encoding
+		let non_utf8_key =
+			|path: MerklePath| match RawMerklePath::try_from(path).unwrap_err().detail() {
+				ErrorDetail::NonUtf8MerkleKey(e) => e.key.clone(),
+				e => panic!("unexpected error {:?}", e),
+			};
+		assert_eq!(non_utf8_key(path), vec![0xff, b'i', b'b', b'c']);
+		assert_eq!(
+			non_utf8_key(MerklePath::new(vec![b"ibc".to_vec(), key])),
+			vec![b'k', 0xc3, 0x28]
+		);
+
+		let raw = RawMerklePath { key_path: vec!["ibc".to_string(), "key".to_string()] };
+		let path = MerklePath::from(raw.clone());
+		assert_eq!(path.key_path, vec![b"ibc".to_vec(), b"key".to_vec()]);
+		assert_eq!(RawMerklePath::try_from(path).unwrap(), raw);
+	}
+}
@@ -3,6 +3,10 @@
 
 pub mod commitment;
 pub mod error;
+pub mod ethereum;
 pub mod merkle;
 pub mod mock;
 pub mod specs;
+pub mod store;
+pub mod substrate;
+pub mod verify;
@@ -1,6 +1,9 @@
-use crate::prelude::*;
+use crate::{core::ics23_commitment::error::Error, prelude::*};
 use ibc_proto::ics23::{InnerSpec as IbcInnerSpec, LeafOp as IbcLeafOp, ProofSpec as IbcProofSpec};
-use ics23::{InnerSpec as Ics23InnerSpec, LeafOp as Ics23LeafOp, ProofSpec as Ics23ProofSpec};
+use ics23::{
+	HashOp, InnerSpec as Ics23InnerSpec, LeafOp as Ics23LeafOp, LengthOp,
+	ProofSpec as Ics23ProofSpec,
+};
 use serde::{Deserialize, Serialize};
 
 /// An array of proof specifications.
@@ -23,9 +26,112 @@ impl ProofSpecs {
 		.into()
 	}
 
+	/// Returns the specification of the proofs of a sparse Merkle tree of depth 256, keyed by the
+	/// hash of the keys, as for a single store using `ics23::smt_spec`.
+	pub fn smt() -> Self {
+		vec![Ics23ProofSpec {
+			leaf_spec: Some(Ics23LeafOp {
+				hash: HashOp::Sha256 as i32,
+				prehash_key: HashOp::Sha256 as i32,
+				prehash_value: HashOp::Sha256 as i32,
+				length: LengthOp::NoPrefix as i32,
+				prefix: vec![0],
+			}),
+			inner_spec: Some(Ics23InnerSpec {
+				child_order: vec![0, 1],
+				child_size: 32,
+				min_prefix_length: 1,
+				max_prefix_length: 1,
+				empty_child: vec![0; 32],
+				hash: HashOp::Sha256 as i32,
+			}),
+			max_depth: 256,
+			min_depth: 0,
+		}]
+		.into()
+	}
+
+	/// Returns the specification of the proofs of a Jellyfish Merkle tree, as used by Penumbra
+	/// for its single store.
+	pub fn jmt() -> Self {
+		const LEAF_PREFIX: &[u8] = b"JMT::LeafNode";
+		const INTERNAL_PREFIX: &[u8] = b"JMT::IntrnalNode";
+		const PLACEHOLDER_HASH: &[u8] = b"SPARSE_MERKLE_PLACEHOLDER_HASH__";
+		vec![Ics23ProofSpec {
+			leaf_spec: Some(Ics23LeafOp {
+				hash: HashOp::Sha256 as i32,
+				prehash_key: HashOp::Sha256 as i32,
+				prehash_value: HashOp::Sha256 as i32,
+				length: LengthOp::NoPrefix as i32,
+				prefix: LEAF_PREFIX.to_vec(),
+			}),
+			inner_spec: Some(Ics23InnerSpec {
+				child_order: vec![0, 1],
+				child_size: 32,
+				min_prefix_length: INTERNAL_PREFIX.len() as i32,
+				max_prefix_length: INTERNAL_PREFIX.len() as i32,
+				empty_child: PLACEHOLDER_HASH.to_vec(),
+				hash: HashOp::Sha256 as i32,
+			}),
+			max_depth: 64,
+			min_depth: 0,
+		}]
+		.into()
+	}
+
 	pub fn is_empty(&self) -> bool {
 		self.0.is_empty()
 	}
+
+	/// Checks that the specs can be used to verify proofs: there must be at least one, each must
+	/// describe hashed leaves and inner nodes, and its depth limits must be consistent.
+	pub fn validate(&self) -> Result<(), Error> {
+		if self.is_empty() {
+			return Err(Error::empty_proof_specs())
+		}
+		for (index, spec) in self.0.iter().enumerate() {
+			validate_spec(&spec.clone().into())
+				.map_err(|reason| Error::invalid_proof_spec(index, reason.to_string()))?;
+		}
+		Ok(())
+	}
+}
+
+fn validate_spec(spec: &Ics23ProofSpec) -> Result<(), &'static str> {
+	let leaf_spec = spec.leaf_spec.as_ref().ok_or("missing leaf spec")?;
+	let inner_spec = spec.inner_spec.as_ref().ok_or("missing inner spec")?;
+	if leaf_spec.hash == HashOp::NoHash as i32 || inner_spec.hash == HashOp::NoHash as i32 {
+		return Err("nodes must be hashed")
+	}
+
+	if inner_spec.child_size <= 0 {
+		return Err("the size of children must be positive")
+	}
+	// the order of the children must be a permutation of their indices
+	let mut child_order = inner_spec.child_order.clone();
+	child_order.sort_unstable();
+	if child_order.len() < 2 || child_order.iter().zip(0..).any(|(child, index)| *child != index) {
+		return Err("invalid child order")
+	}
+	if inner_spec.min_prefix_length < 0 ||
+		inner_spec.min_prefix_length > inner_spec.max_prefix_length
+	{
+		return Err("invalid prefix length limits")
+	}
+	if !inner_spec.empty_child.is_empty() &&
+		inner_spec.empty_child.len() != inner_spec.child_size as usize
+	{
+		return Err("the empty child must have the size of children")
+	}
+
+	// a maximum depth of zero means that the depth is not limited
+	if spec.min_depth < 0 ||
+		spec.max_depth < 0 ||
+		(spec.max_depth > 0 && spec.min_depth > spec.max_depth)
+	{
+		return Err("invalid depth limits")
+	}
+	Ok(())
 }
 
 impl Default for ProofSpecs {
@@ -141,3 +247,33 @@ impl From<InnerSpec> for Ics23InnerSpec {
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_proof_specs_validation() {
+		for specs in [ProofSpecs::cosmos(), ProofSpecs::smt(), ProofSpecs::jmt()] {
+			specs.validate().unwrap();
+		}
+		assert!(ProofSpecs::from(Vec::<Ics23ProofSpec>::new()).validate().is_err());
+
+		let invalid = |change: fn(&mut Ics23ProofSpec)| {
+			let mut spec = ics23::tendermint_spec();
+			change(&mut spec);
+			ProofSpecs::from(vec![ics23::iavl_spec(), spec]).validate().unwrap_err()
+		};
+		let error = invalid(|spec| spec.leaf_spec = None);
+		let expected = Error::invalid_proof_spec(1, "missing leaf spec".to_string());
+		assert_eq!(error.detail(), expected.detail());
+		invalid(|spec| spec.inner_spec.as_mut().unwrap().hash = HashOp::NoHash as i32);
+		invalid(|spec| spec.inner_spec.as_mut().unwrap().child_order = vec![0, 0]);
+		invalid(|spec| spec.inner_spec.as_mut().unwrap().max_prefix_length = -1);
+		invalid(|spec| spec.inner_spec.as_mut().unwrap().empty_child = vec![0; 20]);
+		invalid(|spec| {
+			spec.min_depth = 4;
+			spec.max_depth = 2;
+		});
+	}
+}
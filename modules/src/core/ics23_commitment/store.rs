@@ -0,0 +1,390 @@
+//! A reference commitment store, producing ICS-23 proofs of the IBC state it holds.
+//!
+//! Values are kept in binary Merkle trees built like Tendermint's simple Merkle tree, so that
+//! their proofs follow the published [`ics23::tendermint_spec`] and are verified by
+//! [`MerkleProof`] like the proofs of any other chain. The store is not meant to be efficient,
+//! roots and proofs are computed from scratch on every call.
+
+use core::marker::PhantomData;
+
+use ics23::{
+	batch_entry, calculate_existence_root, commitment_proof::Proof, compress, BatchEntry,
+	BatchProof, CommitmentProof, ExistenceProof, HashOp, HostFunctionsProvider, InnerOp, LeafOp,
+	NonExistenceProof,
+};
+
+use crate::{
+	core::{
+		ics23_commitment::{
+			commitment::{CommitmentPrefix, CommitmentProofBytes, CommitmentRoot},
+			error::Error,
+			merkle::{apply_prefix, MerklePath, MerkleProof},
+			specs::ProofSpecs,
+		},
+		ics24_host::Path,
+	},
+	prelude::*,
+};
+
+/// The byte prefixing the preimage of inner nodes, leaves being prefixed with `0`.
+const INNER_NODE_PREFIX: u8 = 1;
+
+/// A binary Merkle tree over the sorted keys of a map. The leaves are split at the largest power
+/// of two smaller than their number, as in Tendermint's `crypto/merkle`.
+#[derive(Clone, Debug)]
+pub struct MerkleTree<H> {
+	entries: BTreeMap<Vec<u8>, Vec<u8>>,
+	_phantom: PhantomData<H>,
+}
+
+impl<H> Default for MerkleTree<H> {
+	fn default() -> Self {
+		Self { entries: BTreeMap::new(), _phantom: PhantomData }
+	}
+}
+
+impl<H: HostFunctionsProvider> MerkleTree<H> {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn get(&self, key: &[u8]) -> Option<&[u8]> {
+		self.entries.get(key).map(Vec::as_slice)
+	}
+
+	/// Stores `value` under `key`. Neither can be empty, as ICS-23 cannot prove empty ones.
+	pub fn insert(&mut self, key: Vec<u8>, value: Vec<u8>) -> Result<(), Error> {
+		if key.is_empty() || value.is_empty() {
+			return Err(Error::empty_store_entry())
+		}
+		self.entries.insert(key, value);
+		Ok(())
+	}
+
+	pub fn remove(&mut self, key: &[u8]) -> Option<Vec<u8>> {
+		self.entries.remove(key)
+	}
+
+	/// Returns the root of the tree, the hash of the empty string if the tree is empty.
+	pub fn root(&self) -> Vec<u8> {
+		subtree_root::<H>(&self.leaf_hashes())
+	}
+
+	/// Returns a proof of the existence of `key` if it is stored, or of its absence otherwise.
+	pub fn prove(&self, key: &[u8]) -> Result<CommitmentProof, Error> {
+		let keys: Vec<&Vec<u8>> = self.entries.keys().collect();
+		let hashes = self.leaf_hashes();
+		let proof = match keys.binary_search(&&key.to_vec()) {
+			Ok(index) => Proof::Exist(self.existence_proof(&hashes, index)),
+			Err(_) if keys.is_empty() => return Err(Error::empty_store()),
+			// the neighbours of an absent key prove that there is no room for it in the tree
+			Err(index) => Proof::Nonexist(NonExistenceProof {
+				key: key.to_vec(),
+				left: index.checked_sub(1).map(|left| self.existence_proof(&hashes, left)),
+				right: (index < keys.len()).then(|| self.existence_proof(&hashes, index)),
+			}),
+		};
+		Ok(CommitmentProof { proof: Some(proof) })
+	}
+
+	fn leaf_hashes(&self) -> Vec<Vec<u8>> {
+		self.entries
+			.iter()
+			.map(|(key, value)| {
+				let leaf = ExistenceProof {
+					key: key.clone(),
+					value: value.clone(),
+					leaf: Some(leaf_op()),
+					path: vec![],
+				};
+				calculate_existence_root::<H>(&leaf).expect("stored entries are not empty")
+			})
+			.collect()
+	}
+
+	fn existence_proof(&self, hashes: &[Vec<u8>], index: usize) -> ExistenceProof {
+		let (key, value) = self.entries.iter().nth(index).expect("index is within the tree");
+		ExistenceProof {
+			key: key.clone(),
+			value: value.clone(),
+			leaf: Some(leaf_op()),
+			path: inner_path::<H>(hashes, index),
+		}
+	}
+}
+
+fn leaf_op() -> LeafOp {
+	ics23::tendermint_spec().leaf_spec.expect("the tendermint spec has a leaf spec")
+}
+
+/// The number of leaves of the left subtree of a tree with `len` leaves, `len` being at least 2.
+fn split_point(len: usize) -> usize {
+	len.next_power_of_two() / 2
+}
+
+fn inner_hash<H: HostFunctionsProvider>(left: &[u8], right: &[u8]) -> Vec<u8> {
+	let mut preimage = vec![INNER_NODE_PREFIX];
+	preimage.extend_from_slice(left);
+	preimage.extend_from_slice(right);
+	H::sha2_256(&preimage).to_vec()
+}
+
+fn subtree_root<H: HostFunctionsProvider>(hashes: &[Vec<u8>]) -> Vec<u8> {
+	match hashes.len() {
+		0 => H::sha2_256(&[]).to_vec(),
+		1 => hashes[0].clone(),
+		len => {
+			let (left, right) = hashes.split_at(split_point(len));
+			inner_hash::<H>(&subtree_root::<H>(left), &subtree_root::<H>(right))
+		},
+	}
+}
+
+/// Returns the inner nodes from the leaf at `index` up to the root, as ICS-23 operations.
+fn inner_path<H: HostFunctionsProvider>(hashes: &[Vec<u8>], index: usize) -> Vec<InnerOp> {
+	if hashes.len() <= 1 {
+		return vec![]
+	}
+
+	let split = split_point(hashes.len());
+	let (left, right) = hashes.split_at(split);
+	let (mut path, op) = if index < split {
+		let op = InnerOp {
+			hash: HashOp::Sha256 as i32,
+			prefix: vec![INNER_NODE_PREFIX],
+			suffix: subtree_root::<H>(right),
+		};
+		(inner_path::<H>(left, index), op)
+	} else {
+		let mut prefix = vec![INNER_NODE_PREFIX];
+		prefix.extend(subtree_root::<H>(left));
+		let op = InnerOp { hash: HashOp::Sha256 as i32, prefix, suffix: vec![] };
+		(inner_path::<H>(right, index - split), op)
+	};
+	path.push(op);
+	path
+}
+
+/// A store committing to values at ICS-24 [`Path`]s, laid out like the IBC store of a Cosmos SDK
+/// chain: the values are kept in a tree whose root is stored under the commitment prefix in the
+/// root tree. Its proofs are [`MerkleProof`]s verified with [`CommitmentStore::proof_specs`].
+#[derive(Clone, Debug)]
+pub struct CommitmentStore<H> {
+	prefix: CommitmentPrefix,
+	store: MerkleTree<H>,
+}
+
+impl<H: HostFunctionsProvider> CommitmentStore<H> {
+	pub fn new(prefix: CommitmentPrefix) -> Result<Self, Error> {
+		if prefix.as_bytes().is_empty() {
+			return Err(Error::empty_commitment_prefix())
+		}
+		Ok(Self { prefix, store: MerkleTree::new() })
+	}
+
+	/// The specs of the proofs of the store, from the IBC store to the root tree.
+	pub fn proof_specs() -> ProofSpecs {
+		vec![ics23::tendermint_spec(), ics23::tendermint_spec()].into()
+	}
+
+	pub fn prefix(&self) -> &CommitmentPrefix {
+		&self.prefix
+	}
+
+	pub fn get(&self, path: &Path) -> Option<&[u8]> {
+		self.store.get(path.to_string().as_bytes())
+	}
+
+	pub fn set(&mut self, path: impl Into<Path>, value: Vec<u8>) -> Result<(), Error> {
+		self.store.insert(path.into().to_string().into_bytes(), value)
+	}
+
+	pub fn delete(&mut self, path: &Path) -> Option<Vec<u8>> {
+		self.store.remove(path.to_string().as_bytes())
+	}
+
+	/// Returns the keys a proof of `path` is verified against.
+	pub fn merkle_path(&self, path: &Path) -> MerklePath {
+		apply_prefix(&self.prefix, vec![path.to_string().into_bytes()])
+	}
+
+	pub fn root(&self) -> CommitmentRoot {
+		self.root_tree().root().into()
+	}
+
+	/// Returns a proof of the value stored at `path`, or of its absence if there is none.
+	pub fn prove(&self, path: &Path) -> Result<CommitmentProofBytes, Error> {
+		let MerklePath { key_path } = self.merkle_path(path);
+		let store_proof = self.store.prove(&key_path[1])?;
+		let root_proof = self.root_tree().prove(&key_path[0])?;
+		let proof = MerkleProof::<H>::new(vec![store_proof, root_proof]);
+		Ok(CommitmentProofBytes::try_from(proof).expect("merkle proofs are never empty"))
+	}
+
+	/// Returns a single proof of the values stored at each of `paths`, or of their absence, whose
+	/// proof of the IBC store is a compressed batch proof.
+	pub fn prove_batch(&self, paths: &[Path]) -> Result<CommitmentProofBytes, Error> {
+		let entries = paths
+			.iter()
+			.map(|path| {
+				let MerklePath { key_path } = self.merkle_path(path);
+				let proof = match self.store.prove(&key_path[1])?.proof {
+					Some(Proof::Exist(proof)) => batch_entry::Proof::Exist(proof),
+					Some(Proof::Nonexist(proof)) => batch_entry::Proof::Nonexist(proof),
+					_ => unreachable!("the tree only proves single keys"),
+				};
+				Ok(BatchEntry { proof: Some(proof) })
+			})
+			.collect::<Result<_, Error>>()?;
+		let batch = CommitmentProof { proof: Some(Proof::Batch(BatchProof { entries })) };
+		let store_proof = compress(&batch).map_err(|_| Error::invalid_merkle_proof())?;
+		let root_proof = self.root_tree().prove(self.prefix.as_bytes())?;
+		let proof = MerkleProof::<H>::new(vec![store_proof, root_proof]);
+		Ok(CommitmentProofBytes::try_from(proof).expect("merkle proofs are never empty"))
+	}
+
+	fn root_tree(&self) -> MerkleTree<H> {
+		let mut root_tree = MerkleTree::new();
+		root_tree
+			.insert(self.prefix.as_bytes().to_vec(), self.store.root())
+			.expect("the prefix and the root of the store are not empty");
+		root_tree
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		core::ics24_host::{
+			identifier::{ChannelId, ConnectionId, PortId},
+			path::{ClientTypePath, CommitmentsPath, ConnectionsPath},
+		},
+		gas::InfiniteGasMeter,
+		test_utils::Crypto,
+	};
+	use ibc_proto::ibc::core::commitment::v1::{MerkleProof as RawMerkleProof, MerkleRoot};
+
+	fn decode(proof: CommitmentProofBytes) -> MerkleProof<Crypto> {
+		RawMerkleProof::try_from(proof).unwrap().into()
+	}
+
+	#[test]
+	fn test_tree_proofs() {
+		let spec = ics23::tendermint_spec();
+		// trees of all shapes, up to a depth of 3
+		for len in 1..=7u8 {
+			let mut tree = MerkleTree::<Crypto>::new();
+			for i in 0..len {
+				tree.insert(vec![2 * i + 1], vec![i]).unwrap();
+			}
+			let root = tree.root();
+
+			for i in 0..len {
+				let key = [2 * i + 1];
+				let proof = tree.prove(&key).unwrap();
+				assert!(ics23::verify_membership::<Crypto>(&proof, &spec, &root, &key, &[i]));
+				assert!(!ics23::verify_membership::<Crypto>(&proof, &spec, &root, &key, &[0xff]));
+			}
+			// before, between and after the stored keys
+			for key in (0..=len).map(|i| 2 * i) {
+				let proof = tree.prove(&[key]).unwrap();
+				assert!(ics23::verify_non_membership::<Crypto>(&proof, &spec, &root, &[key]));
+			}
+		}
+
+		assert!(MerkleTree::<Crypto>::new().prove(b"key").is_err());
+		assert!(MerkleTree::<Crypto>::new().insert(b"key".to_vec(), vec![]).is_err());
+	}
+
+	#[test]
+	fn test_commitment_store_proofs() {
+		let prefix = CommitmentPrefix::try_from(b"ibc".to_vec()).unwrap();
+		let mut store = CommitmentStore::<Crypto>::new(prefix).unwrap();
+		let specs = CommitmentStore::<Crypto>::proof_specs();
+		let gas = &InfiniteGasMeter;
+
+		let stored: Path = ConnectionsPath(ConnectionId::new(0)).into();
+		let absent: Path = ConnectionsPath(ConnectionId::new(1)).into();
+		store.set(stored.clone(), b"connection".to_vec()).unwrap();
+		store
+			.set(ClientTypePath("07-tendermint-0".parse().unwrap()), b"07-tendermint".to_vec())
+			.unwrap();
+		let root = MerkleRoot::from(store.root());
+
+		let proof = decode(store.prove(&stored).unwrap());
+		let keys = store.merkle_path(&stored);
+		proof
+			.verify_membership(&specs, root.clone(), keys.clone(), b"connection".to_vec(), 0, gas)
+			.unwrap();
+		assert!(proof
+			.verify_membership(&specs, root.clone(), keys, b"other".to_vec(), 0, gas)
+			.is_err());
+
+		let proof = decode(store.prove(&absent).unwrap());
+		proof
+			.verify_non_membership(&specs, root.clone(), store.merkle_path(&absent), gas)
+			.unwrap();
+		assert!(proof
+			.verify_non_membership(&specs, root, store.merkle_path(&stored), gas)
+			.is_err());
+
+		// proofs are invalidated by any change of the store
+		let proof = decode(store.prove(&stored).unwrap());
+		store.delete(&stored);
+		let keys = store.merkle_path(&stored);
+		let root = MerkleRoot::from(store.root());
+		assert!(proof
+			.verify_membership(&specs, root, keys, b"connection".to_vec(), 0, gas)
+			.is_err());
+	}
+
+	#[test]
+	fn test_batch_proofs() {
+		let prefix = CommitmentPrefix::try_from(b"ibc".to_vec()).unwrap();
+		let mut store = CommitmentStore::<Crypto>::new(prefix).unwrap();
+		let specs = CommitmentStore::<Crypto>::proof_specs();
+		let gas = &InfiniteGasMeter;
+		let path = |sequence: u64| -> Path {
+			let (port_id, channel_id) = (PortId::transfer(), ChannelId::new(0));
+			CommitmentsPath { port_id, channel_id, sequence: sequence.into() }.into()
+		};
+		for sequence in 1..=6 {
+			store.set(path(sequence), vec![sequence as u8]).unwrap();
+		}
+		let root = MerkleRoot::from(store.root());
+
+		let stored = [path(2), path(3), path(5)];
+		let proof = decode(store.prove_batch(&stored).unwrap());
+		assert!(matches!(proof.proofs[0].proof, Some(Proof::Compressed(_))));
+		let items = |values: [u8; 3]| {
+			stored
+				.iter()
+				.map(|path| store.merkle_path(path))
+				.zip(values.map(|v| vec![v]))
+				.collect()
+		};
+		proof
+			.verify_batch_membership(&specs, root.clone(), items([2, 3, 5]), gas)
+			.unwrap();
+		assert!(proof
+			.verify_batch_membership(&specs, root.clone(), items([2, 3, 4]), gas)
+			.is_err());
+
+		let absent = [path(0), path(7)];
+		let keys = |paths: &[Path]| paths.iter().map(|path| store.merkle_path(path)).collect();
+		let proof = decode(store.prove_batch(&absent).unwrap());
+		proof
+			.verify_batch_non_membership(&specs, root.clone(), keys(&absent), gas)
+			.unwrap();
+		assert!(proof
+			.verify_batch_non_membership(&specs, root.clone(), keys(&stored), gas)
+			.is_err());
+		assert!(proof.verify_batch_non_membership(&specs, root.clone(), vec![], gas).is_err());
+
+		// the keys must share the path from the IBC store to the root
+		let mut other = keys(&absent);
+		other[1].key_path[0] = b"other".to_vec();
+		assert!(proof.verify_batch_non_membership(&specs, root, other, gas).is_err());
+	}
+}
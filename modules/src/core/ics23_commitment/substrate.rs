@@ -0,0 +1,132 @@
+//! Commitment proofs of Substrate chains, which commit to their state with the root of their
+//! storage trie rather than with ICS-23 Merkle trees.
+//!
+//! A proof is the SCALE encoding of the trie nodes on the way to the proven key, the key of a
+//! [`Path`] being its commitment prefix followed by the path.
+//!
+//! [`Path`]: crate::core::ics24_host::Path
+
+use codec::{Decode, Encode};
+
+use crate::{
+	core::ics23_commitment::{
+		commitment::{CommitmentPrefix, CommitmentProofBytes, CommitmentRoot},
+		error::Error,
+	},
+	gas::GasMeter,
+	prelude::*,
+};
+
+/// Host functions verifying proofs of the storage trie of a Substrate chain, e.g. with
+/// `sp_trie::verify_trie_proof` and the trie layout of that chain.
+pub trait TrieHostFunctions {
+	/// Verifies that `value` is stored under `key` in the trie of root `root`.
+	fn verify_membership_trie_proof(
+		root: &[u8; 32],
+		proof: &[Vec<u8>],
+		key: &[u8],
+		value: &[u8],
+	) -> Result<(), Error>;
+
+	/// Verifies that nothing is stored under `key` in the trie of root `root`.
+	fn verify_non_membership_trie_proof(
+		root: &[u8; 32],
+		proof: &[Vec<u8>],
+		key: &[u8],
+	) -> Result<(), Error>;
+}
+
+/// The [`TrieHostFunctions`] of hosts that verify no trie proofs, because none of their clients
+/// tracks a Substrate chain. Every proof is rejected.
+#[derive(Clone, Copy, Debug)]
+pub enum NoTrieHostFunctions {}
+
+impl TrieHostFunctions for NoTrieHostFunctions {
+	fn verify_membership_trie_proof(
+		_root: &[u8; 32],
+		_proof: &[Vec<u8>],
+		_key: &[u8],
+		_value: &[u8],
+	) -> Result<(), Error> {
+		Err(Error::unsupported_proof_format("Substrate trie".to_string()))
+	}
+
+	fn verify_non_membership_trie_proof(
+		_root: &[u8; 32],
+		_proof: &[Vec<u8>],
+		_key: &[u8],
+	) -> Result<(), Error> {
+		Err(Error::unsupported_proof_format("Substrate trie".to_string()))
+	}
+}
+
+/// The nodes of a storage trie proving the value of a key, or its absence.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct TrieProof {
+	nodes: Vec<Vec<u8>>,
+}
+
+impl TrieProof {
+	pub fn new(nodes: Vec<Vec<u8>>) -> Self {
+		Self { nodes }
+	}
+
+	pub fn nodes(&self) -> &[Vec<u8>] {
+		&self.nodes
+	}
+}
+
+impl TryFrom<&CommitmentProofBytes> for TrieProof {
+	type Error = Error;
+
+	fn try_from(proof: &CommitmentProofBytes) -> Result<Self, Self::Error> {
+		Self::decode(&mut proof.as_bytes())
+			.map_err(|e| Error::trie_proof_decoding_failed(e.to_string()))
+	}
+}
+
+impl From<TrieProof> for CommitmentProofBytes {
+	fn from(proof: TrieProof) -> Self {
+		proof.encode().try_into().expect("SCALE encodings are never empty")
+	}
+}
+
+/// Returns the trie key of `key`, stored in the IBC store of prefix `prefix`.
+pub fn trie_key(prefix: &CommitmentPrefix, key: &[u8]) -> Vec<u8> {
+	let mut trie_key = prefix.as_bytes().to_vec();
+	trie_key.extend_from_slice(key);
+	trie_key
+}
+
+fn trie_root(root: &CommitmentRoot) -> Result<[u8; 32], Error> {
+	root.as_bytes()
+		.try_into()
+		.map_err(|_| Error::invalid_trie_root(root.as_bytes().len()))
+}
+
+/// Verifies a proof that `value` is stored under `key` in the trie of root `root`, charging its
+/// nodes to `gas`.
+pub fn verify_membership<T: TrieHostFunctions>(
+	proof: &CommitmentProofBytes,
+	root: &CommitmentRoot,
+	key: &[u8],
+	value: &[u8],
+	gas: &dyn GasMeter,
+) -> Result<(), Error> {
+	let proof = TrieProof::try_from(proof)?;
+	gas.charge_trie_nodes(proof.nodes().len()).map_err(Error::gas)?;
+	T::verify_membership_trie_proof(&trie_root(root)?, proof.nodes(), key, value)
+}
+
+/// Verifies a proof that nothing is stored under `key` in the trie of root `root`, charging its
+/// nodes to `gas`.
+pub fn verify_non_membership<T: TrieHostFunctions>(
+	proof: &CommitmentProofBytes,
+	root: &CommitmentRoot,
+	key: &[u8],
+	gas: &dyn GasMeter,
+) -> Result<(), Error> {
+	let proof = TrieProof::try_from(proof)?;
+	gas.charge_trie_nodes(proof.nodes().len()).map_err(Error::gas)?;
+	T::verify_non_membership_trie_proof(&trie_root(root)?, proof.nodes(), key)
+}
@@ -0,0 +1,318 @@
+//! Verification of commitment proofs in any of the formats chains may use, selected by the format
+//! declared by the client of the chain, see [`ClientState::proof_format`].
+//!
+//! [`ClientState::proof_format`]: crate::core::ics02_client::client_state::ClientState
+
+use ibc_proto::ibc::core::commitment::v1::MerkleProof as RawMerkleProof;
+use ics23::HostFunctionsProvider;
+
+use crate::{
+	core::{
+		ics23_commitment::{
+			commitment::{CommitmentPrefix, CommitmentProofBytes, CommitmentRoot},
+			error::Error,
+			ethereum::{self, KeccakHostFunctions, StorageLayout},
+			merkle::{apply_prefix, MerklePath, MerkleProof},
+			specs::ProofSpecs,
+			substrate::{self, TrieHostFunctions},
+		},
+		ics24_host::Path,
+	},
+	gas::GasMeter,
+	prelude::*,
+};
+
+/// The format of the commitment proofs of a chain.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProofFormat {
+	/// ICS-23 [`MerkleProof`]s, verified against the given [`ProofSpecs`].
+	Ics23,
+	/// Proofs of the storage trie of a Substrate chain, see [`substrate`].
+	SubstrateTrie,
+	/// Proofs of the storage of the IBC contract at `contract` on an EVM chain, see [`ethereum`].
+	Evm { contract: [u8; 20], layout: StorageLayout },
+}
+
+impl Default for ProofFormat {
+	fn default() -> Self {
+		Self::Ics23
+	}
+}
+
+impl ProofFormat {
+	/// Returns the keys under which chains using this format store the value at `path`, in their
+	/// IBC store of prefix `prefix`, see [`Self::merkle_path_of_key`].
+	pub fn merkle_path(&self, prefix: &CommitmentPrefix, path: &Path) -> MerklePath {
+		self.merkle_path_of_key(prefix, path.to_string().as_bytes())
+	}
+
+	/// Returns the keys under which chains using this format store the value of `key` in their IBC
+	/// store of prefix `prefix`: the prefix, then the key, for the nested stores of ICS-23 chains,
+	/// their concatenation for the single storage trie of Substrate chains, and the key alone for
+	/// EVM chains, whose IBC contract hashes it into a storage slot.
+	pub fn merkle_path_of_key(&self, prefix: &CommitmentPrefix, key: &[u8]) -> MerklePath {
+		match self {
+			ProofFormat::Ics23 => apply_prefix(prefix, vec![key.to_vec()]),
+			ProofFormat::SubstrateTrie => MerklePath::new(vec![substrate::trie_key(prefix, key)]),
+			ProofFormat::Evm { .. } => MerklePath::new(vec![key.to_vec()]),
+		}
+	}
+}
+
+/// Verifies a proof that `value` is stored under `keys`, as returned by
+/// [`ProofFormat::merkle_path`], in the state committed to by `root`. `specs` are ignored by the
+/// formats that have none. The proofs checked are charged to `gas`.
+///
+/// ICS-23 proofs are hashed with `H`, Substrate trie proofs verified with `T`, which hosts
+/// verifying none set to [`substrate::NoTrieHostFunctions`], and EVM proofs hashed with `K`.
+pub fn verify_membership<H, T, K>(
+	format: ProofFormat,
+	specs: &ProofSpecs,
+	proof: &CommitmentProofBytes,
+	root: &CommitmentRoot,
+	keys: MerklePath,
+	value: Vec<u8>,
+	gas: &dyn GasMeter,
+) -> Result<(), Error>
+where
+	H: HostFunctionsProvider,
+	T: TrieHostFunctions,
+	K: KeccakHostFunctions,
+{
+	match format {
+		ProofFormat::Ics23 => {
+			let proof: MerkleProof<H> = RawMerkleProof::try_from(proof.clone())?.into();
+			proof.verify_membership(specs, root.clone().into(), keys, value, 0, gas)
+		},
+		ProofFormat::SubstrateTrie =>
+			substrate::verify_membership::<T>(proof, root, single_key(&keys)?, &value, gas),
+		ProofFormat::Evm { contract, layout } => {
+			let key = single_key(&keys)?;
+			ethereum::verify_membership::<K>(&layout, root, &contract, proof, key, &value, gas)
+		},
+	}
+}
+
+/// Verifies a proof that nothing is stored under `keys`, as returned by
+/// [`ProofFormat::merkle_path`], in the state committed to by `root`. `specs` are ignored by the
+/// formats that have none. The proofs checked are charged to `gas`.
+///
+/// The host functions are those of [`verify_membership`].
+pub fn verify_non_membership<H, T, K>(
+	format: ProofFormat,
+	specs: &ProofSpecs,
+	proof: &CommitmentProofBytes,
+	root: &CommitmentRoot,
+	keys: MerklePath,
+	gas: &dyn GasMeter,
+) -> Result<(), Error>
+where
+	H: HostFunctionsProvider,
+	T: TrieHostFunctions,
+	K: KeccakHostFunctions,
+{
+	match format {
+		ProofFormat::Ics23 => {
+			let proof: MerkleProof<H> = RawMerkleProof::try_from(proof.clone())?.into();
+			proof.verify_non_membership(specs, root.clone().into(), keys, gas)
+		},
+		ProofFormat::SubstrateTrie =>
+			substrate::verify_non_membership::<T>(proof, root, single_key(&keys)?, gas),
+		ProofFormat::Evm { contract, layout } => {
+			let key = single_key(&keys)?;
+			ethereum::verify_non_membership::<K>(&layout, root, &contract, proof, key, gas)
+		},
+	}
+}
+
+/// Returns the key of a value stored outside of any nested store.
+fn single_key(keys: &MerklePath) -> Result<&[u8], Error> {
+	match keys.key_path.as_slice() {
+		[key] => Ok(key),
+		_ => Err(Error::number_of_keys_mismatch()),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		core::{
+			ics23_commitment::{
+				error::ErrorDetail,
+				store::CommitmentStore,
+				substrate::{NoTrieHostFunctions, TrieProof},
+			},
+			ics24_host::{identifier::ConnectionId, path::ConnectionsPath},
+		},
+		gas::{BasicGasMeter, InfiniteGasMeter},
+		test_utils::Crypto,
+	};
+	use sp_core::Blake2Hasher;
+	use sp_trie::{generate_trie_proof, LayoutV0, MemoryDB, TrieDBMut, TrieMut};
+
+	/// Returns the root of a storage trie of the layout of Substrate chains holding `entries`, and
+	/// a proof of the value of `key` in it.
+	fn trie_proof(
+		entries: &[(Vec<u8>, Vec<u8>)],
+		key: &[u8],
+	) -> (CommitmentRoot, CommitmentProofBytes) {
+		let mut db = MemoryDB::<Blake2Hasher>::default();
+		let mut root = Default::default();
+		{
+			let mut trie = TrieDBMut::<LayoutV0<Blake2Hasher>>::new(&mut db, &mut root);
+			for (key, value) in entries {
+				trie.insert(key, value).unwrap();
+			}
+		}
+		let nodes =
+			generate_trie_proof::<LayoutV0<Blake2Hasher>, _, _, _>(&db, root, &[key]).unwrap();
+		(CommitmentRoot::from(root.as_bytes().to_vec()), TrieProof::new(nodes).into())
+	}
+
+	#[test]
+	fn test_proof_format_selection() {
+		let prefix = CommitmentPrefix::try_from(b"ibc/".to_vec()).unwrap();
+		let stored: Path = ConnectionsPath(ConnectionId::new(0)).into();
+		let absent: Path = ConnectionsPath(ConnectionId::new(1)).into();
+		let specs = CommitmentStore::<Crypto>::proof_specs();
+		let gas = &InfiniteGasMeter;
+
+		let format = ProofFormat::Ics23;
+		let mut store = CommitmentStore::<Crypto>::new(prefix.clone()).unwrap();
+		store.set(stored.clone(), b"connection".to_vec()).unwrap();
+		let (root, proof) = (store.root(), store.prove(&stored).unwrap());
+		let keys = format.merkle_path(&prefix, &stored);
+		assert_eq!(keys, store.merkle_path(&stored));
+		verify_membership::<Crypto, Crypto, Crypto>(
+			format,
+			&specs,
+			&proof,
+			&root,
+			keys,
+			b"connection".to_vec(),
+			gas,
+		)
+		.unwrap();
+		let proof = store.prove(&absent).unwrap();
+		let keys = format.merkle_path(&prefix, &absent);
+		verify_non_membership::<Crypto, Crypto, Crypto>(format, &specs, &proof, &root, keys, gas)
+			.unwrap();
+
+		// keys are the prefix followed by the path
+		let format = ProofFormat::SubstrateTrie;
+		let keys = |path: &Path| format.merkle_path(&prefix, path);
+		let other: Path = ConnectionsPath(ConnectionId::new(2)).into();
+		let entries = [&stored, &other]
+			.map(|path| (keys(path).key_path.remove(0), path.to_string().into_bytes()));
+		let (root, proof) = trie_proof(&entries, &keys(&stored).key_path[0]);
+		let verify = |proof: &CommitmentProofBytes, path: &Path, value: &[u8]| {
+			verify_membership::<Crypto, Crypto, Crypto>(
+				format,
+				&specs,
+				proof,
+				&root,
+				keys(path),
+				value.to_vec(),
+				gas,
+			)
+		};
+		verify(&proof, &stored, stored.to_string().as_bytes()).unwrap();
+		assert!(verify(&proof, &stored, b"other").is_err());
+		assert!(verify(&proof, &absent, stored.to_string().as_bytes()).is_err());
+		assert!(verify_non_membership::<Crypto, Crypto, Crypto>(
+			format,
+			&specs,
+			&proof,
+			&root,
+			keys(&stored),
+			gas
+		)
+		.is_err());
+		let (_, proof) = trie_proof(&entries, &keys(&absent).key_path[0]);
+		verify_non_membership::<Crypto, Crypto, Crypto>(
+			format,
+			&specs,
+			&proof,
+			&root,
+			keys(&absent),
+			gas,
+		)
+		.unwrap();
+		assert!(verify(&proof, &absent, stored.to_string().as_bytes()).is_err());
+		// the trie is not nested in another store
+		let nested = ProofFormat::Ics23.merkle_path(&prefix, &absent);
+		assert!(verify_non_membership::<Crypto, Crypto, Crypto>(
+			format, &specs, &proof, &root, nested, gas
+		)
+		.is_err());
+
+		// roots of storage tries are hashes
+		let short_root = CommitmentRoot::from(root.as_bytes()[..20].to_vec());
+		assert!(verify_non_membership::<Crypto, Crypto, Crypto>(
+			format,
+			&specs,
+			&proof,
+			&short_root,
+			keys(&absent),
+			gas
+		)
+		.is_err());
+
+		// hosts that verify no trie proofs reject them
+		let err = verify_non_membership::<Crypto, NoTrieHostFunctions, Crypto>(
+			format,
+			&specs,
+			&proof,
+			&root,
+			keys(&absent),
+			gas,
+		)
+		.unwrap_err();
+		assert!(matches!(err.detail(), ErrorDetail::UnsupportedProofFormat(_)));
+	}
+
+	#[test]
+	fn test_trie_proofs_are_charged_per_node() {
+		let prefix = CommitmentPrefix::try_from(b"ibc/".to_vec()).unwrap();
+		let format = ProofFormat::SubstrateTrie;
+		let specs = ProofSpecs::default();
+		let path: Path = ConnectionsPath(ConnectionId::new(0)).into();
+		let gas_of_proof = |connections: u64| {
+			let entries: Vec<_> = (0..connections)
+				.map(|id| {
+					let path: Path = ConnectionsPath(ConnectionId::new(id)).into();
+					let key = format.merkle_path(&prefix, &path).key_path.remove(0);
+					(key, path.to_string().into_bytes())
+				})
+				.collect();
+			let (root, proof) = trie_proof(&entries, &entries[0].0);
+			let gas = BasicGasMeter::new(u64::MAX);
+			let keys = format.merkle_path(&prefix, &path);
+			let value = path.to_string().into_bytes();
+			verify_membership::<Crypto, Crypto, Crypto>(
+				format, &specs, &proof, &root, keys, value, &gas,
+			)
+			.unwrap();
+			gas.gas_consumed()
+		};
+		// the more values a trie holds, the more nodes prove one of them
+		assert!(gas_of_proof(1) < gas_of_proof(100));
+	}
+
+	#[test]
+	fn test_merkle_path_of_non_utf8_key() {
+		let prefix = CommitmentPrefix::try_from(vec![0xff, b'i', b'b', b'c']).unwrap();
+		let key = [b'k', 0xc3, 0x28];
+
+		let keys = ProofFormat::Ics23.merkle_path_of_key(&prefix, &key);
+		assert_eq!(keys.key_path, vec![prefix.as_bytes().to_vec(), key.to_vec()]);
+
+		let keys = ProofFormat::SubstrateTrie.merkle_path_of_key(&prefix, &key);
+		assert_eq!(keys.key_path, vec![vec![0xff, b'i', b'b', b'c', b'k', 0xc3, 0x28]]);
+
+		let format = ProofFormat::Evm { contract: [1; 20], layout: StorageLayout::default() };
+		let keys = format.merkle_path_of_key(&prefix, &key);
+		assert_eq!(keys.key_path, vec![key.to_vec()]);
+	}
+}
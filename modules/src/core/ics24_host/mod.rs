@@ -6,4 +6,5 @@ pub use path::{ClientUpgradePath, Path, IBC_QUERY_PATH, SDK_UPGRADE_QUERY_PATH};
 pub mod error;
 pub mod identifier;
 pub mod path;
+pub mod store;
 pub mod validate;
@@ -0,0 +1,1048 @@
+//! A generic implementation of the IBC contexts on top of a key-value store.
+//!
+//! A host implementing [`IbcHost`] only provides byte-level access to its store, through
+//! [`IbcStore`], and the information about its own chain. The client, connection, channel and
+//! port contexts are then implemented for it, storing the IBC state under the keys of
+//! [`Path`] with the same encodings as the ones proven to counterparty chains: protobuf for
+//! states and ends, big-endian integers for sequences and raw bytes for commitments.
+
+use core::{fmt::Debug, str::FromStr, time::Duration};
+
+use ibc_proto::{google::protobuf::Any, ibc::core::connection::v1::ClientPaths};
+use prost::Message;
+use tendermint_proto::Protobuf;
+
+use crate::{
+	core::{
+		ics02_client::{
+			client_state::ClientType,
+			context::{ClientKeeper, ClientReader, ClientTypes},
+			error::Error as Ics02Error,
+		},
+		ics03_connection::{
+			connection::ConnectionEnd,
+			context::{ConnectionKeeper, ConnectionReader},
+			error::Error as Ics03Error,
+		},
+		ics04_channel::{
+			channel::ChannelEnd,
+			commitment::{AcknowledgementCommitment, PacketCommitment},
+			context::{ChannelKeeper, ChannelReader},
+			error::Error as Ics04Error,
+			packet::{Packet, Receipt, Sequence},
+		},
+		ics05_port::{
+			capabilities::{CapabilityKeeper, CapabilityName, CapabilityReader},
+			context::{PortKeeper, PortReader},
+			error::Error as Ics05Error,
+		},
+		ics23_commitment::commitment::CommitmentPrefix,
+		ics24_host::{
+			identifier::{ChannelId, ClientId, ConnectionId, PortId},
+			path::{
+				AcksPath, ChannelEndsPath, ClientConnectionsPath, ClientConsensusStatePath,
+				ClientStatePath, ClientTypePath, CommitmentsPath, ConnectionsPath, PortsPath,
+				ReceiptsPath, SeqAcksPath, SeqRecvsPath, SeqSendsPath,
+			},
+			Path,
+		},
+		ics26_routing::context::ModuleId,
+	},
+	prelude::*,
+	timestamp::Timestamp,
+	Height,
+};
+
+/// Key of the number of clients created so far.
+const CLIENT_COUNTER_KEY: &[u8] = b"nextClientSequence";
+/// Key of the number of connections created so far.
+const CONNECTION_COUNTER_KEY: &[u8] = b"nextConnectionSequence";
+/// Key of the number of channels created so far.
+const CHANNEL_COUNTER_KEY: &[u8] = b"nextChannelSequence";
+/// Key of the index the next capability will be created with.
+const NEXT_CAPABILITY_INDEX_KEY: &[u8] = b"nextCapabilityIndex";
+/// Prefix of the keys mapping the names of capabilities to their index.
+const CAPABILITY_INDICES_PREFIX: &str = "capabilityIndices/";
+/// Prefix of the keys mapping the names of capabilities to the module they were issued to.
+const CAPABILITY_OWNERS_PREFIX: &str = "capabilityOwners/";
+/// Prefix of the keys indexing the channels of each connection.
+const CONNECTION_CHANNELS_PREFIX: &str = "connectionChannels/";
+/// Prefix of the keys mapping bound ports to their module, see [`PortsPath`].
+const PORTS_PREFIX: &str = "ports/";
+/// Prefix of the keys mapping claimed port prefixes to their owner.
+const PORT_PREFIXES_PREFIX: &str = "portPrefixes/";
+
+/// Byte-level access to the store the IBC state is kept in.
+pub trait IbcStore {
+	/// Returns the value stored under `key`, if any.
+	fn get(&self, key: &[u8]) -> Option<Vec<u8>>;
+
+	/// Stores `value` under `key`, replacing any previous value.
+	fn set(&mut self, key: Vec<u8>, value: Vec<u8>);
+
+	/// Removes the value stored under `key`, if any.
+	fn delete(&mut self, key: &[u8]);
+
+	/// Iterates over the entries whose key starts with `prefix`, in ascending key order.
+	fn iter_prefix<'a>(
+		&'a self,
+		prefix: &[u8],
+	) -> Box<dyn Iterator<Item = (Vec<u8>, Vec<u8>)> + 'a>;
+}
+
+/// A host chain keeping its IBC state in an [`IbcStore`]. Implementing this trait is enough for
+/// the host to implement [`ClientReader`], [`ClientKeeper`], [`ConnectionReader`],
+/// [`ConnectionKeeper`], [`ChannelReader`], [`ChannelKeeper`], [`CapabilityReader`],
+/// [`CapabilityKeeper`], [`PortReader`] and [`PortKeeper`].
+pub trait IbcHost: ClientTypes + IbcStore
+where
+	Self: Clone + Debug + Eq,
+{
+	/// The client type of the host, as seen by counterparty chains.
+	fn self_client_type(&self) -> String;
+
+	/// The current height of the host chain.
+	fn current_height(&self) -> Height;
+
+	/// The current timestamp of the host chain.
+	fn current_timestamp(&self) -> Timestamp;
+
+	/// The oldest height of the host chain for which a consensus state is still available.
+	fn oldest_height(&self) -> Height;
+
+	/// The consensus state of the host chain at `height`, see
+	/// [`ClientReader::host_consensus_state`].
+	fn self_consensus_state(
+		&self,
+		height: Height,
+		proof: Option<Vec<u8>>,
+	) -> Result<Self::AnyConsensusState, Ics02Error>;
+
+	/// Validates the client state a counterparty chain keeps for the host chain.
+	fn validate_self_client_state(
+		&self,
+		client_state: &Self::AnyClientState,
+	) -> Result<(), Ics02Error>;
+
+	/// The prefix under which the IBC store is committed to.
+	fn store_prefix(&self) -> CommitmentPrefix;
+
+	/// The hash function used for packet and acknowledgement commitments.
+	fn hash_value(&self, value: Vec<u8>) -> Vec<u8>;
+
+	/// The maximum expected time between two blocks of the host chain.
+	fn expected_block_time(&self) -> Duration;
+}
+
+fn store_key(path: impl Into<Path>) -> Vec<u8> {
+	path.into().into_bytes()
+}
+
+fn consensus_state_path(client_id: &ClientId, height: Height) -> ClientConsensusStatePath {
+	ClientConsensusStatePath {
+		client_id: client_id.clone(),
+		epoch: height.revision_number,
+		height: height.revision_height,
+	}
+}
+
+fn processed_time_key(client_id: &ClientId, height: Height) -> Vec<u8> {
+	format!("{}/processedTime", consensus_state_path(client_id, height)).into_bytes()
+}
+
+fn processed_height_key(client_id: &ClientId, height: Height) -> Vec<u8> {
+	format!("{}/processedHeight", consensus_state_path(client_id, height)).into_bytes()
+}
+
+/// Returns the prefix of the keys indexing the channels of the connection `connection_id`, each
+/// followed by the [`ChannelEndsPath`] of the channel.
+fn connection_channels_prefix(connection_id: &ConnectionId) -> String {
+	format!("{}{}/", CONNECTION_CHANNELS_PREFIX, connection_id)
+}
+
+fn capability_key(prefix: &str, name: &CapabilityName) -> Vec<u8> {
+	format!("{}{}", prefix, name).into_bytes()
+}
+
+fn decode_u64(bytes: &[u8]) -> Option<u64> {
+	bytes.try_into().ok().map(u64::from_be_bytes)
+}
+
+fn read_counter<T: IbcStore>(store: &T, key: &[u8]) -> u64 {
+	store.get(key).and_then(|bytes| decode_u64(&bytes)).unwrap_or_default()
+}
+
+fn increase_counter<T: IbcStore>(store: &mut T, key: &[u8]) {
+	let counter = read_counter(store, key) + 1;
+	store.set(key.to_vec(), counter.to_be_bytes().to_vec());
+}
+
+fn read_sequence<T: IbcStore>(store: &T, path: impl Into<Path>) -> Option<Sequence> {
+	store
+		.get(&store_key(path))
+		.and_then(|bytes| decode_u64(&bytes))
+		.map(Sequence::from)
+}
+
+fn write_sequence<T: IbcStore>(store: &mut T, path: impl Into<Path>, seq: Sequence) {
+	store.set(store_key(path), u64::from(seq).to_be_bytes().to_vec())
+}
+
+/// Returns the heights of the consensus states stored for `client_id`, in ascending order.
+fn consensus_heights<T: IbcStore>(store: &T, client_id: &ClientId) -> Vec<Height> {
+	let prefix = format!("clients/{}/consensusStates/", client_id);
+	let mut heights: Vec<Height> = store
+		.iter_prefix(prefix.as_bytes())
+		.filter_map(|(key, _)| {
+			// the processed time and height of a consensus state are stored under sub-keys
+			let suffix = String::from_utf8(key[prefix.len()..].to_vec()).ok()?;
+			let (epoch, height) = suffix.split_once('-')?;
+			Some(Height::new(epoch.parse().ok()?, height.parse().ok()?))
+		})
+		.collect();
+	heights.sort();
+	heights
+}
+
+impl<T: IbcHost> ClientReader for T
+where
+	T::AnyClientState: TryFrom<Any, Error = Ics02Error> + Into<Any>,
+	T::AnyConsensusState: TryFrom<Any, Error = Ics02Error> + Into<Any>,
+{
+	fn client_type(&self, client_id: &ClientId) -> Result<ClientType, Ics02Error> {
+		let bytes = self
+			.get(&store_key(ClientTypePath(client_id.clone())))
+			.ok_or_else(|| Ics02Error::client_not_found(client_id.clone()))?;
+		String::from_utf8(bytes).map_err(|e| Ics02Error::implementation_specific(e.to_string()))
+	}
+
+	fn client_state(&self, client_id: &ClientId) -> Result<T::AnyClientState, Ics02Error> {
+		let bytes = self
+			.get(&store_key(ClientStatePath(client_id.clone())))
+			.ok_or_else(|| Ics02Error::client_not_found(client_id.clone()))?;
+		Any::decode(&*bytes).map_err(Ics02Error::decode)?.try_into()
+	}
+
+	fn consensus_state(
+		&self,
+		client_id: &ClientId,
+		height: Height,
+	) -> Result<T::AnyConsensusState, Ics02Error> {
+		let bytes = self
+			.get(&store_key(consensus_state_path(client_id, height)))
+			.ok_or_else(|| Ics02Error::consensus_state_not_found(client_id.clone(), height))?;
+		Any::decode(&*bytes).map_err(Ics02Error::decode)?.try_into()
+	}
+
+	fn host_client_type(&self) -> String {
+		self.self_client_type()
+	}
+
+	fn next_consensus_state(
+		&self,
+		client_id: &ClientId,
+		height: Height,
+	) -> Result<Option<T::AnyConsensusState>, Ics02Error> {
+		self.client_type(client_id)?;
+		consensus_heights(self, client_id)
+			.into_iter()
+			.find(|h| *h > height)
+			.map(|h| self.consensus_state(client_id, h))
+			.transpose()
+	}
+
+	fn prev_consensus_state(
+		&self,
+		client_id: &ClientId,
+		height: Height,
+	) -> Result<Option<T::AnyConsensusState>, Ics02Error> {
+		self.client_type(client_id)?;
+		consensus_heights(self, client_id)
+			.into_iter()
+			.rev()
+			.find(|h| *h < height)
+			.map(|h| self.consensus_state(client_id, h))
+			.transpose()
+	}
+
+	fn host_height(&self) -> Height {
+		self.current_height()
+	}
+
+	fn host_timestamp(&self) -> Timestamp {
+		self.current_timestamp()
+	}
+
+	fn host_consensus_state(
+		&self,
+		height: Height,
+		proof: Option<Vec<u8>>,
+	) -> Result<T::AnyConsensusState, Ics02Error> {
+		self.self_consensus_state(height, proof)
+	}
+
+	fn client_counter(&self) -> Result<u64, Ics02Error> {
+		Ok(read_counter(self, CLIENT_COUNTER_KEY))
+	}
+}
+
+impl<T: IbcHost> ClientKeeper for T
+where
+	T::AnyClientState: TryFrom<Any, Error = Ics02Error> + Into<Any>,
+	T::AnyConsensusState: TryFrom<Any, Error = Ics02Error> + Into<Any>,
+{
+	fn store_client_type(
+		&mut self,
+		client_id: ClientId,
+		client_type: ClientType,
+	) -> Result<(), Ics02Error> {
+		self.set(store_key(ClientTypePath(client_id)), client_type.into_bytes());
+		Ok(())
+	}
+
+	fn store_client_state(
+		&mut self,
+		client_id: ClientId,
+		client_state: T::AnyClientState,
+	) -> Result<(), Ics02Error> {
+		let client_state: Any = client_state.into();
+		self.set(store_key(ClientStatePath(client_id)), client_state.encode_to_vec());
+		Ok(())
+	}
+
+	fn store_consensus_state(
+		&mut self,
+		client_id: ClientId,
+		height: Height,
+		consensus_state: T::AnyConsensusState,
+	) -> Result<(), Ics02Error> {
+		let consensus_state: Any = consensus_state.into();
+		self.set(
+			store_key(consensus_state_path(&client_id, height)),
+			consensus_state.encode_to_vec(),
+		);
+		Ok(())
+	}
+
+	fn increase_client_counter(&mut self) {
+		increase_counter(self, CLIENT_COUNTER_KEY)
+	}
+
+	fn store_update_time(
+		&mut self,
+		client_id: ClientId,
+		height: Height,
+		timestamp: Timestamp,
+	) -> Result<(), Ics02Error> {
+		let nanoseconds = timestamp.nanoseconds().to_be_bytes().to_vec();
+		self.set(processed_time_key(&client_id, height), nanoseconds);
+		Ok(())
+	}
+
+	fn store_update_height(
+		&mut self,
+		client_id: ClientId,
+		height: Height,
+		host_height: Height,
+	) -> Result<(), Ics02Error> {
+		self.set(processed_height_key(&client_id, height), host_height.encode_vec());
+		Ok(())
+	}
+
+	fn validate_self_client(&self, client_state: &T::AnyClientState) -> Result<(), Ics02Error> {
+		self.validate_self_client_state(client_state)
+	}
+}
+
+impl<T: IbcHost> ConnectionReader for T {
+	fn connection_end(&self, conn_id: &ConnectionId) -> Result<ConnectionEnd, Ics03Error> {
+		let bytes = self
+			.get(&store_key(ConnectionsPath(conn_id.clone())))
+			.ok_or_else(|| Ics03Error::connection_not_found(conn_id.clone()))?;
+		ConnectionEnd::decode_vec(&bytes)
+			.map_err(|e| Ics03Error::implementation_specific(e.to_string()))
+	}
+
+	fn host_oldest_height(&self) -> Height {
+		self.oldest_height()
+	}
+
+	fn commitment_prefix(&self) -> CommitmentPrefix {
+		self.store_prefix()
+	}
+
+	fn connection_counter(&self) -> Result<u64, Ics03Error> {
+		Ok(read_counter(self, CONNECTION_COUNTER_KEY))
+	}
+}
+
+impl<T: IbcHost> ConnectionKeeper for T {
+	fn store_connection(
+		&mut self,
+		connection_id: ConnectionId,
+		connection_end: &ConnectionEnd,
+	) -> Result<(), Ics03Error> {
+		self.set(store_key(ConnectionsPath(connection_id)), connection_end.encode_vec());
+		Ok(())
+	}
+
+	fn store_connection_to_client(
+		&mut self,
+		connection_id: ConnectionId,
+		client_id: &ClientId,
+	) -> Result<(), Ics03Error> {
+		let key = store_key(ClientConnectionsPath(client_id.clone()));
+		let mut connections = match self.get(&key) {
+			Some(bytes) => ClientPaths::decode(&*bytes)
+				.map_err(|e| Ics03Error::implementation_specific(e.to_string()))?,
+			None => ClientPaths::default(),
+		};
+		connections.paths.push(connection_id.to_string());
+		self.set(key, connections.encode_to_vec());
+		Ok(())
+	}
+
+	fn increase_connection_counter(&mut self) {
+		increase_counter(self, CONNECTION_COUNTER_KEY)
+	}
+}
+
+impl<T: IbcHost> ChannelReader for T {
+	fn channel_end(&self, port_channel_id: &(PortId, ChannelId)) -> Result<ChannelEnd, Ics04Error> {
+		let (port_id, channel_id) = port_channel_id.clone();
+		let bytes = self
+			.get(&store_key(ChannelEndsPath(port_id.clone(), channel_id)))
+			.ok_or_else(|| Ics04Error::channel_not_found(port_id, channel_id))?;
+		ChannelEnd::decode_vec(&bytes)
+			.map_err(|e| Ics04Error::implementation_specific(e.to_string()))
+	}
+
+	/// The channels of a connection are read from the index written by
+	/// [`ChannelKeeper::store_connection_channels`].
+	fn connection_channels(
+		&self,
+		cid: &ConnectionId,
+	) -> Result<Vec<(PortId, ChannelId)>, Ics04Error> {
+		let prefix = connection_channels_prefix(cid);
+		self.iter_prefix(prefix.as_bytes())
+			.map(|(key, _)| {
+				let path = String::from_utf8(key[prefix.len()..].to_vec())
+					.ok()
+					.and_then(|path| Path::from_str(&path).ok());
+				match path {
+					Some(Path::ChannelEnds(ChannelEndsPath(port_id, channel_id))) =>
+						Ok((port_id, channel_id)),
+					_ => Err(Ics04Error::implementation_specific(
+						"invalid connection channel entry".to_string(),
+					)),
+				}
+			})
+			.collect()
+	}
+
+	fn get_next_sequence_send(
+		&self,
+		port_channel_id: &(PortId, ChannelId),
+	) -> Result<Sequence, Ics04Error> {
+		let (port_id, channel_id) = port_channel_id.clone();
+		read_sequence(self, SeqSendsPath(port_id, channel_id))
+			.ok_or_else(|| Ics04Error::missing_next_send_seq(port_channel_id.clone()))
+	}
+
+	fn get_next_sequence_recv(
+		&self,
+		port_channel_id: &(PortId, ChannelId),
+	) -> Result<Sequence, Ics04Error> {
+		let (port_id, channel_id) = port_channel_id.clone();
+		read_sequence(self, SeqRecvsPath(port_id, channel_id))
+			.ok_or_else(|| Ics04Error::missing_next_recv_seq(port_channel_id.clone()))
+	}
+
+	fn get_next_sequence_ack(
+		&self,
+		port_channel_id: &(PortId, ChannelId),
+	) -> Result<Sequence, Ics04Error> {
+		let (port_id, channel_id) = port_channel_id.clone();
+		read_sequence(self, SeqAcksPath(port_id, channel_id))
+			.ok_or_else(|| Ics04Error::missing_next_ack_seq(port_channel_id.clone()))
+	}
+
+	fn get_packet_commitment(
+		&self,
+		key: &(PortId, ChannelId, Sequence),
+	) -> Result<PacketCommitment, Ics04Error> {
+		let (port_id, channel_id, sequence) = key.clone();
+		self.get(&store_key(CommitmentsPath { port_id, channel_id, sequence }))
+			.map(PacketCommitment::from)
+			.ok_or_else(|| Ics04Error::packet_commitment_not_found(sequence))
+	}
+
+	fn get_packet_receipt(
+		&self,
+		key: &(PortId, ChannelId, Sequence),
+	) -> Result<Receipt, Ics04Error> {
+		let (port_id, channel_id, sequence) = key.clone();
+		self.get(&store_key(ReceiptsPath { port_id, channel_id, sequence }))
+			.map(|_| Receipt::Ok)
+			.ok_or_else(|| Ics04Error::packet_receipt_not_found(sequence))
+	}
+
+	fn get_packet_acknowledgement(
+		&self,
+		key: &(PortId, ChannelId, Sequence),
+	) -> Result<AcknowledgementCommitment, Ics04Error> {
+		let (port_id, channel_id, sequence) = key.clone();
+		self.get(&store_key(AcksPath { port_id, channel_id, sequence }))
+			.map(AcknowledgementCommitment::from)
+			.ok_or_else(|| Ics04Error::packet_acknowledgement_not_found(sequence))
+	}
+
+	fn hash(&self, value: Vec<u8>) -> Vec<u8> {
+		self.hash_value(value)
+	}
+
+	fn client_update_time(
+		&self,
+		client_id: &ClientId,
+		height: Height,
+	) -> Result<Timestamp, Ics04Error> {
+		self.get(&processed_time_key(client_id, height))
+			.and_then(|bytes| decode_u64(&bytes))
+			.and_then(|nanoseconds| Timestamp::from_nanoseconds(nanoseconds).ok())
+			.ok_or_else(|| Ics04Error::processed_time_not_found(client_id.clone(), height))
+	}
+
+	fn client_update_height(
+		&self,
+		client_id: &ClientId,
+		height: Height,
+	) -> Result<Height, Ics04Error> {
+		self.get(&processed_height_key(client_id, height))
+			.and_then(|bytes| Height::decode_vec(&bytes).ok())
+			.ok_or_else(|| Ics04Error::processed_height_not_found(client_id.clone(), height))
+	}
+
+	fn channel_counter(&self) -> Result<u64, Ics04Error> {
+		Ok(read_counter(self, CHANNEL_COUNTER_KEY))
+	}
+
+	fn max_expected_time_per_block(&self) -> Duration {
+		self.expected_block_time()
+	}
+}
+
+impl<T: IbcHost> ChannelKeeper for T {
+	fn store_packet_commitment(
+		&mut self,
+		key: (PortId, ChannelId, Sequence),
+		commitment: PacketCommitment,
+	) -> Result<(), Ics04Error> {
+		let (port_id, channel_id, sequence) = key;
+		self.set(
+			store_key(CommitmentsPath { port_id, channel_id, sequence }),
+			commitment.into_vec(),
+		);
+		Ok(())
+	}
+
+	/// Packets are not kept in the store, only their commitment is.
+	fn store_send_packet(
+		&mut self,
+		_key: (PortId, ChannelId, Sequence),
+		_packet: Packet,
+	) -> Result<(), Ics04Error> {
+		Ok(())
+	}
+
+	/// Packets are not kept in the store, only their receipt or the next receive sequence is.
+	fn store_recv_packet(
+		&mut self,
+		_key: (PortId, ChannelId, Sequence),
+		_packet: Packet,
+	) -> Result<(), Ics04Error> {
+		Ok(())
+	}
+
+	fn delete_packet_commitment(
+		&mut self,
+		key: (PortId, ChannelId, Sequence),
+	) -> Result<(), Ics04Error> {
+		let (port_id, channel_id, sequence) = key;
+		self.delete(&store_key(CommitmentsPath { port_id, channel_id, sequence }));
+		Ok(())
+	}
+
+	fn store_packet_receipt(
+		&mut self,
+		key: (PortId, ChannelId, Sequence),
+		_receipt: Receipt,
+	) -> Result<(), Ics04Error> {
+		let (port_id, channel_id, sequence) = key;
+		self.set(store_key(ReceiptsPath { port_id, channel_id, sequence }), vec![1]);
+		Ok(())
+	}
+
+	fn store_packet_acknowledgement(
+		&mut self,
+		key: (PortId, ChannelId, Sequence),
+		ack_commitment: AcknowledgementCommitment,
+	) -> Result<(), Ics04Error> {
+		let (port_id, channel_id, sequence) = key;
+		self.set(store_key(AcksPath { port_id, channel_id, sequence }), ack_commitment.into_vec());
+		Ok(())
+	}
+
+	fn delete_packet_acknowledgement(
+		&mut self,
+		key: (PortId, ChannelId, Sequence),
+	) -> Result<(), Ics04Error> {
+		let (port_id, channel_id, sequence) = key;
+		self.delete(&store_key(AcksPath { port_id, channel_id, sequence }));
+		Ok(())
+	}
+
+	/// The channels of a connection are read from the channel ends themselves, see
+	/// [`ChannelReader::connection_channels`].
+	fn store_connection_channels(
+		&mut self,
+		conn_id: ConnectionId,
+		port_channel_id: &(PortId, ChannelId),
+	) -> Result<(), Ics04Error> {
+		let (port_id, channel_id) = port_channel_id.clone();
+		let key = format!(
+			"{}{}",
+			connection_channels_prefix(&conn_id),
+			ChannelEndsPath(port_id, channel_id)
+		);
+		self.set(key.into_bytes(), Vec::new());
+		Ok(())
+	}
+
+	fn store_channel(
+		&mut self,
+		port_channel_id: (PortId, ChannelId),
+		channel_end: &ChannelEnd,
+	) -> Result<(), Ics04Error> {
+		let (port_id, channel_id) = port_channel_id;
+		self.set(store_key(ChannelEndsPath(port_id, channel_id)), channel_end.encode_vec());
+		Ok(())
+	}
+
+	fn store_next_sequence_send(
+		&mut self,
+		port_channel_id: (PortId, ChannelId),
+		seq: Sequence,
+	) -> Result<(), Ics04Error> {
+		let (port_id, channel_id) = port_channel_id;
+		write_sequence(self, SeqSendsPath(port_id, channel_id), seq);
+		Ok(())
+	}
+
+	fn store_next_sequence_recv(
+		&mut self,
+		port_channel_id: (PortId, ChannelId),
+		seq: Sequence,
+	) -> Result<(), Ics04Error> {
+		let (port_id, channel_id) = port_channel_id;
+		write_sequence(self, SeqRecvsPath(port_id, channel_id), seq);
+		Ok(())
+	}
+
+	fn store_next_sequence_ack(
+		&mut self,
+		port_channel_id: (PortId, ChannelId),
+		seq: Sequence,
+	) -> Result<(), Ics04Error> {
+		let (port_id, channel_id) = port_channel_id;
+		write_sequence(self, SeqAcksPath(port_id, channel_id), seq);
+		Ok(())
+	}
+
+	fn increase_channel_counter(&mut self) {
+		increase_counter(self, CHANNEL_COUNTER_KEY)
+	}
+}
+
+impl<T: IbcHost> CapabilityReader for T {
+	fn get_capability_index(&self, name: &CapabilityName) -> Result<Option<u64>, Ics05Error> {
+		self.get(&capability_key(CAPABILITY_INDICES_PREFIX, name))
+			.map(|bytes| {
+				decode_u64(&bytes).ok_or_else(|| {
+					Ics05Error::implementation_specific("invalid capability index".to_string())
+				})
+			})
+			.transpose()
+	}
+
+	fn get_capability_owner(&self, name: &CapabilityName) -> Result<Option<ModuleId>, Ics05Error> {
+		self.get(&capability_key(CAPABILITY_OWNERS_PREFIX, name))
+			.map(|bytes| {
+				String::from_utf8(bytes)
+					.ok()
+					.and_then(|module_id| ModuleId::from_str(&module_id).ok())
+					.ok_or_else(|| {
+						Ics05Error::implementation_specific("invalid capability owner".to_string())
+					})
+			})
+			.transpose()
+	}
+
+	fn get_next_capability_index(&self) -> Result<u64, Ics05Error> {
+		Ok(read_counter(self, NEXT_CAPABILITY_INDEX_KEY))
+	}
+}
+
+impl<T: IbcHost> CapabilityKeeper for T {
+	fn store_capability_index(
+		&mut self,
+		name: CapabilityName,
+		index: Option<u64>,
+	) -> Result<(), Ics05Error> {
+		let key = capability_key(CAPABILITY_INDICES_PREFIX, &name);
+		match index {
+			Some(index) => self.set(key, index.to_be_bytes().to_vec()),
+			None => self.delete(&key),
+		}
+		Ok(())
+	}
+
+	fn store_capability_owner(
+		&mut self,
+		name: CapabilityName,
+		owner: Option<ModuleId>,
+	) -> Result<(), Ics05Error> {
+		let key = capability_key(CAPABILITY_OWNERS_PREFIX, &name);
+		match owner {
+			Some(owner) => self.set(key, owner.to_string().into_bytes()),
+			None => self.delete(&key),
+		}
+		Ok(())
+	}
+
+	fn store_next_capability_index(&mut self, index: u64) -> Result<(), Ics05Error> {
+		self.set(NEXT_CAPABILITY_INDEX_KEY.to_vec(), index.to_be_bytes().to_vec());
+		Ok(())
+	}
+}
+
+impl<T: IbcHost> PortReader for T {
+	fn lookup_module_by_port(&self, port_id: &PortId) -> Result<ModuleId, Ics05Error> {
+		self.get(&store_key(PortsPath(port_id.clone())))
+			.and_then(|bytes| String::from_utf8(bytes).ok())
+			.and_then(|module_id| ModuleId::from_str(&module_id).ok())
+			.ok_or_else(|| Ics05Error::unknown_port(port_id.clone()))
+	}
+
+	fn lookup_ports_by_prefix(&self, prefix: &str) -> Result<Vec<(PortId, ModuleId)>, Ics05Error> {
+		self.iter_prefix(format!("{}{}", PORTS_PREFIX, prefix).as_bytes())
+			.map(|(key, value)| {
+				let port_id = String::from_utf8(key[PORTS_PREFIX.len()..].to_vec())
+					.ok()
+					.and_then(|port_id| PortId::from_str(&port_id).ok());
+				let module_id = String::from_utf8(value)
+					.ok()
+					.and_then(|module_id| ModuleId::from_str(&module_id).ok());
+				match (port_id, module_id) {
+					(Some(port_id), Some(module_id)) => Ok((port_id, module_id)),
+					_ => Err(Ics05Error::implementation_specific("invalid port entry".to_string())),
+				}
+			})
+			.collect()
+	}
+
+	fn get_port_prefixes(&self) -> Result<Vec<(String, ModuleId)>, Ics05Error> {
+		self.iter_prefix(PORT_PREFIXES_PREFIX.as_bytes())
+			.map(|(key, value)| {
+				let prefix = String::from_utf8(key[PORT_PREFIXES_PREFIX.len()..].to_vec());
+				let module_id = String::from_utf8(value)
+					.ok()
+					.and_then(|module_id| ModuleId::from_str(&module_id).ok());
+				match (prefix, module_id) {
+					(Ok(prefix), Some(module_id)) => Ok((prefix, module_id)),
+					_ => Err(Ics05Error::implementation_specific(
+						"invalid port prefix entry".to_string(),
+					)),
+				}
+			})
+			.collect()
+	}
+}
+
+impl<T: IbcHost> PortKeeper for T {
+	fn store_port_module(
+		&mut self,
+		port_id: PortId,
+		module_id: Option<ModuleId>,
+	) -> Result<(), Ics05Error> {
+		let key = store_key(PortsPath(port_id));
+		match module_id {
+			Some(module_id) => self.set(key, module_id.to_string().into_bytes()),
+			None => self.delete(&key),
+		}
+		Ok(())
+	}
+
+	fn store_port_prefix(&mut self, prefix: String, module_id: ModuleId) -> Result<(), Ics05Error> {
+		let key = format!("{}{}", PORT_PREFIXES_PREFIX, prefix).into_bytes();
+		self.set(key, module_id.to_string().into_bytes());
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use alloc::collections::BTreeMap;
+
+	use crate::{
+		core::{
+			ics03_connection::connection::{
+				Counterparty as ConnectionCounterparty, State as ConnectionState,
+			},
+			ics04_channel::channel::{Counterparty, Order, State},
+			ics05_port::capabilities::lookup_capability,
+		},
+		mock::{
+			client_state::{
+				AnyClientState, AnyConsensusState, MockClientState, MockConsensusState,
+			},
+			context::MockClientTypes,
+			header::MockHeader,
+		},
+	};
+
+	#[derive(Clone, Debug, Default, PartialEq, Eq)]
+	struct BTreeHost {
+		store: BTreeMap<Vec<u8>, Vec<u8>>,
+	}
+
+	impl ClientTypes for BTreeHost {
+		type AnyClientMessage = <MockClientTypes as ClientTypes>::AnyClientMessage;
+		type AnyClientState = <MockClientTypes as ClientTypes>::AnyClientState;
+		type AnyConsensusState = <MockClientTypes as ClientTypes>::AnyConsensusState;
+		type ClientDef = <MockClientTypes as ClientTypes>::ClientDef;
+	}
+
+	impl IbcStore for BTreeHost {
+		fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+			self.store.get(key).cloned()
+		}
+
+		fn set(&mut self, key: Vec<u8>, value: Vec<u8>) {
+			self.store.insert(key, value);
+		}
+
+		fn delete(&mut self, key: &[u8]) {
+			self.store.remove(key);
+		}
+
+		fn iter_prefix<'a>(
+			&'a self,
+			prefix: &[u8],
+		) -> Box<dyn Iterator<Item = (Vec<u8>, Vec<u8>)> + 'a> {
+			let prefix = prefix.to_vec();
+			Box::new(
+				self.store
+					.range(prefix.clone()..)
+					.take_while(move |(key, _)| key.starts_with(&prefix))
+					.map(|(key, value)| (key.clone(), value.clone())),
+			)
+		}
+	}
+
+	impl IbcHost for BTreeHost {
+		fn self_client_type(&self) -> String {
+			MockClientState::client_type()
+		}
+
+		fn current_height(&self) -> Height {
+			Height::new(0, 10)
+		}
+
+		fn current_timestamp(&self) -> Timestamp {
+			Timestamp::none()
+		}
+
+		fn oldest_height(&self) -> Height {
+			Height::new(0, 1)
+		}
+
+		fn self_consensus_state(
+			&self,
+			height: Height,
+			_proof: Option<Vec<u8>>,
+		) -> Result<AnyConsensusState, Ics02Error> {
+			Ok(MockConsensusState::new(MockHeader::new(height)).into())
+		}
+
+		fn validate_self_client_state(
+			&self,
+			_client_state: &AnyClientState,
+		) -> Result<(), Ics02Error> {
+			Ok(())
+		}
+
+		fn store_prefix(&self) -> CommitmentPrefix {
+			CommitmentPrefix::try_from(b"ibc".to_vec()).unwrap()
+		}
+
+		fn hash_value(&self, value: Vec<u8>) -> Vec<u8> {
+			value
+		}
+
+		fn expected_block_time(&self) -> Duration {
+			Duration::from_secs(6)
+		}
+	}
+
+	#[test]
+	fn test_store_adapter() {
+		let mut host = BTreeHost::default();
+		let client_id = ClientId::default();
+
+		// consensus states are found around a height, skipping their processed time and height
+		let client_state: AnyClientState =
+			MockClientState::new(MockHeader::new(Height::new(0, 5)).into()).into();
+		host.store_client_type(client_id.clone(), MockClientState::client_type())
+			.unwrap();
+		host.store_client_state(client_id.clone(), client_state.clone()).unwrap();
+		let timestamp = Timestamp::from_nanoseconds(1).unwrap();
+		for height in [Height::new(0, 2), Height::new(0, 5), Height::new(1, 1)] {
+			let consensus_state = MockConsensusState::new(MockHeader::new(height)).into();
+			host.store_consensus_state(client_id.clone(), height, consensus_state).unwrap();
+			host.store_update_time(client_id.clone(), height, timestamp).unwrap();
+			host.store_update_height(client_id.clone(), height, Height::new(0, 9)).unwrap();
+		}
+		assert_eq!(host.client_state(&client_id).unwrap(), client_state);
+		let consensus_state = |height| Some(host.consensus_state(&client_id, height).unwrap());
+		let next = host.next_consensus_state(&client_id, Height::new(0, 2)).unwrap();
+		assert_eq!(next, consensus_state(Height::new(0, 5)));
+		let next = host.next_consensus_state(&client_id, Height::new(0, 10)).unwrap();
+		assert_eq!(next, consensus_state(Height::new(1, 1)));
+		let prev = host.prev_consensus_state(&client_id, Height::new(0, 5)).unwrap();
+		assert_eq!(prev, consensus_state(Height::new(0, 2)));
+		assert_eq!(host.prev_consensus_state(&client_id, Height::new(0, 2)).unwrap(), None);
+		let update_height = host.client_update_height(&client_id, Height::new(0, 5)).unwrap();
+		assert_eq!(update_height, Height::new(0, 9));
+		assert!(host.client_update_time(&client_id, Height::new(0, 3)).is_err());
+
+		// the channels of a connection are those whose first hop is the connection
+		let connection_id = ConnectionId::new(0);
+		let connection_end = ConnectionEnd::new(
+			ConnectionState::Init,
+			client_id.clone(),
+			ConnectionCounterparty::new(client_id.clone(), None, host.store_prefix()),
+			vec![],
+			Duration::ZERO,
+		);
+		host.store_connection(connection_id.clone(), &connection_end).unwrap();
+		assert_eq!(host.connection_end(&connection_id).unwrap(), connection_end);
+		host.store_connection_to_client(connection_id.clone(), &client_id).unwrap();
+		host.store_connection_to_client(ConnectionId::new(1), &client_id).unwrap();
+		let client_connections =
+			host.get(&store_key(ClientConnectionsPath(client_id.clone()))).unwrap();
+		assert_eq!(
+			ClientPaths::decode(&*client_connections).unwrap().paths,
+			vec![connection_id.to_string(), ConnectionId::new(1).to_string()]
+		);
+		let port_id = PortId::transfer();
+		let counterparty = Counterparty::new(port_id.clone(), None);
+		for (channel_id, hop) in [(0, 0), (1, 1), (2, 0)] {
+			let channel_end = ChannelEnd::new(
+				State::Init,
+				Order::Unordered,
+				counterparty.clone(),
+				vec![ConnectionId::new(hop)],
+				Default::default(),
+			);
+			let port_channel_id = (port_id.clone(), ChannelId::new(channel_id));
+			host.store_channel(port_channel_id.clone(), &channel_end).unwrap();
+			host.store_connection_channels(ConnectionId::new(hop), &port_channel_id)
+				.unwrap();
+		}
+		let channels = host.connection_channels(&connection_id).unwrap();
+		assert_eq!(
+			channels,
+			vec![(port_id.clone(), ChannelId::new(0)), (port_id.clone(), ChannelId::new(2))]
+		);
+
+		let port_channel_id = (port_id.clone(), ChannelId::new(0));
+		host.store_next_sequence_send(port_channel_id.clone(), Sequence::from(3))
+			.unwrap();
+		assert_eq!(host.get_next_sequence_send(&port_channel_id).unwrap(), Sequence::from(3));
+		assert!(host.get_next_sequence_recv(&port_channel_id).is_err());
+		let key = (port_id, ChannelId::new(0), Sequence::from(1));
+		host.store_packet_commitment(key.clone(), PacketCommitment::from(vec![1, 2]))
+			.unwrap();
+		assert_eq!(host.get_packet_commitment(&key).unwrap(), PacketCommitment::from(vec![1, 2]));
+		host.delete_packet_commitment(key.clone()).unwrap();
+		assert!(host.get_packet_commitment(&key).is_err());
+
+		host.increase_client_counter();
+		host.increase_client_counter();
+		assert_eq!(host.client_counter().unwrap(), 2);
+		assert_eq!(host.channel_counter().unwrap(), 0);
+	}
+
+	#[test]
+	fn test_store_ports_and_capabilities() {
+		let mut host = BTreeHost::default();
+		let transfer: ModuleId = "transfer".parse().unwrap();
+		let controller: ModuleId = "icacontroller".parse().unwrap();
+
+		// capabilities are issued with increasing indices, and can only be looked up by their owner
+		let port_id = PortId::transfer();
+		let capability = host.bind_port(port_id.clone(), transfer.clone()).unwrap();
+		assert_eq!(host.lookup_module_by_port(&port_id).unwrap(), transfer);
+		let name = CapabilityName::port(&port_id);
+		assert_eq!(host.get_capability_index(&name).unwrap(), Some(0));
+		assert_eq!(host.get_capability_owner(&name).unwrap(), Some(transfer.clone()));
+		assert_eq!(lookup_capability(&host, &name, &transfer).unwrap(), capability);
+		assert!(lookup_capability(&host, &name, &controller).is_err());
+		let channel = CapabilityName::channel(&port_id, ChannelId::new(0));
+		let channel_capability = host.new_capability(channel.clone(), transfer.clone()).unwrap();
+		assert_eq!(
+			host.lookup_channel_capability(&port_id, ChannelId::new(0), &capability)
+				.unwrap(),
+			channel_capability
+		);
+		assert_eq!(host.get_capability_index(&channel).unwrap(), Some(1));
+		assert_eq!(host.get_next_capability_index().unwrap(), 2);
+
+		// the port is free again once released, and its index is not reused
+		host.release_port(port_id.clone(), capability).unwrap();
+		assert!(host.lookup_module_by_port(&port_id).is_err());
+		assert_eq!(host.get_capability_index(&name).unwrap(), None);
+		assert_eq!(host.get_capability_owner(&name).unwrap(), None);
+		host.bind_port(port_id.clone(), transfer.clone()).unwrap();
+		assert_eq!(host.get_capability_index(&name).unwrap(), Some(2));
+
+		// ports are found by prefix, and prefixes cannot cover the ports of other modules
+		let alice: PortId = "icacontroller-alice".parse().unwrap();
+		host.bind_port(alice.clone(), controller.clone()).unwrap();
+		assert_eq!(
+			host.lookup_ports_by_prefix("icacontroller-").unwrap(),
+			vec![(alice.clone(), controller.clone())]
+		);
+		assert!(host.claim_port_prefix("ica".to_string(), transfer.clone()).is_err());
+		let prefix_capability = host
+			.claim_port_prefix("icacontroller-".to_string(), controller.clone())
+			.unwrap();
+		assert_eq!(
+			host.get_port_prefixes().unwrap(),
+			vec![("icacontroller-".to_string(), controller.clone())]
+		);
+		let bob: PortId = "icacontroller-bob".parse().unwrap();
+		assert_eq!(
+			host.lookup_port_prefix(&bob).unwrap(),
+			Some(("icacontroller-".to_string(), controller.clone()))
+		);
+		assert!(host.bind_port(bob.clone(), transfer).is_err());
+		host.bind_prefixed_port(bob.clone(), &prefix_capability).unwrap();
+		assert_eq!(
+			host.lookup_ports_by_prefix("icacontroller-").unwrap(),
+			vec![(alice, controller.clone()), (bob, controller)]
+		);
+	}
+}
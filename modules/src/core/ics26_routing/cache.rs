@@ -0,0 +1,1663 @@
+//! A context buffering the writes of the IBC handlers, for hosts without transactional storage.
+//!
+//! [`CachedContext`] wraps a host context and implements the same reader and keeper traits. Its
+//! keepers record every write in an overlay instead of applying it: reads go through the overlay
+//! first, so that handlers observe their own writes, and the writes are only applied to the host
+//! context once [`CachedContext::commit`] is called. [`CachedContext::rollback`] discards them,
+//! which is what the runtime is expected to do when [`dispatch`](super::handler::dispatch)
+//! fails.
+//!
+//! Writes are applied in the order they were made, including the ones to the bank and other
+//! side effects of ICS20. The bank keeper of the cache reads the balances of the host through
+//! [`BankReader`], so that a transfer from an account without enough funds fails when it is
+//! made rather than upon commit.
+//!
+//! Modules keeping state of their own, outside of the keepers of the host, are not cached. Hosts
+//! with such modules implement [`ModuleSnapshot`], which [`CachedContext::deliver`] uses to
+//! restore the state of the modules when a message fails.
+
+use alloc::{
+	collections::{btree_map::BTreeMap, btree_set::BTreeSet},
+	sync::Arc,
+};
+use core::{
+	any::Any as AnyValue,
+	fmt::{Debug, Formatter},
+	mem,
+	ops::Bound::{Excluded, Unbounded},
+	time::Duration,
+};
+
+use ibc_proto::google::protobuf::Any;
+
+use crate::{
+	applications::transfer::{
+		context::{BankKeeper, BankReader, Ics20Context, Ics20Keeper, Ics20Reader},
+		enablement::EnablementKey,
+		error::Error as Ics20Error,
+		escrow::EscrowAddressScheme,
+		metadata::DenomMetadata,
+		packet::PacketData,
+		Amount, PrefixedCoin, PrefixedDenom,
+	},
+	core::{
+		ics02_client::{
+			client_state::ClientType,
+			context::{ClientKeeper, ClientReader, ClientTypes},
+			error::Error as Ics02Error,
+		},
+		ics03_connection::{
+			connection::ConnectionEnd,
+			context::{ConnectionKeeper, ConnectionReader},
+			error::Error as Ics03Error,
+			version::Version as ConnectionVersion,
+		},
+		ics04_channel::{
+			channel::ChannelEnd,
+			commitment::{AcknowledgementCommitment, PacketCommitment},
+			context::{ChannelKeeper, ChannelReader},
+			error::Error as Ics04Error,
+			msgs::acknowledgement::Acknowledgement,
+			packet::{Packet, Receipt, Sequence},
+		},
+		ics05_port::{
+			capabilities::{CapabilityKeeper, CapabilityName, CapabilityReader},
+			context::{PortKeeper, PortReader},
+			error::Error as Ics05Error,
+		},
+		ics23_commitment::commitment::CommitmentPrefix,
+		ics24_host::identifier::{ChannelId, ClientId, ConnectionId, PortId},
+		ics26_routing::{
+			context::{Ics26Context, ModuleId, ReaderContext},
+			error::Error,
+			handler::{deliver, MsgReceipt},
+			msgs::Ics26Envelope,
+		},
+	},
+	gas::GasMeter,
+	prelude::*,
+	timestamp::Timestamp,
+	Height,
+};
+
+/// A write recorded by the cache, to be applied to the host context upon commit.
+#[derive(Clone)]
+struct PendingWrite<Ctx>(Arc<dyn Fn(&mut Ctx) -> Result<(), Error> + Send + Sync>);
+
+impl<Ctx> Debug for PendingWrite<Ctx> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+		f.write_str("PendingWrite")
+	}
+}
+
+/// A condition a write recorded by the cache relies on, checked against the host context before
+/// any write is applied.
+#[derive(Clone)]
+struct PendingCheck<Ctx>(Arc<dyn Fn(&Ctx) -> Result<(), Error> + Send + Sync>);
+
+impl<Ctx> Debug for PendingCheck<Ctx> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+		f.write_str("PendingCheck")
+	}
+}
+
+/// The tokens of a denomination an account received and spent through the cache.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct BankFlow {
+	/// The balance of the account in the host context.
+	balance: Amount,
+	credited: Amount,
+	debited: Amount,
+}
+
+impl BankFlow {
+	/// The balance of the account once the flows are applied.
+	fn cached_balance(&self) -> Option<Amount> {
+		self.balance.checked_add(self.credited)?.checked_sub(self.debited)
+	}
+
+	/// The part of the debits the credits do not cover, which the host balance must cover.
+	fn required_balance(&self) -> Amount {
+		self.debited.checked_sub(self.credited).unwrap_or_else(|| 0u64.into())
+	}
+}
+
+type Flows<AccountId> = BTreeMap<(AccountId, PrefixedDenom), BankFlow>;
+
+/// [`Flows`] of any account type.
+trait AnyFlows: Send + Sync {
+	fn as_any(&self) -> &dyn AnyValue;
+
+	fn eq_flows(&self, other: &dyn AnyFlows) -> bool;
+}
+
+impl<AccountId: Ord + Send + Sync + 'static> AnyFlows for Flows<AccountId> {
+	fn as_any(&self) -> &dyn AnyValue {
+		self
+	}
+
+	fn eq_flows(&self, other: &dyn AnyFlows) -> bool {
+		other.as_any().downcast_ref::<Self>().map_or(false, |other| self == other)
+	}
+}
+
+/// The flows recorded by the bank keeper of the cache. The type of the accounts is only known to
+/// the bank keeper, which stores them as [`Flows`] of its account type.
+#[derive(Clone, Default)]
+struct BankFlows(Option<Arc<dyn AnyFlows>>);
+
+impl BankFlows {
+	fn get<AccountId: Clone + Ord + Send + Sync + 'static>(
+		&self,
+		account: &AccountId,
+		denom: &PrefixedDenom,
+	) -> Option<BankFlow> {
+		let flows = self.0.as_ref()?.as_any().downcast_ref::<Flows<AccountId>>()?;
+		flows.get(&(account.clone(), denom.clone())).copied()
+	}
+
+	fn insert<AccountId: Clone + Ord + Send + Sync + 'static>(
+		&mut self,
+		account: AccountId,
+		denom: PrefixedDenom,
+		flow: BankFlow,
+	) {
+		let mut flows = self
+			.0
+			.as_ref()
+			.and_then(|flows| flows.as_any().downcast_ref::<Flows<AccountId>>())
+			.cloned()
+			.unwrap_or_default();
+		flows.insert((account, denom), flow);
+		self.0 = Some(Arc::new(flows));
+	}
+}
+
+impl PartialEq for BankFlows {
+	fn eq(&self, other: &Self) -> bool {
+		match (&self.0, &other.0) {
+			(Some(flows), Some(other)) => flows.eq_flows(&**other),
+			(flows, other) => flows.is_none() && other.is_none(),
+		}
+	}
+}
+
+impl Debug for BankFlows {
+	fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+		f.write_str("BankFlows")
+	}
+}
+
+type PacketKey = (PortId, ChannelId, Sequence);
+
+/// The writes made through the cache since the last commit or rollback. Besides the writes
+/// themselves, the values they store are kept for the readers of the cache.
+#[derive(Clone, Debug)]
+struct Overlay<Ctx: ClientTypes> {
+	writes: Vec<PendingWrite<Ctx>>,
+	checks: Vec<PendingCheck<Ctx>>,
+	client_types: BTreeMap<ClientId, ClientType>,
+	client_states: BTreeMap<ClientId, Ctx::AnyClientState>,
+	consensus_states: BTreeMap<ClientId, BTreeMap<Height, Ctx::AnyConsensusState>>,
+	update_times: BTreeMap<(ClientId, Height), Timestamp>,
+	update_heights: BTreeMap<(ClientId, Height), Height>,
+	new_clients: u64,
+	connections: BTreeMap<ConnectionId, ConnectionEnd>,
+	new_connections: u64,
+	channels: BTreeMap<(PortId, ChannelId), ChannelEnd>,
+	connection_channels: BTreeMap<ConnectionId, Vec<(PortId, ChannelId)>>,
+	next_sequences_send: BTreeMap<(PortId, ChannelId), Sequence>,
+	next_sequences_recv: BTreeMap<(PortId, ChannelId), Sequence>,
+	next_sequences_ack: BTreeMap<(PortId, ChannelId), Sequence>,
+	packet_commitments: BTreeMap<PacketKey, Option<PacketCommitment>>,
+	packet_receipts: BTreeSet<PacketKey>,
+	packet_acknowledgements: BTreeMap<PacketKey, Option<AcknowledgementCommitment>>,
+	new_channels: u64,
+	capabilities: BTreeMap<CapabilityName, Option<u64>>,
+	capability_owners: BTreeMap<CapabilityName, Option<ModuleId>>,
+	next_capability_index: Option<u64>,
+	port_modules: BTreeMap<PortId, Option<ModuleId>>,
+	port_prefixes: BTreeMap<String, ModuleId>,
+	denom_traces: BTreeMap<String, PrefixedDenom>,
+	transfers_disabled: BTreeMap<EnablementKey, Option<String>>,
+	total_escrows: BTreeMap<PrefixedDenom, Amount>,
+	bank_flows: BankFlows,
+}
+
+/// Overlays are equal if they store the same values, the pending writes and checks being derived
+/// from them.
+impl<Ctx: ClientTypes> PartialEq for Overlay<Ctx> {
+	fn eq(&self, other: &Self) -> bool {
+		self.client_types == other.client_types &&
+			self.client_states == other.client_states &&
+			self.consensus_states == other.consensus_states &&
+			self.update_times == other.update_times &&
+			self.update_heights == other.update_heights &&
+			self.new_clients == other.new_clients &&
+			self.connections == other.connections &&
+			self.new_connections == other.new_connections &&
+			self.channels == other.channels &&
+			self.connection_channels == other.connection_channels &&
+			self.next_sequences_send == other.next_sequences_send &&
+			self.next_sequences_recv == other.next_sequences_recv &&
+			self.next_sequences_ack == other.next_sequences_ack &&
+			self.packet_commitments == other.packet_commitments &&
+			self.packet_receipts == other.packet_receipts &&
+			self.packet_acknowledgements == other.packet_acknowledgements &&
+			self.new_channels == other.new_channels &&
+			self.capabilities == other.capabilities &&
+			self.capability_owners == other.capability_owners &&
+			self.next_capability_index == other.next_capability_index &&
+			self.port_modules == other.port_modules &&
+			self.port_prefixes == other.port_prefixes &&
+			self.denom_traces == other.denom_traces &&
+			self.transfers_disabled == other.transfers_disabled &&
+			self.total_escrows == other.total_escrows &&
+			self.bank_flows == other.bank_flows
+	}
+}
+
+impl<Ctx: ClientTypes> Default for Overlay<Ctx> {
+	fn default() -> Self {
+		Self {
+			writes: Vec::new(),
+			checks: Vec::new(),
+			client_types: BTreeMap::new(),
+			client_states: BTreeMap::new(),
+			consensus_states: BTreeMap::new(),
+			update_times: BTreeMap::new(),
+			update_heights: BTreeMap::new(),
+			new_clients: 0,
+			connections: BTreeMap::new(),
+			new_connections: 0,
+			channels: BTreeMap::new(),
+			connection_channels: BTreeMap::new(),
+			next_sequences_send: BTreeMap::new(),
+			next_sequences_recv: BTreeMap::new(),
+			next_sequences_ack: BTreeMap::new(),
+			packet_commitments: BTreeMap::new(),
+			packet_receipts: BTreeSet::new(),
+			packet_acknowledgements: BTreeMap::new(),
+			new_channels: 0,
+			capabilities: BTreeMap::new(),
+			capability_owners: BTreeMap::new(),
+			next_capability_index: None,
+			port_modules: BTreeMap::new(),
+			port_prefixes: BTreeMap::new(),
+			denom_traces: BTreeMap::new(),
+			transfers_disabled: BTreeMap::new(),
+			total_escrows: BTreeMap::new(),
+			bank_flows: BankFlows::default(),
+		}
+	}
+}
+
+/// A host context whose writes are buffered until they are committed, see the
+/// [module documentation](self).
+///
+/// The router is the one of the host context, see [`ModuleSnapshot`] for modules keeping their own
+/// state. An ICS20 module can also run on a `CachedContext` of its own context, in which case the
+/// `WriteFn` returned by [`on_recv_packet`](crate::applications::transfer::context::on_recv_packet)
+/// must be executed against that cached context as well.
+#[derive(Clone, Debug)]
+pub struct CachedContext<Ctx: ClientTypes> {
+	inner: Ctx,
+	overlay: Overlay<Ctx>,
+}
+
+impl<Ctx: ClientTypes + PartialEq> PartialEq for CachedContext<Ctx> {
+	fn eq(&self, other: &Self) -> bool {
+		self.inner == other.inner && self.overlay == other.overlay
+	}
+}
+
+impl<Ctx: ClientTypes + Eq> Eq for CachedContext<Ctx> {}
+
+/// Implemented by host contexts whose modules keep state of their own, which a [`CachedContext`]
+/// cannot buffer. [`CachedContext::deliver`] takes a snapshot of that state before delivering a
+/// message, and restores it if the message fails. Hosts whose modules keep their state through
+/// the keepers of the host use `()` as their snapshot.
+pub trait ModuleSnapshot {
+	type Snapshot;
+
+	fn snapshot_modules(&self) -> Self::Snapshot;
+
+	fn restore_modules(&mut self, snapshot: Self::Snapshot);
+}
+
+impl<Ctx: ClientTypes> CachedContext<Ctx> {
+	pub fn new(inner: Ctx) -> Self {
+		Self { inner, overlay: Overlay::default() }
+	}
+
+	/// The host context, without the writes that were not committed yet.
+	pub fn inner(&self) -> &Ctx {
+		&self.inner
+	}
+
+	/// Discards the writes that were not committed and returns the host context.
+	pub fn into_inner(self) -> Ctx {
+		self.inner
+	}
+
+	/// Returns `true` if writes were made since the last commit or rollback.
+	pub fn is_dirty(&self) -> bool {
+		!self.overlay.writes.is_empty()
+	}
+
+	/// Applies the buffered writes to the host context, in the order they were made.
+	/// The conditions the writes rely on, such as the balances of the accounts tokens are taken
+	/// from, are checked against the host context first. If one does not hold anymore, the writes
+	/// are discarded without any being applied. Once they hold, the keepers of the host are
+	/// expected not to fail on the values the handlers produced.
+	pub fn commit(&mut self) -> Result<(), Error> {
+		let overlay = mem::take(&mut self.overlay);
+		overlay.checks.iter().try_for_each(|check| (check.0)(&self.inner))?;
+		overlay.writes.iter().try_for_each(|write| (write.0)(&mut self.inner))
+	}
+
+	/// Discards the writes made since the last commit or rollback.
+	pub fn rollback(&mut self) {
+		self.overlay = Overlay::default();
+	}
+
+	/// Delivers `message` as [`deliver`] does, committing its writes if it is processed
+	/// successfully and rolling them back otherwise, along with the state of the modules.
+	pub fn deliver(&mut self, message: Any) -> Result<MsgReceipt, Error>
+	where
+		Self: Ics26Context + ReaderContext,
+		Ctx: ModuleSnapshot,
+		Ics26Envelope<Self>: TryFrom<Any>,
+		Error: From<<Ics26Envelope<Self> as TryFrom<Any>>::Error>,
+	{
+		let snapshot = self.inner.snapshot_modules();
+		let result = deliver(self, message).and_then(|receipt| {
+			self.commit()?;
+			Ok(receipt)
+		});
+		if result.is_err() {
+			self.rollback();
+			self.inner.restore_modules(snapshot);
+		}
+		result
+	}
+
+	fn defer(&mut self, write: impl Fn(&mut Ctx) -> Result<(), Error> + Send + Sync + 'static) {
+		self.overlay.writes.push(PendingWrite(Arc::new(write)))
+	}
+
+	fn check(&mut self, check: impl Fn(&Ctx) -> Result<(), Error> + Send + Sync + 'static) {
+		self.overlay.checks.push(PendingCheck(Arc::new(check)))
+	}
+}
+
+impl<Ctx: ClientTypes> ClientTypes for CachedContext<Ctx> {
+	type AnyClientMessage = Ctx::AnyClientMessage;
+	type AnyClientState = Ctx::AnyClientState;
+	type AnyConsensusState = Ctx::AnyConsensusState;
+	type ClientDef = Ctx::ClientDef;
+}
+
+impl<Ctx: ClientReader> ClientReader for CachedContext<Ctx> {
+	fn client_type(&self, client_id: &ClientId) -> Result<ClientType, Ics02Error> {
+		match self.overlay.client_types.get(client_id) {
+			Some(client_type) => Ok(client_type.clone()),
+			None => self.inner.client_type(client_id),
+		}
+	}
+
+	fn client_state(&self, client_id: &ClientId) -> Result<Ctx::AnyClientState, Ics02Error> {
+		match self.overlay.client_states.get(client_id) {
+			Some(client_state) => Ok(client_state.clone()),
+			None => self.inner.client_state(client_id),
+		}
+	}
+
+	fn consensus_state(
+		&self,
+		client_id: &ClientId,
+		height: Height,
+	) -> Result<Ctx::AnyConsensusState, Ics02Error> {
+		let cached = self.overlay.consensus_states.get(client_id).and_then(|cs| cs.get(&height));
+		match cached {
+			Some(consensus_state) => Ok(consensus_state.clone()),
+			None => self.inner.consensus_state(client_id, height),
+		}
+	}
+
+	fn host_client_type(&self) -> String {
+		self.inner.host_client_type()
+	}
+
+	/// The host context only returns consensus states, not their heights. When both the cache
+	/// and the host have a state above `height`, the one of the host comes first unless it is
+	/// also the next state of the host above, or at, the height of the cached one.
+	fn next_consensus_state(
+		&self,
+		client_id: &ClientId,
+		height: Height,
+	) -> Result<Option<Ctx::AnyConsensusState>, Ics02Error> {
+		let cached = self
+			.overlay
+			.consensus_states
+			.get(client_id)
+			.and_then(|cs| cs.range((Excluded(height), Unbounded)).next());
+		let inner = self.inner.next_consensus_state(client_id, height)?;
+		match (cached, inner) {
+			(Some((cached_height, cached)), Some(inner)) => {
+				let at_cached = self.inner.maybe_consensus_state(client_id, *cached_height)?;
+				let after_cached = self.inner.next_consensus_state(client_id, *cached_height)?;
+				let inner = Some(inner);
+				if at_cached == inner || after_cached == inner {
+					Ok(Some(cached.clone()))
+				} else {
+					Ok(inner)
+				}
+			},
+			(Some((_, cached)), None) => Ok(Some(cached.clone())),
+			(None, inner) => Ok(inner),
+		}
+	}
+
+	/// See [`CachedContext::next_consensus_state`].
+	fn prev_consensus_state(
+		&self,
+		client_id: &ClientId,
+		height: Height,
+	) -> Result<Option<Ctx::AnyConsensusState>, Ics02Error> {
+		let cached = self
+			.overlay
+			.consensus_states
+			.get(client_id)
+			.and_then(|cs| cs.range(..height).next_back());
+		let inner = self.inner.prev_consensus_state(client_id, height)?;
+		match (cached, inner) {
+			(Some((cached_height, cached)), Some(inner)) => {
+				let at_cached = self.inner.maybe_consensus_state(client_id, *cached_height)?;
+				let before_cached = self.inner.prev_consensus_state(client_id, *cached_height)?;
+				let inner = Some(inner);
+				if at_cached == inner || before_cached == inner {
+					Ok(Some(cached.clone()))
+				} else {
+					Ok(inner)
+				}
+			},
+			(Some((_, cached)), None) => Ok(Some(cached.clone())),
+			(None, inner) => Ok(inner),
+		}
+	}
+
+	fn host_height(&self) -> Height {
+		self.inner.host_height()
+	}
+
+	fn host_timestamp(&self) -> Timestamp {
+		self.inner.host_timestamp()
+	}
+
+	fn host_consensus_state(
+		&self,
+		height: Height,
+		proof: Option<Vec<u8>>,
+	) -> Result<Ctx::AnyConsensusState, Ics02Error> {
+		self.inner.host_consensus_state(height, proof)
+	}
+
+	fn client_counter(&self) -> Result<u64, Ics02Error> {
+		Ok(self.inner.client_counter()? + self.overlay.new_clients)
+	}
+}
+
+impl<Ctx: ClientKeeper> ClientKeeper for CachedContext<Ctx> {
+	fn store_client_type(
+		&mut self,
+		client_id: ClientId,
+		client_type: ClientType,
+	) -> Result<(), Ics02Error> {
+		self.overlay.client_types.insert(client_id.clone(), client_type.clone());
+		self.defer(move |ctx| {
+			ctx.store_client_type(client_id.clone(), client_type.clone())
+				.map_err(Error::ics02_client)
+		});
+		Ok(())
+	}
+
+	fn store_client_state(
+		&mut self,
+		client_id: ClientId,
+		client_state: Ctx::AnyClientState,
+	) -> Result<(), Ics02Error> {
+		self.overlay.client_states.insert(client_id.clone(), client_state.clone());
+		self.defer(move |ctx| {
+			ctx.store_client_state(client_id.clone(), client_state.clone())
+				.map_err(Error::ics02_client)
+		});
+		Ok(())
+	}
+
+	fn store_consensus_state(
+		&mut self,
+		client_id: ClientId,
+		height: Height,
+		consensus_state: Ctx::AnyConsensusState,
+	) -> Result<(), Ics02Error> {
+		self.overlay
+			.consensus_states
+			.entry(client_id.clone())
+			.or_default()
+			.insert(height, consensus_state.clone());
+		self.defer(move |ctx| {
+			ctx.store_consensus_state(client_id.clone(), height, consensus_state.clone())
+				.map_err(Error::ics02_client)
+		});
+		Ok(())
+	}
+
+	fn increase_client_counter(&mut self) {
+		self.overlay.new_clients += 1;
+		self.defer(|ctx| {
+			ctx.increase_client_counter();
+			Ok(())
+		});
+	}
+
+	fn store_update_time(
+		&mut self,
+		client_id: ClientId,
+		height: Height,
+		timestamp: Timestamp,
+	) -> Result<(), Ics02Error> {
+		self.overlay.update_times.insert((client_id.clone(), height), timestamp);
+		self.defer(move |ctx| {
+			ctx.store_update_time(client_id.clone(), height, timestamp)
+				.map_err(Error::ics02_client)
+		});
+		Ok(())
+	}
+
+	fn store_update_height(
+		&mut self,
+		client_id: ClientId,
+		height: Height,
+		host_height: Height,
+	) -> Result<(), Ics02Error> {
+		self.overlay.update_heights.insert((client_id.clone(), height), host_height);
+		self.defer(move |ctx| {
+			ctx.store_update_height(client_id.clone(), height, host_height)
+				.map_err(Error::ics02_client)
+		});
+		Ok(())
+	}
+
+	fn validate_self_client(&self, client_state: &Ctx::AnyClientState) -> Result<(), Ics02Error> {
+		self.inner.validate_self_client(client_state)
+	}
+}
+
+impl<Ctx: ClientTypes + ConnectionReader> ConnectionReader for CachedContext<Ctx> {
+	fn connection_end(&self, conn_id: &ConnectionId) -> Result<ConnectionEnd, Ics03Error> {
+		match self.overlay.connections.get(conn_id) {
+			Some(connection_end) => Ok(connection_end.clone()),
+			None => self.inner.connection_end(conn_id),
+		}
+	}
+
+	fn host_oldest_height(&self) -> Height {
+		self.inner.host_oldest_height()
+	}
+
+	fn commitment_prefix(&self) -> CommitmentPrefix {
+		self.inner.commitment_prefix()
+	}
+
+	fn get_compatible_versions(&self) -> Vec<ConnectionVersion> {
+		self.inner.get_compatible_versions()
+	}
+
+	fn pick_version(
+		&self,
+		supported_versions: Vec<ConnectionVersion>,
+		counterparty_candidate_versions: Vec<ConnectionVersion>,
+	) -> Result<ConnectionVersion, Ics03Error> {
+		self.inner.pick_version(supported_versions, counterparty_candidate_versions)
+	}
+
+	fn connection_counter(&self) -> Result<u64, Ics03Error> {
+		Ok(self.inner.connection_counter()? + self.overlay.new_connections)
+	}
+}
+
+impl<Ctx: ClientTypes + ConnectionKeeper> ConnectionKeeper for CachedContext<Ctx> {
+	fn store_connection(
+		&mut self,
+		connection_id: ConnectionId,
+		connection_end: &ConnectionEnd,
+	) -> Result<(), Ics03Error> {
+		self.overlay.connections.insert(connection_id.clone(), connection_end.clone());
+		let connection_end = connection_end.clone();
+		self.defer(move |ctx| {
+			ctx.store_connection(connection_id.clone(), &connection_end)
+				.map_err(Error::ics03_connection)
+		});
+		Ok(())
+	}
+
+	fn store_connection_to_client(
+		&mut self,
+		connection_id: ConnectionId,
+		client_id: &ClientId,
+	) -> Result<(), Ics03Error> {
+		let client_id = client_id.clone();
+		self.defer(move |ctx| {
+			ctx.store_connection_to_client(connection_id.clone(), &client_id)
+				.map_err(Error::ics03_connection)
+		});
+		Ok(())
+	}
+
+	fn increase_connection_counter(&mut self) {
+		self.overlay.new_connections += 1;
+		self.defer(|ctx| {
+			ctx.increase_connection_counter();
+			Ok(())
+		});
+	}
+}
+
+impl<Ctx: ClientTypes + ChannelReader> ChannelReader for CachedContext<Ctx> {
+	fn channel_end(&self, port_channel_id: &(PortId, ChannelId)) -> Result<ChannelEnd, Ics04Error> {
+		match self.overlay.channels.get(port_channel_id) {
+			Some(channel_end) => Ok(channel_end.clone()),
+			None => self.inner.channel_end(port_channel_id),
+		}
+	}
+
+	/// Hosts may fail when a connection has no channel, which is not an error anymore if
+	/// channels were added to the connection in the cache.
+	fn connection_channels(
+		&self,
+		cid: &ConnectionId,
+	) -> Result<Vec<(PortId, ChannelId)>, Ics04Error> {
+		match self.overlay.connection_channels.get(cid) {
+			Some(cached) => {
+				let mut channels = self.inner.connection_channels(cid).unwrap_or_default();
+				for port_channel_id in cached {
+					if !channels.contains(port_channel_id) {
+						channels.push(port_channel_id.clone());
+					}
+				}
+				Ok(channels)
+			},
+			None => self.inner.connection_channels(cid),
+		}
+	}
+
+	fn get_next_sequence_send(
+		&self,
+		port_channel_id: &(PortId, ChannelId),
+	) -> Result<Sequence, Ics04Error> {
+		match self.overlay.next_sequences_send.get(port_channel_id) {
+			Some(seq) => Ok(*seq),
+			None => self.inner.get_next_sequence_send(port_channel_id),
+		}
+	}
+
+	fn get_next_sequence_recv(
+		&self,
+		port_channel_id: &(PortId, ChannelId),
+	) -> Result<Sequence, Ics04Error> {
+		match self.overlay.next_sequences_recv.get(port_channel_id) {
+			Some(seq) => Ok(*seq),
+			None => self.inner.get_next_sequence_recv(port_channel_id),
+		}
+	}
+
+	fn get_next_sequence_ack(
+		&self,
+		port_channel_id: &(PortId, ChannelId),
+	) -> Result<Sequence, Ics04Error> {
+		match self.overlay.next_sequences_ack.get(port_channel_id) {
+			Some(seq) => Ok(*seq),
+			None => self.inner.get_next_sequence_ack(port_channel_id),
+		}
+	}
+
+	fn get_packet_commitment(&self, key: &PacketKey) -> Result<PacketCommitment, Ics04Error> {
+		match self.overlay.packet_commitments.get(key) {
+			Some(Some(commitment)) => Ok(commitment.clone()),
+			Some(None) => Err(Ics04Error::packet_commitment_not_found(key.2)),
+			None => self.inner.get_packet_commitment(key),
+		}
+	}
+
+	fn get_packet_receipt(&self, key: &PacketKey) -> Result<Receipt, Ics04Error> {
+		if self.overlay.packet_receipts.contains(key) {
+			return Ok(Receipt::Ok)
+		}
+		self.inner.get_packet_receipt(key)
+	}
+
+	fn get_packet_acknowledgement(
+		&self,
+		key: &PacketKey,
+	) -> Result<AcknowledgementCommitment, Ics04Error> {
+		match self.overlay.packet_acknowledgements.get(key) {
+			Some(Some(ack)) => Ok(ack.clone()),
+			Some(None) => Err(Ics04Error::packet_acknowledgement_not_found(key.2)),
+			None => self.inner.get_packet_acknowledgement(key),
+		}
+	}
+
+	fn packet_commitment(
+		&self,
+		packet_data: Vec<u8>,
+		timeout_height: Height,
+		timeout_timestamp: Timestamp,
+	) -> PacketCommitment {
+		self.inner.packet_commitment(packet_data, timeout_height, timeout_timestamp)
+	}
+
+	fn ack_commitment(&self, ack: Acknowledgement) -> AcknowledgementCommitment {
+		self.inner.ack_commitment(ack)
+	}
+
+	fn hash(&self, value: Vec<u8>) -> Vec<u8> {
+		self.inner.hash(value)
+	}
+
+	fn client_update_time(
+		&self,
+		client_id: &ClientId,
+		height: Height,
+	) -> Result<Timestamp, Ics04Error> {
+		match self.overlay.update_times.get(&(client_id.clone(), height)) {
+			Some(timestamp) => Ok(*timestamp),
+			None => self.inner.client_update_time(client_id, height),
+		}
+	}
+
+	fn client_update_height(
+		&self,
+		client_id: &ClientId,
+		height: Height,
+	) -> Result<Height, Ics04Error> {
+		match self.overlay.update_heights.get(&(client_id.clone(), height)) {
+			Some(host_height) => Ok(*host_height),
+			None => self.inner.client_update_height(client_id, height),
+		}
+	}
+
+	fn channel_counter(&self) -> Result<u64, Ics04Error> {
+		Ok(self.inner.channel_counter()? + self.overlay.new_channels)
+	}
+
+	fn max_expected_time_per_block(&self) -> Duration {
+		self.inner.max_expected_time_per_block()
+	}
+
+	fn block_delay(&self, delay_period_time: Duration) -> u64 {
+		self.inner.block_delay(delay_period_time)
+	}
+}
+
+impl<Ctx: ClientTypes + ChannelKeeper> ChannelKeeper for CachedContext<Ctx> {
+	fn store_packet_commitment(
+		&mut self,
+		key: PacketKey,
+		commitment: PacketCommitment,
+	) -> Result<(), Ics04Error> {
+		self.overlay.packet_commitments.insert(key.clone(), Some(commitment.clone()));
+		self.defer(move |ctx| {
+			ctx.store_packet_commitment(key.clone(), commitment.clone())
+				.map_err(Error::ics04_channel)
+		});
+		Ok(())
+	}
+
+	fn store_send_packet(&mut self, key: PacketKey, packet: Packet) -> Result<(), Ics04Error> {
+		self.defer(move |ctx| {
+			ctx.store_send_packet(key.clone(), packet.clone()).map_err(Error::ics04_channel)
+		});
+		Ok(())
+	}
+
+	fn store_recv_packet(&mut self, key: PacketKey, packet: Packet) -> Result<(), Ics04Error> {
+		self.defer(move |ctx| {
+			ctx.store_recv_packet(key.clone(), packet.clone()).map_err(Error::ics04_channel)
+		});
+		Ok(())
+	}
+
+	fn delete_packet_commitment(&mut self, key: PacketKey) -> Result<(), Ics04Error> {
+		self.overlay.packet_commitments.insert(key.clone(), None);
+		self.defer(move |ctx| {
+			ctx.delete_packet_commitment(key.clone()).map_err(Error::ics04_channel)
+		});
+		Ok(())
+	}
+
+	fn store_packet_receipt(&mut self, key: PacketKey, receipt: Receipt) -> Result<(), Ics04Error> {
+		self.overlay.packet_receipts.insert(key.clone());
+		self.defer(move |ctx| {
+			ctx.store_packet_receipt(key.clone(), receipt.clone())
+				.map_err(Error::ics04_channel)
+		});
+		Ok(())
+	}
+
+	fn store_packet_acknowledgement(
+		&mut self,
+		key: PacketKey,
+		ack_commitment: AcknowledgementCommitment,
+	) -> Result<(), Ics04Error> {
+		self.overlay
+			.packet_acknowledgements
+			.insert(key.clone(), Some(ack_commitment.clone()));
+		self.defer(move |ctx| {
+			ctx.store_packet_acknowledgement(key.clone(), ack_commitment.clone())
+				.map_err(Error::ics04_channel)
+		});
+		Ok(())
+	}
+
+	fn delete_packet_acknowledgement(&mut self, key: PacketKey) -> Result<(), Ics04Error> {
+		self.overlay.packet_acknowledgements.insert(key.clone(), None);
+		self.defer(move |ctx| {
+			ctx.delete_packet_acknowledgement(key.clone()).map_err(Error::ics04_channel)
+		});
+		Ok(())
+	}
+
+	fn store_connection_channels(
+		&mut self,
+		conn_id: ConnectionId,
+		port_channel_id: &(PortId, ChannelId),
+	) -> Result<(), Ics04Error> {
+		let port_channel_id = port_channel_id.clone();
+		let channels = self.overlay.connection_channels.entry(conn_id.clone()).or_default();
+		channels.push(port_channel_id.clone());
+		self.defer(move |ctx| {
+			ctx.store_connection_channels(conn_id.clone(), &port_channel_id)
+				.map_err(Error::ics04_channel)
+		});
+		Ok(())
+	}
+
+	fn store_channel(
+		&mut self,
+		port_channel_id: (PortId, ChannelId),
+		channel_end: &ChannelEnd,
+	) -> Result<(), Ics04Error> {
+		self.overlay.channels.insert(port_channel_id.clone(), channel_end.clone());
+		let channel_end = channel_end.clone();
+		self.defer(move |ctx| {
+			ctx.store_channel(port_channel_id.clone(), &channel_end)
+				.map_err(Error::ics04_channel)
+		});
+		Ok(())
+	}
+
+	fn store_next_sequence_send(
+		&mut self,
+		port_channel_id: (PortId, ChannelId),
+		seq: Sequence,
+	) -> Result<(), Ics04Error> {
+		self.overlay.next_sequences_send.insert(port_channel_id.clone(), seq);
+		self.defer(move |ctx| {
+			ctx.store_next_sequence_send(port_channel_id.clone(), seq)
+				.map_err(Error::ics04_channel)
+		});
+		Ok(())
+	}
+
+	fn store_next_sequence_recv(
+		&mut self,
+		port_channel_id: (PortId, ChannelId),
+		seq: Sequence,
+	) -> Result<(), Ics04Error> {
+		self.overlay.next_sequences_recv.insert(port_channel_id.clone(), seq);
+		self.defer(move |ctx| {
+			ctx.store_next_sequence_recv(port_channel_id.clone(), seq)
+				.map_err(Error::ics04_channel)
+		});
+		Ok(())
+	}
+
+	fn store_next_sequence_ack(
+		&mut self,
+		port_channel_id: (PortId, ChannelId),
+		seq: Sequence,
+	) -> Result<(), Ics04Error> {
+		self.overlay.next_sequences_ack.insert(port_channel_id.clone(), seq);
+		self.defer(move |ctx| {
+			ctx.store_next_sequence_ack(port_channel_id.clone(), seq)
+				.map_err(Error::ics04_channel)
+		});
+		Ok(())
+	}
+
+	fn increase_channel_counter(&mut self) {
+		self.overlay.new_channels += 1;
+		self.defer(|ctx| {
+			ctx.increase_channel_counter();
+			Ok(())
+		});
+	}
+}
+
+impl<Ctx: ClientTypes + CapabilityReader> CapabilityReader for CachedContext<Ctx> {
+	fn get_capability_index(&self, name: &CapabilityName) -> Result<Option<u64>, Ics05Error> {
+		match self.overlay.capabilities.get(name) {
+			Some(index) => Ok(*index),
+			None => self.inner.get_capability_index(name),
+		}
+	}
+
+	fn get_capability_owner(&self, name: &CapabilityName) -> Result<Option<ModuleId>, Ics05Error> {
+		match self.overlay.capability_owners.get(name) {
+			Some(owner) => Ok(owner.clone()),
+			None => self.inner.get_capability_owner(name),
+		}
+	}
+
+	fn get_next_capability_index(&self) -> Result<u64, Ics05Error> {
+		match self.overlay.next_capability_index {
+			Some(index) => Ok(index),
+			None => self.inner.get_next_capability_index(),
+		}
+	}
+}
+
+impl<Ctx: ClientTypes + CapabilityKeeper> CapabilityKeeper for CachedContext<Ctx> {
+	fn store_capability_index(
+		&mut self,
+		name: CapabilityName,
+		index: Option<u64>,
+	) -> Result<(), Ics05Error> {
+		self.overlay.capabilities.insert(name.clone(), index);
+		self.defer(move |ctx| {
+			ctx.store_capability_index(name.clone(), index).map_err(Error::ics05_port)
+		});
+		Ok(())
+	}
+
+	fn store_capability_owner(
+		&mut self,
+		name: CapabilityName,
+		owner: Option<ModuleId>,
+	) -> Result<(), Ics05Error> {
+		self.overlay.capability_owners.insert(name.clone(), owner.clone());
+		self.defer(move |ctx| {
+			ctx.store_capability_owner(name.clone(), owner.clone())
+				.map_err(Error::ics05_port)
+		});
+		Ok(())
+	}
+
+	fn store_next_capability_index(&mut self, index: u64) -> Result<(), Ics05Error> {
+		self.overlay.next_capability_index = Some(index);
+		self.defer(move |ctx| ctx.store_next_capability_index(index).map_err(Error::ics05_port));
+		Ok(())
+	}
+}
+
+impl<Ctx: ClientTypes + PortReader> PortReader for CachedContext<Ctx> {
+	fn lookup_module_by_port(&self, port_id: &PortId) -> Result<ModuleId, Ics05Error> {
+		match self.overlay.port_modules.get(port_id) {
+			Some(Some(module_id)) => Ok(module_id.clone()),
+			Some(None) => Err(Ics05Error::unknown_port(port_id.clone())),
+			None => self.inner.lookup_module_by_port(port_id),
+		}
+	}
+
+	fn lookup_ports_by_prefix(&self, prefix: &str) -> Result<Vec<(PortId, ModuleId)>, Ics05Error> {
+		let mut ports: BTreeMap<PortId, ModuleId> =
+			self.inner.lookup_ports_by_prefix(prefix)?.into_iter().collect();
+		for (port_id, module_id) in &self.overlay.port_modules {
+			if !port_id.as_str().starts_with(prefix) {
+				continue
+			}
+			match module_id {
+				Some(module_id) => ports.insert(port_id.clone(), module_id.clone()),
+				None => ports.remove(port_id),
+			};
+		}
+		Ok(ports.into_iter().collect())
+	}
+
+	fn get_port_prefixes(&self) -> Result<Vec<(String, ModuleId)>, Ics05Error> {
+		let mut prefixes: BTreeMap<String, ModuleId> =
+			self.inner.get_port_prefixes()?.into_iter().collect();
+		prefixes.extend(self.overlay.port_prefixes.clone());
+		Ok(prefixes.into_iter().collect())
+	}
+}
+
+impl<Ctx: ClientTypes + PortKeeper> PortKeeper for CachedContext<Ctx> {
+	fn store_port_module(
+		&mut self,
+		port_id: PortId,
+		module_id: Option<ModuleId>,
+	) -> Result<(), Ics05Error> {
+		self.overlay.port_modules.insert(port_id.clone(), module_id.clone());
+		self.defer(move |ctx| {
+			ctx.store_port_module(port_id.clone(), module_id.clone())
+				.map_err(Error::ics05_port)
+		});
+		Ok(())
+	}
+
+	fn store_port_prefix(&mut self, prefix: String, module_id: ModuleId) -> Result<(), Ics05Error> {
+		self.overlay.port_prefixes.insert(prefix.clone(), module_id.clone());
+		self.defer(move |ctx| {
+			ctx.store_port_prefix(prefix.clone(), module_id.clone())
+				.map_err(Error::ics05_port)
+		});
+		Ok(())
+	}
+}
+
+impl<Ctx: ReaderContext> ReaderContext for CachedContext<Ctx> {
+	fn gas_meter(&self) -> &dyn GasMeter {
+		self.inner.gas_meter()
+	}
+}
+
+impl<Ctx: Ics26Context> Ics26Context for CachedContext<Ctx> {
+	type Router = Ctx::Router;
+
+	fn router(&self) -> &Self::Router {
+		self.inner.router()
+	}
+
+	fn router_mut(&mut self) -> &mut Self::Router {
+		self.inner.router_mut()
+	}
+}
+
+impl<Ctx> CachedContext<Ctx>
+where
+	Ctx: ClientTypes + BankReader,
+	<Ctx as BankReader>::AccountId: Clone + Ord + Send + Sync + 'static,
+{
+	fn bank_flow(
+		&self,
+		account: &<Ctx as BankReader>::AccountId,
+		denom: &PrefixedDenom,
+	) -> Result<BankFlow, Ics20Error> {
+		match self.overlay.bank_flows.get(account, denom) {
+			Some(flow) => Ok(flow),
+			None => {
+				let balance = self.inner.get_balance(account, denom)?;
+				let zero = Amount::from(0u64);
+				Ok(BankFlow { balance, credited: zero, debited: zero })
+			},
+		}
+	}
+
+	fn credit(
+		&mut self,
+		account: &<Ctx as BankReader>::AccountId,
+		amt: &PrefixedCoin,
+	) -> Result<(), Ics20Error> {
+		let mut flow = self.bank_flow(account, &amt.denom)?;
+		flow.credited = flow
+			.credited
+			.checked_add(amt.amount)
+			.ok_or_else(|| Ics20Error::balance_overflow(amt.denom.to_string()))?;
+		self.overlay.bank_flows.insert(account.clone(), amt.denom.clone(), flow);
+		Ok(())
+	}
+
+	/// Takes `amt` from `account`, failing if its cached balance does not cover it. The host must
+	/// still hold the part of the debits of the account its credits do not cover upon commit.
+	fn debit(
+		&mut self,
+		account: &<Ctx as BankReader>::AccountId,
+		amt: &PrefixedCoin,
+	) -> Result<(), Ics20Error> {
+		let overflow = || Ics20Error::balance_overflow(amt.denom.to_string());
+		let mut flow = self.bank_flow(account, &amt.denom)?;
+		let balance = flow.cached_balance().ok_or_else(overflow)?;
+		if balance < amt.amount {
+			return Err(Ics20Error::insufficient_funds(
+				amt.denom.to_string(),
+				balance.to_string(),
+				amt.amount.to_string(),
+			))
+		}
+		flow.debited = flow.debited.checked_add(amt.amount).ok_or_else(overflow)?;
+		self.overlay.bank_flows.insert(account.clone(), amt.denom.clone(), flow);
+
+		let (account, denom, required) =
+			(account.clone(), amt.denom.clone(), flow.required_balance());
+		self.check(move |ctx| {
+			let balance = ctx
+				.get_balance(&account, &denom)
+				.map_err(Error::ics20_fungible_token_transfer)?;
+			if balance < required {
+				return Err(Error::ics20_fungible_token_transfer(Ics20Error::insufficient_funds(
+					denom.to_string(),
+					balance.to_string(),
+					required.to_string(),
+				)))
+			}
+			Ok(())
+		});
+		Ok(())
+	}
+}
+
+impl<Ctx> BankReader for CachedContext<Ctx>
+where
+	Ctx: ClientTypes + BankReader,
+	<Ctx as BankReader>::AccountId: Clone + Ord + Send + Sync + 'static,
+{
+	type AccountId = <Ctx as BankReader>::AccountId;
+
+	fn get_balance(
+		&self,
+		account: &Self::AccountId,
+		denom: &PrefixedDenom,
+	) -> Result<Amount, Ics20Error> {
+		let flow = self.bank_flow(account, denom)?;
+		flow.cached_balance()
+			.ok_or_else(|| Ics20Error::balance_overflow(denom.to_string()))
+	}
+}
+
+impl<Ctx> BankKeeper for CachedContext<Ctx>
+where
+	Ctx: ClientTypes + BankKeeper + BankReader<AccountId = <Ctx as BankKeeper>::AccountId>,
+	<Ctx as BankKeeper>::AccountId: Clone + Ord + Send + Sync + 'static,
+{
+	type AccountId = <Ctx as BankKeeper>::AccountId;
+
+	fn send_coins(
+		&mut self,
+		from: &Self::AccountId,
+		to: &Self::AccountId,
+		amt: &PrefixedCoin,
+	) -> Result<(), Ics20Error> {
+		self.debit(from, amt)?;
+		self.credit(to, amt)?;
+		let (from, to, amt) = (from.clone(), to.clone(), amt.clone());
+		self.defer(move |ctx| {
+			ctx.send_coins(&from, &to, &amt).map_err(Error::ics20_fungible_token_transfer)
+		});
+		Ok(())
+	}
+
+	fn mint_coins(
+		&mut self,
+		account: &Self::AccountId,
+		amt: &PrefixedCoin,
+	) -> Result<(), Ics20Error> {
+		self.credit(account, amt)?;
+		let (account, amt) = (account.clone(), amt.clone());
+		self.defer(move |ctx| {
+			ctx.mint_coins(&account, &amt).map_err(Error::ics20_fungible_token_transfer)
+		});
+		Ok(())
+	}
+
+	fn burn_coins(
+		&mut self,
+		account: &Self::AccountId,
+		amt: &PrefixedCoin,
+	) -> Result<(), Ics20Error> {
+		self.debit(account, amt)?;
+		let (account, amt) = (account.clone(), amt.clone());
+		self.defer(move |ctx| {
+			ctx.burn_coins(&account, &amt).map_err(Error::ics20_fungible_token_transfer)
+		});
+		Ok(())
+	}
+}
+
+impl<Ctx> Ics20Keeper for CachedContext<Ctx>
+where
+	Ctx: ClientTypes + Ics20Keeper + BankReader<AccountId = <Ctx as Ics20Keeper>::AccountId>,
+	<Ctx as Ics20Keeper>::AccountId: Clone + Ord + Send + Sync + 'static,
+{
+	type AccountId = <Ctx as Ics20Keeper>::AccountId;
+
+	fn store_denom_trace(
+		&mut self,
+		trace_hash: String,
+		denom: PrefixedDenom,
+	) -> Result<(), Ics20Error> {
+		self.overlay.denom_traces.insert(trace_hash.clone(), denom.clone());
+		self.defer(move |ctx| {
+			ctx.store_denom_trace(trace_hash.clone(), denom.clone())
+				.map_err(Error::ics20_fungible_token_transfer)
+		});
+		Ok(())
+	}
+
+	fn store_transfer_disabled(
+		&mut self,
+		key: EnablementKey,
+		reason: Option<String>,
+	) -> Result<(), Ics20Error> {
+		self.overlay.transfers_disabled.insert(key.clone(), reason.clone());
+		self.defer(move |ctx| {
+			ctx.store_transfer_disabled(key.clone(), reason.clone())
+				.map_err(Error::ics20_fungible_token_transfer)
+		});
+		Ok(())
+	}
+
+	fn store_total_escrow(
+		&mut self,
+		denom: &PrefixedDenom,
+		amount: Amount,
+	) -> Result<(), Ics20Error> {
+		self.overlay.total_escrows.insert(denom.clone(), amount);
+		let denom = denom.clone();
+		self.defer(move |ctx| {
+			ctx.store_total_escrow(&denom, amount)
+				.map_err(Error::ics20_fungible_token_transfer)
+		});
+		Ok(())
+	}
+
+	fn register_denom_metadata(
+		&mut self,
+		denom: &PrefixedDenom,
+		metadata: DenomMetadata,
+	) -> Result<(), Ics20Error> {
+		let denom = denom.clone();
+		self.defer(move |ctx| {
+			ctx.register_denom_metadata(&denom, metadata.clone())
+				.map_err(Error::ics20_fungible_token_transfer)
+		});
+		Ok(())
+	}
+
+	fn on_recv_packet_hook(
+		&mut self,
+		packet: &Packet,
+		data: &PacketData,
+	) -> Result<(), Ics20Error> {
+		let (packet, data) = (packet.clone(), data.clone());
+		self.defer(move |ctx| {
+			ctx.on_recv_packet_hook(&packet, &data)
+				.map_err(Error::ics20_fungible_token_transfer)
+		});
+		Ok(())
+	}
+}
+
+impl<Ctx: ClientTypes + Ics20Reader> Ics20Reader for CachedContext<Ctx> {
+	type AccountId = <Ctx as Ics20Reader>::AccountId;
+	type HostFunctions = <Ctx as Ics20Reader>::HostFunctions;
+
+	fn get_port(&self) -> Result<PortId, Ics20Error> {
+		self.inner.get_port()
+	}
+
+	fn get_channel_escrow_address(
+		&self,
+		port_id: &PortId,
+		channel_id: ChannelId,
+	) -> Result<Self::AccountId, Ics20Error> {
+		self.inner.get_channel_escrow_address(port_id, channel_id)
+	}
+
+	fn escrow_address_scheme(&self) -> EscrowAddressScheme {
+		self.inner.escrow_address_scheme()
+	}
+
+	fn escrow_account(&self, address: Vec<u8>) -> Result<Self::AccountId, Ics20Error> {
+		self.inner.escrow_account(address)
+	}
+
+	fn is_send_enabled(&self) -> bool {
+		self.inner.is_send_enabled()
+	}
+
+	fn is_receive_enabled(&self) -> bool {
+		self.inner.is_receive_enabled()
+	}
+
+	fn get_transfer_disabled(&self, key: &EnablementKey) -> Result<Option<String>, Ics20Error> {
+		match self.overlay.transfers_disabled.get(key) {
+			Some(reason) => Ok(reason.clone()),
+			None => self.inner.get_transfer_disabled(key),
+		}
+	}
+
+	fn get_total_escrow(&self, denom: &PrefixedDenom) -> Result<Amount, Ics20Error> {
+		match self.overlay.total_escrows.get(denom) {
+			Some(amount) => Ok(*amount),
+			None => self.inner.get_total_escrow(denom),
+		}
+	}
+
+	fn get_denom_trace(&self, trace_hash: &str) -> Result<Option<PrefixedDenom>, Ics20Error> {
+		match self.overlay.denom_traces.get(trace_hash) {
+			Some(denom) => Ok(Some(denom.clone())),
+			None => self.inner.get_denom_trace(trace_hash),
+		}
+	}
+
+	/// Denom traces are never removed, the page of the host is completed with the cached traces.
+	fn get_denom_traces(
+		&self,
+		start_after: Option<&str>,
+		limit: u64,
+	) -> Result<Vec<(String, PrefixedDenom)>, Ics20Error> {
+		let mut traces: BTreeMap<String, PrefixedDenom> =
+			self.inner.get_denom_traces(start_after, limit)?.into_iter().collect();
+		let cached = self
+			.overlay
+			.denom_traces
+			.iter()
+			.filter(|(hash, _)| start_after.map_or(true, |start| hash.as_str() > start));
+		traces.extend(cached.map(|(hash, denom)| (hash.clone(), denom.clone())));
+		Ok(traces.into_iter().take(limit as usize).collect())
+	}
+
+	fn denom_trace_hash(&self, denom: &PrefixedDenom) -> String {
+		self.inner.denom_trace_hash(denom)
+	}
+
+	fn ibc_denom(&self, denom: &PrefixedDenom) -> String {
+		self.inner.ibc_denom(denom)
+	}
+}
+
+impl<Ctx> Ics20Context for CachedContext<Ctx>
+where
+	Ctx: Ics20Context + BankReader<AccountId = <Ctx as Ics20Context>::AccountId>,
+	<Ctx as Ics20Context>::AccountId: Clone + Ord + Send + Sync + 'static,
+{
+	type AccountId = <Ctx as Ics20Context>::AccountId;
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::sync::Mutex;
+
+	use crate::{
+		core::{
+			ics02_client::msgs::{create_client::MsgCreateAnyClient, ClientMsg},
+			ics03_connection::{
+				connection::State as ConnectionState,
+				msgs::conn_open_init::{
+					test_util::get_dummy_raw_msg_conn_open_init, MsgConnectionOpenInit,
+				},
+				version::get_compatible_versions,
+			},
+			ics04_channel::{
+				channel::{Counterparty, Order},
+				msgs::chan_open_init::{
+					test_util::get_dummy_raw_msg_chan_open_init, MsgChannelOpenInit,
+				},
+				Version,
+			},
+			ics26_routing::{
+				context::{Module, ModuleOutputBuilder, RouterBuilder},
+				handler::dispatch,
+			},
+		},
+		mock::{
+			client_state::{MockClientState, MockConsensusState},
+			context::{MockClientTypes, MockContext, MockIbcStore, MockRouterBuilder},
+			header::MockHeader,
+		},
+		test_utils::get_dummy_account_id,
+		tx_msg::Msg,
+	};
+
+	#[test]
+	fn test_cached_context() {
+		let mut cached = CachedContext::new(MockContext::<MockClientTypes>::default());
+		let height = Height::new(0, 42);
+		let create_client = || {
+			let msg = MsgCreateAnyClient::new(
+				MockClientState::new(MockHeader::new(height).into()).into(),
+				MockConsensusState::new(MockHeader::new(height)).into(),
+				get_dummy_account_id(),
+			)
+			.unwrap();
+			Ics26Envelope::Ics2Msg(ClientMsg::CreateClient(msg))
+		};
+		let client_id = ClientId::new(&MockClientState::client_type(), 0).unwrap();
+
+		// the writes are only visible through the cache until they are committed
+		dispatch(&mut cached, create_client()).unwrap();
+		assert!(cached.client_state(&client_id).is_ok());
+		assert_eq!(cached.client_counter().unwrap(), 1);
+		assert!(cached.inner().client_state(&client_id).is_err());
+		cached.rollback();
+		assert!(cached.client_state(&client_id).is_err());
+		assert_eq!(cached.client_counter().unwrap(), 0);
+
+		dispatch(&mut cached, create_client()).unwrap();
+		assert!(cached.is_dirty());
+		cached.commit().unwrap();
+		assert!(!cached.is_dirty());
+		assert!(cached.inner().client_state(&client_id).is_ok());
+		assert_eq!(cached.inner().client_counter().unwrap(), 1);
+
+		// consensus states are searched both in the cache and in the host
+		for h in [40, 45] {
+			let consensus_state = MockConsensusState::new(MockHeader::new(Height::new(0, h)));
+			cached
+				.store_consensus_state(client_id.clone(), Height::new(0, h), consensus_state.into())
+				.unwrap();
+		}
+		let consensus_state =
+			|h| Some(cached.consensus_state(&client_id, Height::new(0, h)).unwrap());
+		let next = cached.next_consensus_state(&client_id, Height::new(0, 1)).unwrap();
+		assert_eq!(next, consensus_state(40));
+		let next = cached.next_consensus_state(&client_id, Height::new(0, 40)).unwrap();
+		assert_eq!(next, consensus_state(42));
+		let prev = cached.prev_consensus_state(&client_id, Height::new(0, 45)).unwrap();
+		assert_eq!(prev, consensus_state(42));
+		let prev = cached.prev_consensus_state(&client_id, Height::new(0, 42)).unwrap();
+		assert_eq!(prev, consensus_state(40));
+		assert_eq!(cached.next_consensus_state(&client_id, Height::new(0, 45)).unwrap(), None);
+	}
+
+	#[test]
+	fn test_cached_channel_writes() {
+		let mut cached = CachedContext::new(MockContext::<MockClientTypes>::default());
+		let port_channel = (PortId::transfer(), ChannelId::new(0));
+		let key = (port_channel.0.clone(), port_channel.1, Sequence::from(1));
+		let channel_end = ChannelEnd::default();
+		let commitment = PacketCommitment::from(vec![1]);
+
+		cached.store_channel(port_channel.clone(), &channel_end).unwrap();
+		cached.store_next_sequence_send(port_channel.clone(), 2.into()).unwrap();
+		cached.store_packet_commitment(key.clone(), commitment.clone()).unwrap();
+		assert_eq!(cached.channel_end(&port_channel).unwrap(), channel_end);
+		assert_eq!(cached.get_next_sequence_send(&port_channel).unwrap(), 2.into());
+		assert_eq!(cached.get_packet_commitment(&key).unwrap(), commitment);
+		assert!(cached.inner().channel_end(&port_channel).is_err());
+		assert!(cached.inner().get_next_sequence_send(&port_channel).is_err());
+		assert!(cached.inner().get_packet_commitment(&key).is_err());
+
+		cached.commit().unwrap();
+		assert_eq!(cached.inner().channel_end(&port_channel).unwrap(), channel_end);
+		assert_eq!(cached.inner().get_next_sequence_send(&port_channel).unwrap(), 2.into());
+		assert_eq!(cached.inner().get_packet_commitment(&key).unwrap(), commitment);
+
+		// deleted commitments are hidden from the readers of the cache until the deletion is
+		// committed
+		cached.delete_packet_commitment(key.clone()).unwrap();
+		assert!(cached.get_packet_commitment(&key).is_err());
+		assert!(cached.inner().get_packet_commitment(&key).is_ok());
+		cached.rollback();
+		assert_eq!(cached.get_packet_commitment(&key).unwrap(), commitment);
+	}
+
+	/// A module writing to the store it shares with the context before rejecting channels.
+	struct RejectingModule(Arc<Mutex<MockIbcStore<MockClientTypes>>>);
+
+	impl Module for RejectingModule {
+		fn on_chan_open_init(
+			&mut self,
+			_output: &mut ModuleOutputBuilder,
+			_order: Order,
+			_connection_hops: &[ConnectionId],
+			port_id: &PortId,
+			channel_id: &ChannelId,
+			_counterparty: &Counterparty,
+			_version: &Version,
+		) -> Result<(), Ics04Error> {
+			let port_channel = (port_id.clone(), *channel_id);
+			self.0.lock().unwrap().next_sequence_send.insert(port_channel, 1.into());
+			Err(Ics04Error::app_module("channel rejected".to_string()))
+		}
+
+		fn on_chan_open_try(
+			&mut self,
+			_output: &mut ModuleOutputBuilder,
+			_order: Order,
+			_connection_hops: &[ConnectionId],
+			_port_id: &PortId,
+			_channel_id: &ChannelId,
+			_counterparty: &Counterparty,
+			_version: &Version,
+			counterparty_version: &Version,
+		) -> Result<Version, Ics04Error> {
+			Ok(counterparty_version.clone())
+		}
+	}
+
+	#[test]
+	fn test_cached_deliver_rollback() {
+		let msg = MsgChannelOpenInit::try_from(get_dummy_raw_msg_chan_open_init()).unwrap();
+		let msg_conn_init =
+			MsgConnectionOpenInit::try_from(get_dummy_raw_msg_conn_open_init()).unwrap();
+		let connection_end = ConnectionEnd::new(
+			ConnectionState::Init,
+			msg_conn_init.client_id,
+			msg_conn_init.counterparty,
+			get_compatible_versions(),
+			msg_conn_init.delay_period,
+		);
+		let module_id: ModuleId = "rejecting".parse().unwrap();
+
+		let ctx = MockContext::<MockClientTypes>::default()
+			.with_connection(ConnectionId::default(), connection_end);
+		let module = RejectingModule(ctx.ibc_store_share());
+		let router = MockRouterBuilder::default()
+			.add_route(module_id.clone(), module)
+			.unwrap()
+			.build();
+		let mut ctx = ctx.with_router(router);
+		ctx.scope_port_to_module(msg.port_id.clone(), module_id);
+		let mut cached = CachedContext::new(ctx);
+
+		let client_id = ClientId::new(&MockClientState::client_type(), 7).unwrap();
+		cached
+			.store_client_type(client_id.clone(), MockClientState::client_type())
+			.unwrap();
+		let port_channel = (msg.port_id.clone(), ChannelId::new(0));
+		assert!(cached.deliver(msg.to_any()).is_err());
+
+		// neither the writes made through the cache nor those of the module are kept
+		assert!(!cached.is_dirty());
+		assert!(cached.client_type(&client_id).is_err());
+		assert!(cached.channel_end(&port_channel).is_err());
+		assert!(cached.inner().get_next_sequence_send(&port_channel).is_err());
+	}
+
+	/// A bank holding the balances of accounts identified by name.
+	#[derive(Clone, Debug, Default, PartialEq, Eq)]
+	struct MockBank(BTreeMap<(String, PrefixedDenom), Amount>);
+
+	impl ClientTypes for MockBank {
+		type AnyClientMessage = <MockClientTypes as ClientTypes>::AnyClientMessage;
+		type AnyClientState = <MockClientTypes as ClientTypes>::AnyClientState;
+		type AnyConsensusState = <MockClientTypes as ClientTypes>::AnyConsensusState;
+		type ClientDef = <MockClientTypes as ClientTypes>::ClientDef;
+	}
+
+	impl BankReader for MockBank {
+		type AccountId = String;
+
+		fn get_balance(
+			&self,
+			account: &String,
+			denom: &PrefixedDenom,
+		) -> Result<Amount, Ics20Error> {
+			let balance = self.0.get(&(account.clone(), denom.clone())).copied();
+			Ok(balance.unwrap_or_else(|| 0u64.into()))
+		}
+	}
+
+	impl BankKeeper for MockBank {
+		type AccountId = String;
+
+		fn send_coins(
+			&mut self,
+			from: &String,
+			to: &String,
+			amt: &PrefixedCoin,
+		) -> Result<(), Ics20Error> {
+			self.burn_coins(from, amt)?;
+			self.mint_coins(to, amt)
+		}
+
+		fn mint_coins(&mut self, account: &String, amt: &PrefixedCoin) -> Result<(), Ics20Error> {
+			let balance = self.get_balance(account, &amt.denom)?;
+			let balance = balance.checked_add(amt.amount).ok_or_else(Ics20Error::invalid_token)?;
+			self.0.insert((account.clone(), amt.denom.clone()), balance);
+			Ok(())
+		}
+
+		fn burn_coins(&mut self, account: &String, amt: &PrefixedCoin) -> Result<(), Ics20Error> {
+			let balance = self.get_balance(account, &amt.denom)?;
+			let balance = balance.checked_sub(amt.amount).ok_or_else(Ics20Error::invalid_token)?;
+			self.0.insert((account.clone(), amt.denom.clone()), balance);
+			Ok(())
+		}
+	}
+
+	#[test]
+	fn test_cached_bank() {
+		let denom: PrefixedDenom = "uatom".parse().unwrap();
+		let coin = |amount: u64| PrefixedCoin { denom: denom.clone(), amount: amount.into() };
+		let (alice, bob) = ("alice".to_string(), "bob".to_string());
+		let balance = |bank: &MockBank, account: &String| -> u64 {
+			bank.get_balance(account, &denom).unwrap().as_u256().as_u64()
+		};
+		let mut cached = CachedContext::new(MockBank::default());
+
+		// transfers are checked against the balances of the cache, and only made upon commit
+		assert!(cached.send_coins(&alice, &bob, &coin(1)).is_err());
+		cached.mint_coins(&alice, &coin(10)).unwrap();
+		cached.send_coins(&alice, &bob, &coin(4)).unwrap();
+		assert!(cached.burn_coins(&bob, &coin(5)).is_err());
+		assert_eq!(cached.get_balance(&alice, &denom).unwrap(), 6u64.into());
+		assert_eq!(cached.get_balance(&bob, &denom).unwrap(), 4u64.into());
+		assert_eq!(balance(cached.inner(), &bob), 0);
+
+		// caches are equal if they hold the same values
+		let mut other = CachedContext::new(MockBank::default());
+		other.mint_coins(&alice, &coin(10)).unwrap();
+		assert!(other != cached);
+		other.send_coins(&alice, &bob, &coin(4)).unwrap();
+		assert!(other == cached);
+
+		cached.commit().unwrap();
+		assert_eq!(balance(cached.inner(), &alice), 6);
+		assert_eq!(balance(cached.inner(), &bob), 4);
+
+		// a commit whose debits the host does not cover anymore applies none of its writes
+		cached.send_coins(&bob, &alice, &coin(4)).unwrap();
+		cached.mint_coins(&bob, &coin(1)).unwrap();
+		cached.inner.0.insert((bob.clone(), denom.clone()), 3u64.into());
+		assert!(cached.commit().is_err());
+		assert!(!cached.is_dirty());
+		assert_eq!(balance(cached.inner(), &alice), 6);
+		assert_eq!(balance(cached.inner(), &bob), 3);
+	}
+}
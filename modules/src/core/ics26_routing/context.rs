@@ -2,9 +2,13 @@ use crate::prelude::*;
 
 use crate::{
 	core::{
-		ics02_client::context::{ClientKeeper, ClientReader},
+		ics02_client::{
+			context::{ClientKeeper, ClientReader},
+			error::Error as Ics02Error,
+		},
 		ics03_connection::context::{ConnectionKeeper, ConnectionReader},
 		ics04_channel::{
+			acknowledgement::AcknowledgementEnvelope,
 			channel::{Counterparty, Order},
 			context::{ChannelKeeper, ChannelReader},
 			error::Error,
@@ -12,24 +16,68 @@ use crate::{
 			packet::Packet,
 			Version,
 		},
-		ics05_port::context::PortReader,
-		ics24_host::identifier::{ChannelId, ConnectionId, PortId},
+		ics05_port::{capabilities::CapabilityReader, context::PortKeeper},
+		ics24_host::identifier::{ChannelId, ClientId, ConnectionId, PortId},
 	},
 	events::ModuleEvent,
+	gas::{GasMeter, InfiniteGasMeter},
 	handler::HandlerOutputBuilder,
 	signer::Signer,
+	Height,
 };
 use alloc::borrow::{Borrow, Cow};
 use core::{any::Any, fmt, fmt::Debug, str::FromStr};
 use serde::{Deserialize, Serialize};
 
 /// This trait captures all the functional dependencies of needed in light client implementations
-pub trait ReaderContext: ClientKeeper + ClientReader + ConnectionReader + ChannelReader {}
+pub trait ReaderContext:
+	ClientKeeper + ClientReader + ConnectionReader + ChannelReader + CapabilityReader
+{
+	/// Returns the meter the work of the handlers is charged against. Work is not metered by
+	/// default.
+	fn gas_meter(&self) -> &dyn GasMeter {
+		&InfiniteGasMeter
+	}
+
+	/// Reads the consensus state of `client_id` at `height`, charging the read to the gas meter.
+	/// Clients should read the consensus states they verify client messages against through the
+	/// metered reads.
+	fn metered_consensus_state(
+		&self,
+		client_id: &ClientId,
+		height: Height,
+	) -> Result<Self::AnyConsensusState, Ics02Error> {
+		self.gas_meter().charge_consensus_state_read().map_err(Ics02Error::gas)?;
+		self.consensus_state(client_id, height)
+	}
+
+	/// See [`ReaderContext::metered_consensus_state`] and
+	/// [`ClientReader::next_consensus_state`].
+	fn metered_next_consensus_state(
+		&self,
+		client_id: &ClientId,
+		height: Height,
+	) -> Result<Option<Self::AnyConsensusState>, Ics02Error> {
+		self.gas_meter().charge_consensus_state_read().map_err(Ics02Error::gas)?;
+		self.next_consensus_state(client_id, height)
+	}
+
+	/// See [`ReaderContext::metered_consensus_state`] and
+	/// [`ClientReader::prev_consensus_state`].
+	fn metered_prev_consensus_state(
+		&self,
+		client_id: &ClientId,
+		height: Height,
+	) -> Result<Option<Self::AnyConsensusState>, Ics02Error> {
+		self.gas_meter().charge_consensus_state_read().map_err(Ics02Error::gas)?;
+		self.prev_consensus_state(client_id, height)
+	}
+}
 
 /// This trait captures all the functional dependencies (i.e., context) which the ICS26 module
 /// requires to be able to dispatch and process IBC messages. In other words, this is the
 /// representation of a chain from the perspective of the IBC module of that chain.
-pub trait Ics26Context: ConnectionKeeper + ChannelKeeper + PortReader + ReaderContext {
+pub trait Ics26Context: ConnectionKeeper + ChannelKeeper + PortKeeper + ReaderContext {
 	type Router: Router;
 
 	fn router(&self) -> &Self::Router;
@@ -74,7 +122,19 @@ impl Borrow<str> for ModuleId {
 }
 
 /// Types implementing this trait are expected to implement `From<GenericAcknowledgement>`
-pub trait Acknowledgement: AsRef<[u8]> {}
+pub trait Acknowledgement: AsRef<[u8]> {
+	/// Tells whether the acknowledgement reports the successful processing of its packet.
+	///
+	/// By default, the bytes are decoded as an [`AcknowledgementEnvelope`], and acknowledgements
+	/// in another format are considered failed.
+	fn is_successful(&self) -> bool {
+		AcknowledgementEnvelope::decode(self.as_ref())
+			.map(|ack| ack.is_successful())
+			.unwrap_or(false)
+	}
+}
+
+impl Acknowledgement for GenericAcknowledgement {}
 
 pub type WriteFn = dyn FnOnce(&mut dyn Any) -> Result<(), String>;
 
@@ -93,6 +153,42 @@ impl OnRecvPacketAck {
 pub type ModuleOutputBuilder = HandlerOutputBuilder<(), ModuleEvent>;
 
 pub trait Module: Send + Sync + AsAnyMut {
+	/// Returns the module wrapped by this one, if it is a middleware. The `_validate` hooks a
+	/// middleware does not override are those of the module it wraps.
+	fn inner_module(&self) -> Option<&dyn Module> {
+		None
+	}
+
+	/// Checks that the module accepts the opening of a channel, without modifying any state.
+	/// The `_validate` hooks are run ahead of the matching callbacks on execution, and on their
+	/// own by [`validate`](super::handler::validate).
+	#[allow(clippy::too_many_arguments)]
+	fn on_chan_open_init_validate(
+		&self,
+		order: Order,
+		connection_hops: &[ConnectionId],
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		counterparty: &Counterparty,
+		version: &Version,
+	) -> Result<(), Error> {
+		match self.inner_module() {
+			Some(inner) => inner.on_chan_open_init_validate(
+				order,
+				connection_hops,
+				port_id,
+				channel_id,
+				counterparty,
+				version,
+			),
+			None => Ok(()),
+		}
+	}
+
+	/// Called on the opening of a channel on a port bound to this module. Once the callback
+	/// succeeds, the capability of the channel is issued to the module, which recovers it with
+	/// [`CapabilityReader::lookup_channel_capability`], presenting the capability of the port, to
+	/// send packets on the channel and to write their acknowledgements.
 	#[allow(clippy::too_many_arguments)]
 	fn on_chan_open_init(
 		&mut self,
@@ -107,6 +203,32 @@ pub trait Module: Send + Sync + AsAnyMut {
 		Ok(())
 	}
 
+	#[allow(clippy::too_many_arguments)]
+	fn on_chan_open_try_validate(
+		&self,
+		order: Order,
+		connection_hops: &[ConnectionId],
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		counterparty: &Counterparty,
+		version: &Version,
+		counterparty_version: &Version,
+	) -> Result<(), Error> {
+		match self.inner_module() {
+			Some(inner) => inner.on_chan_open_try_validate(
+				order,
+				connection_hops,
+				port_id,
+				channel_id,
+				counterparty,
+				version,
+				counterparty_version,
+			),
+			None => Ok(()),
+		}
+	}
+
+	/// Like [`Module::on_chan_open_init`], for channels opened by the counterparty chain.
 	#[allow(clippy::too_many_arguments)]
 	fn on_chan_open_try(
 		&mut self,
@@ -120,6 +242,19 @@ pub trait Module: Send + Sync + AsAnyMut {
 		_counterparty_version: &Version,
 	) -> Result<Version, Error>;
 
+	fn on_chan_open_ack_validate(
+		&self,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		counterparty_version: &Version,
+	) -> Result<(), Error> {
+		match self.inner_module() {
+			Some(inner) =>
+				inner.on_chan_open_ack_validate(port_id, channel_id, counterparty_version),
+			None => Ok(()),
+		}
+	}
+
 	fn on_chan_open_ack(
 		&mut self,
 		_output: &mut ModuleOutputBuilder,
@@ -130,6 +265,17 @@ pub trait Module: Send + Sync + AsAnyMut {
 		Ok(())
 	}
 
+	fn on_chan_open_confirm_validate(
+		&self,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+	) -> Result<(), Error> {
+		match self.inner_module() {
+			Some(inner) => inner.on_chan_open_confirm_validate(port_id, channel_id),
+			None => Ok(()),
+		}
+	}
+
 	fn on_chan_open_confirm(
 		&mut self,
 		_output: &mut ModuleOutputBuilder,
@@ -139,6 +285,17 @@ pub trait Module: Send + Sync + AsAnyMut {
 		Ok(())
 	}
 
+	fn on_chan_close_init_validate(
+		&self,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+	) -> Result<(), Error> {
+		match self.inner_module() {
+			Some(inner) => inner.on_chan_close_init_validate(port_id, channel_id),
+			None => Ok(()),
+		}
+	}
+
 	fn on_chan_close_init(
 		&mut self,
 		_output: &mut ModuleOutputBuilder,
@@ -148,6 +305,17 @@ pub trait Module: Send + Sync + AsAnyMut {
 		Ok(())
 	}
 
+	fn on_chan_close_confirm_validate(
+		&self,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+	) -> Result<(), Error> {
+		match self.inner_module() {
+			Some(inner) => inner.on_chan_close_confirm_validate(port_id, channel_id),
+			None => Ok(()),
+		}
+	}
+
 	fn on_chan_close_confirm(
 		&mut self,
 		_output: &mut ModuleOutputBuilder,
@@ -157,6 +325,17 @@ pub trait Module: Send + Sync + AsAnyMut {
 		Ok(())
 	}
 
+	/// Checks that the module can process a received packet, without modifying any state. Unlike
+	/// [`Module::on_recv_packet`], which answers the packets it rejects with an error
+	/// acknowledgement, an error fails the message delivering the packet, e.g. for packets that
+	/// cannot be acknowledged at all.
+	fn on_recv_packet_validate(&self, packet: &Packet, relayer: &Signer) -> Result<(), Error> {
+		match self.inner_module() {
+			Some(inner) => inner.on_recv_packet_validate(packet, relayer),
+			None => Ok(()),
+		}
+	}
+
 	fn on_recv_packet(
 		&self,
 		_output: &mut ModuleOutputBuilder,
@@ -166,6 +345,19 @@ pub trait Module: Send + Sync + AsAnyMut {
 		OnRecvPacketAck::Nil(Box::new(|_| Ok(())))
 	}
 
+	fn on_acknowledgement_packet_validate(
+		&self,
+		packet: &Packet,
+		acknowledgement: &GenericAcknowledgement,
+		relayer: &Signer,
+	) -> Result<(), Error> {
+		match self.inner_module() {
+			Some(inner) =>
+				inner.on_acknowledgement_packet_validate(packet, acknowledgement, relayer),
+			None => Ok(()),
+		}
+	}
+
 	fn on_acknowledgement_packet(
 		&mut self,
 		_output: &mut ModuleOutputBuilder,
@@ -176,6 +368,13 @@ pub trait Module: Send + Sync + AsAnyMut {
 		Ok(())
 	}
 
+	fn on_timeout_packet_validate(&self, packet: &Packet, relayer: &Signer) -> Result<(), Error> {
+		match self.inner_module() {
+			Some(inner) => inner.on_timeout_packet_validate(packet, relayer),
+			None => Ok(()),
+		}
+	}
+
 	fn on_timeout_packet(
 		&mut self,
 		_output: &mut ModuleOutputBuilder,
@@ -213,6 +412,10 @@ impl<M: Any + Module> AsAnyMut for M {
 /// expose APIs to add new routes once constructed. Routes may only be added at the time of
 /// instantiation using the `RouterBuilder`.
 pub trait Router {
+	/// Returns a reference to a `Module` registered against the specified `ModuleId`, which
+	/// [`validate`](super::handler::validate) runs the checks of.
+	fn get_route(&self, module_id: &impl Borrow<ModuleId>) -> Option<&dyn Module>;
+
 	/// Returns a mutable reference to a `Module` registered against the specified `ModuleId`
 	fn get_route_mut(&mut self, module_id: &impl Borrow<ModuleId>) -> Option<&mut dyn Module>;
 
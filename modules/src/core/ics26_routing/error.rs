@@ -3,7 +3,7 @@ use flex_error::{define_error, TraceError};
 
 use crate::{
 	applications::transfer,
-	core::{ics02_client, ics03_connection, ics04_channel},
+	core::{ics02_client, ics03_connection, ics04_channel, ics05_port},
 };
 
 define_error! {
@@ -21,6 +21,10 @@ define_error! {
 			[ ics04_channel::error::Error ]
 			| _ | { "ICS04 channel error" },
 
+		Ics05Port
+			[ ics05_port::error::Error ]
+			| _ | { "ICS05 port error" },
+
 		Ics20FungibleTokenTransfer
 			[ transfer::error::Error ]
 			| _ | { "ICS20 fungible token transfer error" },
@@ -32,5 +36,9 @@ define_error! {
 		MalformedMessageBytes
 			[ TraceError<tendermint_proto::Error> ]
 			| _ | { "the message is malformed and cannot be decoded" },
+
+		OutOfGas
+			{ consumed: u64 }
+			| e | { format_args!("out of gas after consuming {0}", e.consumed) },
 	}
 }
@@ -5,9 +5,15 @@ use crate::{
 		ics03_connection::handler::dispatch as ics3_msg_dispatcher,
 		ics04_channel::{
 			handler::{
-				channel_callback as ics4_callback, channel_dispatch as ics4_msg_dispatcher,
-				channel_validate as ics4_validate, get_module_for_packet_msg,
+				channel_callback as ics4_callback,
+				channel_callback_validate as ics4_callback_validate,
+				channel_dispatch as ics4_msg_dispatcher, channel_validate as ics4_validate,
+				get_module_for_packet_batch_msg, get_module_for_packet_msg,
+				packet_batch_callback as ics4_packet_batch_callback,
+				packet_batch_callback_validate as ics4_packet_batch_callback_validate,
+				packet_batch_dispatch as ics4_packet_batch_msg_dispatcher,
 				packet_callback as ics4_packet_callback,
+				packet_callback_validate as ics4_packet_callback_validate,
 				packet_dispatch as ics4_packet_msg_dispatcher, recv_packet::RecvPacketResult,
 			},
 			packet::PacketResult,
@@ -15,10 +21,13 @@ use crate::{
 		ics26_routing::{
 			context::{Ics26Context, ModuleOutputBuilder, ReaderContext},
 			error::Error,
-			msgs::Ics26Envelope::{self, Ics2Msg, Ics3Msg, Ics4ChannelMsg, Ics4PacketMsg},
+			msgs::Ics26Envelope::{
+				self, Ics2Msg, Ics3Msg, Ics4ChannelMsg, Ics4PacketBatchMsg, Ics4PacketMsg,
+			},
 		},
 	},
 	events::IbcEvent,
+	gas::GasMeter,
 	handler::HandlerOutput,
 	prelude::*,
 };
@@ -60,12 +69,146 @@ where
 	message.try_into().map_err(Into::into)
 }
 
+/// Mimics the CheckTx ABCI interface for a single message: runs all the checks [`deliver`] would
+/// run on `message`, without modifying the `Ctx`.
+/// Returns the events that delivering `message` in the current state would generate.
+pub fn check<Ctx>(ctx: &Ctx, message: Any) -> Result<MsgReceipt, Error>
+where
+	Ctx: Ics26Context + ReaderContext,
+	Ics26Envelope<Ctx>: TryFrom<Any>,
+	Error: From<<Ics26Envelope<Ctx> as TryFrom<Any>>::Error>,
+{
+	let envelope = decode::<Ctx>(message)?;
+
+	let HandlerOutput { log, events, .. } = validate::<_>(ctx, envelope)?;
+
+	Ok(MsgReceipt { events, log })
+}
+
 /// Top-level ICS dispatch function. Routes incoming IBC messages to their corresponding module.
+/// Equivalent to [`execute`].
+pub fn dispatch<Ctx>(ctx: &mut Ctx, msg: Ics26Envelope<Ctx>) -> Result<HandlerOutput<()>, Error>
+where
+	Ctx: Ics26Context + ClientKeeper,
+{
+	execute(ctx, msg)
+}
+
+/// Runs all the ICS02, ICS03 and ICS04 checks on `msg`, along with those of the module it is
+/// routed to, without modifying the `Ctx`. Returns the log and events executing `msg` in the
+/// current state would produce.
+/// Only the validation hooks of modules are run, not their callbacks, so that a message may pass
+/// validation and still fail on execution.
+/// The message and its events are charged to the gas meter of the `Ctx`, along with the work done
+/// by the handlers, see [`gas`](crate::gas).
+pub fn validate<Ctx>(ctx: &Ctx, msg: Ics26Envelope<Ctx>) -> Result<HandlerOutput<()>, Error>
+where
+	Ctx: Ics26Context,
+{
+	let gas = ctx.gas_meter();
+	gas.charge_message().map_err(|_| Error::out_of_gas(gas.gas_consumed()))?;
+	metered(gas, validate_unmetered(ctx, msg))
+}
+
+fn validate_unmetered<Ctx>(ctx: &Ctx, msg: Ics26Envelope<Ctx>) -> Result<HandlerOutput<()>, Error>
+where
+	Ctx: Ics26Context,
+{
+	let gas = ctx.gas_meter();
+	let output = match msg {
+		Ics2Msg(msg) => {
+			let handler_output =
+				ics2_msg_dispatcher::<Ctx>(ctx, msg).map_err(Error::ics02_client)?;
+			charge_events(gas, handler_output.events.len())?;
+
+			HandlerOutput::builder()
+				.with_log(handler_output.log)
+				.with_events(handler_output.events)
+				.with_result(())
+		},
+
+		Ics3Msg(msg) => {
+			let handler_output =
+				ics3_msg_dispatcher::<_>(ctx, msg).map_err(Error::ics03_connection)?;
+			charge_events(gas, handler_output.events.len())?;
+
+			HandlerOutput::builder()
+				.with_log(handler_output.log)
+				.with_events(handler_output.events)
+				.with_result(())
+		},
+
+		Ics4ChannelMsg(msg) => {
+			let module_id = ics4_validate(ctx, &msg).map_err(Error::ics04_channel)?;
+			let (handler_builder, channel_result) =
+				ics4_msg_dispatcher::<_>(ctx, &msg).map_err(Error::ics04_channel)?;
+			charge_events(gas, handler_builder.events().len())?;
+
+			charge_module_callback(gas)?;
+			ics4_callback_validate(ctx, &module_id, &msg, &channel_result)
+				.map_err(Error::ics04_channel)?;
+
+			handler_builder.with_result(())
+		},
+
+		Ics4PacketMsg(msg) => {
+			let module_id = get_module_for_packet_msg(ctx, &msg).map_err(Error::ics04_channel)?;
+			let (handler_builder, packet_result) =
+				ics4_packet_msg_dispatcher::<_>(ctx, &msg).map_err(Error::ics04_channel)?;
+			charge_events(gas, handler_builder.events().len())?;
+
+			if !matches!(packet_result, PacketResult::Recv(RecvPacketResult::NoOp)) {
+				charge_module_callback(gas)?;
+				ics4_packet_callback_validate(ctx, &module_id, &msg)
+					.map_err(Error::ics04_channel)?;
+			}
+
+			handler_builder.with_result(())
+		},
+
+		Ics4PacketBatchMsg(msg) => {
+			let module_id =
+				get_module_for_packet_batch_msg(ctx, &msg).map_err(Error::ics04_channel)?;
+			let (handler_builder, packet_results) =
+				ics4_packet_batch_msg_dispatcher::<_>(ctx, &msg).map_err(Error::ics04_channel)?;
+			charge_events(gas, handler_builder.events().len())?;
+
+			for (packet, packet_result) in msg.packets().iter().zip(packet_results) {
+				if !matches!(packet_result, PacketResult::Recv(RecvPacketResult::NoOp)) {
+					charge_module_callback(gas)?;
+					ics4_packet_batch_callback_validate(ctx, &module_id, &msg, packet)
+						.map_err(Error::ics04_channel)?;
+				}
+			}
+
+			handler_builder.with_result(())
+		},
+	};
+
+	Ok(output)
+}
+
+/// Processes `msg` and applies its results to the `Ctx`, running all the checks [`validate`] does
+/// along the way, so that it does not need to be called beforehand.
 /// Returns a handler output with empty result of type `HandlerOutput<()>` which contains the log
 /// and events produced after processing the input `msg`.
 /// If this method returns an error, the runtime is expected to rollback all state modifications to
 /// the `Ctx` caused by all messages from the transaction that this `msg` is a part of.
-pub fn dispatch<Ctx>(ctx: &mut Ctx, msg: Ics26Envelope<Ctx>) -> Result<HandlerOutput<()>, Error>
+pub fn execute<Ctx>(ctx: &mut Ctx, msg: Ics26Envelope<Ctx>) -> Result<HandlerOutput<()>, Error>
+where
+	Ctx: Ics26Context + ClientKeeper,
+{
+	ctx.gas_meter()
+		.charge_message()
+		.map_err(|_| Error::out_of_gas(ctx.gas_meter().gas_consumed()))?;
+	let result = execute_unmetered(ctx, msg);
+	metered(ctx.gas_meter(), result)
+}
+
+fn execute_unmetered<Ctx>(
+	ctx: &mut Ctx,
+	msg: Ics26Envelope<Ctx>,
+) -> Result<HandlerOutput<()>, Error>
 where
 	Ctx: Ics26Context + ClientKeeper,
 {
@@ -73,6 +216,7 @@ where
 		Ics2Msg(msg) => {
 			let handler_output =
 				ics2_msg_dispatcher::<Ctx>(ctx, msg).map_err(Error::ics02_client)?;
+			charge_events(ctx.gas_meter(), handler_output.events.len())?;
 
 			// Apply the result to the context (host chain store).
 			ctx.store_client_result(handler_output.result).map_err(Error::ics02_client)?;
@@ -86,6 +230,7 @@ where
 		Ics3Msg(msg) => {
 			let handler_output =
 				ics3_msg_dispatcher::<_>(ctx, msg).map_err(Error::ics03_connection)?;
+			charge_events(ctx.gas_meter(), handler_output.events.len())?;
 
 			// Apply any results to the host chain store.
 			ctx.store_connection_result(handler_output.result)
@@ -101,10 +246,14 @@ where
 			let module_id = ics4_validate(ctx, &msg).map_err(Error::ics04_channel)?;
 			let (mut handler_builder, channel_result) =
 				ics4_msg_dispatcher::<_>(ctx, &msg).map_err(Error::ics04_channel)?;
+			charge_events(ctx.gas_meter(), handler_builder.events().len())?;
 
+			// The checks of the module are run along with its callback.
+			charge_module_callback(ctx.gas_meter())?;
 			let mut module_output = ModuleOutputBuilder::new();
 			let cb_result =
 				ics4_callback(ctx, &module_id, &msg, channel_result, &mut module_output);
+			charge_events(ctx.gas_meter(), module_output.events().len())?;
 			handler_builder.merge(module_output);
 			let channel_result = cb_result.map_err(Error::ics04_channel)?;
 
@@ -118,13 +267,17 @@ where
 			let module_id = get_module_for_packet_msg(ctx, &msg).map_err(Error::ics04_channel)?;
 			let (mut handler_builder, packet_result) =
 				ics4_packet_msg_dispatcher::<_>(ctx, &msg).map_err(Error::ics04_channel)?;
+			charge_events(ctx.gas_meter(), handler_builder.events().len())?;
 
 			if matches!(packet_result, PacketResult::Recv(RecvPacketResult::NoOp)) {
-				return Ok(handler_builder.with_result(()));
+				return Ok(handler_builder.with_result(()))
 			}
 
+			// The checks of the module are run along with its callback.
+			charge_module_callback(ctx.gas_meter())?;
 			let mut module_output = ModuleOutputBuilder::new();
 			let cb_result = ics4_packet_callback(ctx, &module_id, &msg, &mut module_output);
+			charge_events(ctx.gas_meter(), module_output.events().len())?;
 			handler_builder.merge(module_output);
 			cb_result.map_err(Error::ics04_channel)?;
 
@@ -133,11 +286,61 @@ where
 
 			handler_builder.with_result(())
 		},
+
+		Ics4PacketBatchMsg(msg) => {
+			let module_id =
+				get_module_for_packet_batch_msg(ctx, &msg).map_err(Error::ics04_channel)?;
+			let (mut handler_builder, packet_results) =
+				ics4_packet_batch_msg_dispatcher::<_>(ctx, &msg).map_err(Error::ics04_channel)?;
+			charge_events(ctx.gas_meter(), handler_builder.events().len())?;
+
+			for (packet, packet_result) in msg.packets().iter().zip(packet_results) {
+				if matches!(packet_result, PacketResult::Recv(RecvPacketResult::NoOp)) {
+					continue
+				}
+
+				// The checks of the module are run along with its callback.
+				charge_module_callback(ctx.gas_meter())?;
+				let mut module_output = ModuleOutputBuilder::new();
+				let cb_result =
+					ics4_packet_batch_callback(ctx, &module_id, &msg, packet, &mut module_output);
+				charge_events(ctx.gas_meter(), module_output.events().len())?;
+				handler_builder.merge(module_output);
+				cb_result.map_err(Error::ics04_channel)?;
+
+				// Apply any results to the host chain store.
+				ctx.store_packet_result(packet_result).map_err(Error::ics04_channel)?;
+			}
+
+			handler_builder.with_result(())
+		},
 	};
 
 	Ok(output)
 }
 
+/// Charges `count` events, before the results emitting them are applied to the `Ctx`.
+fn charge_events(gas: &dyn GasMeter, count: usize) -> Result<(), Error> {
+	gas.charge_events(count).map_err(|_| Error::out_of_gas(gas.gas_consumed()))
+}
+
+/// Charges the callback, or validation hook, of the module a message is routed to, before it runs.
+fn charge_module_callback(gas: &dyn GasMeter) -> Result<(), Error> {
+	gas.charge_module_callback().map_err(|_| Error::out_of_gas(gas.gas_consumed()))
+}
+
+/// Turns any failure caused by running out of gas into an out of gas error, whatever the handler
+/// that ran out reported.
+fn metered(
+	gas: &dyn GasMeter,
+	result: Result<HandlerOutput<()>, Error>,
+) -> Result<HandlerOutput<()>, Error> {
+	if gas.is_out_of_gas() {
+		return Err(Error::out_of_gas(gas.gas_consumed()))
+	}
+	result
+}
+
 #[cfg(test)]
 mod tests {
 	use crate::prelude::*;
@@ -149,6 +352,7 @@ mod tests {
 			context::test::deliver as ics20_deliver,
 			msgs::transfer::{test_util::get_dummy_msg_transfer, MsgTransfer},
 			packet::PacketData,
+			relay::send_transfer::validate_transfer,
 			PrefixedCoin, MODULE_ID_STR,
 		},
 		core::{
@@ -156,29 +360,52 @@ mod tests {
 				create_client::MsgCreateAnyClient, update_client::MsgUpdateAnyClient,
 				upgrade_client::MsgUpgradeAnyClient, ClientMsg,
 			},
-			ics03_connection::msgs::{
-				conn_open_ack::{test_util::get_dummy_raw_msg_conn_open_ack, MsgConnectionOpenAck},
-				conn_open_init::{
-					test_util::get_dummy_raw_msg_conn_open_init, MsgConnectionOpenInit,
+			ics03_connection::{
+				connection::{
+					ConnectionEnd, Counterparty as ConnectionCounterparty, State as ConnectionState,
 				},
-				conn_open_try::{test_util::get_dummy_raw_msg_conn_open_try, MsgConnectionOpenTry},
-				ConnectionMsg,
-			},
-			ics04_channel::msgs::{
-				chan_close_confirm::{
-					test_util::get_dummy_raw_msg_chan_close_confirm, MsgChannelCloseConfirm,
+				msgs::{
+					conn_open_ack::{
+						test_util::get_dummy_raw_msg_conn_open_ack, MsgConnectionOpenAck,
+					},
+					conn_open_init::{
+						test_util::get_dummy_raw_msg_conn_open_init, MsgConnectionOpenInit,
+					},
+					conn_open_try::{
+						test_util::get_dummy_raw_msg_conn_open_try, MsgConnectionOpenTry,
+					},
+					test_util::get_dummy_raw_counterparty,
+					ConnectionMsg,
 				},
-				chan_close_init::{
-					test_util::get_dummy_raw_msg_chan_close_init, MsgChannelCloseInit,
-				},
-				chan_open_ack::{test_util::get_dummy_raw_msg_chan_open_ack, MsgChannelOpenAck},
-				chan_open_init::{test_util::get_dummy_raw_msg_chan_open_init, MsgChannelOpenInit},
-				chan_open_try::{test_util::get_dummy_raw_msg_chan_open_try, MsgChannelOpenTry},
-				recv_packet::{test_util::get_dummy_raw_msg_recv_packet, MsgRecvPacket},
-				timeout_on_close::{
-					test_util::get_dummy_raw_msg_timeout_on_close, MsgTimeoutOnClose,
+				version::get_compatible_versions,
+			},
+			ics04_channel::{
+				channel::{ChannelEnd, Counterparty, Order, State},
+				error::{Error as Ics04Error, ErrorDetail as Ics04ErrorDetail},
+				msgs::{
+					chan_close_confirm::{
+						test_util::get_dummy_raw_msg_chan_close_confirm, MsgChannelCloseConfirm,
+					},
+					chan_close_init::{
+						test_util::get_dummy_raw_msg_chan_close_init, MsgChannelCloseInit,
+					},
+					chan_open_ack::{
+						test_util::get_dummy_raw_msg_chan_open_ack, MsgChannelOpenAck,
+					},
+					chan_open_init::{
+						test_util::get_dummy_raw_msg_chan_open_init, MsgChannelOpenInit,
+					},
+					chan_open_try::{
+						test_util::get_dummy_raw_msg_chan_open_try, MsgChannelOpenTry,
+					},
+					recv_packet::{test_util::get_dummy_raw_msg_recv_packet, MsgRecvPacket},
+					timeout_on_close::{
+						test_util::get_dummy_raw_msg_timeout_on_close, MsgTimeoutOnClose,
+					},
+					ChannelMsg, PacketMsg,
 				},
-				ChannelMsg, PacketMsg,
+				packet::Packet,
+				Version,
 			},
 		},
 		events::IbcEvent,
@@ -188,11 +415,15 @@ mod tests {
 	use crate::mock::header::MockClientMessage;
 	use crate::{
 		core::{
-			ics24_host::identifier::ConnectionId,
+			ics04_channel::context::ChannelReader,
+			ics05_port::error::ErrorDetail as Ics05ErrorDetail,
+			ics24_host::identifier::{ChannelId, ClientId, ConnectionId, PortId},
 			ics26_routing::{
-				context::{Ics26Context, ModuleId, Router, RouterBuilder},
-				error::Error,
-				handler::dispatch,
+				context::{
+					Ics26Context, Module, ModuleId, ModuleOutputBuilder, Router, RouterBuilder,
+				},
+				error::{Error, ErrorDetail},
+				handler::{dispatch, validate},
 				msgs::Ics26Envelope,
 			},
 		},
@@ -202,8 +433,9 @@ mod tests {
 			context::{MockClientTypes, MockContext, MockRouterBuilder},
 			header::MockHeader,
 		},
+		signer::Signer,
 		test_utils::{get_dummy_account_id, DummyTransferModule},
-		timestamp::Timestamp,
+		timestamp::{Timestamp, ZERO_DURATION},
 		Height,
 	};
 
@@ -333,6 +565,7 @@ mod tests {
 				token: PrefixedCoin { denom, amount: msg_transfer_two.token.amount },
 				sender: msg_transfer_two.sender.clone(),
 				receiver: msg_transfer_two.receiver.clone(),
+				memo: msg_transfer_two.memo.clone(),
 			};
 			serde_json::to_vec(&data).expect("PacketData's infallible Serialize impl failed")
 		};
@@ -547,11 +780,45 @@ mod tests {
 
 		for test in tests {
 			let res = match test.msg.clone() {
-				TestMsg::Ics26(msg) => dispatch(&mut ctx, msg).map(|_| ()),
+				TestMsg::Ics26(msg) => {
+					// validation runs the same checks as execution, without modifying the context
+					let state = format!("{:?}", ctx);
+					let validated = validate(&ctx, msg.clone());
+					assert_eq!(state, format!("{:?}", ctx), "validate modified the context");
+					assert_eq!(
+						test.want_pass,
+						validated.is_ok(),
+						"ICS26 routing validate test '{}' failed for {:?}\nwith result: {:?}",
+						test.name,
+						test.msg,
+						validated
+					);
+					dispatch(&mut ctx, msg).map(|_| ())
+				},
 				TestMsg::Ics20(msg) => {
+					// validating the transfer returns the packet it sends, without sending it
+					let state = format!("{:?}", ctx);
+					let validated = validate_transfer(
+						&DummyTransferModule::<MockClientTypes>::new(ctx.ibc_store_share()),
+						msg.clone(),
+					);
+					assert_eq!(state, format!("{:?}", ctx), "validate modified the context");
+					assert_eq!(
+						test.want_pass,
+						validated.is_ok(),
+						"ICS20 validate test '{}' failed for {:?}\nwith result: {:?}",
+						test.name,
+						test.msg,
+						validated
+					);
+					let commitment_key = validated.ok().map(|output| {
+						let packet = output.result;
+						(packet.source_port, packet.source_channel, packet.sequence)
+					});
+
 					let transfer_module =
 						ctx.router_mut().get_route_mut(&transfer_module_id).unwrap();
-					ics20_deliver(
+					let res = ics20_deliver(
 						transfer_module
 							.as_any_mut()
 							.downcast_mut::<DummyTransferModule<MockClientTypes>>()
@@ -560,7 +827,12 @@ mod tests {
 						msg,
 					)
 					.map(|_| ())
-					.map_err(Error::ics04_channel)
+					.map_err(Error::ics04_channel);
+					// the packet sent is the one validation returned
+					if let (Ok(()), Some(key)) = (&res, commitment_key) {
+						assert!(ctx.get_packet_commitment(&key).is_ok());
+					}
+					res
 				},
 			};
 
@@ -574,4 +846,208 @@ mod tests {
 			);
 		}
 	}
+
+	/// A module accepting, or rejecting in its checks, the channels and packets routed to it.
+	#[derive(Debug)]
+	struct CheckedModule {
+		reject: bool,
+	}
+
+	impl CheckedModule {
+		fn check(&self) -> Result<(), Ics04Error> {
+			if self.reject {
+				return Err(Ics04Error::app_module("rejected".to_string()))
+			}
+			Ok(())
+		}
+	}
+
+	impl Module for CheckedModule {
+		fn on_chan_open_try_validate(
+			&self,
+			_order: Order,
+			_connection_hops: &[ConnectionId],
+			_port_id: &PortId,
+			_channel_id: &ChannelId,
+			_counterparty: &Counterparty,
+			_version: &Version,
+			_counterparty_version: &Version,
+		) -> Result<(), Ics04Error> {
+			self.check()
+		}
+
+		fn on_chan_open_try(
+			&mut self,
+			_output: &mut ModuleOutputBuilder,
+			_order: Order,
+			_connection_hops: &[ConnectionId],
+			_port_id: &PortId,
+			_channel_id: &ChannelId,
+			_counterparty: &Counterparty,
+			_version: &Version,
+			counterparty_version: &Version,
+		) -> Result<Version, Ics04Error> {
+			Ok(counterparty_version.clone())
+		}
+
+		fn on_recv_packet_validate(
+			&self,
+			_packet: &Packet,
+			_relayer: &Signer,
+		) -> Result<(), Ics04Error> {
+			self.check()
+		}
+	}
+
+	fn with_checked_module(
+		ctx: MockContext<MockClientTypes>,
+		port_id: PortId,
+		reject: bool,
+	) -> MockContext<MockClientTypes> {
+		let module_id: ModuleId = "checked".parse().unwrap();
+		let router = MockRouterBuilder::default()
+			.add_route(module_id.clone(), CheckedModule { reject })
+			.unwrap()
+			.build();
+		let mut ctx = ctx.with_router(router);
+		ctx.scope_port_to_module(port_id, module_id);
+		ctx
+	}
+
+	/// Asserts that `msg` passes validation unless the module rejects it, in which case neither
+	/// validation nor execution modify the context.
+	fn assert_rejected_by_module(
+		ctx: impl Fn(bool) -> MockContext<MockClientTypes>,
+		msg: Ics26Envelope<MockContext<MockClientTypes>>,
+	) {
+		validate(&ctx(false), msg.clone()).unwrap();
+
+		let mut ctx = ctx(true);
+		let state = format!("{:?}", ctx);
+		let errors =
+			[validate(&ctx, msg.clone()).unwrap_err(), dispatch(&mut ctx, msg).unwrap_err()];
+		for err in errors {
+			assert!(
+				matches!(
+					err.detail(),
+					ErrorDetail::Ics04Channel(e) if matches!(e.source, Ics04ErrorDetail::AppModule(_))
+				),
+				"{:?}",
+				err
+			);
+		}
+		assert_eq!(state, format!("{:?}", ctx), "a rejected message modified the context");
+	}
+
+	#[test]
+	fn validate_runs_module_checks() {
+		// a channel opened by the counterparty
+		let proof_height = 10;
+		let conn_id = ConnectionId::new(2);
+		let client_id = ClientId::new(&MockClientState::client_type(), 45).unwrap();
+		let conn_end = ConnectionEnd::new(
+			ConnectionState::Open,
+			client_id.clone(),
+			ConnectionCounterparty::try_from(get_dummy_raw_counterparty()).unwrap(),
+			get_compatible_versions(),
+			ZERO_DURATION,
+		);
+		let mut msg =
+			MsgChannelOpenTry::try_from(get_dummy_raw_msg_chan_open_try(proof_height)).unwrap();
+		msg.channel.connection_hops = vec![conn_id.clone()];
+		let port_id = msg.port_id.clone();
+		let ctx = MockContext::default()
+			.with_client(&client_id, Height::new(0, proof_height))
+			.with_connection(conn_id, conn_end);
+		assert_rejected_by_module(
+			|reject| with_checked_module(ctx.clone(), port_id.clone(), reject),
+			Ics26Envelope::Ics4ChannelMsg(ChannelMsg::ChannelOpenTry(msg)),
+		);
+
+		// a packet received on an open channel
+		let (ctx, msg, chan_end) = recv_packet_context();
+		let packet = msg.packet.clone();
+		assert_rejected_by_module(
+			|reject| {
+				with_checked_module(ctx.clone(), packet.destination_port.clone(), reject)
+					.with_channel(
+						packet.destination_port.clone(),
+						packet.destination_channel,
+						chan_end.clone(),
+					)
+					.with_recv_sequence(
+						packet.destination_port.clone(),
+						packet.destination_channel,
+						packet.sequence,
+					)
+			},
+			Ics26Envelope::Ics4PacketMsg(PacketMsg::RecvPacket(msg)),
+		);
+	}
+
+	/// Returns a host with an open connection to receive the message returned on, and the end of
+	/// the channel the packet of the message is received on.
+	fn recv_packet_context() -> (MockContext<MockClientTypes>, MsgRecvPacket, ChannelEnd) {
+		let ctx = MockContext::default();
+		let host_height = ctx.latest_height().increment();
+		let client_height = host_height.increment();
+		let msg =
+			MsgRecvPacket::try_from(get_dummy_raw_msg_recv_packet(client_height.revision_height))
+				.unwrap();
+		let conn_end = ConnectionEnd::new(
+			ConnectionState::Open,
+			ClientId::default(),
+			ConnectionCounterparty::new(
+				ClientId::default(),
+				Some(ConnectionId::default()),
+				Default::default(),
+			),
+			get_compatible_versions(),
+			ZERO_DURATION,
+		);
+		let chan_end = ChannelEnd::new(
+			State::Open,
+			Order::default(),
+			Counterparty::new(msg.packet.source_port.clone(), Some(msg.packet.source_channel)),
+			vec![ConnectionId::default()],
+			Version::ics20(),
+		);
+		let ctx = ctx
+			.with_client(&ClientId::default(), client_height)
+			.with_connection(ConnectionId::default(), conn_end)
+			.with_height(host_height);
+		(ctx, msg, chan_end)
+	}
+
+	#[test]
+	fn packets_are_routed_to_channel_owners() {
+		// the channel is opened by another module than the one later bound to its port
+		let (ctx, msg, chan_end) = recv_packet_context();
+		let packet = msg.packet.clone();
+		let ctx = ctx
+			.with_channel(packet.destination_port.clone(), packet.destination_channel, chan_end)
+			.with_recv_sequence(
+				packet.destination_port.clone(),
+				packet.destination_channel,
+				packet.sequence,
+			);
+		let mut ctx = with_checked_module(ctx, packet.destination_port, false);
+
+		let msg = Ics26Envelope::Ics4PacketMsg(PacketMsg::RecvPacket(msg));
+		let state = format!("{:?}", ctx);
+		let errors =
+			[validate(&ctx, msg.clone()).unwrap_err(), dispatch(&mut ctx, msg).unwrap_err()];
+		for err in errors {
+			let not_owned = match err.detail() {
+				ErrorDetail::Ics04Channel(e) => match &e.source {
+					Ics04ErrorDetail::Ics05Port(e) =>
+						matches!(e.source, Ics05ErrorDetail::CapabilityNotOwned(_)),
+					_ => false,
+				},
+				_ => false,
+			};
+			assert!(not_owned, "{:?}", err);
+		}
+		assert_eq!(state, format!("{:?}", ctx), "a rejected message modified the context");
+	}
 }
@@ -1,6 +1,7 @@
 //! ICS 26: Routing module keeps a lookup table of modules for looking
 //! the appropriate module to relay to when a packet is received.
 
+pub mod cache;
 pub mod context;
 pub mod error;
 pub mod handler;
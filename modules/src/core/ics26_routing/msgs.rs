@@ -15,8 +15,8 @@ use crate::core::{
 	},
 	ics04_channel::msgs::{
 		acknowledgement, chan_close_confirm, chan_close_init, chan_open_ack, chan_open_confirm,
-		chan_open_init, chan_open_try, recv_packet, timeout, timeout_on_close, ChannelMsg,
-		PacketMsg,
+		chan_open_init, chan_open_try, recv_packet, recv_packet_batch, timeout, timeout_batch,
+		timeout_on_close, ChannelMsg, PacketBatchMsg, PacketMsg,
 	},
 	ics26_routing::error::Error,
 };
@@ -38,6 +38,7 @@ where
 	Ics3Msg(ConnectionMsg<C>),
 	Ics4ChannelMsg(ChannelMsg),
 	Ics4PacketMsg(PacketMsg),
+	Ics4PacketBatchMsg(PacketBatchMsg),
 }
 
 impl<C> TryFrom<Any> for Ics26Envelope<C>
@@ -163,6 +164,16 @@ where
 					.map_err(Error::malformed_message_bytes)?;
 				Ok(Ics26Envelope::Ics4PacketMsg(PacketMsg::ToClosePacket(domain_msg)))
 			},
+			recv_packet_batch::TYPE_URL => {
+				let domain_msg = recv_packet_batch::MsgRecvPacketBatch::decode_vec(&any_msg.value)
+					.map_err(Error::malformed_message_bytes)?;
+				Ok(Ics26Envelope::Ics4PacketBatchMsg(PacketBatchMsg::RecvPackets(domain_msg)))
+			},
+			timeout_batch::TYPE_URL => {
+				let domain_msg = timeout_batch::MsgTimeoutBatch::decode_vec(&any_msg.value)
+					.map_err(Error::malformed_message_bytes)?;
+				Ok(Ics26Envelope::Ics4PacketBatchMsg(PacketBatchMsg::ToPackets(domain_msg)))
+			},
 			_ => Err(Error::unknown_message_type_url(any_msg.type_url)),
 		}
 	}
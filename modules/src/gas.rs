@@ -0,0 +1,379 @@
+//! Metering of the work done by the IBC handlers, so that hosts can charge relayers for it, e.g. as
+//! the weight of the extrinsics of a Substrate chain.
+//!
+//! Handlers charge the [`GasMeter`] of their context, returned by
+//! [`ReaderContext::gas_meter`](crate::core::ics26_routing::context::ReaderContext::gas_meter), as
+//! they process a message: once per message, event and module callback, per consensus state read,
+//! and per proof verified by a client along with its size. [`MerkleProof`]s charge each of the
+//! existence or non-existence proofs they are made of, and trie proofs each of their nodes.
+//!
+//! Events are charged before the results emitting them are written to the context. A message that
+//! runs out of gas fails with an out of gas error, whatever the handler it happened in reported.
+//!
+//! [`MerkleProof`]: crate::core::ics23_commitment::merkle::MerkleProof
+
+use crate::prelude::*;
+use core::sync::atomic::{AtomicU64, Ordering};
+use flex_error::define_error;
+
+define_error! {
+	#[derive(Debug, PartialEq, Eq)]
+	GasError {
+		OutOfGas
+			{ descriptor: String, limit: u64 }
+			| e | {
+				format_args!("out of gas charging for {0}: the limit of {1} was exceeded",
+					e.descriptor, e.limit)
+			},
+	}
+}
+
+/// The gas charged for each unit of work done by the handlers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GasCosts {
+	/// Charged once per message.
+	pub message: u64,
+	/// Charged per event emitted while processing a message.
+	pub event: u64,
+	/// Charged per callback, or validation hook, of a module run while processing a message.
+	pub module_callback: u64,
+	/// Charged per consensus state read to verify a proof or a client message against.
+	pub consensus_state_read: u64,
+	/// Charged per proof or client message verified by a client.
+	pub client_verification: u64,
+	/// Charged per byte of the proofs verified by clients.
+	pub proof_byte: u64,
+	/// Charged per existence or non-existence proof checked, i.e. per nested store a value is
+	/// proven to be stored in or absent from.
+	pub merkle_op: u64,
+	/// Charged per node of the Substrate or Ethereum trie proofs checked, each hashed once.
+	pub trie_node: u64,
+}
+
+impl GasCosts {
+	/// Costs charging nothing, for contexts that do not meter their work.
+	pub const FREE: GasCosts = GasCosts {
+		message: 0,
+		event: 0,
+		module_callback: 0,
+		consensus_state_read: 0,
+		client_verification: 0,
+		proof_byte: 0,
+		merkle_op: 0,
+		trie_node: 0,
+	};
+}
+
+impl Default for GasCosts {
+	fn default() -> Self {
+		Self {
+			message: 10_000,
+			event: 1_000,
+			module_callback: 5_000,
+			consensus_state_read: 5_000,
+			client_verification: 25_000,
+			proof_byte: 10,
+			merkle_op: 2_500,
+			trie_node: 500,
+		}
+	}
+}
+
+/// Keeps track of the gas consumed by the processing of a message or transaction.
+///
+/// Gas is charged through a shared reference, since most handlers only get one to their context.
+pub trait GasMeter {
+	/// Returns the costs that work is charged with.
+	fn costs(&self) -> &GasCosts;
+
+	/// Consumes `amount` of gas for the work described by `descriptor`, failing if the limit of
+	/// the meter is exceeded. A meter out of gas fails all subsequent charges.
+	fn consume_gas(&self, amount: u64, descriptor: &str) -> Result<(), GasError>;
+
+	/// Returns the gas consumed so far.
+	fn gas_consumed(&self) -> u64;
+
+	/// Returns whether a charge exceeded the limit of the meter.
+	fn is_out_of_gas(&self) -> bool;
+
+	fn charge_message(&self) -> Result<(), GasError> {
+		self.consume_gas(self.costs().message, "message")
+	}
+
+	fn charge_events(&self, count: usize) -> Result<(), GasError> {
+		self.consume_gas(self.costs().event.saturating_mul(count as u64), "events")
+	}
+
+	fn charge_module_callback(&self) -> Result<(), GasError> {
+		self.consume_gas(self.costs().module_callback, "module callback")
+	}
+
+	fn charge_consensus_state_read(&self) -> Result<(), GasError> {
+		self.consume_gas(self.costs().consensus_state_read, "consensus state read")
+	}
+
+	/// Charges the verification by a client of a message updating it. The consensus states it is
+	/// verified against are charged as they are read.
+	fn charge_client_message(&self) -> Result<(), GasError> {
+		self.consume_gas(self.costs().client_verification, "client message verification")
+	}
+
+	/// Charges the verification by a client of `proof`, against one of its consensus states.
+	fn charge_proof_verification(&self, proof: &[u8]) -> Result<(), GasError> {
+		let costs = self.costs();
+		let proof_len = proof.len() as u64;
+		let amount = costs
+			.consensus_state_read
+			.saturating_add(costs.client_verification)
+			.saturating_add(costs.proof_byte.saturating_mul(proof_len));
+		self.consume_gas(amount, "proof verification")
+	}
+
+	fn charge_merkle_ops(&self, count: usize) -> Result<(), GasError> {
+		self.consume_gas(self.costs().merkle_op.saturating_mul(count as u64), "merkle proofs")
+	}
+
+	fn charge_trie_nodes(&self, count: usize) -> Result<(), GasError> {
+		self.consume_gas(self.costs().trie_node.saturating_mul(count as u64), "trie nodes")
+	}
+}
+
+/// A meter that never runs out and charges nothing, for contexts that do not meter their work.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct InfiniteGasMeter;
+
+impl GasMeter for InfiniteGasMeter {
+	fn costs(&self) -> &GasCosts {
+		&GasCosts::FREE
+	}
+
+	fn consume_gas(&self, _amount: u64, _descriptor: &str) -> Result<(), GasError> {
+		Ok(())
+	}
+
+	fn gas_consumed(&self) -> u64 {
+		0
+	}
+
+	fn is_out_of_gas(&self) -> bool {
+		false
+	}
+}
+
+/// A meter with a fixed limit, e.g. the gas or weight a transaction pays for.
+#[derive(Debug)]
+pub struct BasicGasMeter {
+	costs: GasCosts,
+	limit: u64,
+	consumed: AtomicU64,
+}
+
+impl BasicGasMeter {
+	pub fn new(limit: u64) -> Self {
+		Self::with_costs(limit, GasCosts::default())
+	}
+
+	pub fn with_costs(limit: u64, costs: GasCosts) -> Self {
+		Self { costs, limit, consumed: AtomicU64::new(0) }
+	}
+
+	pub fn limit(&self) -> u64 {
+		self.limit
+	}
+}
+
+impl GasMeter for BasicGasMeter {
+	fn costs(&self) -> &GasCosts {
+		&self.costs
+	}
+
+	fn consume_gas(&self, amount: u64, descriptor: &str) -> Result<(), GasError> {
+		// the closure never returns `None`, so neither does the update
+		let previous = self
+			.consumed
+			.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |consumed| {
+				Some(consumed.checked_add(amount).unwrap_or(u64::MAX))
+			})
+			.unwrap_or_else(|consumed| consumed);
+		match previous.checked_add(amount) {
+			Some(consumed) if consumed <= self.limit => Ok(()),
+			_ => Err(GasError::out_of_gas(descriptor.to_string(), self.limit)),
+		}
+	}
+
+	fn gas_consumed(&self) -> u64 {
+		self.consumed.load(Ordering::Relaxed)
+	}
+
+	fn is_out_of_gas(&self) -> bool {
+		self.gas_consumed() > self.limit
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		core::{
+			ics02_client::{
+				context::ClientReader,
+				msgs::{create_client::MsgCreateAnyClient, ClientMsg},
+			},
+			ics03_connection::{
+				connection::{ConnectionEnd, State as ConnectionState},
+				msgs::conn_open_init::{
+					test_util::get_dummy_raw_msg_conn_open_init, MsgConnectionOpenInit,
+				},
+				version::get_compatible_versions,
+			},
+			ics04_channel::{
+				channel::{Counterparty, Order},
+				error::Error as Ics04Error,
+				msgs::{
+					chan_open_init::{
+						test_util::get_dummy_raw_msg_chan_open_init, MsgChannelOpenInit,
+					},
+					ChannelMsg,
+				},
+				Version,
+			},
+			ics24_host::identifier::{ChannelId, ConnectionId, PortId},
+			ics26_routing::{
+				context::{Module, ModuleId, ModuleOutputBuilder, RouterBuilder},
+				error::ErrorDetail,
+				handler::{execute, validate},
+				msgs::Ics26Envelope,
+			},
+		},
+		mock::{
+			client_state::{
+				AnyClientState, AnyConsensusState, MockClientState, MockConsensusState,
+			},
+			context::{MockClientTypes, MockContext, MockRouterBuilder},
+			header::{MockClientMessage, MockHeader},
+		},
+		test_utils::get_dummy_account_id,
+		Height,
+	};
+	use std::sync::Arc;
+
+	#[test]
+	fn test_basic_gas_meter() {
+		let costs = GasCosts { event: 10, ..GasCosts::FREE };
+		let meter = BasicGasMeter::with_costs(25, costs);
+
+		meter.charge_events(2).unwrap();
+		assert_eq!(meter.gas_consumed(), 20);
+		assert!(!meter.is_out_of_gas());
+
+		assert!(meter.charge_events(1).is_err());
+		assert!(meter.is_out_of_gas());
+		// a meter out of gas stays so, even for free work
+		assert!(meter.charge_merkle_ops(1).is_err());
+
+		// overflowing charges run out of gas, even with no limit
+		let meter = BasicGasMeter::with_costs(u64::MAX, costs);
+		meter.consume_gas(u64::MAX, "everything").unwrap();
+		assert!(meter.consume_gas(1, "more").is_err());
+		assert_eq!(meter.gas_consumed(), u64::MAX);
+	}
+
+	#[test]
+	fn test_metered_handlers() {
+		let height = Height::new(0, 42);
+		let msg = Ics26Envelope::Ics2Msg(ClientMsg::CreateClient(
+			MsgCreateAnyClient::new(
+				AnyClientState::from(MockClientState::new(MockClientMessage::Header(
+					MockHeader::new(height),
+				))),
+				AnyConsensusState::Mock(MockConsensusState::new(MockHeader::new(height))),
+				get_dummy_account_id(),
+			)
+			.unwrap(),
+		));
+		let costs = GasCosts::default();
+
+		// processing stops before any work is done once the meter runs out
+		let meter = Arc::new(BasicGasMeter::new(costs.message - 1));
+		let ctx = MockContext::default().with_gas_meter(meter.clone());
+		let res = validate(&ctx, msg.clone());
+		assert!(matches!(res.unwrap_err().detail(), ErrorDetail::OutOfGas(_)));
+		assert_eq!(meter.gas_consumed(), costs.message);
+
+		// the events of the message are charged once it is processed
+		let meter = Arc::new(BasicGasMeter::new(costs.message));
+		let ctx = MockContext::default().with_gas_meter(meter.clone());
+		let res = validate(&ctx, msg.clone());
+		assert!(matches!(res.unwrap_err().detail(), ErrorDetail::OutOfGas(_)));
+
+		let meter = Arc::new(BasicGasMeter::new(u64::MAX));
+		let mut ctx = MockContext::default().with_gas_meter(meter.clone());
+		let output = execute(&mut ctx, msg.clone()).unwrap();
+		let events = output.events.len() as u64;
+		assert_eq!(meter.gas_consumed(), costs.message + costs.event * events);
+
+		// events are charged before their results are written
+		let meter = Arc::new(BasicGasMeter::new(costs.message));
+		let mut ctx = MockContext::default().with_gas_meter(meter);
+		let res = execute(&mut ctx, msg);
+		assert!(matches!(res.unwrap_err().detail(), ErrorDetail::OutOfGas(_)));
+		assert_eq!(ctx.client_counter().unwrap(), 0);
+	}
+
+	#[derive(Debug, Default)]
+	struct AcceptingModule;
+
+	impl Module for AcceptingModule {
+		fn on_chan_open_try(
+			&mut self,
+			_output: &mut ModuleOutputBuilder,
+			_order: Order,
+			_connection_hops: &[ConnectionId],
+			_port_id: &PortId,
+			_channel_id: &ChannelId,
+			_counterparty: &Counterparty,
+			_version: &Version,
+			counterparty_version: &Version,
+		) -> Result<Version, Ics04Error> {
+			Ok(counterparty_version.clone())
+		}
+	}
+
+	#[test]
+	fn test_metered_module_callbacks() {
+		let msg = MsgChannelOpenInit::try_from(get_dummy_raw_msg_chan_open_init()).unwrap();
+		let msg_conn_init =
+			MsgConnectionOpenInit::try_from(get_dummy_raw_msg_conn_open_init()).unwrap();
+		let connection_end = ConnectionEnd::new(
+			ConnectionState::Init,
+			msg_conn_init.client_id,
+			msg_conn_init.counterparty,
+			get_compatible_versions(),
+			msg_conn_init.delay_period,
+		);
+		let module_id: ModuleId = "accepting".parse().unwrap();
+		let router = MockRouterBuilder::default()
+			.add_route(module_id.clone(), AcceptingModule)
+			.unwrap()
+			.build();
+		let costs = GasCosts::default();
+
+		let meter = Arc::new(BasicGasMeter::new(u64::MAX));
+		let mut ctx = MockContext::<MockClientTypes>::default()
+			.with_connection(ConnectionId::default(), connection_end)
+			.with_router(router)
+			.with_gas_meter(meter.clone());
+		ctx.scope_port_to_module(msg.port_id.clone(), module_id);
+		let msg = Ics26Envelope::Ics4ChannelMsg(ChannelMsg::ChannelOpenInit(msg));
+
+		let output = validate(&ctx, msg.clone()).unwrap();
+		let events = output.events.len() as u64;
+		let validated = costs.message + costs.event * events + costs.module_callback;
+		assert_eq!(meter.gas_consumed(), validated);
+
+		let output = execute(&mut ctx, msg).unwrap();
+		let events = output.events.len() as u64;
+		let executed = costs.message + costs.event * events + costs.module_callback;
+		assert_eq!(meter.gas_consumed(), validated + executed);
+	}
+}
@@ -46,6 +46,10 @@ impl<T, E> HandlerOutputBuilder<T, E> {
 		self.events.push(event);
 	}
 
+	pub fn events(&self) -> &[E] {
+		&self.events
+	}
+
 	pub fn with_result(self, result: T) -> HandlerOutput<T, E> {
 		HandlerOutput { result, log: self.log, events: self.events }
 	}
@@ -60,6 +60,7 @@ pub mod applications;
 pub mod bigint;
 pub mod core;
 pub mod events;
+pub mod gas;
 pub mod handler;
 pub mod keys;
 #[macro_use]
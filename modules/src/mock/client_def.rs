@@ -1,6 +1,10 @@
+use ibc_proto::ibc::core::commitment::v1::MerkleProof as RawMerkleProof;
+use tendermint_proto::Protobuf;
+
 use crate::core::ics02_client::{
 	client_consensus::ConsensusState,
 	client_def::{ClientDef, ConsensusUpdateResult},
+	client_state::ClientState,
 };
 
 use crate::mock::header::MockClientMessage;
@@ -15,11 +19,16 @@ use crate::{
 		},
 		ics23_commitment::{
 			commitment::{CommitmentPrefix, CommitmentProofBytes, CommitmentRoot},
-			merkle::apply_prefix,
+			merkle::{MerklePath, MerkleProof},
+			substrate::NoTrieHostFunctions,
+			verify,
 		},
 		ics24_host::{
 			identifier::{ChannelId, ClientId, ConnectionId, PortId},
-			path::ClientConsensusStatePath,
+			path::{
+				AcksPath, ChannelEndsPath, ClientConsensusStatePath, ClientStatePath,
+				CommitmentsPath, ConnectionsPath, ReceiptsPath, SeqRecvsPath,
+			},
 			Path,
 		},
 		ics26_routing::context::ReaderContext,
@@ -29,6 +38,7 @@ use crate::{
 		header::AnyClientMessage,
 	},
 	prelude::*,
+	test_utils::Crypto,
 	Height,
 };
 use core::fmt::Debug;
@@ -77,137 +87,235 @@ impl ClientDef for MockClient {
 
 	fn verify_client_consensus_state<Ctx: ReaderContext>(
 		&self,
-		_ctx: &Ctx,
-		_client_state: &Self::ClientState,
+		ctx: &Ctx,
+		client_state: &Self::ClientState,
 		_height: Height,
 		prefix: &CommitmentPrefix,
-		_proof: &CommitmentProofBytes,
-		_root: &CommitmentRoot,
+		proof: &CommitmentProofBytes,
+		root: &CommitmentRoot,
 		client_id: &ClientId,
 		consensus_height: Height,
-		_expected_consensus_state: &Ctx::AnyConsensusState,
+		expected_consensus_state: &Ctx::AnyConsensusState,
 	) -> Result<(), Error> {
-		let client_prefixed_path = Path::ClientConsensusState(ClientConsensusStatePath {
+		let path = ClientConsensusStatePath {
 			client_id: client_id.clone(),
 			epoch: consensus_height.revision_number,
 			height: consensus_height.revision_height,
-		})
-		.to_string();
-
-		let _path = apply_prefix(prefix, vec![client_prefixed_path]);
-
-		Ok(())
+		};
+		let value = expected_consensus_state.encode_to_vec();
+		check_membership(ctx, client_state, proof, root, keys(client_state, prefix, path), value)
 	}
 
 	fn verify_connection_state<Ctx: ReaderContext>(
 		&self,
-		_ctx: &Ctx,
+		ctx: &Ctx,
 		_client_id: &ClientId,
-		_client_state: &Self::ClientState,
+		client_state: &Self::ClientState,
 		_height: Height,
-		_prefix: &CommitmentPrefix,
-		_proof: &CommitmentProofBytes,
-		_root: &CommitmentRoot,
-		_connection_id: &ConnectionId,
-		_expected_connection_end: &ConnectionEnd,
+		prefix: &CommitmentPrefix,
+		proof: &CommitmentProofBytes,
+		root: &CommitmentRoot,
+		connection_id: &ConnectionId,
+		expected_connection_end: &ConnectionEnd,
 	) -> Result<(), Error> {
-		Ok(())
+		let path = ConnectionsPath(connection_id.clone());
+		let value = expected_connection_end.encode_vec();
+		check_membership(ctx, client_state, proof, root, keys(client_state, prefix, path), value)
 	}
 
 	fn verify_channel_state<Ctx: ReaderContext>(
 		&self,
-		_ctx: &Ctx,
+		ctx: &Ctx,
 		_client_id: &ClientId,
-		_client_state: &Self::ClientState,
+		client_state: &Self::ClientState,
 		_height: Height,
-		_prefix: &CommitmentPrefix,
-		_proof: &CommitmentProofBytes,
-		_root: &CommitmentRoot,
-		_port_id: &PortId,
-		_channel_id: &ChannelId,
-		_expected_channel_end: &ChannelEnd,
+		prefix: &CommitmentPrefix,
+		proof: &CommitmentProofBytes,
+		root: &CommitmentRoot,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		expected_channel_end: &ChannelEnd,
 	) -> Result<(), Error> {
-		Ok(())
+		let path = ChannelEndsPath(port_id.clone(), *channel_id);
+		let value = expected_channel_end.encode_vec();
+		check_membership(ctx, client_state, proof, root, keys(client_state, prefix, path), value)
 	}
 
 	fn verify_client_full_state<Ctx: ReaderContext>(
 		&self,
-		_ctx: &Ctx,
-		_client_state: &Self::ClientState,
+		ctx: &Ctx,
+		client_state: &Self::ClientState,
 		_height: Height,
-		_prefix: &CommitmentPrefix,
-		_proof: &CommitmentProofBytes,
-		_root: &CommitmentRoot,
-		_client_id: &ClientId,
-		_expected_client_state: &Ctx::AnyClientState,
+		prefix: &CommitmentPrefix,
+		proof: &CommitmentProofBytes,
+		root: &CommitmentRoot,
+		client_id: &ClientId,
+		expected_client_state: &Ctx::AnyClientState,
 	) -> Result<(), Error> {
-		Ok(())
+		let path = ClientStatePath(client_id.clone());
+		let value = expected_client_state.encode_to_vec();
+		check_membership(ctx, client_state, proof, root, keys(client_state, prefix, path), value)
 	}
 
 	fn verify_packet_data<Ctx: ReaderContext>(
 		&self,
-		_ctx: &Ctx,
+		ctx: &Ctx,
 		_client_id: &ClientId,
-		_client_state: &Self::ClientState,
+		client_state: &Self::ClientState,
 		_height: Height,
-		_connection_end: &ConnectionEnd,
-		_proof: &CommitmentProofBytes,
-		_root: &CommitmentRoot,
-		_port_id: &PortId,
-		_channel_id: &ChannelId,
-		_sequence: Sequence,
-		_commitment: PacketCommitment,
+		connection_end: &ConnectionEnd,
+		proof: &CommitmentProofBytes,
+		root: &CommitmentRoot,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		sequence: Sequence,
+		commitment: PacketCommitment,
 	) -> Result<(), Error> {
-		Ok(())
+		let path = CommitmentsPath { port_id: port_id.clone(), channel_id: *channel_id, sequence };
+		let keys = keys(client_state, connection_end.counterparty().prefix(), path);
+		check_membership(ctx, client_state, proof, root, keys, commitment.into_vec())
 	}
 
 	fn verify_packet_acknowledgement<Ctx: ReaderContext>(
 		&self,
-		_ctx: &Ctx,
+		ctx: &Ctx,
 		_client_id: &ClientId,
-		_client_state: &Self::ClientState,
+		client_state: &Self::ClientState,
 		_height: Height,
-		_connection_end: &ConnectionEnd,
-		_proof: &CommitmentProofBytes,
-		_root: &CommitmentRoot,
-		_port_id: &PortId,
-		_channel_id: &ChannelId,
-		_sequence: Sequence,
-		_ack: AcknowledgementCommitment,
+		connection_end: &ConnectionEnd,
+		proof: &CommitmentProofBytes,
+		root: &CommitmentRoot,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		sequence: Sequence,
+		ack: AcknowledgementCommitment,
 	) -> Result<(), Error> {
-		Ok(())
+		let path = AcksPath { port_id: port_id.clone(), channel_id: *channel_id, sequence };
+		let keys = keys(client_state, connection_end.counterparty().prefix(), path);
+		check_membership(ctx, client_state, proof, root, keys, ack.into_vec())
 	}
 
 	fn verify_next_sequence_recv<Ctx: ReaderContext>(
 		&self,
-		_ctx: &Ctx,
+		ctx: &Ctx,
 		_client_id: &ClientId,
-		_client_state: &Self::ClientState,
+		client_state: &Self::ClientState,
 		_height: Height,
-		_connection_end: &ConnectionEnd,
-		_proof: &CommitmentProofBytes,
-		_root: &CommitmentRoot,
-		_port_id: &PortId,
-		_channel_id: &ChannelId,
-		_sequence: Sequence,
+		connection_end: &ConnectionEnd,
+		proof: &CommitmentProofBytes,
+		root: &CommitmentRoot,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		sequence: Sequence,
 	) -> Result<(), Error> {
-		Ok(())
+		let path = SeqRecvsPath(port_id.clone(), *channel_id);
+		let keys = keys(client_state, connection_end.counterparty().prefix(), path);
+		check_membership(
+			ctx,
+			client_state,
+			proof,
+			root,
+			keys,
+			u64::from(sequence).to_be_bytes().to_vec(),
+		)
 	}
 
 	fn verify_packet_receipt_absence<Ctx: ReaderContext>(
 		&self,
-		_ctx: &Ctx,
+		ctx: &Ctx,
 		_client_id: &ClientId,
-		_client_state: &Self::ClientState,
+		client_state: &Self::ClientState,
 		_height: Height,
-		_connection_end: &ConnectionEnd,
-		_proof: &CommitmentProofBytes,
-		_root: &CommitmentRoot,
-		_port_id: &PortId,
-		_channel_id: &ChannelId,
-		_sequence: Sequence,
+		connection_end: &ConnectionEnd,
+		proof: &CommitmentProofBytes,
+		root: &CommitmentRoot,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		sequence: Sequence,
 	) -> Result<(), Error> {
-		Ok(())
+		let path = ReceiptsPath { port_id: port_id.clone(), channel_id: *channel_id, sequence };
+		let keys = keys(client_state, connection_end.counterparty().prefix(), path);
+		check_non_membership(ctx, client_state, proof, root, keys)
+	}
+
+	fn verify_packet_data_batch<Ctx: ReaderContext>(
+		&self,
+		ctx: &Ctx,
+		_client_id: &ClientId,
+		client_state: &Self::ClientState,
+		_height: Height,
+		connection_end: &ConnectionEnd,
+		proof: &CommitmentProofBytes,
+		root: &CommitmentRoot,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		commitments: Vec<(Sequence, PacketCommitment)>,
+	) -> Result<(), Error> {
+		let proof = match decode_batch_proof(proof, root)? {
+			Some(proof) => proof,
+			None => return Ok(()),
+		};
+		let prefix = connection_end.counterparty().prefix();
+		let items = commitments
+			.into_iter()
+			.map(|(sequence, commitment)| {
+				let path =
+					CommitmentsPath { port_id: port_id.clone(), channel_id: *channel_id, sequence };
+				(keys(client_state, prefix, path), commitment.into_vec())
+			})
+			.collect();
+		let specs = client_state.proof_specs();
+		proof
+			.verify_batch_membership(&specs, root.clone().into(), items, ctx.gas_meter())
+			.map_err(Error::invalid_commitment_proof)
+	}
+
+	fn verify_packet_receipt_absence_batch<Ctx: ReaderContext>(
+		&self,
+		ctx: &Ctx,
+		_client_id: &ClientId,
+		client_state: &Self::ClientState,
+		_height: Height,
+		connection_end: &ConnectionEnd,
+		proof: &CommitmentProofBytes,
+		root: &CommitmentRoot,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		sequences: Vec<Sequence>,
+	) -> Result<(), Error> {
+		let proof = match decode_batch_proof(proof, root)? {
+			Some(proof) => proof,
+			None => return Ok(()),
+		};
+		let prefix = connection_end.counterparty().prefix();
+		let keys = sequences
+			.into_iter()
+			.map(|sequence| {
+				let path =
+					ReceiptsPath { port_id: port_id.clone(), channel_id: *channel_id, sequence };
+				keys(client_state, prefix, path)
+			})
+			.collect();
+		let specs = client_state.proof_specs();
+		proof
+			.verify_batch_non_membership(&specs, root.clone().into(), keys, ctx.gas_meter())
+			.map_err(Error::invalid_commitment_proof)
+	}
+
+	fn verify_membership<Ctx: ReaderContext>(
+		&self,
+		ctx: &Ctx,
+		_client_id: &ClientId,
+		client_state: &Self::ClientState,
+		_height: Height,
+		prefix: &CommitmentPrefix,
+		proof: &CommitmentProofBytes,
+		root: &CommitmentRoot,
+		path: String,
+		value: Vec<u8>,
+	) -> Result<(), Error> {
+		let keys = client_state.proof_format().merkle_path_of_key(prefix, path.as_bytes());
+		check_membership(ctx, client_state, proof, root, keys, value)
 	}
 
 	fn verify_upgrade_and_update_state<Ctx: ReaderContext>(
@@ -254,3 +362,74 @@ impl ClientDef for MockClient {
 		Ok(false)
 	}
 }
+
+/// Returns the keys of the value stored at `path` in an IBC store of prefix `prefix`.
+fn keys(
+	client_state: &MockClientState,
+	prefix: &CommitmentPrefix,
+	path: impl Into<Path>,
+) -> MerklePath {
+	client_state.proof_format().merkle_path(prefix, &path.into())
+}
+
+/// Decodes a batch proof of the state committed to by `root`, unless `root` is the
+/// [placeholder root](MockConsensusState::placeholder_root), under which any proof is accepted.
+fn decode_batch_proof(
+	proof: &CommitmentProofBytes,
+	root: &CommitmentRoot,
+) -> Result<Option<MerkleProof<Crypto>>, Error> {
+	if *root == MockConsensusState::placeholder_root() {
+		return Ok(None)
+	}
+	let proof = RawMerkleProof::try_from(proof.clone()).map_err(Error::invalid_commitment_proof)?;
+	Ok(Some(proof.into()))
+}
+
+/// Verifies a proof that `value` is stored under `keys` in the state committed to by `root`, in
+/// the proof format of the client. Any proof is accepted under the
+/// [placeholder root](MockConsensusState::placeholder_root).
+fn check_membership<Ctx: ReaderContext>(
+	ctx: &Ctx,
+	client_state: &MockClientState,
+	proof: &CommitmentProofBytes,
+	root: &CommitmentRoot,
+	keys: MerklePath,
+	value: Vec<u8>,
+) -> Result<(), Error> {
+	if *root == MockConsensusState::placeholder_root() {
+		return Ok(())
+	}
+	verify::verify_membership::<Crypto, NoTrieHostFunctions, Crypto>(
+		client_state.proof_format(),
+		&client_state.proof_specs(),
+		proof,
+		root,
+		keys,
+		value,
+		ctx.gas_meter(),
+	)
+	.map_err(Error::invalid_commitment_proof)
+}
+
+/// Verifies a proof that nothing is stored under `keys` in the state committed to by `root`, like
+/// [`check_membership`].
+fn check_non_membership<Ctx: ReaderContext>(
+	ctx: &Ctx,
+	client_state: &MockClientState,
+	proof: &CommitmentProofBytes,
+	root: &CommitmentRoot,
+	keys: MerklePath,
+) -> Result<(), Error> {
+	if *root == MockConsensusState::placeholder_root() {
+		return Ok(())
+	}
+	verify::verify_non_membership::<Crypto, NoTrieHostFunctions, Crypto>(
+		client_state.proof_format(),
+		&client_state.proof_specs(),
+		proof,
+		root,
+		keys,
+		ctx.gas_meter(),
+	)
+	.map_err(Error::invalid_commitment_proof)
+}
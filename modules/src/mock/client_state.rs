@@ -19,7 +19,7 @@ use crate::{
 			client_state::{ClientState, ClientType},
 			error::Error,
 		},
-		ics23_commitment::commitment::CommitmentRoot,
+		ics23_commitment::{commitment::CommitmentRoot, specs::ProofSpecs, store::CommitmentStore},
 		ics24_host::identifier::ChainId,
 	},
 	downcast,
@@ -28,12 +28,14 @@ use crate::{
 		context::HostBlockType,
 		header::MockHeader,
 	},
+	test_utils::Crypto,
 	timestamp::Timestamp,
 	Height,
 };
 use ibc_proto::ibc::mock::{
 	ClientState as RawMockClientState, ConsensusState as RawMockConsensusState,
 };
+use ics23::ProofSpec as Ics23ProofSpec;
 
 pub const MOCK_CLIENT_STATE_TYPE_URL: &str = "/ibc.mock.ClientState";
 
@@ -70,13 +72,17 @@ pub enum AnyClientState {
 pub struct MockClientState {
 	pub header: MockHeader,
 	pub frozen_height: Option<Height>,
+	/// Whether the client verifies proofs against invalid specs, to test that such clients are
+	/// rejected.
+	#[serde(default)]
+	pub invalid_proof_specs: bool,
 }
 
 impl Protobuf<RawMockClientState> for MockClientState {}
 
 impl MockClientState {
 	pub fn new(client_message: MockClientMessage) -> Self {
-		Self { header: client_message.header(), frozen_height: None }
+		Self { header: client_message.header(), frozen_height: None, invalid_proof_specs: false }
 	}
 
 	pub fn refresh_time(&self) -> Option<Duration> {
@@ -141,6 +147,17 @@ impl ClientState for MockClientState {
 		self.expired(elapsed)
 	}
 
+	/// The specs of the proofs of the [`CommitmentStore`]s of mock contexts, see
+	/// [`MockContext::commitment_store`].
+	///
+	/// [`MockContext::commitment_store`]: crate::mock::context::MockContext::commitment_store
+	fn proof_specs(&self) -> ProofSpecs {
+		if self.invalid_proof_specs {
+			return Vec::<Ics23ProofSpec>::new().into()
+		}
+		CommitmentStore::<Crypto>::proof_specs()
+	}
+
 	fn encode_to_vec(&self) -> Vec<u8> {
 		self.encode_vec()
 	}
@@ -253,7 +270,14 @@ pub struct MockConsensusState {
 
 impl MockConsensusState {
 	pub fn new(header: MockHeader) -> Self {
-		MockConsensusState { header, root: CommitmentRoot::from(vec![0]) }
+		MockConsensusState { header, root: Self::placeholder_root() }
+	}
+
+	/// The root of the consensus states of mock headers, which commits to no state. The mock
+	/// client accepts any proof under it, so that tests not concerned with proofs can use dummy
+	/// ones.
+	pub fn placeholder_root() -> CommitmentRoot {
+		CommitmentRoot::from(vec![0])
 	}
 
 	pub fn timestamp(&self) -> Timestamp {
@@ -269,7 +293,7 @@ impl TryFrom<RawMockConsensusState> for MockConsensusState {
 	fn try_from(raw: RawMockConsensusState) -> Result<Self, Self::Error> {
 		let raw_header = raw.header.ok_or_else(Error::missing_raw_consensus_state)?;
 
-		Ok(Self { header: MockHeader::try_from(raw_header)?, root: CommitmentRoot::from(vec![0]) })
+		Ok(Self { header: MockHeader::try_from(raw_header)?, root: Self::placeholder_root() })
 	}
 }
 
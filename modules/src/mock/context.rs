@@ -12,7 +12,10 @@ use core::{
 use std::{marker::PhantomData, sync::Mutex};
 
 use ibc_proto::google::protobuf::Any;
+use ics23::HostFunctionsProvider;
+use prost::Message;
 use sha2::Digest;
+use tendermint_proto::Protobuf;
 use tracing::debug;
 
 use crate::core::ics02_client::context::ClientTypes;
@@ -38,18 +41,33 @@ use crate::{
 			packet::{Receipt, Sequence},
 		},
 		ics05_port::{
-			context::PortReader,
+			capabilities::{
+				lookup_capability, Capability, CapabilityKeeper, CapabilityName, CapabilityReader,
+			},
+			context::{PortKeeper, PortReader},
 			error::{Error as Ics05Error, Error},
 		},
-		ics23_commitment::commitment::CommitmentPrefix,
-		ics24_host::identifier::{ChainId, ChannelId, ClientId, ConnectionId, PortId},
+		ics23_commitment::{
+			commitment::{CommitmentPrefix, CommitmentRoot},
+			store::CommitmentStore,
+		},
+		ics24_host::{
+			identifier::{ChainId, ChannelId, ClientId, ConnectionId, PortId},
+			path::{
+				AcksPath, ChannelEndsPath, ClientConsensusStatePath, ClientStatePath,
+				ClientTypePath, CommitmentsPath, ConnectionsPath, ReceiptsPath, SeqRecvsPath,
+			},
+			Path,
+		},
 		ics26_routing::{
+			cache::ModuleSnapshot,
 			context::{Ics26Context, Module, ModuleId, ReaderContext, Router, RouterBuilder},
 			error::Error as Ics26Error,
 			handler::dispatch,
 			msgs::Ics26Envelope,
 		},
 	},
+	gas::{BasicGasMeter, GasMeter, InfiniteGasMeter},
 	mock::{
 		client_def::AnyClient,
 		client_state::{
@@ -90,6 +108,9 @@ pub struct MockContext<C: HostBlockType = MockClientTypes> {
 	/// ICS26 router impl
 	pub router: MockRouter,
 
+	/// The meter handlers charge their work against, if work is metered.
+	pub gas_meter: Option<Arc<BasicGasMeter>>,
+
 	pub _phantom: PhantomData<C>,
 }
 
@@ -137,6 +158,7 @@ impl<C: HostBlockType> Clone for MockContext<C> {
 			block_time: self.block_time,
 			ibc_store,
 			router: self.router.clone(),
+			gas_meter: self.gas_meter.clone(),
 			_phantom: Default::default(),
 		}
 	}
@@ -201,6 +223,7 @@ where
 			block_time,
 			ibc_store: Arc::new(Mutex::new(MockIbcStore::<C>::default())),
 			router: Default::default(),
+			gas_meter: None,
 			_phantom: Default::default(),
 		}
 	}
@@ -215,16 +238,22 @@ where
 		self
 	}
 
-	/// Associates a channel (in an arbitrary state) to this context.
+	/// Associates a channel (in an arbitrary state) to this context, and issues the capability of
+	/// the channel (see [`MockContext::channel_capability`]).
 	pub fn with_channel(
-		self,
+		mut self,
 		port_id: PortId,
 		chan_id: ChannelId,
 		channel_end: ChannelEnd,
 	) -> Self {
 		let mut channels = self.ibc_store.lock().unwrap().channels.clone();
-		channels.insert((port_id, chan_id), channel_end);
+		channels.insert((port_id.clone(), chan_id), channel_end);
 		self.ibc_store.lock().unwrap().channels = channels;
+		let name = CapabilityName::channel(&port_id, chan_id);
+		if self.get_capability_index(&name).unwrap().is_none() {
+			let owner = self.port_module(&port_id);
+			self.new_capability(name, owner).unwrap();
+		}
 		self
 	}
 
@@ -302,6 +331,11 @@ where
 		Self { router, ..self }
 	}
 
+	/// Meters the work of the handlers with `gas_meter`, shared with the caller to inspect it.
+	pub fn with_gas_meter(self, gas_meter: Arc<BasicGasMeter>) -> Self {
+		Self { gas_meter: Some(gas_meter), ..self }
+	}
+
 	/// Accessor for a block of the local (host) chain from this context.
 	/// Returns `None` if the block at the requested height does not exist.
 	pub fn host_block(&self, target_height: Height) -> Option<&C::HostBlock> {
@@ -377,11 +411,31 @@ where
 
 	pub fn add_port(&mut self, port_id: PortId) {
 		let module_id = ModuleId::new(format!("module{}", port_id).into()).unwrap();
-		self.ibc_store.lock().unwrap().port_to_module.insert(port_id, module_id);
+		self.scope_port_to_module(port_id, module_id);
 	}
 
 	pub fn scope_port_to_module(&mut self, port_id: PortId, module_id: ModuleId) {
-		self.ibc_store.lock().unwrap().port_to_module.insert(port_id, module_id);
+		self.bind_port(port_id, module_id).unwrap();
+	}
+
+	/// Returns the module bound to a port. Channels on unbound ports are taken to be opened by the
+	/// module whose id is the port id stripped of its non-alphanumeric characters, e.g. `transfer`.
+	fn port_module(&self, port_id: &PortId) -> ModuleId {
+		self.lookup_module_by_port(port_id).unwrap_or_else(|_| {
+			let module_id: String =
+				port_id.as_str().chars().filter(|c| c.is_alphanumeric()).collect();
+			module_id.parse().unwrap()
+		})
+	}
+
+	/// Returns the capability of a channel, as issued to the module bound to its port.
+	pub fn channel_capability(
+		&self,
+		port_id: &PortId,
+		channel_id: ChannelId,
+	) -> Option<Capability> {
+		let name = CapabilityName::channel(port_id, channel_id);
+		lookup_capability(self, &name, &self.port_module(port_id)).ok()
 	}
 
 	pub fn consensus_states(&self, client_id: &ClientId) -> Vec<AnyConsensusStateWithHeight<C>> {
@@ -420,6 +474,71 @@ where
 	pub fn ibc_store_share(&self) -> Arc<Mutex<MockIbcStore<C>>> {
 		self.ibc_store.clone()
 	}
+
+	/// Returns a commitment store holding the IBC state of the context, under its commitment
+	/// prefix, for tests to prove that state the way a counterparty chain would. Values are
+	/// encoded as in ibc-go: client and consensus states as protobuf `Any`s, connections and
+	/// channels in protobuf, sequences in big endian and receipts as `[1]`.
+	pub fn commitment_store<H: HostFunctionsProvider>(&self) -> CommitmentStore<H> {
+		let mut store = CommitmentStore::new(self.commitment_prefix())
+			.expect("the commitment prefix is not empty");
+		let ibc_store = self.ibc_store.lock().unwrap();
+		let mut set = |path: Path, value: Vec<u8>| {
+			store.set(path, value).expect("stored values are not empty")
+		};
+
+		for (client_id, record) in &ibc_store.clients {
+			set(ClientTypePath(client_id.clone()).into(), record.client_type.as_bytes().to_vec());
+			if let Some(client_state) = &record.client_state {
+				let client_state: Any = client_state.clone().into();
+				set(ClientStatePath(client_id.clone()).into(), client_state.encode_to_vec());
+			}
+			for (height, consensus_state) in &record.consensus_states {
+				let path = ClientConsensusStatePath {
+					client_id: client_id.clone(),
+					epoch: height.revision_number,
+					height: height.revision_height,
+				};
+				let consensus_state: Any = consensus_state.clone().into();
+				set(path.into(), consensus_state.encode_to_vec());
+			}
+		}
+		for (connection_id, connection_end) in &ibc_store.connections {
+			set(ConnectionsPath(connection_id.clone()).into(), connection_end.encode_vec());
+		}
+		for ((port_id, channel_id), channel_end) in &ibc_store.channels {
+			let path = ChannelEndsPath(port_id.clone(), *channel_id);
+			set(path.into(), channel_end.encode_vec());
+		}
+		for ((port_id, channel_id), sequence) in &ibc_store.next_sequence_recv {
+			let path = SeqRecvsPath(port_id.clone(), *channel_id);
+			set(path.into(), u64::from(*sequence).to_be_bytes().to_vec());
+		}
+		for ((port_id, channel_id, sequence), commitment) in &ibc_store.packet_commitment {
+			let path = CommitmentsPath {
+				port_id: port_id.clone(),
+				channel_id: *channel_id,
+				sequence: *sequence,
+			};
+			set(path.into(), commitment.clone().into_vec());
+		}
+		for ((port_id, channel_id, sequence), ack) in &ibc_store.packet_acknowledgement {
+			let path =
+				AcksPath { port_id: port_id.clone(), channel_id: *channel_id, sequence: *sequence };
+			set(path.into(), ack.clone().into_vec());
+		}
+		for (port_id, channel_id, sequence) in ibc_store.packet_receipt.keys() {
+			let path = ReceiptsPath {
+				port_id: port_id.clone(),
+				channel_id: *channel_id,
+				sequence: *sequence,
+			};
+			set(path.into(), vec![1]);
+		}
+		drop(ibc_store);
+
+		store
+	}
 }
 
 impl<C: HostBlockType + Default> MockContext<C>
@@ -471,6 +590,41 @@ where
 		self.ibc_store.lock().unwrap().clients.insert(client_id.clone(), client_record);
 		self
 	}
+
+	/// Sets the commitment root of the consensus state of a mock client at `height`, typically to
+	/// the root of the [`commitment_store`](Self::commitment_store) of the counterparty context, so
+	/// that the client verifies the proofs of that store instead of accepting any proof.
+	pub fn with_client_root(
+		self,
+		client_id: &ClientId,
+		height: Height,
+		root: CommitmentRoot,
+	) -> Self {
+		let consensus_state = MockConsensusState { header: MockHeader::new(height), root };
+		self.ibc_store
+			.lock()
+			.unwrap()
+			.clients
+			.get_mut(client_id)
+			.expect("the client exists")
+			.consensus_states
+			.insert(height, consensus_state.into());
+		self
+	}
+
+	/// Makes the mock client `client_id` verify proofs against invalid specs.
+	pub fn with_invalid_client_proof_specs(self, client_id: &ClientId) -> Self {
+		let mut ibc_store = self.ibc_store.lock().unwrap();
+		let record = ibc_store.clients.get_mut(client_id).expect("the client exists");
+		let height = record.client_state.as_ref().expect("the client exists").latest_height();
+		let client_state = MockClientState {
+			invalid_proof_specs: true,
+			..MockClientState::new(MockHeader::new(height).into())
+		};
+		record.client_state = Some(client_state.into());
+		drop(ibc_store);
+		self
+	}
 }
 
 /// An object that stores all IBC related data.
@@ -521,6 +675,18 @@ pub struct MockIbcStore<C: HostBlockType> {
 	/// Maps ports to the the module that owns it
 	pub port_to_module: BTreeMap<PortId, ModuleId>,
 
+	/// Maps the port prefixes claimed by modules to their owner.
+	pub port_prefixes: BTreeMap<String, ModuleId>,
+
+	/// The indices of the capabilities issued, by name.
+	pub capabilities: BTreeMap<CapabilityName, u64>,
+
+	/// The modules the capabilities were issued to, by name.
+	pub capability_owners: BTreeMap<CapabilityName, ModuleId>,
+
+	/// The index the next capability will be issued with.
+	pub next_capability_index: u64,
+
 	/// Constant-size commitments to packets data fields
 	pub packet_commitment: BTreeMap<(PortId, ChannelId, Sequence), PacketCommitment>,
 
@@ -556,6 +722,10 @@ impl Debug for MockRouter {
 }
 
 impl Router for MockRouter {
+	fn get_route(&self, module_id: &impl Borrow<ModuleId>) -> Option<&dyn Module> {
+		self.0.get(module_id.borrow()).map(Arc::as_ref)
+	}
+
 	fn get_route_mut(&mut self, module_id: &impl Borrow<ModuleId>) -> Option<&mut dyn Module> {
 		self.0.get_mut(module_id.borrow()).and_then(Arc::get_mut)
 	}
@@ -572,6 +742,12 @@ where
 	C::AnyConsensusState:
 		Eq + TryFrom<Any, Error = Ics02Error> + Into<Any> + From<C::HostBlock> + 'static,
 {
+	fn gas_meter(&self) -> &dyn GasMeter {
+		match &self.gas_meter {
+			Some(gas_meter) => gas_meter.as_ref(),
+			None => &InfiniteGasMeter,
+		}
+	}
 }
 
 impl<C: HostBlockType + Default> Ics26Context for MockContext<C>
@@ -592,6 +768,20 @@ where
 	}
 }
 
+/// The mock modules, such as [`DummyTransferModule`](crate::test_utils::DummyTransferModule),
+/// keep their state in the store they share with the context.
+impl<C: HostBlockType> ModuleSnapshot for MockContext<C> {
+	type Snapshot = MockIbcStore<C>;
+
+	fn snapshot_modules(&self) -> Self::Snapshot {
+		self.ibc_store.lock().unwrap().clone()
+	}
+
+	fn restore_modules(&mut self, snapshot: Self::Snapshot) {
+		*self.ibc_store.lock().unwrap() = snapshot;
+	}
+}
+
 impl<C: HostBlockType> PortReader for MockContext<C> {
 	fn lookup_module_by_port(&self, port_id: &PortId) -> Result<ModuleId, Error> {
 		match self.ibc_store.lock().unwrap().port_to_module.get(port_id) {
@@ -599,6 +789,88 @@ impl<C: HostBlockType> PortReader for MockContext<C> {
 			None => Err(Ics05Error::unknown_port(port_id.clone())),
 		}
 	}
+
+	fn lookup_ports_by_prefix(&self, prefix: &str) -> Result<Vec<(PortId, ModuleId)>, Error> {
+		let ibc_store = self.ibc_store.lock().unwrap();
+		Ok(ibc_store
+			.port_to_module
+			.iter()
+			.filter(|(port_id, _)| port_id.as_str().starts_with(prefix))
+			.map(|(p, m)| (p.clone(), m.clone()))
+			.collect())
+	}
+
+	fn get_port_prefixes(&self) -> Result<Vec<(String, ModuleId)>, Error> {
+		let ibc_store = self.ibc_store.lock().unwrap();
+		Ok(ibc_store.port_prefixes.iter().map(|(p, m)| (p.clone(), m.clone())).collect())
+	}
+}
+
+impl<C: HostBlockType> PortKeeper for MockContext<C> {
+	fn store_port_module(
+		&mut self,
+		port_id: PortId,
+		module_id: Option<ModuleId>,
+	) -> Result<(), Error> {
+		let mut ibc_store = self.ibc_store.lock().unwrap();
+		match module_id {
+			Some(module_id) => ibc_store.port_to_module.insert(port_id, module_id),
+			None => ibc_store.port_to_module.remove(&port_id),
+		};
+		Ok(())
+	}
+
+	fn store_port_prefix(&mut self, prefix: String, module_id: ModuleId) -> Result<(), Error> {
+		self.ibc_store.lock().unwrap().port_prefixes.insert(prefix, module_id);
+		Ok(())
+	}
+}
+
+impl<C: HostBlockType> CapabilityReader for MockContext<C> {
+	fn get_capability_index(&self, name: &CapabilityName) -> Result<Option<u64>, Error> {
+		Ok(self.ibc_store.lock().unwrap().capabilities.get(name).copied())
+	}
+
+	fn get_capability_owner(&self, name: &CapabilityName) -> Result<Option<ModuleId>, Error> {
+		Ok(self.ibc_store.lock().unwrap().capability_owners.get(name).cloned())
+	}
+
+	fn get_next_capability_index(&self) -> Result<u64, Error> {
+		Ok(self.ibc_store.lock().unwrap().next_capability_index)
+	}
+}
+
+impl<C: HostBlockType> CapabilityKeeper for MockContext<C> {
+	fn store_capability_index(
+		&mut self,
+		name: CapabilityName,
+		index: Option<u64>,
+	) -> Result<(), Error> {
+		let mut ibc_store = self.ibc_store.lock().unwrap();
+		match index {
+			Some(index) => ibc_store.capabilities.insert(name, index),
+			None => ibc_store.capabilities.remove(&name),
+		};
+		Ok(())
+	}
+
+	fn store_capability_owner(
+		&mut self,
+		name: CapabilityName,
+		owner: Option<ModuleId>,
+	) -> Result<(), Error> {
+		let mut ibc_store = self.ibc_store.lock().unwrap();
+		match owner {
+			Some(owner) => ibc_store.capability_owners.insert(name, owner),
+			None => ibc_store.capability_owners.remove(&name),
+		};
+		Ok(())
+	}
+
+	fn store_next_capability_index(&mut self, index: u64) -> Result<(), Error> {
+		self.ibc_store.lock().unwrap().next_capability_index = index;
+		Ok(())
+	}
 }
 
 impl<C: HostBlockType> ChannelReader for MockContext<C> {
@@ -1158,27 +1430,44 @@ mod tests {
 
 	use crate::{
 		core::{
+			ics02_client::{
+				client_consensus::ConsensusState, client_def::ClientDef, client_state::ClientState,
+				context::ClientReader,
+			},
+			ics03_connection::connection::{ConnectionEnd, State},
 			ics04_channel::{
-				channel::{Counterparty, Order},
+				channel::{ChannelEnd, Counterparty, Order},
+				commitment::PacketCommitment,
 				error::Error,
-				packet::Packet,
+				packet::{Packet, Sequence},
 				Version,
 			},
-			ics24_host::identifier::{ChainId, ChannelId, ConnectionId, PortId},
+			ics23_commitment::{
+				commitment::CommitmentProofBytes, merkle::MerkleProof, store::CommitmentStore,
+			},
+			ics24_host::{
+				identifier::{ChainId, ChannelId, ClientId, ConnectionId, PortId},
+				path::{ChannelEndsPath, CommitmentsPath, ConnectionsPath, ReceiptsPath},
+				Path,
+			},
 			ics26_routing::context::{
 				Acknowledgement, Module, ModuleId, ModuleOutputBuilder, OnRecvPacketAck, Router,
 				RouterBuilder,
 			},
 		},
+		gas::InfiniteGasMeter,
 		mock::{
+			client_state::MockConsensusState,
 			context::{MockClientTypes, MockContext, MockRouterBuilder},
 			host::{HostBlock, MockHostType},
 		},
 		prelude::*,
 		signer::Signer,
-		test_utils::get_dummy_bech32_account,
+		test_utils::{get_dummy_bech32_account, get_dummy_proof, Crypto},
 		Height,
 	};
+	use ibc_proto::ibc::core::commitment::v1::{MerkleProof as RawMerkleProof, MerkleRoot};
+	use tendermint_proto::Protobuf;
 
 	#[test]
 	fn test_history_manipulation() {
@@ -1383,6 +1672,110 @@ mod tests {
 				write_fn(ctx.router.get_route_mut(&mid).unwrap().as_any_mut()).unwrap()
 			});
 	}
+
+	#[test]
+	fn test_commitment_store() {
+		let connection_id = ConnectionId::new(0);
+		let port_id = PortId::transfer();
+		let channel_id = ChannelId::new(0);
+		let sequence = Sequence::from(1);
+		let commitment = PacketCommitment::from(vec![1, 2, 3]);
+		let ctx = MockContext::<MockClientTypes>::default()
+			.with_client(&ClientId::default(), Height::new(0, 5))
+			.with_connection(connection_id.clone(), ConnectionEnd::default())
+			.with_channel(port_id.clone(), channel_id, ChannelEnd::default())
+			.with_packet_commitment(port_id.clone(), channel_id, sequence, commitment.clone());
+
+		let store = ctx.commitment_store::<Crypto>();
+		let specs = CommitmentStore::<Crypto>::proof_specs();
+		let root = MerkleRoot::from(store.root());
+		let gas = &InfiniteGasMeter;
+		let verify_membership = |path: Path, value: Vec<u8>| {
+			let proof: MerkleProof<Crypto> =
+				RawMerkleProof::try_from(store.prove(&path).unwrap()).unwrap().into();
+			proof.verify_membership(&specs, root.clone(), store.merkle_path(&path), value, 0, gas)
+		};
+
+		let path = ConnectionsPath(connection_id).into();
+		verify_membership(path, ConnectionEnd::default().encode_vec()).unwrap();
+		let path = ChannelEndsPath(port_id.clone(), channel_id).into();
+		verify_membership(path, ChannelEnd::default().encode_vec()).unwrap();
+		let path = CommitmentsPath { port_id: port_id.clone(), channel_id, sequence };
+		verify_membership(path.into(), commitment.into_vec()).unwrap();
+		let path = ChannelEndsPath(port_id.clone(), channel_id).into();
+		assert!(verify_membership(path, vec![0]).is_err());
+
+		// no receipt was written for the packet
+		let path = ReceiptsPath { port_id, channel_id, sequence }.into();
+		let proof: MerkleProof<Crypto> =
+			RawMerkleProof::try_from(store.prove(&path).unwrap()).unwrap().into();
+		proof
+			.verify_non_membership(&specs, root, store.merkle_path(&path), gas)
+			.unwrap();
+	}
+
+	#[test]
+	fn test_mock_client_verifies_proofs() {
+		let client_id = ClientId::default();
+		let connection_id = ConnectionId::new(0);
+		let proof_height = Height::new(0, 5);
+		let counterparty = MockContext::<MockClientTypes>::default()
+			.with_connection(connection_id.clone(), ConnectionEnd::default())
+			.with_packet_commitment(
+				PortId::transfer(),
+				ChannelId::new(0),
+				Sequence::from(1),
+				vec![1, 2, 3].into(),
+			);
+		let store = counterparty.commitment_store::<Crypto>();
+		let prefix = store.prefix().clone();
+
+		let ctx = MockContext::<MockClientTypes>::default()
+			.with_client(&client_id, proof_height)
+			.with_client_root(&client_id, proof_height, store.root());
+		let client_state = ctx.client_state(&client_id).unwrap();
+		let consensus_state = ctx.consensus_state(&client_id, proof_height).unwrap();
+		let verify = |ctx: &MockContext<MockClientTypes>,
+		              proof: &CommitmentProofBytes,
+		              connection_end: &ConnectionEnd| {
+			let root = ctx.consensus_state(&client_id, proof_height).unwrap().root().clone();
+			client_state.client_def().verify_connection_state(
+				ctx,
+				&client_id,
+				&client_state,
+				proof_height,
+				&prefix,
+				proof,
+				&root,
+				&connection_id,
+				connection_end,
+			)
+		};
+
+		let proof = store.prove(&ConnectionsPath(connection_id.clone()).into()).unwrap();
+		verify(&ctx, &proof, &ConnectionEnd::default()).unwrap();
+
+		// the proof does not hold for another connection end
+		let mut connection_end = ConnectionEnd::default();
+		connection_end.set_state(State::Open);
+		assert!(verify(&ctx, &proof, &connection_end).is_err());
+
+		// nor does the proof of another path, or a dummy proof
+		let path = CommitmentsPath {
+			port_id: PortId::transfer(),
+			channel_id: ChannelId::new(0),
+			sequence: Sequence::from(1),
+		};
+		let other_proof = store.prove(&path.into()).unwrap();
+		assert!(verify(&ctx, &other_proof, &ConnectionEnd::default()).is_err());
+		let dummy_proof = CommitmentProofBytes::try_from(get_dummy_proof()).unwrap();
+		assert!(verify(&ctx, &dummy_proof, &ConnectionEnd::default()).is_err());
+
+		// which clients of mock headers, whose root is a placeholder, accept
+		assert_ne!(consensus_state.root(), &MockConsensusState::placeholder_root());
+		let ctx = MockContext::<MockClientTypes>::default().with_client(&client_id, proof_height);
+		verify(&ctx, &dummy_proof, &connection_end).unwrap();
+	}
 }
 
 #[cfg(test)]
@@ -1,13 +1,15 @@
 use std::{
+	collections::BTreeMap,
 	sync::{Arc, Mutex},
 	time::Duration,
 };
 
 use crate::{
 	applications::transfer::{
-		context::{BankKeeper, Ics20Context, Ics20Keeper, Ics20Reader},
+		context::{on_recv_packet, BankKeeper, Ics20Context, Ics20Keeper, Ics20Reader},
+		enablement::EnablementKey,
 		error::Error as Ics20Error,
-		PrefixedCoin,
+		Amount, PrefixedCoin, PrefixedDenom,
 	},
 	core::{
 		ics02_client::{
@@ -23,12 +25,21 @@ use crate::{
 			commitment::{AcknowledgementCommitment, PacketCommitment},
 			context::{ChannelKeeper, ChannelReader},
 			error::Error,
-			packet::{Receipt, Sequence},
+			packet::{Packet, Receipt, Sequence},
 			Version,
 		},
-		ics05_port::{context::PortReader, error::Error as PortError},
+		ics05_port::{
+			capabilities::{CapabilityName, CapabilityReader},
+			context::PortReader,
+			error::Error as PortError,
+		},
+		ics23_commitment::{
+			error::Error as Ics23Error, ethereum::KeccakHostFunctions, substrate::TrieHostFunctions,
+		},
 		ics24_host::identifier::{ChannelId, ClientId, ConnectionId, PortId},
-		ics26_routing::context::{Module, ModuleId, ModuleOutputBuilder, ReaderContext},
+		ics26_routing::context::{
+			Module, ModuleId, ModuleOutputBuilder, OnRecvPacketAck, ReaderContext,
+		},
 	},
 	mock::context::{HostBlockType, MockIbcStore},
 	prelude::*,
@@ -38,6 +49,8 @@ use crate::{
 };
 
 use crate::core::ics02_client::context::ClientTypes;
+use sp_core::Blake2Hasher;
+use sp_trie::LayoutV0;
 use tendermint::{block, consensus, evidence, public_key::Algorithm};
 
 // Needed in mocks.
@@ -66,26 +79,66 @@ pub fn get_dummy_bech32_account() -> String {
 	"cosmos1wxeyh7zgn4tctjzs0vtqpc6p5cxq5t2muzl7ng".to_string()
 }
 
+/// A mock of the context of the transfer application, sharing the IBC store of a
+/// [`MockContext`](crate::mock::context::MockContext). The applications built on top of ICS20 keep
+/// their own state in `S`, implementing their context traits for the mock next to their tests.
 #[derive(Debug, Clone)]
-pub struct DummyTransferModule<C: HostBlockType> {
+pub struct DummyTransferModule<C: HostBlockType, S = ()> {
 	ibc_store: Arc<Mutex<MockIbcStore<C>>>,
+	denom_traces: BTreeMap<String, PrefixedDenom>,
+	total_escrow: BTreeMap<PrefixedDenom, Amount>,
+	disabled_transfers: BTreeMap<EnablementKey, String>,
+	host_timestamp: Timestamp,
+	bank_transfers: Vec<(Signer, Signer, PrefixedCoin)>,
+	state: S,
 }
 
-impl<C: HostBlockType> PartialEq for DummyTransferModule<C> {
+impl<C: HostBlockType, S> PartialEq for DummyTransferModule<C, S> {
 	fn eq(&self, _other: &Self) -> bool {
 		false
 	}
 }
 
-impl<C: HostBlockType> Eq for DummyTransferModule<C> {}
+impl<C: HostBlockType, S> Eq for DummyTransferModule<C, S> {}
 
 impl<C: HostBlockType> DummyTransferModule<C> {
 	pub fn new(ibc_store: Arc<Mutex<MockIbcStore<C>>>) -> Self {
-		Self { ibc_store }
+		Self::with_state(ibc_store, ())
+	}
+}
+
+impl<C: HostBlockType, S> DummyTransferModule<C, S> {
+	pub fn with_state(ibc_store: Arc<Mutex<MockIbcStore<C>>>, state: S) -> Self {
+		Self {
+			ibc_store,
+			denom_traces: BTreeMap::new(),
+			total_escrow: BTreeMap::new(),
+			disabled_transfers: BTreeMap::new(),
+			host_timestamp: Timestamp::none(),
+			bank_transfers: Vec::new(),
+			state,
+		}
+	}
+
+	pub fn state(&self) -> &S {
+		&self.state
+	}
+
+	pub fn state_mut(&mut self) -> &mut S {
+		&mut self.state
+	}
+
+	pub fn set_host_timestamp(&mut self, timestamp: Timestamp) {
+		self.host_timestamp = timestamp;
+	}
+
+	/// Returns the tokens sent between accounts so far, in order, as `(from, to, tokens)`.
+	pub fn bank_transfers(&self) -> &[(Signer, Signer, PrefixedCoin)] {
+		&self.bank_transfers
 	}
 }
 
-impl<C: HostBlockType + 'static> Module for DummyTransferModule<C> {
+impl<C: HostBlockType + 'static, S: Send + Sync + 'static> Module for DummyTransferModule<C, S> {
 	fn on_chan_open_try(
 		&mut self,
 		_output: &mut ModuleOutputBuilder,
@@ -99,6 +152,15 @@ impl<C: HostBlockType + 'static> Module for DummyTransferModule<C> {
 	) -> Result<Version, Error> {
 		Ok(counterparty_version.clone())
 	}
+
+	fn on_recv_packet(
+		&self,
+		output: &mut ModuleOutputBuilder,
+		packet: &Packet,
+		relayer: &Signer,
+	) -> OnRecvPacketAck {
+		on_recv_packet(self, output, packet, relayer)
+	}
 }
 
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
@@ -223,20 +285,32 @@ impl HostFunctionsProvider for Crypto {
 
 // implementation for ics23
 impl ics23::HostFunctionsProvider for Crypto {
-	fn sha2_256(_message: &[u8]) -> [u8; 32] {
-		unimplemented!()
+	fn sha2_256(message: &[u8]) -> [u8; 32] {
+		use sha2::Digest;
+		let mut res = [0u8; 32];
+		res.copy_from_slice(&sha2::Sha256::digest(message));
+		res
 	}
 
-	fn sha2_512(_message: &[u8]) -> [u8; 64] {
-		unimplemented!()
+	fn sha2_512(message: &[u8]) -> [u8; 64] {
+		use sha2::Digest;
+		let mut res = [0u8; 64];
+		res.copy_from_slice(&sha2::Sha512::digest(message));
+		res
 	}
 
-	fn sha2_512_truncated(_message: &[u8]) -> [u8; 32] {
-		unimplemented!()
+	fn sha2_512_truncated(message: &[u8]) -> [u8; 32] {
+		let hash = Self::sha2_512(message);
+		let mut res = [0u8; 32];
+		res.copy_from_slice(&hash[..32]);
+		res
 	}
 
-	fn sha3_512(_message: &[u8]) -> [u8; 64] {
-		unimplemented!()
+	fn sha3_512(message: &[u8]) -> [u8; 64] {
+		use sha3::Digest;
+		let mut res = [0u8; 64];
+		res.copy_from_slice(&sha3::Sha3_512::digest(message));
+		res
 	}
 
 	fn ripemd160(_message: &[u8]) -> [u8; 20] {
@@ -244,11 +318,81 @@ impl ics23::HostFunctionsProvider for Crypto {
 	}
 }
 
-impl<C: HostBlockType> Ics20Keeper for DummyTransferModule<C> {
+impl KeccakHostFunctions for Crypto {
+	fn keccak_256(input: &[u8]) -> [u8; 32] {
+		use sha3::Digest;
+		let mut res = [0u8; 32];
+		res.copy_from_slice(&sha3::Keccak256::digest(input));
+		res
+	}
+}
+
+impl TrieHostFunctions for Crypto {
+	fn verify_membership_trie_proof(
+		root: &[u8; 32],
+		proof: &[Vec<u8>],
+		key: &[u8],
+		value: &[u8],
+	) -> Result<(), Ics23Error> {
+		let item = vec![(key, Some(value))];
+		sp_trie::verify_trie_proof::<LayoutV0<Blake2Hasher>, _, _, _>(
+			&sp_core::H256::from_slice(root),
+			proof,
+			&item,
+		)
+		.map_err(|_| Ics23Error::verification_failure())
+	}
+
+	fn verify_non_membership_trie_proof(
+		root: &[u8; 32],
+		proof: &[Vec<u8>],
+		key: &[u8],
+	) -> Result<(), Ics23Error> {
+		let item: Vec<(&[u8], Option<&[u8]>)> = vec![(key, None)];
+		sp_trie::verify_trie_proof::<LayoutV0<Blake2Hasher>, _, _, _>(
+			&sp_core::H256::from_slice(root),
+			proof,
+			&item,
+		)
+		.map_err(|_| Ics23Error::verification_failure())
+	}
+}
+
+impl<C: HostBlockType, S> Ics20Keeper for DummyTransferModule<C, S> {
 	type AccountId = Signer;
+
+	fn store_denom_trace(
+		&mut self,
+		trace_hash: String,
+		denom: PrefixedDenom,
+	) -> Result<(), Ics20Error> {
+		self.denom_traces.insert(trace_hash, denom);
+		Ok(())
+	}
+
+	fn store_transfer_disabled(
+		&mut self,
+		key: EnablementKey,
+		reason: Option<String>,
+	) -> Result<(), Ics20Error> {
+		match reason {
+			Some(reason) => self.disabled_transfers.insert(key, reason),
+			None => self.disabled_transfers.remove(&key),
+		};
+		Ok(())
+	}
+
+	fn store_total_escrow(
+		&mut self,
+		denom: &PrefixedDenom,
+		amount: Amount,
+	) -> Result<(), Ics20Error> {
+		self.total_escrow.insert(denom.clone(), amount);
+		Ok(())
+	}
 }
 
-impl<C: HostBlockType> ChannelKeeper for DummyTransferModule<C> {
+impl<C: HostBlockType, S> ChannelKeeper for DummyTransferModule<C, S> {
 	fn store_packet_commitment(
 		&mut self,
 		key: (PortId, ChannelId, Sequence),
@@ -275,10 +419,11 @@ impl<C: HostBlockType> ChannelKeeper for DummyTransferModule<C> {
 
 	fn store_packet_acknowledgement(
 		&mut self,
-		_key: (PortId, ChannelId, Sequence),
-		_ack: AcknowledgementCommitment,
+		key: (PortId, ChannelId, Sequence),
+		ack: AcknowledgementCommitment,
 	) -> Result<(), Error> {
-		unimplemented!()
+		self.ibc_store.lock().unwrap().packet_acknowledgement.insert(key, ack);
+		Ok(())
 	}
 
 	fn delete_packet_acknowledgement(
@@ -350,21 +495,40 @@ impl<C: HostBlockType> ChannelKeeper for DummyTransferModule<C> {
 	}
 }
 
-impl<C: HostBlockType> PortReader for DummyTransferModule<C> {
+impl<C: HostBlockType, S> PortReader for DummyTransferModule<C, S> {
 	fn lookup_module_by_port(&self, _port_id: &PortId) -> Result<ModuleId, PortError> {
 		unimplemented!()
 	}
+
+	fn lookup_ports_by_prefix(&self, _prefix: &str) -> Result<Vec<(PortId, ModuleId)>, PortError> {
+		unimplemented!()
+	}
+}
+
+impl<C: HostBlockType, S> CapabilityReader for DummyTransferModule<C, S> {
+	fn get_capability_index(&self, name: &CapabilityName) -> Result<Option<u64>, PortError> {
+		Ok(self.ibc_store.lock().unwrap().capabilities.get(name).copied())
+	}
+
+	fn get_capability_owner(&self, name: &CapabilityName) -> Result<Option<ModuleId>, PortError> {
+		Ok(self.ibc_store.lock().unwrap().capability_owners.get(name).cloned())
+	}
+
+	fn get_next_capability_index(&self) -> Result<u64, PortError> {
+		Ok(self.ibc_store.lock().unwrap().next_capability_index)
+	}
 }
 
-impl<C: HostBlockType> BankKeeper for DummyTransferModule<C> {
+impl<C: HostBlockType, S> BankKeeper for DummyTransferModule<C, S> {
 	type AccountId = Signer;
 
 	fn send_coins(
 		&mut self,
-		_from: &Self::AccountId,
-		_to: &Self::AccountId,
-		_amt: &PrefixedCoin,
+		from: &Self::AccountId,
+		to: &Self::AccountId,
+		amt: &PrefixedCoin,
 	) -> Result<(), Ics20Error> {
+		self.bank_transfers.push((from.clone(), to.clone(), amt.clone()));
 		Ok(())
 	}
 
@@ -385,8 +549,9 @@ impl<C: HostBlockType> BankKeeper for DummyTransferModule<C> {
 	}
 }
 
-impl<C: HostBlockType> Ics20Reader for DummyTransferModule<C> {
+impl<C: HostBlockType, S> Ics20Reader for DummyTransferModule<C, S> {
 	type AccountId = Signer;
+	type HostFunctions = Crypto;
 
 	fn get_port(&self) -> Result<PortId, Ics20Error> {
 		Ok(PortId::transfer())
@@ -399,9 +564,36 @@ impl<C: HostBlockType> Ics20Reader for DummyTransferModule<C> {
 	fn is_receive_enabled(&self) -> bool {
 		true
 	}
+
+	fn get_transfer_disabled(&self, key: &EnablementKey) -> Result<Option<String>, Ics20Error> {
+		Ok(self.disabled_transfers.get(key).cloned())
+	}
+
+	fn get_total_escrow(&self, denom: &PrefixedDenom) -> Result<Amount, Ics20Error> {
+		Ok(self.total_escrow.get(denom).copied().unwrap_or_else(|| 0u64.into()))
+	}
+
+	fn get_denom_trace(&self, trace_hash: &str) -> Result<Option<PrefixedDenom>, Ics20Error> {
+		Ok(self.denom_traces.get(trace_hash).cloned())
+	}
+
+	fn get_denom_traces(
+		&self,
+		start_after: Option<&str>,
+		limit: u64,
+	) -> Result<Vec<(String, PrefixedDenom)>, Ics20Error> {
+		let traces = self
+			.denom_traces
+			.iter()
+			.filter(|(hash, _)| start_after.map_or(true, |start| hash.as_str() > start))
+			.take(limit as usize)
+			.map(|(hash, denom)| (hash.clone(), denom.clone()))
+			.collect();
+		Ok(traces)
+	}
 }
 
-impl<C: HostBlockType> ConnectionReader for DummyTransferModule<C> {
+impl<C: HostBlockType, S> ConnectionReader for DummyTransferModule<C, S> {
 	fn connection_end(&self, cid: &ConnectionId) -> Result<ConnectionEnd, Ics03Error> {
 		match self.ibc_store.lock().unwrap().connections.get(cid) {
 			Some(connection_end) => Ok(connection_end.clone()),
@@ -422,7 +614,7 @@ impl<C: HostBlockType> ConnectionReader for DummyTransferModule<C> {
 	}
 }
 
-impl<C: HostBlockType> ClientReader for DummyTransferModule<C> {
+impl<C: HostBlockType, S> ClientReader for DummyTransferModule<C, S> {
 	fn client_state(&self, client_id: &ClientId) -> Result<Self::AnyClientState, Ics02Error> {
 		match self.ibc_store.lock().unwrap().clients.get(client_id) {
 			Some(client_record) => client_record
@@ -480,7 +672,7 @@ impl<C: HostBlockType> ClientReader for DummyTransferModule<C> {
 	}
 
 	fn host_timestamp(&self) -> Timestamp {
-		todo!()
+		self.host_timestamp
 	}
 
 	fn client_counter(&self) -> Result<u64, Ics02Error> {
@@ -492,7 +684,7 @@ impl<C: HostBlockType> ClientReader for DummyTransferModule<C> {
 	}
 }
 
-impl<C: HostBlockType> ChannelReader for DummyTransferModule<C> {
+impl<C: HostBlockType, S> ChannelReader for DummyTransferModule<C, S> {
 	fn channel_end(&self, pcid: &(PortId, ChannelId)) -> Result<ChannelEnd, Error> {
 		match self.ibc_store.lock().unwrap().channels.get(pcid) {
 			Some(channel_end) => Ok(channel_end.clone()),
@@ -541,9 +733,12 @@ impl<C: HostBlockType> ChannelReader for DummyTransferModule<C> {
 
 	fn get_packet_acknowledgement(
 		&self,
-		_key: &(PortId, ChannelId, Sequence),
+		key: &(PortId, ChannelId, Sequence),
 	) -> Result<AcknowledgementCommitment, Error> {
-		unimplemented!()
+		match self.ibc_store.lock().unwrap().packet_acknowledgement.get(key) {
+			Some(ack) => Ok(ack.clone()),
+			None => Err(Error::packet_acknowledgement_not_found(key.2)),
+		}
 	}
 
 	fn hash(&self, value: Vec<u8>) -> Vec<u8> {
@@ -577,18 +772,18 @@ impl<C: HostBlockType> ChannelReader for DummyTransferModule<C> {
 	}
 }
 
-impl<C: HostBlockType> ClientTypes for DummyTransferModule<C> {
+impl<C: HostBlockType, S> ClientTypes for DummyTransferModule<C, S> {
 	type AnyClientMessage = C::AnyClientMessage;
 	type AnyClientState = C::AnyClientState;
 	type AnyConsensusState = C::AnyConsensusState;
 	type ClientDef = C::ClientDef;
 }
 
-impl<C: HostBlockType> HostBlockType for DummyTransferModule<C> {
+impl<C: HostBlockType, S> HostBlockType for DummyTransferModule<C, S> {
 	type HostBlock = C::HostBlock;
 }
 
-impl<C: HostBlockType> ClientKeeper for DummyTransferModule<C> {
+impl<C: HostBlockType, S> ClientKeeper for DummyTransferModule<C, S> {
 	fn store_client_type(
 		&mut self,
 		_client_id: ClientId,
@@ -641,8 +836,8 @@ impl<C: HostBlockType> ClientKeeper for DummyTransferModule<C> {
 	}
 }
 
-impl<C: HostBlockType> Ics20Context for DummyTransferModule<C> {
+impl<C: HostBlockType, S> Ics20Context for DummyTransferModule<C, S> {
 	type AccountId = Signer;
 }
 
-impl<C: HostBlockType> ReaderContext for DummyTransferModule<C> {}
+impl<C: HostBlockType, S> ReaderContext for DummyTransferModule<C, S> {}
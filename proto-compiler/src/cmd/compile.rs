@@ -61,13 +61,25 @@ impl CompileCmd {
 	fn compile_ibc_protos(ibc_dir: &Path, out_dir: &Path) {
 		println!("[info ] Compiling IBC .proto files to Rust into '{}'...", out_dir.display());
 
+		let root = env!("CARGO_MANIFEST_DIR");
+		// Definitions extending those of ibc-go, e.g. with fields of later ibc-go versions, which
+		// replace the ibc-go files at the same path
+		let overrides_dir = PathBuf::from(format!("{}/../proto/definitions", root));
+		let ibc_proto_dir = PathBuf::from(format!("{}/proto", ibc_dir.display()));
+		let is_overridden = |path: &Path| {
+			path.strip_prefix(&ibc_proto_dir)
+				.map_or(false, |path| overrides_dir.join(path).exists())
+		};
+
 		// Paths
 		let proto_paths = [
+			format!("{}/ibc", overrides_dir.display()),
 			// ibc-go proto files
 			format!("{}/proto/ibc", ibc_dir.display()),
 		];
 
 		let proto_includes_paths = [
+			overrides_dir.display().to_string(),
 			format!("{}/proto", ibc_dir.display()),
 			format!("{}/third_party/proto", ibc_dir.display()),
 		];
@@ -83,7 +95,8 @@ impl CompileCmd {
 					.filter(|e| {
 						e.file_type().is_file() &&
 							e.path().extension().is_some() &&
-							e.path().extension().unwrap() == "proto"
+							e.path().extension().unwrap() == "proto" &&
+							!is_overridden(e.path())
 					})
 					.map(|e| e.into_path())
 					.collect(),
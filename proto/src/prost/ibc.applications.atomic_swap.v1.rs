@@ -0,0 +1,83 @@
+/// MsgMakeSwap makes an order selling sell_token for buy_token on the chain at
+/// the other end of source_channel.
+#[derive(::serde::Serialize, ::serde::Deserialize)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgMakeSwap {
+    /// the port on which the order is announced
+    #[prost(string, tag="1")]
+    pub source_port: ::prost::alloc::string::String,
+    /// the channel by which the order is announced
+    #[prost(string, tag="2")]
+    pub source_channel: ::prost::alloc::string::String,
+    /// the tokens sold by the maker
+    #[prost(message, optional, tag="3")]
+    pub sell_token: ::core::option::Option<super::super::super::super::cosmos::base::v1beta1::Coin>,
+    /// the tokens bought by the maker, as denominated on the counterparty chain
+    #[prost(message, optional, tag="4")]
+    pub buy_token: ::core::option::Option<super::super::super::super::cosmos::base::v1beta1::Coin>,
+    /// the maker address
+    #[prost(string, tag="5")]
+    pub maker_address: ::prost::alloc::string::String,
+    /// the address receiving buy_token on the counterparty chain
+    #[prost(string, tag="6")]
+    pub maker_receiving_address: ::prost::alloc::string::String,
+    /// the only address allowed to take the order, any address if empty
+    #[prost(string, tag="7")]
+    pub desired_taker: ::prost::alloc::string::String,
+    /// Timestamp in absolute nanoseconds since unix epoch after which the order
+    /// can no longer be taken. The expiration is disabled when set to 0.
+    #[prost(uint64, tag="8")]
+    pub expiration_timestamp: u64,
+    /// Timeout height of the packet announcing the order.
+    /// The timeout is disabled when set to 0.
+    #[prost(message, optional, tag="9")]
+    pub timeout_height: ::core::option::Option<super::super::super::core::client::v1::Height>,
+    /// Timeout timestamp of the packet announcing the order.
+    /// The timeout is disabled when set to 0.
+    #[prost(uint64, tag="10")]
+    pub timeout_timestamp: u64,
+}
+/// MsgTakeSwap takes an order received from the counterparty chain.
+#[derive(::serde::Serialize, ::serde::Deserialize)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgTakeSwap {
+    /// the identifier of the order
+    #[prost(string, tag="1")]
+    pub order_id: ::prost::alloc::string::String,
+    /// the tokens sold by the taker, which must match those bought by the maker
+    #[prost(message, optional, tag="2")]
+    pub sell_token: ::core::option::Option<super::super::super::super::cosmos::base::v1beta1::Coin>,
+    /// the taker address
+    #[prost(string, tag="3")]
+    pub taker_address: ::prost::alloc::string::String,
+    /// the address receiving the tokens sold by the maker on the counterparty chain
+    #[prost(string, tag="4")]
+    pub taker_receiving_address: ::prost::alloc::string::String,
+    /// Timeout height of the packet taking the order.
+    /// The timeout is disabled when set to 0.
+    #[prost(message, optional, tag="5")]
+    pub timeout_height: ::core::option::Option<super::super::super::core::client::v1::Height>,
+    /// Timeout timestamp of the packet taking the order.
+    /// The timeout is disabled when set to 0.
+    #[prost(uint64, tag="6")]
+    pub timeout_timestamp: u64,
+}
+/// MsgCancelSwap cancels an order that has not been taken yet.
+#[derive(::serde::Serialize, ::serde::Deserialize)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgCancelSwap {
+    /// the identifier of the order
+    #[prost(string, tag="1")]
+    pub order_id: ::prost::alloc::string::String,
+    /// the maker address
+    #[prost(string, tag="2")]
+    pub maker_address: ::prost::alloc::string::String,
+    /// Timeout height of the packet cancelling the order.
+    /// The timeout is disabled when set to 0.
+    #[prost(message, optional, tag="3")]
+    pub timeout_height: ::core::option::Option<super::super::super::core::client::v1::Height>,
+    /// Timeout timestamp of the packet cancelling the order.
+    /// The timeout is disabled when set to 0.
+    #[prost(uint64, tag="4")]
+    pub timeout_timestamp: u64,
+}
@@ -0,0 +1,18 @@
+/// MsgSubmitQueryResult submits the result of a pending cross-chain query,
+/// along with the proof of the result at the query height.
+#[derive(::serde::Serialize, ::serde::Deserialize)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgSubmitQueryResult {
+    /// the identifier of the query, of the form query-{sequence}
+    #[prost(string, tag="1")]
+    pub query_id: ::prost::alloc::string::String,
+    /// the value stored under the queried path
+    #[prost(bytes="vec", tag="2")]
+    pub result: ::prost::alloc::vec::Vec<u8>,
+    /// the proof of the result against the consensus state at the query height
+    #[prost(bytes="vec", tag="3")]
+    pub proof: ::prost::alloc::vec::Vec<u8>,
+    /// the submitter of the result
+    #[prost(string, tag="4")]
+    pub signer: ::prost::alloc::string::String,
+}
@@ -15,4 +15,7 @@ pub struct FungibleTokenPacketData {
     /// the recipient address on the destination chain
     #[prost(string, tag="4")]
     pub receiver: ::prost::alloc::string::String,
+    /// optional memo
+    #[prost(string, tag="5")]
+    pub memo: ::prost::alloc::string::String,
 }
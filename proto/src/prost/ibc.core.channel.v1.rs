@@ -447,6 +447,34 @@ pub struct MsgAcknowledgementResponse {
     #[prost(enumeration="ResponseResultType", tag="1")]
     pub result: i32,
 }
+/// MsgRecvPacketBatch receives incoming IBC packets sent over the same unordered
+/// channel, with a single proof of all their commitments
+#[derive(::serde::Serialize, ::serde::Deserialize)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgRecvPacketBatch {
+    #[prost(message, repeated, tag="1")]
+    pub packets: ::prost::alloc::vec::Vec<Packet>,
+    #[prost(bytes="vec", tag="2")]
+    pub proof_commitment: ::prost::alloc::vec::Vec<u8>,
+    #[prost(message, optional, tag="3")]
+    pub proof_height: ::core::option::Option<super::super::client::v1::Height>,
+    #[prost(string, tag="4")]
+    pub signer: ::prost::alloc::string::String,
+}
+/// MsgTimeoutBatch receives timed-out packets sent over the same unordered
+/// channel, with a single proof that none of them has been received
+#[derive(::serde::Serialize, ::serde::Deserialize)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgTimeoutBatch {
+    #[prost(message, repeated, tag="1")]
+    pub packets: ::prost::alloc::vec::Vec<Packet>,
+    #[prost(bytes="vec", tag="2")]
+    pub proof_unreceived: ::prost::alloc::vec::Vec<u8>,
+    #[prost(message, optional, tag="3")]
+    pub proof_height: ::core::option::Option<super::super::client::v1::Height>,
+    #[prost(string, tag="4")]
+    pub signer: ::prost::alloc::string::String,
+}
 /// ResponseResultType defines the possible outcomes of the execution of a message
 #[derive(::serde::Serialize, ::serde::Deserialize)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]